@@ -9,8 +9,12 @@ use log::{self, error, LevelFilter};
 use serde::{Deserialize, Serialize};
 use toml::Value;
 
+#[cfg(not(windows))]
+use alacritty_terminal::tty::device::{Device, SerialOptions};
 use alacritty_terminal::tty::Options as PtyOptions;
 
+#[cfg(not(windows))]
+use crate::config::device::{FlowControl, Parity};
 use crate::config::ui_config::Program;
 use crate::config::window::{Class, Identity};
 use crate::config::UiConfig;
@@ -148,8 +152,18 @@ fn parse_hex_or_decimal(input: &str) -> Option<u32> {
         .or_else(|| input.parse().ok())
 }
 
+/// Parse the replay speed CLI parameter.
+#[cfg(not(windows))]
+fn parse_replay_speed(input: &str) -> Result<f64, String> {
+    match input.parse::<f64>() {
+        Ok(speed) if speed.is_finite() && speed > 0. => Ok(speed),
+        Ok(_) => Err(String::from("Speed must be a positive number")),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
 /// Terminal specific cli options which can be passed to new windows via IPC.
-#[derive(Serialize, Deserialize, Args, Default, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Args, Default, Debug, Clone, PartialEq)]
 pub struct TerminalOptions {
     /// Start the shell in the specified working directory.
     #[clap(long, value_hint = ValueHint::FilePath)]
@@ -159,8 +173,57 @@ pub struct TerminalOptions {
     #[clap(long)]
     pub hold: bool,
 
+    /// Connect to a serial port instead of running a shell.
+    #[cfg(not(windows))]
+    #[clap(long, value_hint = ValueHint::FilePath, group = "device")]
+    pub serial: Option<PathBuf>,
+
+    /// Serial line speed [default: 115200].
+    #[cfg(not(windows))]
+    #[clap(long, requires = "serial")]
+    pub baud_rate: Option<u32>,
+
+    /// Serial parity [default: none].
+    #[cfg(not(windows))]
+    #[clap(long, requires = "serial", value_enum)]
+    pub parity: Option<Parity>,
+
+    /// Serial flow control [default: none].
+    #[cfg(not(windows))]
+    #[clap(long, requires = "serial", value_enum)]
+    pub flow_control: Option<FlowControl>,
+
+    /// Connect to an inherited file descriptor instead of running a shell.
+    #[cfg(not(windows))]
+    #[clap(long, group = "device")]
+    pub device_fd: Option<i32>,
+
+    /// Connect to a Unix socket instead of running a shell.
+    #[cfg(not(windows))]
+    #[clap(long, value_hint = ValueHint::FilePath, group = "device")]
+    pub device_socket: Option<PathBuf>,
+
+    /// Replay an asciicast recording instead of running a shell.
+    #[cfg(not(windows))]
+    #[clap(long, value_hint = ValueHint::FilePath, group = "device")]
+    pub replay: Option<PathBuf>,
+
+    /// Replay speed multiplier [default: 1].
+    #[cfg(not(windows))]
+    #[clap(long, requires = "replay", value_parser = parse_replay_speed)]
+    pub replay_speed: Option<f64>,
+
+    /// Record the terminal output to an asciicast file.
+    #[clap(long, value_hint = ValueHint::FilePath)]
+    pub record: Option<PathBuf>,
+
+    /// Include keyboard input in the recording.
+    #[clap(long, requires = "record")]
+    pub record_input: bool,
+
     /// Command and args to execute (must be last argument).
     #[clap(short = 'e', long, allow_hyphen_values = true, num_args = 1..)]
+    #[cfg_attr(not(windows), clap(conflicts_with = "device"))]
     command: Vec<String>,
 }
 
@@ -183,10 +246,48 @@ impl TerminalOptions {
 
         if let Some(command) = self.command() {
             pty_config.shell = Some(command.into());
+            #[cfg(not(windows))]
+            {
+                pty_config.device = None;
+            }
+        }
+
+        #[cfg(not(windows))]
+        if let Some(device) = self.device(pty_config.device.take()) {
+            pty_config.device = Some(device);
         }
 
         pty_config.hold |= self.hold;
     }
+
+    /// Device override passed through the CLI.
+    ///
+    /// Serial line settings which aren't specified are taken from the configured serial port.
+    #[cfg(not(windows))]
+    fn device(&self, config_device: Option<Device>) -> Option<Device> {
+        let path = match (&self.serial, self.device_fd, &self.device_socket) {
+            (Some(path), ..) => path.clone(),
+            (_, Some(fd), _) => return Some(Device::Fd(fd)),
+            (.., Some(path)) => return Some(Device::Socket(path.clone())),
+            _ => return config_device,
+        };
+
+        let mut serial = match config_device {
+            Some(Device::Serial(serial)) => SerialOptions { path, ..serial },
+            _ => SerialOptions::new(path),
+        };
+        if let Some(baud_rate) = self.baud_rate {
+            serial.baud_rate = baud_rate;
+        }
+        if let Some(parity) = self.parity {
+            serial.parity = parity.into();
+        }
+        if let Some(flow_control) = self.flow_control {
+            serial.flow_control = flow_control.into();
+        }
+
+        Some(Device::Serial(serial))
+    }
 }
 
 impl From<TerminalOptions> for PtyOptions {
@@ -194,6 +295,8 @@ impl From<TerminalOptions> for PtyOptions {
         PtyOptions {
             working_directory: options.working_directory.take(),
             shell: options.command().map(Into::into),
+            #[cfg(not(windows))]
+            device: options.device(None),
             hold: options.hold,
         }
     }
@@ -246,10 +349,10 @@ pub struct MessageOptions {
 
 /// Available socket messages.
 #[cfg(unix)]
-#[derive(Subcommand, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Subcommand, Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum SocketMessage {
     /// Create a new window in the same Alacritty process.
-    CreateWindow(WindowOptions),
+    CreateWindow(Box<WindowOptions>),
 
     /// Update the Alacritty configuration.
     Config(IpcConfig),
@@ -280,7 +383,7 @@ pub struct MigrateOptions {
 }
 
 /// Subset of options that we pass to 'create-window' IPC subcommand.
-#[derive(Serialize, Deserialize, Args, Default, Clone, Debug, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Args, Default, Clone, Debug, PartialEq)]
 pub struct WindowOptions {
     /// Terminal options which can be passed via IPC.
     #[clap(flatten)]
@@ -379,6 +482,7 @@ impl ParsedOptions {
         // Override cloned config.
         let mut config = (*config).clone();
         self.override_config(&mut config);
+        config.scrolling.validate_history();
 
         Rc::new(config)
     }
@@ -446,6 +550,32 @@ mod tests {
         assert!(config.window.dynamic_title);
     }
 
+    #[cfg(not(windows))]
+    #[test]
+    fn serial_device_override() {
+        let mut config = UiConfig::default();
+        config.device.serial = Some(PathBuf::from("/dev/ttyS0"));
+        config.device.baud_rate = 9600;
+        config.device.parity = Parity::Even;
+        let mut pty_config = config.pty_config();
+
+        let options =
+            Options::parse_from(["alacritty", "--serial", "/dev/ttyUSB0", "--parity", "odd"]);
+        options.window_options.terminal_options.override_pty_config(&mut pty_config);
+
+        let mut serial = SerialOptions::new(PathBuf::from("/dev/ttyUSB0"));
+        serial.baud_rate = 9600;
+        serial.parity = Parity::Odd.into();
+        assert_eq!(pty_config.device, Some(Device::Serial(serial)));
+
+        let options = Options::parse_from(["alacritty", "-e", "sh"]);
+        options.window_options.terminal_options.override_pty_config(&mut pty_config);
+        assert_eq!(pty_config.device, None);
+
+        let result = Options::try_parse_from(["alacritty", "--serial", "/dev/ttyS0", "-e", "sh"]);
+        assert!(result.is_err());
+    }
+
     #[test]
     fn valid_option_as_value() {
         // Test with a single field.
@@ -503,6 +633,20 @@ mod tests {
         assert!(class.is_err());
     }
 
+    #[test]
+    #[cfg(not(windows))]
+    fn parse_valid_replay_speed() {
+        assert_eq!(parse_replay_speed("2.5"), Ok(2.5));
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn parse_invalid_replay_speed() {
+        for input in ["0", "-1", "NaN", "inf", "fast"] {
+            assert!(parse_replay_speed(input).is_err(), "{input} should be rejected");
+        }
+    }
+
     #[test]
     fn valid_decimal() {
         let value = parse_hex_or_decimal("10485773");
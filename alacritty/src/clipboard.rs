@@ -1,3 +1,6 @@
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+
 use log::{debug, warn};
 use raw_window_handle::RawDisplayHandle;
 
@@ -13,9 +16,43 @@ use copypasta::x11_clipboard::{Primary as X11SelectionClipboard, X11ClipboardCon
 use copypasta::ClipboardContext;
 use copypasta::ClipboardProvider;
 
+#[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
+use crate::clipboard::x11::X11RichTextClipboard;
+
+#[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
+mod x11;
+
+/// Maximum number of entries in the clipboard history.
+const MAX_HISTORY: usize = 50;
+
+/// Text with an HTML representation.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RichText {
+    pub text: String,
+    pub html: String,
+}
+
+/// Clipboard which can store HTML alongside plain text.
+///
+/// This is only implemented for X11. The Wayland, macOS and Windows clipboards are accessed
+/// through copypasta, which only supports plain text, so rich text copies store the plain text
+/// there.
+trait RichTextProvider {
+    fn store(&mut self, ty: ClipboardType, rich_text: RichText) -> Result<(), Box<dyn Error>>;
+}
+
 pub struct Clipboard {
     clipboard: Box<dyn ClipboardProvider>,
     selection: Option<Box<dyn ClipboardProvider>>,
+
+    /// Provider for HTML, if the platform clipboard supports multiple formats.
+    rich_text: Option<Box<dyn RichTextProvider>>,
+
+    /// Copied text, from newest to oldest.
+    history: VecDeque<String>,
+
+    /// Vi mode registers `a` to `z`.
+    registers: HashMap<char, String>,
 }
 
 impl Clipboard {
@@ -25,7 +62,7 @@ impl Clipboard {
             RawDisplayHandle::Wayland(display) => {
                 let (selection, clipboard) =
                     wayland_clipboard::create_clipboards_from_external(display.display);
-                Self { clipboard: Box::new(clipboard), selection: Some(Box::new(selection)) }
+                Self::with_providers(Box::new(clipboard), Some(Box::new(selection)))
             },
             _ => Self::default(),
         }
@@ -35,19 +72,35 @@ impl Clipboard {
     /// feature.
     #[cfg(any(test, not(any(feature = "x11", target_os = "macos", windows))))]
     pub fn new_nop() -> Self {
-        Self { clipboard: Box::new(NopClipboardContext::new().unwrap()), selection: None }
+        Self::with_providers(Box::new(NopClipboardContext::new().unwrap()), None)
+    }
+
+    fn with_providers(
+        clipboard: Box<dyn ClipboardProvider>,
+        selection: Option<Box<dyn ClipboardProvider>>,
+    ) -> Self {
+        Self {
+            clipboard,
+            selection,
+            rich_text: None,
+            history: Default::default(),
+            registers: Default::default(),
+        }
     }
 }
 
 impl Default for Clipboard {
     fn default() -> Self {
         #[cfg(any(target_os = "macos", windows))]
-        return Self { clipboard: Box::new(ClipboardContext::new().unwrap()), selection: None };
+        return Self::with_providers(Box::new(ClipboardContext::new().unwrap()), None);
 
         #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
         return Self {
-            clipboard: Box::new(ClipboardContext::new().unwrap()),
-            selection: Some(Box::new(X11ClipboardContext::<X11SelectionClipboard>::new().unwrap())),
+            rich_text: Some(Box::<X11RichTextClipboard>::default()),
+            ..Self::with_providers(
+                Box::new(ClipboardContext::new().unwrap()),
+                Some(Box::new(X11ClipboardContext::<X11SelectionClipboard>::new().unwrap())),
+            )
         };
 
         #[cfg(not(any(feature = "x11", target_os = "macos", windows)))]
@@ -68,6 +121,76 @@ impl Clipboard {
         });
     }
 
+    /// Store text in the clipboard and record it in the clipboard history.
+    pub fn copy(&mut self, ty: ClipboardType, text: String) {
+        self.record(&text);
+        self.store(ty, text);
+    }
+
+    /// Store text with its HTML representation and record it in the clipboard history.
+    ///
+    /// Only the plain text is stored if the clipboard does not support multiple formats.
+    pub fn copy_rich_text(&mut self, ty: ClipboardType, rich_text: RichText) {
+        let provider = match &mut self.rich_text {
+            Some(provider) => provider,
+            None => return self.copy(ty, rich_text.text),
+        };
+
+        let text = rich_text.text.clone();
+        match provider.store(ty, rich_text) {
+            Ok(()) => self.record(&text),
+            Err(err) => {
+                warn!("Unable to store rich text in clipboard: {}", err);
+                self.copy(ty, text);
+            },
+        }
+    }
+
+    /// Add text to the clipboard history.
+    fn record(&mut self, text: &str) {
+        if !text.is_empty() {
+            self.history.retain(|entry| entry != text);
+            self.history.push_front(text.into());
+            self.history.truncate(MAX_HISTORY);
+        }
+    }
+
+    /// Copied text, from newest to oldest.
+    #[inline]
+    pub fn history(&self) -> &VecDeque<String> {
+        &self.history
+    }
+
+    /// Store text in a register.
+    ///
+    /// Uppercase register names append the text to the lowercase register instead of replacing
+    /// its content, like in vi.
+    pub fn store_register(&mut self, name: char, text: String) {
+        let register = self.registers.entry(name.to_ascii_lowercase()).or_default();
+        if name.is_ascii_uppercase() {
+            register.push_str(&text);
+        } else {
+            *register = text;
+        }
+    }
+
+    /// Text stored in a register.
+    pub fn register(&self, name: char) -> Option<&str> {
+        self.registers.get(&name.to_ascii_lowercase()).map(String::as_str)
+    }
+
+    /// All non-empty registers, ordered by their name.
+    pub fn registers(&self) -> Vec<(char, &str)> {
+        let mut registers: Vec<_> = self
+            .registers
+            .iter()
+            .filter(|(_, text)| !text.is_empty())
+            .map(|(name, text)| (*name, text.as_str()))
+            .collect();
+        registers.sort_unstable_by_key(|(name, _)| *name);
+        registers
+    }
+
     pub fn load(&mut self, ty: ClipboardType) -> String {
         let clipboard = match (ty, &mut self.selection) {
             (ClipboardType::Selection, Some(provider)) => provider,
@@ -83,3 +206,37 @@ impl Clipboard {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history() {
+        let mut clipboard = Clipboard::new_nop();
+        for i in 0..=MAX_HISTORY {
+            clipboard.copy(ClipboardType::Clipboard, i.to_string());
+        }
+        assert_eq!(clipboard.history().len(), MAX_HISTORY);
+        assert_eq!(clipboard.history().back().unwrap(), "1");
+
+        // Copying an entry again moves it to the front.
+        clipboard.copy(ClipboardType::Selection, "3".into());
+        clipboard.copy(ClipboardType::Clipboard, String::new());
+        assert_eq!(clipboard.history().len(), MAX_HISTORY);
+        assert_eq!(clipboard.history().front().unwrap(), "3");
+    }
+
+    #[test]
+    fn registers() {
+        let mut clipboard = Clipboard::new_nop();
+        clipboard.store_register('b', "bar".into());
+        clipboard.store_register('a', "foo".into());
+        clipboard.store_register('A', "bar".into());
+        clipboard.store_register('c', String::new());
+
+        assert_eq!(clipboard.register('a'), Some("foobar"));
+        assert_eq!(clipboard.register('z'), None);
+        assert_eq!(clipboard.registers(), [('a', "foobar"), ('b', "bar")]);
+    }
+}
@@ -0,0 +1,225 @@
+//! X11 selection owner offering HTML alongside plain text.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use log::debug;
+use x11rb::connection::{Connection, RequestConnection};
+use x11rb::protocol::xproto::{
+    Atom, AtomEnum, ConnectionExt as _, CreateWindowAux, EventMask, PropMode, SelectionNotifyEvent,
+    SelectionRequestEvent, Window, WindowClass, SELECTION_NOTIFY_EVENT,
+};
+use x11rb::protocol::Event;
+use x11rb::rust_connection::RustConnection;
+use x11rb::wrapper::ConnectionExt as _;
+use x11rb::{COPY_DEPTH_FROM_PARENT, COPY_FROM_PARENT, CURRENT_TIME, NONE};
+
+use alacritty_terminal::term::ClipboardType;
+use alacritty_terminal::thread;
+
+use crate::clipboard::{RichText, RichTextProvider};
+
+/// Size of the `ChangeProperty` request without its data.
+const PROPERTY_REQUEST_SIZE: usize = 24;
+
+x11rb::atom_manager! {
+    Atoms: AtomsCookie {
+        CLIPBOARD,
+        TARGETS,
+        UTF8_STRING,
+        TEXT_HTML: b"text/html",
+        TEXT_PLAIN_UTF8: b"text/plain;charset=utf-8",
+        TEXT_PLAIN: b"text/plain",
+    }
+}
+
+/// X11 clipboard storing HTML alongside plain text.
+///
+/// A single connection owns the selections for all copies. It is opened on the first copy and
+/// served by its own thread, which keeps running while other clients own the selections.
+#[derive(Default)]
+pub struct X11RichTextClipboard {
+    owner: Option<Arc<SelectionOwner>>,
+}
+
+impl RichTextProvider for X11RichTextClipboard {
+    fn store(&mut self, ty: ClipboardType, rich_text: RichText) -> Result<(), Box<dyn Error>> {
+        let owner = match &self.owner {
+            Some(owner) if !owner.stopped.load(Ordering::Relaxed) => owner.clone(),
+            _ => {
+                let owner = Arc::new(SelectionOwner::new()?);
+                self.owner = Some(owner.clone());
+
+                let server = owner.clone();
+                thread::spawn_named("clipboard owner", move || {
+                    if let Err(err) = server.serve() {
+                        debug!("Clipboard owner stopped: {}", err);
+                    }
+                    server.stopped.store(true, Ordering::Relaxed);
+                });
+
+                owner
+            },
+        };
+
+        owner.own(ty, rich_text)
+    }
+}
+
+/// Owner of the X11 selections, serving their content until other clients take ownership.
+struct SelectionOwner {
+    connection: RustConnection,
+    window: Window,
+    atoms: Atoms,
+
+    /// Content of each selection while it's owned by this window.
+    content: Mutex<HashMap<Atom, RichText>>,
+
+    /// Whether the thread answering selection requests has stopped.
+    stopped: AtomicBool,
+}
+
+impl SelectionOwner {
+    /// Open the connection and create the window owning the selections.
+    fn new() -> Result<Self, Box<dyn Error>> {
+        let (connection, screen) = x11rb::connect(None)?;
+        let root = connection.setup().roots[screen].root;
+        let atoms = Atoms::new(&connection)?.reply()?;
+
+        let window = connection.generate_id()?;
+        connection
+            .create_window(
+                COPY_DEPTH_FROM_PARENT,
+                window,
+                root,
+                0,
+                0,
+                1,
+                1,
+                0,
+                WindowClass::INPUT_OUTPUT,
+                COPY_FROM_PARENT,
+                &CreateWindowAux::new(),
+            )?
+            .check()?;
+
+        Ok(Self {
+            connection,
+            window,
+            atoms,
+            content: Default::default(),
+            stopped: Default::default(),
+        })
+    }
+
+    /// Take ownership of a selection, replacing its content.
+    fn own(&self, ty: ClipboardType, rich_text: RichText) -> Result<(), Box<dyn Error>> {
+        let selection = match ty {
+            ClipboardType::Clipboard => self.atoms.CLIPBOARD,
+            ClipboardType::Selection => AtomEnum::PRIMARY.into(),
+        };
+
+        // Hold the lock until ownership is settled, so a pending `SelectionClear` from a previous
+        // owner change cannot drop the new content.
+        let mut content = self.content.lock().unwrap();
+        self.connection.set_selection_owner(self.window, selection, CURRENT_TIME)?.check()?;
+        if !self.owns(selection)? {
+            content.remove(&selection);
+            return Err("selection is owned by another client".into());
+        }
+        content.insert(selection, rich_text);
+
+        Ok(())
+    }
+
+    /// Check if the window currently owns a selection.
+    fn owns(&self, selection: Atom) -> Result<bool, Box<dyn Error>> {
+        Ok(self.connection.get_selection_owner(selection)?.reply()?.owner == self.window)
+    }
+
+    /// Answer selection requests until the connection is closed.
+    fn serve(&self) -> Result<(), Box<dyn Error>> {
+        loop {
+            match self.connection.wait_for_event()? {
+                Event::SelectionRequest(request) => self.respond(&request)?,
+                // Ownership might have been taken back by a newer copy already.
+                Event::SelectionClear(clear) if clear.owner == self.window => {
+                    let mut content = self.content.lock().unwrap();
+                    if !self.owns(clear.selection)? {
+                        content.remove(&clear.selection);
+                    }
+                },
+                _ => (),
+            }
+        }
+    }
+
+    /// Store the requested format in the requestor's property.
+    fn respond(&self, request: &SelectionRequestEvent) -> Result<(), Box<dyn Error>> {
+        let atoms = &self.atoms;
+        let plain_text =
+            [atoms.UTF8_STRING, atoms.TEXT_PLAIN_UTF8, atoms.TEXT_PLAIN, AtomEnum::STRING.into()];
+
+        // Obsolete clients do not specify a property for the response.
+        let mut property = request.property;
+        if property == NONE {
+            property = request.target;
+        }
+
+        let content = self.content.lock().unwrap();
+        let rich_text = content.get(&request.selection);
+        let content = if request.target == atoms.TEXT_HTML {
+            rich_text.map(|rich_text| &rich_text.html)
+        } else if plain_text.contains(&request.target) {
+            rich_text.map(|rich_text| &rich_text.text)
+        } else {
+            None
+        };
+
+        let max_size = self.connection.maximum_request_bytes() - PROPERTY_REQUEST_SIZE;
+        let requestor = request.requestor;
+        match content {
+            _ if request.target == atoms.TARGETS && rich_text.is_some() => {
+                let targets: Vec<Atom> =
+                    [atoms.TARGETS, atoms.TEXT_HTML].into_iter().chain(plain_text).collect();
+                let ty = AtomEnum::ATOM;
+                self.connection.change_property32(
+                    PropMode::REPLACE,
+                    requestor,
+                    property,
+                    ty,
+                    &targets,
+                )?;
+            },
+            // Incremental transfers are not supported, so larger content is refused.
+            Some(content) if content.len() <= max_size => {
+                let ty = request.target;
+                let content = content.as_bytes();
+                self.connection.change_property8(
+                    PropMode::REPLACE,
+                    requestor,
+                    property,
+                    ty,
+                    content,
+                )?;
+            },
+            _ => property = NONE,
+        }
+
+        let notify = SelectionNotifyEvent {
+            response_type: SELECTION_NOTIFY_EVENT,
+            sequence: 0,
+            time: request.time,
+            requestor,
+            selection: request.selection,
+            target: request.target,
+            property,
+        };
+        self.connection.send_event(false, requestor, EventMask::NO_EVENT, notify)?;
+        self.connection.flush()?;
+
+        Ok(())
+    }
+}
@@ -120,12 +120,18 @@ pub enum Action {
     /// Store current selection into clipboard.
     Copy,
 
+    /// Store current selection into clipboard as HTML, including its colors.
+    CopyRichText,
+
     /// Store current selection into selection buffer.
     CopySelection,
 
     /// Paste contents of selection buffer.
     PasteSelection,
 
+    /// Pick an entry of the clipboard history or a register to paste.
+    ClipboardHistory,
+
     /// Increase font size.
     IncreaseFontSize,
 
@@ -177,6 +183,12 @@ pub enum Action {
     /// Clear warning and error notices.
     ClearLogNotice,
 
+    /// Start or stop recording the terminal output.
+    ToggleRecording,
+
+    /// Restart the exited command of a window kept open by `hold`.
+    RestartCommand,
+
     /// Spawn a new instance of Alacritty.
     SpawnNewInstance,
 
@@ -311,6 +323,10 @@ pub enum ViAction {
     SearchStart,
     /// Jump to the next end of a match to the right of the origin.
     SearchEnd,
+    /// Search forward for the word below the vi mode cursor.
+    SearchWordForward,
+    /// Search backward for the word below the vi mode cursor.
+    SearchWordBackward,
     /// Launch the URL below the vi mode cursor.
     Open,
     /// Centers the screen around the vi mode cursor.
@@ -327,6 +343,10 @@ pub enum ViAction {
     InlineSearchNext,
     /// Jump to the previous inline search match.
     InlineSearchPrevious,
+    /// Go back to the origin of the previous jump.
+    JumpBackward,
+    /// Go forward to the position before jumping backward.
+    JumpForward,
 }
 
 /// Search mode specific actions.
@@ -349,6 +369,14 @@ pub enum SearchAction {
     SearchHistoryPrevious,
     /// Go to the next regex in the search history.
     SearchHistoryNext,
+    /// Recall search history entries matching the search regex as fuzzy filter.
+    SearchHistoryFilter,
+    /// Toggle between matching the search text literally and as a regex.
+    SearchToggleLiteral,
+    /// Cycle between smart, sensitive and insensitive case matching.
+    SearchToggleCase,
+    /// Toggle matching only whole words.
+    SearchToggleWholeWord,
 }
 
 /// Mouse binding specific actions.
@@ -479,6 +507,8 @@ pub fn default_key_bindings() -> Vec<KeyBinding> {
         "v",      ModifiersState::ALT,      +BindingMode::VI, ~BindingMode::SEARCH; ViAction::ToggleSemanticSelection;
         "n",                                +BindingMode::VI, ~BindingMode::SEARCH; ViAction::SearchNext;
         "n",      ModifiersState::SHIFT,    +BindingMode::VI, ~BindingMode::SEARCH; ViAction::SearchPrevious;
+        "*",      ModifiersState::SHIFT,    +BindingMode::VI, ~BindingMode::SEARCH; ViAction::SearchWordForward;
+        "#",      ModifiersState::SHIFT,    +BindingMode::VI, ~BindingMode::SEARCH; ViAction::SearchWordBackward;
         Enter,                              +BindingMode::VI, ~BindingMode::SEARCH; ViAction::Open;
         "z",                                +BindingMode::VI, ~BindingMode::SEARCH; ViAction::CenterAroundViCursor;
         "f",                                +BindingMode::VI, ~BindingMode::SEARCH; ViAction::InlineSearchForward;
@@ -487,6 +517,8 @@ pub fn default_key_bindings() -> Vec<KeyBinding> {
         "t",      ModifiersState::SHIFT,    +BindingMode::VI, ~BindingMode::SEARCH; ViAction::InlineSearchBackwardShort;
         ";",                                +BindingMode::VI, ~BindingMode::SEARCH; ViAction::InlineSearchNext;
         ",",                                +BindingMode::VI, ~BindingMode::SEARCH; ViAction::InlineSearchPrevious;
+        "o",      ModifiersState::CONTROL,  +BindingMode::VI, ~BindingMode::SEARCH; ViAction::JumpBackward;
+        "i",      ModifiersState::CONTROL,  +BindingMode::VI, ~BindingMode::SEARCH; ViAction::JumpForward;
         "k",                                +BindingMode::VI, ~BindingMode::SEARCH; ViMotion::Up;
         "j",                                +BindingMode::VI, ~BindingMode::SEARCH; ViMotion::Down;
         "h",                                +BindingMode::VI, ~BindingMode::SEARCH; ViMotion::Left;
@@ -520,6 +552,10 @@ pub fn default_key_bindings() -> Vec<KeyBinding> {
         "n",      ModifiersState::CONTROL,  +BindingMode::SEARCH; SearchAction::SearchHistoryNext;
         ArrowUp,                            +BindingMode::SEARCH; SearchAction::SearchHistoryPrevious;
         ArrowDown,                          +BindingMode::SEARCH; SearchAction::SearchHistoryNext;
+        "r",      ModifiersState::CONTROL,  +BindingMode::SEARCH; SearchAction::SearchHistoryFilter;
+        "r",      ModifiersState::ALT,      +BindingMode::SEARCH; SearchAction::SearchToggleLiteral;
+        "c",      ModifiersState::ALT,      +BindingMode::SEARCH; SearchAction::SearchToggleCase;
+        "w",      ModifiersState::ALT,      +BindingMode::SEARCH; SearchAction::SearchToggleWholeWord;
         Enter,                              +BindingMode::SEARCH, ~BindingMode::VI; SearchAction::SearchFocusNext;
         Enter, ModifiersState::SHIFT,       +BindingMode::SEARCH, ~BindingMode::VI; SearchAction::SearchFocusPrevious;
     );
@@ -0,0 +1,96 @@
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use alacritty_config_derive::ConfigDeserialize;
+#[cfg(not(windows))]
+use alacritty_terminal::tty::device::{self, SerialOptions};
+
+/// Device connected instead of a shell.
+#[derive(ConfigDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Device {
+    /// Path of a serial port.
+    pub serial: Option<PathBuf>,
+
+    /// Serial line speed.
+    pub baud_rate: u32,
+
+    /// Serial parity.
+    pub parity: Parity,
+
+    /// Serial flow control.
+    pub flow_control: FlowControl,
+
+    /// Path of a Unix stream socket.
+    pub socket: Option<PathBuf>,
+}
+
+impl Default for Device {
+    fn default() -> Self {
+        Self {
+            baud_rate: 115200,
+            serial: Default::default(),
+            parity: Default::default(),
+            flow_control: Default::default(),
+            socket: Default::default(),
+        }
+    }
+}
+
+#[cfg(not(windows))]
+impl Device {
+    /// Device which should be used instead of the shell.
+    ///
+    /// The serial port takes precedence over the socket.
+    pub fn device(&self) -> Option<device::Device> {
+        if let Some(path) = &self.serial {
+            Some(device::Device::Serial(SerialOptions {
+                path: path.clone(),
+                baud_rate: self.baud_rate,
+                parity: self.parity.into(),
+                flow_control: self.flow_control.into(),
+            }))
+        } else {
+            self.socket.clone().map(device::Device::Socket)
+        }
+    }
+}
+
+#[derive(ConfigDeserialize, ValueEnum, Serialize, Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Parity {
+    #[default]
+    None,
+    Odd,
+    Even,
+}
+
+#[cfg(not(windows))]
+impl From<Parity> for device::Parity {
+    fn from(parity: Parity) -> Self {
+        match parity {
+            Parity::None => Self::None,
+            Parity::Odd => Self::Odd,
+            Parity::Even => Self::Even,
+        }
+    }
+}
+
+#[derive(ConfigDeserialize, ValueEnum, Serialize, Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FlowControl {
+    #[default]
+    None,
+    Software,
+    Hardware,
+}
+
+#[cfg(not(windows))]
+impl From<FlowControl> for device::FlowControl {
+    fn from(flow_control: FlowControl) -> Self {
+        match flow_control {
+            FlowControl::None => Self::None,
+            FlowControl::Software => Self::Software,
+            FlowControl::Hardware => Self::Hardware,
+        }
+    }
+}
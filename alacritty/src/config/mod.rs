@@ -14,11 +14,14 @@ pub mod bell;
 pub mod color;
 pub mod cursor;
 pub mod debug;
+pub mod device;
 pub mod font;
 pub mod monitor;
 pub mod scrolling;
+pub mod search;
 pub mod selection;
 pub mod serde_utils;
+pub mod session;
 pub mod terminal;
 pub mod ui_config;
 pub mod window;
@@ -171,6 +174,12 @@ fn after_loading(config: &mut UiConfig, options: &mut Options) {
     // Override config with CLI options.
     options.override_config(config);
 
+    // Drop hints with invalid command placeholders.
+    config.hints.validate_commands();
+
+    // Reset history exceeding the in-memory limit.
+    config.scrolling.validate_history();
+
     // Create key bindings for regex hints.
     config.generate_hint_bindings();
 }
@@ -1,37 +1,123 @@
-use serde::de::Error as SerdeError;
+use std::fmt;
+use std::time::Duration;
+
+use serde::de::{self, Unexpected, Visitor};
 use serde::{Deserialize, Deserializer};
 
+use log::error;
+
 use alacritty_config_derive::{ConfigDeserialize, SerdeReplace};
+use alacritty_terminal::grid::SpillConfig;
+
+use crate::logging::LOG_TARGET_CONFIG;
 
 /// Maximum scrollback amount configurable.
 pub const MAX_SCROLLBACK_LINES: u32 = 100_000;
 
+/// Bytes per mebibyte.
+const MIB: usize = 1024 * 1024;
+
 /// Struct for scrolling related settings.
 #[derive(ConfigDeserialize, Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Scrolling {
     pub multiplier: u8,
 
+    /// History kept in memory before spilling to disk, in MiB.
+    pub memory_budget: u32,
+
+    /// History stored on disk, in MiB.
+    ///
+    /// Only runs of identical cells are compressed, so lines full of text are not much smaller
+    /// than in memory.
+    ///
+    /// Spilling history to disk is disabled when this is `0`.
+    pub disk_budget: u32,
+
+    /// Overlay scrollbar showing the viewport position in history.
+    pub scrollbar: ScrollbarConfig,
+
     history: ScrollingHistory,
 }
 
 impl Default for Scrolling {
     fn default() -> Self {
-        Self { multiplier: 3, history: Default::default() }
+        Self {
+            multiplier: 3,
+            memory_budget: 128,
+            disk_budget: 0,
+            scrollbar: Default::default(),
+            history: Default::default(),
+        }
     }
 }
 
 impl Scrolling {
     pub fn history(self) -> u32 {
-        self.history.0
+        match self.history {
+            ScrollingHistory::Lines(lines) => lines,
+            ScrollingHistory::Unlimited => u32::MAX,
+        }
+    }
+
+    /// Reset the history to its default if it exceeds the in-memory limit without a disk budget.
+    pub fn validate_history(&mut self) {
+        if self.disk_budget > 0 {
+            return;
+        }
+
+        let err = match self.history {
+            ScrollingHistory::Lines(lines) if lines > MAX_SCROLLBACK_LINES => format!(
+                "exceeded maximum scrolling history without disk_budget \
+                 ({lines}/{MAX_SCROLLBACK_LINES})"
+            ),
+            ScrollingHistory::Unlimited => String::from("unlimited history requires disk_budget"),
+            ScrollingHistory::Lines(_) => return,
+        };
+
+        error!(target: LOG_TARGET_CONFIG, "Config error: scrolling.history: {}", err);
+        self.history = ScrollingHistory::default();
+    }
+
+    /// Budgets for moving old history out of memory.
+    pub fn spill(self) -> Option<SpillConfig> {
+        (self.disk_budget > 0).then(|| SpillConfig {
+            memory_budget: self.memory_budget as usize * MIB,
+            disk_budget: self.disk_budget as usize * MIB,
+        })
+    }
+}
+
+#[derive(ConfigDeserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ScrollbarConfig {
+    /// Draw the scrollbar while there is history to scroll through.
+    pub enabled: bool,
+
+    /// Inactivity in milliseconds after which the scrollbar is hidden.
+    hide_timeout: u64,
+}
+
+impl Default for ScrollbarConfig {
+    fn default() -> Self {
+        Self { enabled: false, hide_timeout: 1500 }
+    }
+}
+
+impl ScrollbarConfig {
+    /// Inactivity after which the scrollbar is hidden, if it should be hidden at all.
+    pub fn hide_timeout(&self) -> Option<Duration> {
+        (self.hide_timeout > 0).then(|| Duration::from_millis(self.hide_timeout))
     }
 }
 
 #[derive(SerdeReplace, Copy, Clone, Debug, PartialEq, Eq)]
-struct ScrollingHistory(u32);
+enum ScrollingHistory {
+    Lines(u32),
+    Unlimited,
+}
 
 impl Default for ScrollingHistory {
     fn default() -> Self {
-        Self(10_000)
+        Self::Lines(10_000)
     }
 }
 
@@ -40,14 +126,83 @@ impl<'de> Deserialize<'de> for ScrollingHistory {
     where
         D: Deserializer<'de>,
     {
-        let lines = u32::deserialize(deserializer)?;
-
-        if lines > MAX_SCROLLBACK_LINES {
-            Err(SerdeError::custom(format!(
-                "exceeded maximum scrolling history ({lines}/{MAX_SCROLLBACK_LINES})"
-            )))
-        } else {
-            Ok(Self(lines))
+        struct HistoryVisitor;
+
+        impl Visitor<'_> for HistoryVisitor {
+            type Value = ScrollingHistory;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a number of lines or \"unlimited\"")
+            }
+
+            fn visit_u64<E: de::Error>(self, lines: u64) -> Result<Self::Value, E> {
+                let lines = u32::try_from(lines)
+                    .map_err(|_| E::invalid_value(Unexpected::Unsigned(lines), &self))?;
+                Ok(ScrollingHistory::Lines(lines))
+            }
+
+            fn visit_i64<E: de::Error>(self, lines: i64) -> Result<Self::Value, E> {
+                let lines = u64::try_from(lines)
+                    .map_err(|_| E::invalid_value(Unexpected::Signed(lines), &self))?;
+                self.visit_u64(lines)
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                if value.eq_ignore_ascii_case("unlimited") {
+                    Ok(ScrollingHistory::Unlimited)
+                } else {
+                    Err(E::invalid_value(Unexpected::Str(value), &self))
+                }
+            }
         }
+
+        deserializer.deserialize_any(HistoryVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use toml::Value;
+
+    fn scrolling(config: &str) -> Result<Scrolling, toml::de::Error> {
+        Scrolling::deserialize(toml::from_str::<Value>(config)?)
+    }
+
+    #[test]
+    fn history_limited_without_disk() {
+        let mut config = scrolling("history = 100000").unwrap();
+        config.validate_history();
+        assert_eq!(config.history(), MAX_SCROLLBACK_LINES);
+        assert_eq!(config.spill(), None);
+
+        let mut config = scrolling("history = 250000").unwrap();
+        config.validate_history();
+        assert_eq!(config.history(), 10_000);
+
+        let mut config = scrolling("history = \"unlimited\"").unwrap();
+        config.validate_history();
+        assert_eq!(config.history(), 10_000);
+    }
+
+    #[test]
+    fn history_with_disk() {
+        let mut config = scrolling("history = 250000\ndisk_budget = 512").unwrap();
+        config.validate_history();
+        assert_eq!(config.history(), 250_000);
+        assert_eq!(
+            config.spill(),
+            Some(SpillConfig { memory_budget: 128 * MIB, disk_budget: 512 * MIB })
+        );
+
+        let config = scrolling("history = \"unlimited\"\ndisk_budget = 1").unwrap();
+        assert_eq!(config.history(), u32::MAX);
+    }
+
+    #[test]
+    fn invalid_history() {
+        assert!(ScrollingHistory::deserialize(Value::String("lots".into())).is_err());
+        assert!(ScrollingHistory::deserialize(Value::Integer(-1)).is_err());
     }
 }
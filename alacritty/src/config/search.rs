@@ -0,0 +1,17 @@
+use alacritty_config_derive::ConfigDeserialize;
+
+/// Terminal search configuration.
+#[derive(ConfigDeserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SearchConfig {
+    /// Maximum number of search terms stored in the history.
+    pub history_size: usize,
+
+    /// Save the search history to disk, sharing it between all Alacritty instances.
+    pub persist_history: bool,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self { history_size: 255, persist_history: true }
+    }
+}
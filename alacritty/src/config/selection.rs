@@ -1,10 +1,42 @@
-use alacritty_config_derive::ConfigDeserialize;
+use std::cmp::Reverse;
+
+use serde::de::Error as SerdeError;
+use serde::{Deserialize, Deserializer};
+
+use alacritty_config_derive::{ConfigDeserialize, SerdeReplace};
 use alacritty_terminal::term::SEMANTIC_ESCAPE_CHARS;
 
+use crate::config::ui_config::{LazyRegex, URL_REGEX};
+
+/// Regex used for the default file path rule, including an optional `:line:column` suffix.
+#[rustfmt::skip]
+const PATH_REGEX: &str = "(?:[\\w.~+@-]*/)+[\\w.+@-]*(?::[0-9]+){0,2}\
+                          |[\\w.+@-]+\\.\\w+:[0-9]+(?::[0-9]+)?";
+
+/// Regex used for the default UUID rule.
+const UUID_REGEX: &str = "(?-u:\\b)[0-9a-fA-F]{8}(?:-[0-9a-fA-F]{4}){3}-[0-9a-fA-F]{12}(?-u:\\b)";
+
+/// Regex used for the default IPv6 address rule.
+///
+/// Compressed addresses require at least three groups, to avoid matching paths like `a::b`.
+#[rustfmt::skip]
+const IPV6_REGEX: &str = "(?-u:\\b)(?:[0-9a-fA-F]{1,4}:){7}[0-9a-fA-F]{1,4}(?-u:\\b)\
+                          |(?-u:\\b)[0-9a-fA-F]{1,4}(?::[0-9a-fA-F]{1,4})+::\
+                           (?:[0-9a-fA-F]{1,4}(?::[0-9a-fA-F]{1,4})*(?-u:\\b))?\
+                          |(?-u:\\b)[0-9a-fA-F]{1,4}::[0-9a-fA-F]{1,4}(?::[0-9a-fA-F]{1,4})+\
+                           (?-u:\\b)";
+
+/// Names and regexes of the built-in smart selection rules.
+const DEFAULT_RULES: [(&str, &str); 4] =
+    [("url", URL_REGEX), ("path", PATH_REGEX), ("uuid", UUID_REGEX), ("ipv6", IPV6_REGEX)];
+
 #[derive(ConfigDeserialize, Clone, Debug, PartialEq, Eq)]
 pub struct Selection {
     pub semantic_escape_chars: String,
     pub save_to_clipboard: bool,
+
+    /// Regex rules tried at the click point before falling back to semantic selection.
+    smart_rules: SmartRules,
 }
 
 impl Default for Selection {
@@ -12,6 +44,139 @@ impl Default for Selection {
         Self {
             semantic_escape_chars: SEMANTIC_ESCAPE_CHARS.to_owned(),
             save_to_clipboard: Default::default(),
+            smart_rules: Default::default(),
         }
     }
 }
+
+impl Selection {
+    /// Enabled smart selection rules, from highest to lowest priority.
+    ///
+    /// Rules with the same priority are tried in the order they were configured in, after the
+    /// built-in rules.
+    pub fn smart_rules(&self) -> impl Iterator<Item = &SmartRule> {
+        let mut rules: Vec<_> = self.smart_rules.0.iter().filter(|rule| rule.enabled).collect();
+        rules.sort_by_key(|rule| Reverse(rule.priority));
+        rules.into_iter()
+    }
+}
+
+/// Built-in smart selection rules, updated by the configured rules.
+#[derive(SerdeReplace, Clone, Debug, PartialEq, Eq)]
+struct SmartRules(Vec<SmartRule>);
+
+impl Default for SmartRules {
+    fn default() -> Self {
+        let rules = DEFAULT_RULES.into_iter().map(|(name, regex)| SmartRule {
+            name: Some(name.into()),
+            regex: LazyRegex::new(regex),
+            enabled: true,
+            priority: 0,
+        });
+        Self(rules.collect())
+    }
+}
+
+impl<'de> Deserialize<'de> for SmartRules {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut rules = Self::default();
+
+        for config in Vec::<SmartRuleConfig>::deserialize(deserializer)? {
+            // Rules named like a built-in rule modify it, instead of adding a new rule.
+            let builtin = rules
+                .0
+                .iter_mut()
+                .find(|rule| rule.name.is_some() && rule.name.as_deref() == config.name.as_deref());
+
+            match builtin {
+                Some(rule) => {
+                    rule.regex = config.regex.unwrap_or_else(|| rule.regex.clone());
+                    rule.enabled = config.enabled.unwrap_or(rule.enabled);
+                    rule.priority = config.priority.unwrap_or(rule.priority);
+                },
+                None => {
+                    let regex = config.regex.ok_or_else(|| match &config.name {
+                        Some(name) => D::Error::custom(format!(
+                            "smart rule {name:?} requires a regex, since it's not a built-in rule"
+                        )),
+                        None => D::Error::missing_field("regex"),
+                    })?;
+
+                    rules.0.push(SmartRule {
+                        name: config.name,
+                        regex,
+                        enabled: config.enabled.unwrap_or(true),
+                        priority: config.priority.unwrap_or_default(),
+                    });
+                },
+            }
+        }
+
+        Ok(rules)
+    }
+}
+
+/// Smart selection rule, as written in the configuration file.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SmartRuleConfig {
+    name: Option<String>,
+    regex: Option<LazyRegex>,
+    enabled: Option<bool>,
+    priority: Option<i32>,
+}
+
+/// Regex rule for selecting text on double-click.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SmartRule {
+    /// Name used to refer to the rule in the configuration file.
+    name: Option<String>,
+
+    /// Regex for finding matches.
+    pub regex: LazyRegex,
+
+    /// Whether the rule is used.
+    enabled: bool,
+
+    /// Rules with higher priority are tried first.
+    priority: i32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn smart_rule_priority() {
+        let selection: Selection = toml::from_str(
+            r#"
+            smart_rules = [
+                { regex = "a" },
+                { regex = "b", priority = 1 },
+                { regex = "c", enabled = false, priority = 2 },
+                { name = "d", regex = "d" },
+                { name = "path", enabled = false },
+                { name = "uuid", priority = -1 },
+                { name = "ipv6", regex = "::1" },
+            ]
+            "#,
+        )
+        .unwrap();
+
+        let names: Vec<_> = selection.smart_rules().map(|rule| rule.name.as_deref()).collect();
+        assert_eq!(names, [None, Some("url"), Some("ipv6"), None, Some("d"), Some("uuid")]);
+
+        let regexes: Vec<_> = selection.smart_rules().map(|rule| rule.regex.clone()).collect();
+        assert_eq!(regexes[0], LazyRegex::new("b"));
+        assert_eq!(regexes[2], LazyRegex::new("::1"));
+    }
+
+    #[test]
+    fn smart_rule_without_regex() {
+        let rules: toml::Value = toml::from_str("rules = [{ name = \"a\" }]").unwrap();
+        assert!(SmartRules::deserialize(rules["rules"].clone()).is_err());
+    }
+}
@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+use alacritty_config_derive::ConfigDeserialize;
+
+/// Session persistence configuration.
+#[derive(ConfigDeserialize, Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub struct Session {
+    /// Save terminal content to disk when windows are closed.
+    pub persist: bool,
+
+    /// Restore saved terminal content on startup.
+    pub restore: bool,
+
+    /// Seconds between automatic saves, `0` only saves on exit.
+    save_interval: u64,
+}
+
+impl Session {
+    /// Interval between periodic session saves.
+    #[inline]
+    pub fn save_interval(&self) -> Option<Duration> {
+        if self.persist && self.save_interval > 0 {
+            Some(Duration::from_secs(self.save_interval))
+        } else {
+            None
+        }
+    }
+}
@@ -6,10 +6,19 @@ use alacritty_terminal::term::Osc52;
 
 use crate::config::ui_config::StringVisitor;
 
-#[derive(ConfigDeserialize, Default, Copy, Clone, Debug, PartialEq)]
+#[derive(ConfigDeserialize, Copy, Clone, Debug, PartialEq)]
 pub struct Terminal {
     /// OSC52 support mode.
     pub osc52: SerdeOsc52,
+
+    /// Allow IME input while a password is being entered.
+    pub password_ime: bool,
+}
+
+impl Default for Terminal {
+    fn default() -> Self {
+        Self { osc52: Default::default(), password_ime: true }
+    }
 }
 
 #[derive(SerdeReplace, Default, Copy, Clone, Debug, PartialEq)]
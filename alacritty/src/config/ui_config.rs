@@ -9,6 +9,9 @@ use alacritty_config::SerdeReplace;
 use alacritty_terminal::term::Config as TermConfig;
 use alacritty_terminal::tty::{Options as PtyOptions, Shell};
 use log::{error, warn};
+use regex_automata::meta::{BuildError as MetaBuildError, Regex as MetaRegex};
+use regex_automata::util::syntax::Config as SyntaxConfig;
+use regex_automata::PatternID;
 use serde::de::{Error as SerdeError, MapAccess, Visitor};
 use serde::{self, Deserialize, Deserializer};
 use unicode_width::UnicodeWidthChar;
@@ -25,17 +28,20 @@ use crate::config::bindings::{
 use crate::config::color::Colors;
 use crate::config::cursor::Cursor;
 use crate::config::debug::Debug;
+use crate::config::device::Device;
 use crate::config::font::Font;
 use crate::config::mouse::{Mouse, MouseBindings};
 use crate::config::scrolling::Scrolling;
+use crate::config::search::SearchConfig;
 use crate::config::selection::Selection;
+use crate::config::session::Session;
 use crate::config::terminal::Terminal;
 use crate::config::window::WindowConfig;
 use crate::config::LOG_TARGET_CONFIG;
 
 /// Regex used for the default URL hint.
 #[rustfmt::skip]
-const URL_REGEX: &str = "(ipfs:|ipns:|magnet:|mailto:|gemini://|gopher://|https://|http://|news:|file:|git://|ssh:|ftp://)\
+pub const URL_REGEX: &str = "(ipfs:|ipns:|magnet:|mailto:|gemini://|gopher://|https://|http://|news:|file:|git://|ssh:|ftp://)\
                          [^\u{0000}-\u{001F}\u{007F}-\u{009F}<>\"\\s{-}\\^⟨⟩`]+";
 
 #[derive(ConfigDeserialize, Clone, Debug, PartialEq)]
@@ -64,6 +70,12 @@ pub struct UiConfig {
     /// Debug options.
     pub debug: Debug,
 
+    /// Session persistence.
+    pub session: Session,
+
+    /// Terminal search.
+    pub search: SearchConfig,
+
     /// Send escape sequences using the alt key.
     #[config(removed = "It's now always set to 'true'. If you're on macOS use \
                         'window.option_as_alt' to alter behavior of Option")]
@@ -95,6 +107,9 @@ pub struct UiConfig {
     /// Path to a shell program to run on startup.
     pub shell: Option<Program>,
 
+    /// Device connected instead of a shell.
+    pub device: Device,
+
     /// Shell startup directory.
     pub working_directory: Option<PathBuf>,
 
@@ -137,10 +152,13 @@ impl Default for UiConfig {
             keyboard: Default::default(),
             terminal: Default::default(),
             import: Default::default(),
+            session: Default::default(),
+            search: Default::default(),
             cursor: Default::default(),
             window: Default::default(),
             colors: Default::default(),
             shell: Default::default(),
+            device: Default::default(),
             mouse: Default::default(),
             debug: Default::default(),
             hints: Default::default(),
@@ -157,6 +175,7 @@ impl UiConfig {
         TermConfig {
             semantic_escape_chars: self.selection.semantic_escape_chars.clone(),
             scrolling_history: self.scrolling.history() as usize,
+            scrolling_spill: self.scrolling.spill(),
             vi_mode_cursor_style: self.cursor.vi_mode_style(),
             default_cursor_style: self.cursor.style(),
             osc52: self.terminal.osc52.0,
@@ -167,7 +186,13 @@ impl UiConfig {
     /// Derive [`PtyOptions`] from the config.
     pub fn pty_config(&self) -> PtyOptions {
         let shell = self.shell.clone().map(Into::into);
-        PtyOptions { shell, working_directory: self.working_directory.clone(), hold: false }
+        PtyOptions {
+            shell,
+            #[cfg(not(windows))]
+            device: self.device.device(),
+            working_directory: self.working_directory.clone(),
+            hold: false,
+        }
     }
 
     /// Generate key bindings for all keyboard hints.
@@ -307,9 +332,7 @@ pub struct Hints {
 impl Default for Hints {
     fn default() -> Self {
         // Add URL hint by default when no other hint is present.
-        let pattern = LazyRegexVariant::Pattern(String::from(URL_REGEX));
-        let regex = LazyRegex(Rc::new(RefCell::new(pattern)));
-        let content = HintContent::new(Some(regex), true);
+        let content = HintContent::new(Some(LazyRegex::new(URL_REGEX)), true);
 
         #[cfg(not(any(target_os = "macos", windows)))]
         let action = HintAction::Command(Program::Just(String::from("xdg-open")));
@@ -327,6 +350,7 @@ impl Default for Hints {
                 action,
                 persist: false,
                 post_processing: true,
+                placeholders: false,
                 mouse: Some(HintMouse { enabled: true, mods: Default::default() }),
                 binding: Some(HintBinding {
                     key: BindingKey::Keycode {
@@ -347,6 +371,17 @@ impl Hints {
     pub fn alphabet(&self) -> &str {
         &self.alphabet.0
     }
+
+    /// Remove all hints with invalid placeholders in their command arguments.
+    pub fn validate_commands(&mut self) {
+        self.enabled.retain(|hint| match hint.validate_command() {
+            Ok(()) => true,
+            Err(err) => {
+                error!(target: LOG_TARGET_CONFIG, "Config error: hint's command: {}", err);
+                false
+            },
+        });
+    }
 }
 
 #[derive(SerdeReplace, Clone, Debug, PartialEq, Eq)]
@@ -386,6 +421,8 @@ impl<'de> Deserialize<'de> for HintsAlphabet {
 pub enum HintInternalAction {
     /// Copy the text to the clipboard.
     Copy,
+    /// Copy the text to the clipboard as HTML, including its colors.
+    CopyRichText,
     /// Write the text to the PTY/search.
     Paste,
     /// Select the text matching the hint.
@@ -425,6 +462,10 @@ pub struct Hint {
     #[serde(default)]
     pub persist: bool,
 
+    /// Replace placeholders in the command arguments, instead of appending the hint's text.
+    #[serde(default)]
+    pub placeholders: bool,
+
     /// Hint mouse highlighting.
     pub mouse: Option<HintMouse>,
 
@@ -518,6 +559,114 @@ pub struct HintBinding {
     pub mode: ModeWrapper,
 }
 
+impl Hint {
+    /// Check that all placeholders in the command arguments can be resolved.
+    fn validate_command(&self) -> Result<(), String> {
+        let args = match &self.action {
+            HintAction::Command(command) if self.placeholders => command.args(),
+            _ => return Ok(()),
+        };
+
+        // Only build the captures regex when capture groups are referenced.
+        let mut regex = None;
+        for arg in args {
+            expand_hint_placeholders(arg, |placeholder| {
+                let known = match placeholder {
+                    HintPlaceholder::Group(0)
+                    | HintPlaceholder::WorkingDirectory
+                    | HintPlaceholder::Uri => true,
+                    _ => regex
+                        .get_or_insert_with(|| {
+                            self.content
+                                .regex
+                                .as_ref()
+                                .and_then(|regex| regex.captures_regex().ok())
+                        })
+                        .as_ref()
+                        .is_some_and(|regex| placeholder.group_index(regex).is_some()),
+                };
+                known.then(String::new)
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Placeholder in the arguments of a hint command.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HintPlaceholder<'a> {
+    /// Numbered capture group, with `0` being the entire hint text.
+    Group(usize),
+
+    /// Named capture group.
+    NamedGroup(&'a str),
+
+    /// Working directory of the terminal's foreground process.
+    WorkingDirectory,
+
+    /// URI of the hint's hyperlink.
+    Uri,
+}
+
+impl<'a> HintPlaceholder<'a> {
+    fn new(name: &'a str) -> Self {
+        match name {
+            "cwd" => Self::WorkingDirectory,
+            "uri" => Self::Uri,
+            name => name.parse().map_or(Self::NamedGroup(name), Self::Group),
+        }
+    }
+
+    /// Index of the capture group referenced by this placeholder.
+    pub fn group_index(&self, regex: &MetaRegex) -> Option<usize> {
+        let group_info = regex.group_info();
+        match *self {
+            Self::Group(index) => (index < group_info.group_len(PatternID::ZERO)).then_some(index),
+            Self::NamedGroup(name) => group_info.to_index(PatternID::ZERO, name),
+            Self::WorkingDirectory | Self::Uri => None,
+        }
+    }
+}
+
+/// Replace all `{placeholder}`s in a hint command argument.
+///
+/// Like with `format!`, literal braces are written as `{{` and `}}`. Placeholders the `resolve`
+/// function returns `None` for are rejected as unknown.
+pub fn expand_hint_placeholders<'a, F>(arg: &'a str, mut resolve: F) -> Result<String, String>
+where
+    F: FnMut(HintPlaceholder<'a>) -> Option<String>,
+{
+    let mut expanded = String::new();
+    let mut rest = arg;
+    while let Some(start) = rest.find(['{', '}']) {
+        expanded.push_str(&rest[..start]);
+        let brace = &rest[start..start + 1];
+        rest = &rest[start + 1..];
+
+        if let Some(escaped) = rest.strip_prefix(brace) {
+            expanded.push_str(brace);
+            rest = escaped;
+            continue;
+        }
+
+        if brace == "}" {
+            return Err(format!("unmatched }} in {:?}", arg));
+        }
+
+        let end = rest.find('}').ok_or_else(|| format!("unclosed placeholder in {:?}", arg))?;
+        let name = &rest[..end];
+        match resolve(HintPlaceholder::new(name)) {
+            Some(value) => expanded.push_str(&value),
+            None => return Err(format!("unknown placeholder {{{}}} in {:?}", name, arg)),
+        }
+        rest = &rest[end + 1..];
+    }
+    expanded.push_str(rest);
+
+    Ok(expanded)
+}
+
 /// Hint mouse highlighting.
 #[derive(ConfigDeserialize, Default, Copy, Clone, Debug, PartialEq, Eq)]
 pub struct HintMouse {
@@ -530,15 +679,34 @@ pub struct HintMouse {
 
 /// Lazy regex with interior mutability.
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct LazyRegex(Rc<RefCell<LazyRegexVariant>>);
+pub struct LazyRegex {
+    pattern: Rc<str>,
+    variant: Rc<RefCell<LazyRegexVariant>>,
+}
 
 impl LazyRegex {
+    /// Create a regex which is compiled on first use.
+    pub fn new(regex: &str) -> Self {
+        let variant = Rc::new(RefCell::new(LazyRegexVariant::Pattern(regex.into())));
+        Self { pattern: regex.into(), variant }
+    }
+
     /// Execute a function with the compiled regex DFAs as parameter.
     pub fn with_compiled<T, F>(&self, f: F) -> Option<T>
     where
         F: FnMut(&mut RegexSearch) -> T,
     {
-        self.0.borrow_mut().compiled().map(f)
+        self.variant.borrow_mut().compiled().map(f)
+    }
+
+    /// Build a regex reporting the capture groups of a match.
+    ///
+    /// Unlike the search DFAs this is not cached, since it is only required when a hint is fired.
+    pub fn captures_regex(&self) -> Result<MetaRegex, Box<MetaBuildError>> {
+        // Use the same smart case sensitivity as the search DFAs.
+        let case_insensitive = !self.pattern.chars().any(|c| c.is_uppercase());
+        let syntax_config = SyntaxConfig::new().case_insensitive(case_insensitive);
+        Ok(MetaRegex::builder().syntax(syntax_config).build(&self.pattern)?)
     }
 }
 
@@ -547,8 +715,7 @@ impl<'de> Deserialize<'de> for LazyRegex {
     where
         D: Deserializer<'de>,
     {
-        let regex = LazyRegexVariant::Pattern(String::deserialize(deserializer)?);
-        Ok(Self(Rc::new(RefCell::new(regex))))
+        Ok(Self::new(&String::deserialize(deserializer)?))
     }
 }
 
@@ -738,4 +905,36 @@ mod tests {
             )
         }
     }
+
+    #[test]
+    fn hint_command_placeholders() {
+        let hint = |args: &str, placeholders: bool| -> Hint {
+            let hint = format!(
+                "regex = '(?P<path>[^:]+):([0-9]+)'\nplaceholders = {}\ncommand = {{ program = \
+                 'e', args = {} }}",
+                placeholders, args
+            );
+            toml::from_str(&hint).unwrap()
+        };
+
+        assert!(hint(r#"["{{}}", "+{2}", "{path}", "{0}", "{cwd}", "{uri}"]"#, true)
+            .validate_command()
+            .is_ok());
+        assert!(hint(r#"["{3}"]"#, true).validate_command().is_err());
+        assert!(hint(r#"["{line}"]"#, true).validate_command().is_err());
+        assert!(hint(r#"["{path"]"#, true).validate_command().is_err());
+        assert!(hint(r#"["path}"]"#, true).validate_command().is_err());
+
+        // Arguments are only parsed when placeholders are enabled.
+        assert!(hint(r#"["awk '{if(x){print}}'"]"#, false).validate_command().is_ok());
+
+        let expanded = expand_hint_placeholders("{{{0}}}:{cwd}", |placeholder| match placeholder {
+            HintPlaceholder::Group(0) => Some(String::from("text")),
+            _ => None,
+        });
+        assert_eq!(expanded, Err(String::from("unknown placeholder {cwd} in \"{{{0}}}:{cwd}\"")));
+
+        let expanded = expand_hint_placeholders("{{{0}}}", |_| Some(String::from("text")));
+        assert_eq!(expanded.as_deref(), Ok("{text}"));
+    }
 }
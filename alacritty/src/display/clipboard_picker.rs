@@ -0,0 +1,243 @@
+//! Overlay for picking an entry of the clipboard history or a register.
+
+use std::cmp::min;
+use std::iter;
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::clipboard::Clipboard;
+
+/// Entry which can be picked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PickerEntry {
+    /// Label shown in front of the text, like `1` or `"a`.
+    label: String,
+    text: String,
+}
+
+/// State of the clipboard history picker.
+#[derive(Debug, Default)]
+pub struct ClipboardPicker {
+    /// Clipboard history followed by the registers, empty while the picker is inactive.
+    entries: Vec<PickerEntry>,
+
+    /// Index of the highlighted entry.
+    selected: usize,
+
+    /// Label characters typed so far.
+    label_input: String,
+}
+
+impl ClipboardPicker {
+    /// Open the picker with the current clipboard history and registers.
+    ///
+    /// Returns `false` if there is nothing to pick.
+    pub fn start(&mut self, clipboard: &Clipboard) -> bool {
+        let history = clipboard
+            .history()
+            .iter()
+            .enumerate()
+            .map(|(i, text)| PickerEntry { label: (i + 1).to_string(), text: text.clone() });
+        let registers = clipboard
+            .registers()
+            .into_iter()
+            .map(|(name, text)| PickerEntry { label: format!("\"{}", name), text: text.into() });
+
+        self.entries = history.chain(registers).collect();
+        self.selected = 0;
+        self.label_input.clear();
+
+        self.active()
+    }
+
+    /// Close the picker.
+    pub fn stop(&mut self) {
+        self.entries.clear();
+    }
+
+    #[inline]
+    pub fn active(&self) -> bool {
+        !self.entries.is_empty()
+    }
+
+    /// Highlight the next entry, wrapping around at the end.
+    pub fn select_next(&mut self) {
+        if self.active() {
+            self.selected = (self.selected + 1) % self.entries.len();
+            self.label_input.clear();
+        }
+    }
+
+    /// Highlight the previous entry, wrapping around at the start.
+    pub fn select_previous(&mut self) {
+        if self.active() {
+            self.selected = self.selected.checked_sub(1).unwrap_or(self.entries.len() - 1);
+            self.label_input.clear();
+        }
+    }
+
+    /// Add typed text to the label input, highlighting the entry whose label matches it.
+    ///
+    /// Returns `true` once the input can no longer match any other label, like `3` when there
+    /// are less than 30 entries. Input which doesn't start any label is discarded.
+    pub fn label_input(&mut self, text: &str) -> bool {
+        self.label_input.push_str(text);
+
+        let input = self.label_input.as_str();
+        let mut candidates = self.entries.iter().filter(|entry| entry.label.starts_with(input));
+        match (candidates.next(), candidates.next()) {
+            (None, _) => {
+                self.label_input.clear();
+                return false;
+            },
+            (Some(entry), None) => return self.select_label(&entry.label.clone()),
+            _ => (),
+        }
+
+        // Highlight exact matches while a longer label could still be typed.
+        let input = self.label_input.clone();
+        self.select_label(&input);
+
+        false
+    }
+
+    /// Highlight the entry with a label.
+    ///
+    /// Returns `false` if no entry has this label.
+    fn select_label(&mut self, label: &str) -> bool {
+        match self.entries.iter().position(|entry| entry.label == label) {
+            Some(index) => {
+                self.selected = index;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Text of the highlighted entry.
+    pub fn selected_text(&self) -> Option<&str> {
+        self.entries.get(self.selected).map(|entry| entry.text.as_str())
+    }
+
+    /// Visible lines of the picker, with `true` for the highlighted entry.
+    ///
+    /// Lines are truncated or padded to fill exactly `columns` cells, with wide characters
+    /// followed by a spacer. The list is scrolled to keep the highlighted entry within
+    /// `max_lines`.
+    pub fn lines(&self, columns: usize, max_lines: usize) -> Vec<(String, bool)> {
+        let num_lines = min(self.entries.len(), max_lines);
+        let first = (self.selected + 1).saturating_sub(num_lines);
+        let label_width = self.entries.iter().map(|entry| entry.label.len()).max().unwrap_or(0);
+
+        self.entries[first..first + num_lines]
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                // Show line breaks and tabs as spaces to fit every entry into a single line.
+                let text = entry.text.trim().chars().map(|c| match c {
+                    c if c.is_whitespace() || c.is_control() => ' ',
+                    c => c,
+                });
+
+                let mut line = format!(" {:>1$} ", entry.label, label_width);
+                let mut width = line.len();
+                for c in text {
+                    let c_width = c.width().unwrap_or(0);
+                    if width + c_width > columns {
+                        break;
+                    }
+
+                    width += c_width;
+                    line.push(c);
+                    if c_width == 2 {
+                        line.push(' ');
+                    }
+                }
+                line.extend(iter::repeat(' ').take(columns.saturating_sub(width)));
+
+                (line, first + i == self.selected)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alacritty_terminal::term::ClipboardType;
+
+    #[test]
+    fn pick_entries() {
+        let mut clipboard = Clipboard::new_nop();
+        let mut picker = ClipboardPicker::default();
+        assert!(!picker.start(&clipboard));
+
+        clipboard.copy(ClipboardType::Clipboard, "old".into());
+        clipboard.copy(ClipboardType::Clipboard, "first\nsecond".into());
+        clipboard.store_register('a', "register".into());
+        assert!(picker.start(&clipboard));
+
+        assert_eq!(
+            picker.lines(10, 10),
+            [
+                ("  1 first ".into(), true),
+                ("  2 old   ".into(), false),
+                (" \"a regist".into(), false),
+            ]
+        );
+
+        // The list scrolls to the highlighted entry.
+        picker.select_previous();
+        assert_eq!(picker.selected_text(), Some("register"));
+        assert_eq!(
+            picker.lines(14, 2),
+            [("  2 old       ".into(), false), (" \"a register  ".into(), true)]
+        );
+
+        assert!(picker.label_input("2"));
+        assert_eq!(picker.selected_text(), Some("old"));
+        assert!(!picker.label_input("3"));
+        assert!(picker.label_input("\""));
+        assert_eq!(picker.selected_text(), Some("register"));
+
+        picker.stop();
+        assert_eq!(picker.selected_text(), None);
+    }
+
+    #[test]
+    fn multi_digit_labels() {
+        let mut clipboard = Clipboard::new_nop();
+        for i in 0..12 {
+            clipboard.copy(ClipboardType::Clipboard, i.to_string());
+        }
+
+        let mut picker = ClipboardPicker::default();
+        assert!(picker.start(&clipboard));
+
+        // Typing `1` only highlights the first entry, since it could still be `10` to `12`.
+        picker.select_next();
+        assert!(!picker.label_input("1"));
+        assert_eq!(picker.selected_text(), Some("11"));
+        assert!(picker.label_input("2"));
+        assert_eq!(picker.selected_text(), Some("0"));
+
+        assert!(picker.start(&clipboard));
+        assert!(!picker.label_input("1"));
+        assert!(picker.label_input("0"));
+        assert_eq!(picker.selected_text(), Some("2"));
+    }
+
+    #[test]
+    fn wide_char_lines() {
+        let mut clipboard = Clipboard::new_nop();
+        clipboard.copy(ClipboardType::Clipboard, "漢字a".into());
+
+        let mut picker = ClipboardPicker::default();
+        assert!(picker.start(&clipboard));
+
+        // Wide characters are followed by a spacer and never overflow the line.
+        assert_eq!(picker.lines(6, 1), [(" 1 漢  ".into(), true)]);
+        assert_eq!(picker.lines(10, 1), [(" 1 漢 字 a  ".into(), true)]);
+    }
+}
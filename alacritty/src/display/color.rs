@@ -7,10 +7,12 @@ use serde::de::{Error as SerdeError, Visitor};
 use serde::{Deserialize, Deserializer};
 
 use alacritty_config_derive::SerdeReplace;
-use alacritty_terminal::term::color::COUNT;
-use alacritty_terminal::vte::ansi::{NamedColor, Rgb as VteRgb};
+use alacritty_terminal::term::cell::Flags;
+use alacritty_terminal::term::color::{Colors as TermColors, COUNT};
+use alacritty_terminal::vte::ansi::{Color, NamedColor, Rgb as VteRgb};
 
 use crate::config::color::Colors;
+use crate::config::UiConfig;
 
 /// Factor for automatic computation of dim colors.
 pub const DIM_FACTOR: f32 = 0.66;
@@ -171,6 +173,85 @@ impl IndexMut<NamedColor> for List {
     }
 }
 
+/// Resolution of terminal cell colors to RGB.
+///
+/// Colors changed through escape sequences take precedence over the configured colors.
+#[derive(Copy, Clone)]
+pub struct ColorResolver<'a> {
+    config: &'a UiConfig,
+    colors: &'a List,
+    overrides: &'a TermColors,
+}
+
+impl<'a> ColorResolver<'a> {
+    pub fn new(config: &'a UiConfig, colors: &'a List, overrides: &'a TermColors) -> Self {
+        Self { config, colors, overrides }
+    }
+
+    /// Get the RGB value for a color index.
+    #[inline]
+    pub fn color(&self, index: usize) -> Rgb {
+        self.overrides[index].map(Rgb).unwrap_or(self.colors[index])
+    }
+
+    /// Get the RGB color from a cell's foreground color.
+    pub fn foreground(&self, fg: Color, flags: Flags) -> Rgb {
+        let config = self.config;
+        match fg {
+            Color::Spec(rgb) => match flags & Flags::DIM {
+                Flags::DIM => {
+                    let rgb: Rgb = rgb.into();
+                    rgb * DIM_FACTOR
+                },
+                _ => rgb.into(),
+            },
+            Color::Named(ansi) => {
+                match (config.draw_bold_text_with_bright_colors(), flags & Flags::DIM_BOLD) {
+                    // If no bright foreground is set, treat it like the BOLD flag doesn't exist.
+                    (_, Flags::DIM_BOLD)
+                        if ansi == NamedColor::Foreground
+                            && config.colors.primary.bright_foreground.is_none() =>
+                    {
+                        self.color(NamedColor::DimForeground as usize)
+                    },
+                    // Draw bold text in bright colors *and* contains bold flag.
+                    (true, Flags::BOLD) => self.color(ansi.to_bright() as usize),
+                    // Cell is marked as dim and not bold.
+                    (_, Flags::DIM) | (false, Flags::DIM_BOLD) => {
+                        self.color(ansi.to_dim() as usize)
+                    },
+                    // None of the above, keep original color..
+                    _ => self.color(ansi as usize),
+                }
+            },
+            Color::Indexed(idx) => {
+                let idx = match (
+                    config.draw_bold_text_with_bright_colors(),
+                    flags & Flags::DIM_BOLD,
+                    idx,
+                ) {
+                    (true, Flags::BOLD, 0..=7) => idx as usize + 8,
+                    (false, Flags::DIM, 8..=15) => idx as usize - 8,
+                    (false, Flags::DIM, 0..=7) => NamedColor::DimBlack as usize + idx as usize,
+                    _ => idx as usize,
+                };
+
+                self.color(idx)
+            },
+        }
+    }
+
+    /// Get the RGB color from a cell's background color.
+    #[inline]
+    pub fn background(&self, bg: Color) -> Rgb {
+        match bg {
+            Color::Spec(rgb) => rgb.into(),
+            Color::Named(ansi) => self.color(ansi as usize),
+            Color::Indexed(idx) => self.color(idx as usize),
+        }
+    }
+}
+
 #[derive(SerdeReplace, Debug, Eq, PartialEq, Copy, Clone, Default)]
 pub struct Rgb(pub VteRgb);
 
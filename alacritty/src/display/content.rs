@@ -1,20 +1,19 @@
 use std::borrow::Cow;
 use std::ops::Deref;
-use std::{cmp, mem};
+use std::{cmp, mem, slice};
 
-use alacritty_terminal::event::EventListener;
 use alacritty_terminal::graphics::GraphicId;
 use alacritty_terminal::grid::{Dimensions, Indexed};
 use alacritty_terminal::index::{Column, Line, Point};
 use alacritty_terminal::selection::SelectionRange;
 use alacritty_terminal::term::cell::{Cell, Flags, Hyperlink};
-use alacritty_terminal::term::search::{Match, RegexSearch};
-use alacritty_terminal::term::{self, RenderableContent as TerminalContent, Term, TermMode};
+use alacritty_terminal::term::search::Match;
+use alacritty_terminal::term::snapshot::{Highlights, Snapshot};
+use alacritty_terminal::term::{self, TermMode};
 use alacritty_terminal::vte::ansi::{Color, CursorShape, NamedColor};
 
 use crate::config::UiConfig;
-use crate::display::color::{CellRgb, List, Rgb, DIM_FACTOR};
-use crate::display::hint::{self, HintState};
+use crate::display::color::{CellRgb, ColorResolver, Rgb};
 use crate::display::{Display, SizeInfo};
 use crate::event::SearchState;
 
@@ -27,59 +26,59 @@ pub const MIN_CURSOR_CONTRAST: f64 = 1.5;
 ///
 /// This provides the terminal cursor and an iterator over all non-empty cells.
 pub struct RenderableContent<'a> {
-    terminal_content: TerminalContent<'a>,
+    snapshot: &'a Snapshot,
+    cells: slice::Iter<'a, Indexed<Cell>>,
+    dirty_rows: &'a [bool],
     cursor: RenderableCursor,
     cursor_shape: CursorShape,
     cursor_point: Point<usize>,
     search: Option<HintMatches<'a>>,
     hint: Option<Hint<'a>>,
     config: &'a UiConfig,
-    colors: &'a List,
+    colors: ColorResolver<'a>,
     focused_match: Option<&'a Match>,
     size: &'a SizeInfo,
 }
 
 impl<'a> RenderableContent<'a> {
-    pub fn new<T: EventListener>(
+    pub fn new(
         config: &'a UiConfig,
-        display: &'a mut Display,
-        term: &'a Term<T>,
-        search_state: &'a mut SearchState,
+        display: &'a Display,
+        snapshot: &'a Snapshot,
+        search_state: &'a SearchState,
     ) -> Self {
-        let search = search_state.dfas().map(|dfas| HintMatches::visible_regex_matches(term, dfas));
+        let highlights = &snapshot.highlights;
+        let search =
+            (!highlights.search.is_empty()).then(|| HintMatches::new(highlights.search.as_slice()));
         let focused_match = search_state.focused_match();
-        let terminal_content = term.renderable_content();
 
         // Find terminal cursor shape.
-        let cursor_shape = if terminal_content.cursor.shape == CursorShape::Hidden
+        let cursor_shape = if snapshot.cursor.shape == CursorShape::Hidden
             || display.cursor_hidden
             || search_state.regex().is_some()
             || display.ime.preedit().is_some()
         {
             CursorShape::Hidden
-        } else if !term.is_focused && config.cursor.unfocused_hollow {
+        } else if !snapshot.is_focused && config.cursor.unfocused_hollow {
             CursorShape::HollowBlock
         } else {
-            terminal_content.cursor.shape
+            snapshot.cursor.shape
         };
 
         // Convert terminal cursor point to viewport position.
-        let cursor_point = terminal_content.cursor.point;
-        let display_offset = terminal_content.display_offset;
+        let cursor_point = snapshot.cursor.point;
+        let display_offset = snapshot.display_offset;
         let cursor_point = term::point_to_viewport(display_offset, cursor_point).unwrap();
 
-        let hint = if display.hint_state.active() {
-            display.hint_state.update_matches(term);
-            Some(Hint::from(&display.hint_state))
-        } else {
-            None
-        };
+        let hint = (!highlights.hints.is_empty()).then(|| Hint::from(highlights));
 
         Self {
-            colors: &display.colors,
+            colors: ColorResolver::new(config, &display.colors, &snapshot.colors),
             size: &display.size_info,
             cursor: RenderableCursor::new_hidden(),
-            terminal_content,
+            cells: snapshot.cells.iter(),
+            dirty_rows: &[],
+            snapshot,
             focused_match,
             cursor_shape,
             cursor_point,
@@ -89,9 +88,17 @@ impl<'a> RenderableContent<'a> {
         }
     }
 
+    /// Skip all cells outside of the dirty viewport rows.
+    ///
+    /// The cursor is only available if its row is dirty.
+    pub fn with_dirty_rows(mut self, dirty_rows: &'a [bool]) -> Self {
+        self.dirty_rows = dirty_rows;
+        self
+    }
+
     /// Viewport offset.
     pub fn display_offset(&self) -> usize {
-        self.terminal_content.display_offset
+        self.snapshot.display_offset
     }
 
     /// Get the terminal cursor.
@@ -104,22 +111,22 @@ impl<'a> RenderableContent<'a> {
 
     /// Get the RGB value for a color index.
     pub fn color(&self, color: usize) -> Rgb {
-        self.terminal_content.colors[color].map(Rgb).unwrap_or(self.colors[color])
+        self.colors.color(color)
     }
 
     pub fn selection_range(&self) -> Option<SelectionRange> {
-        self.terminal_content.selection
+        self.snapshot.selection
     }
 
     /// Assemble the information required to render the terminal cursor.
     fn renderable_cursor(&mut self, cell: &RenderableCell) -> RenderableCursor {
         // Cursor colors.
-        let color = if self.terminal_content.mode.contains(TermMode::VI) {
+        let color = if self.snapshot.mode.contains(TermMode::VI) {
             self.config.colors.vi_mode_cursor
         } else {
             self.config.colors.cursor
         };
-        let cursor_color = self.terminal_content.colors[NamedColor::Cursor]
+        let cursor_color = self.snapshot.colors[NamedColor::Cursor]
             .map_or(color.background, |c| CellRgb::Rgb(Rgb(c)));
         let text_color = color.foreground;
 
@@ -157,7 +164,15 @@ impl<'a> Iterator for RenderableContent<'a> {
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            let cell = self.terminal_content.display_iter.next()?;
+            let cell = self.cells.next()?;
+
+            // Skip rows which were not damaged.
+            let line = (cell.point.line.0 + self.snapshot.display_offset as i32) as usize;
+            if !self.dirty_rows.get(line).copied().unwrap_or(true) {
+                continue;
+            }
+
+            let cell = Indexed { point: cell.point, cell: &cell.cell };
             let mut cell = RenderableCell::new(self, cell);
 
             if self.cursor_point == cell.point {
@@ -219,25 +234,21 @@ pub struct RenderableCellExtra {
 impl RenderableCell {
     fn new(content: &mut RenderableContent<'_>, cell: Indexed<&Cell>) -> Self {
         // Lookup RGB values.
-        let mut fg = Self::compute_fg_rgb(content, cell.fg, cell.flags);
-        let mut bg = Self::compute_bg_rgb(content, cell.bg);
+        let mut fg = Self::compute_fg_rgb(content, cell.fg(), cell.flags);
+        let mut bg = Self::compute_bg_rgb(content, cell.bg());
 
         let mut bg_alpha = if cell.flags.contains(Flags::INVERSE) {
             mem::swap(&mut fg, &mut bg);
             1.0
         } else {
-            Self::compute_bg_alpha(content.config, cell.bg)
+            Self::compute_bg_alpha(content.config, cell.bg())
         };
 
-        let is_selected = content.terminal_content.selection.map_or(false, |selection| {
-            selection.contains_cell(
-                &cell,
-                content.terminal_content.cursor.point,
-                content.cursor_shape,
-            )
+        let is_selected = content.snapshot.selection.map_or(false, |selection| {
+            selection.contains_cell(&cell, content.snapshot.cursor.point, content.cursor_shape)
         });
 
-        let display_offset = content.terminal_content.display_offset;
+        let display_offset = content.snapshot.display_offset;
         let viewport_start = Point::new(Line(-(display_offset as i32)), Column(0));
         let colors = &content.config.colors;
         let mut character = cell.c;
@@ -346,60 +357,15 @@ impl RenderableCell {
     }
 
     /// Get the RGB color from a cell's foreground color.
+    #[inline]
     fn compute_fg_rgb(content: &RenderableContent<'_>, fg: Color, flags: Flags) -> Rgb {
-        let config = &content.config;
-        match fg {
-            Color::Spec(rgb) => match flags & Flags::DIM {
-                Flags::DIM => {
-                    let rgb: Rgb = rgb.into();
-                    rgb * DIM_FACTOR
-                },
-                _ => rgb.into(),
-            },
-            Color::Named(ansi) => {
-                match (config.draw_bold_text_with_bright_colors(), flags & Flags::DIM_BOLD) {
-                    // If no bright foreground is set, treat it like the BOLD flag doesn't exist.
-                    (_, Flags::DIM_BOLD)
-                        if ansi == NamedColor::Foreground
-                            && config.colors.primary.bright_foreground.is_none() =>
-                    {
-                        content.color(NamedColor::DimForeground as usize)
-                    },
-                    // Draw bold text in bright colors *and* contains bold flag.
-                    (true, Flags::BOLD) => content.color(ansi.to_bright() as usize),
-                    // Cell is marked as dim and not bold.
-                    (_, Flags::DIM) | (false, Flags::DIM_BOLD) => {
-                        content.color(ansi.to_dim() as usize)
-                    },
-                    // None of the above, keep original color..
-                    _ => content.color(ansi as usize),
-                }
-            },
-            Color::Indexed(idx) => {
-                let idx = match (
-                    config.draw_bold_text_with_bright_colors(),
-                    flags & Flags::DIM_BOLD,
-                    idx,
-                ) {
-                    (true, Flags::BOLD, 0..=7) => idx as usize + 8,
-                    (false, Flags::DIM, 8..=15) => idx as usize - 8,
-                    (false, Flags::DIM, 0..=7) => NamedColor::DimBlack as usize + idx as usize,
-                    _ => idx as usize,
-                };
-
-                content.color(idx)
-            },
-        }
+        content.colors.foreground(fg, flags)
     }
 
     /// Get the RGB color from a cell's background color.
     #[inline]
     fn compute_bg_rgb(content: &RenderableContent<'_>, bg: Color) -> Rgb {
-        match bg {
-            Color::Spec(rgb) => rgb.into(),
-            Color::Named(ansi) => content.color(ansi as usize),
-            Color::Indexed(idx) => content.color(idx as usize),
-        }
+        content.colors.background(bg)
     }
 
     /// Compute background alpha based on cell's original color.
@@ -515,10 +481,10 @@ impl<'a> Hint<'a> {
     }
 }
 
-impl<'a> From<&'a HintState> for Hint<'a> {
-    fn from(hint_state: &'a HintState) -> Self {
-        let matches = HintMatches::new(hint_state.matches());
-        Self { labels: hint_state.labels(), matches }
+impl<'a> From<&'a Highlights> for Hint<'a> {
+    fn from(highlights: &'a Highlights) -> Self {
+        let matches = HintMatches::new(highlights.hints.as_slice());
+        Self { labels: &highlights.hint_labels, matches }
     }
 }
 
@@ -538,12 +504,6 @@ impl<'a> HintMatches<'a> {
         Self { matches: matches.into(), index: 0 }
     }
 
-    /// Create from regex matches on term visible part.
-    fn visible_regex_matches<T>(term: &Term<T>, dfas: &mut RegexSearch) -> Self {
-        let matches = hint::visible_regex_match_iter(term, dfas).collect::<Vec<_>>();
-        Self::new(matches)
-    }
-
     /// Advance the regex tracker to the next point.
     ///
     /// This will return `true` if the point passed is part of a regex match.
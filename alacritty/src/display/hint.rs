@@ -1,8 +1,11 @@
 use std::cmp::Reverse;
 use std::collections::HashSet;
 use std::iter;
+use std::path::Path;
 
 use ahash::RandomState;
+use log::warn;
+use regex_automata::{Anchored, Input};
 use winit::keyboard::ModifiersState;
 
 use alacritty_terminal::grid::{BidirectionalIterator, Dimensions};
@@ -11,7 +14,10 @@ use alacritty_terminal::term::cell::Hyperlink;
 use alacritty_terminal::term::search::{Match, RegexIter, RegexSearch};
 use alacritty_terminal::term::{Term, TermMode};
 
-use crate::config::ui_config::{Hint, HintAction};
+use crate::config::selection::Selection;
+use crate::config::ui_config::{
+    expand_hint_placeholders, Hint, HintAction, HintPlaceholder, LazyRegex, Program,
+};
 use crate::config::UiConfig;
 
 /// Maximum number of linewraps followed outside of the viewport during search highlighting.
@@ -151,6 +157,8 @@ impl HintState {
         if label.len() == 1 {
             let bounds = self.matches[index].clone();
             let action = hint.action.clone();
+            let regex = hint.content.regex.clone();
+            let placeholders = hint.placeholders;
 
             // Exit hint mode unless it requires explicit dismissal.
             if hint.persist {
@@ -161,7 +169,7 @@ impl HintState {
 
             // Hyperlinks take precedence over regex matches.
             let hyperlink = term.grid()[*bounds.start()].hyperlink();
-            Some(HintMatch { action, bounds, hyperlink })
+            Some(HintMatch { action, bounds, hyperlink, regex, placeholders })
         } else {
             // Store character to preserve the selection.
             self.keys.push(c);
@@ -190,7 +198,7 @@ impl HintState {
 }
 
 /// Hint match which was selected by the user.
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(Debug, Clone)]
 pub struct HintMatch {
     /// Action for handling the text.
     action: HintAction,
@@ -199,8 +207,26 @@ pub struct HintMatch {
     bounds: Match,
 
     hyperlink: Option<Hyperlink>,
+
+    /// Regex of the hint, used to resolve capture groups in command arguments.
+    regex: Option<LazyRegex>,
+
+    /// Whether command arguments contain placeholders.
+    placeholders: bool,
+}
+
+impl PartialEq for HintMatch {
+    fn eq(&self, other: &Self) -> bool {
+        // Compiled regexes never compare equal, so the regex is ignored to avoid redundant redraws.
+        self.action == other.action
+            && self.bounds == other.bounds
+            && self.hyperlink == other.hyperlink
+            && self.placeholders == other.placeholders
+    }
 }
 
+impl Eq for HintMatch {}
+
 impl HintMatch {
     #[inline]
     pub fn should_highlight(&self, point: Point, pointed_hyperlink: Option<&Hyperlink>) -> bool {
@@ -220,6 +246,74 @@ impl HintMatch {
     pub fn hyperlink(&self) -> Option<&Hyperlink> {
         self.hyperlink.as_ref()
     }
+
+    /// Arguments for launching the hint's command with the hint's text.
+    ///
+    /// With placeholders enabled, they are replaced by the text, its capture groups, the working
+    /// directory and the hyperlink URI. Otherwise the text is appended as the last argument.
+    ///
+    /// Returns `None` if the capture groups could not be resolved.
+    pub fn command_args(
+        &self,
+        command: &Program,
+        text: String,
+        working_directory: Option<&Path>,
+    ) -> Option<Vec<String>> {
+        let args = command.args();
+        if !self.placeholders {
+            let mut args = args.to_vec();
+            args.push(text);
+            return Some(args);
+        }
+
+        // Capture groups are only available for regex matches.
+        let captures = self.regex.as_ref().filter(|_| self.hyperlink.is_none()).and_then(|regex| {
+            let regex = regex.captures_regex().ok()?;
+            let mut captures = regex.create_captures();
+            regex.captures(Input::new(&text).anchored(Anchored::Yes), &mut captures);
+            captures.is_match().then_some((regex, captures))
+        });
+
+        let mut missing_captures = false;
+        let mut resolve = |placeholder: HintPlaceholder<'_>| {
+            let value = match placeholder {
+                HintPlaceholder::Group(0) => Some(text.clone()),
+                HintPlaceholder::WorkingDirectory => {
+                    working_directory.map(|directory| directory.to_string_lossy().into_owned())
+                },
+                HintPlaceholder::Uri => self.hyperlink.as_ref().map(|link| link.uri().to_owned()),
+                _ => match &captures {
+                    Some((regex, captures)) => {
+                        let span =
+                            placeholder.group_index(regex).and_then(|i| captures.get_group(i));
+                        span.map(|span| text[span.range()].to_owned())
+                    },
+                    None => {
+                        missing_captures = true;
+                        None
+                    },
+                },
+            };
+
+            // Groups which did not participate in the match are left empty.
+            Some(value.unwrap_or_default())
+        };
+
+        let expanded: Result<Vec<_>, _> =
+            args.iter().map(|arg| expand_hint_placeholders(arg, &mut resolve)).collect();
+        match expanded {
+            // Post-processing might have removed text required by the regex.
+            Ok(_) if missing_captures => {
+                warn!("Hint {:?} does not match its regex, unable to resolve capture groups", text);
+                None
+            },
+            Ok(args) => Some(args),
+            Err(err) => {
+                warn!("Unable to expand hint command arguments: {}", err);
+                None
+            },
+        }
+    }
 }
 
 /// Generator for creating new hint labels.
@@ -385,6 +479,8 @@ pub fn highlighted_at<T>(
                 bounds,
                 action: hint.action.clone(),
                 hyperlink: Some(hyperlink),
+                regex: None,
+                placeholders: hint.placeholders,
             });
         }
 
@@ -392,13 +488,26 @@ pub fn highlighted_at<T>(
             regex.with_compiled(|regex| regex_match_at(term, point, regex, hint.post_processing))
         });
         if let Some(bounds) = bounds.flatten() {
-            return Some(HintMatch { bounds, action: hint.action.clone(), hyperlink: None });
+            return Some(HintMatch {
+                bounds,
+                action: hint.action.clone(),
+                hyperlink: None,
+                regex: hint.content.regex.clone(),
+                placeholders: hint.placeholders,
+            });
         }
 
         None
     })
 }
 
+/// Retrieve the match of the highest priority smart selection rule at the specified point.
+pub fn smart_selection_at<T>(term: &Term<T>, selection: &Selection, point: Point) -> Option<Match> {
+    selection.smart_rules().find_map(|rule| {
+        rule.regex.with_compiled(|regex| regex_match_at(term, point, regex, true)).flatten()
+    })
+}
+
 /// Retrieve the hyperlink with its range, if there is one at the specified point.
 fn hyperlink_at<T>(term: &Term<T>, point: Point) -> Option<(Hyperlink, Match)> {
     let hyperlink = term.grid()[point].hyperlink()?;
@@ -700,4 +809,70 @@ mod tests {
         // The iterator should match everything in the viewport.
         assert_eq!(visible_regex_match_iter(&term, &mut regex).count(), 4096);
     }
+
+    #[test]
+    fn smart_selection_default_rules() {
+        let term = mock_term(
+            "see https://example.org/a.\r\n\
+             at src/main.rs:12:5, or lib.rs:3\r\n\
+             id 123e4567-e89b-12d3-a456-426614174000\r\n\
+             ip fe80::1ff:fe23:4567:890a and std::vec\r\n\
+             Self::add a::b dead::beef",
+        );
+        let selection = Selection::default();
+        let text_at = |line, column| {
+            let bounds =
+                smart_selection_at(&term, &selection, Point::new(Line(line), Column(column)))?;
+            Some(term.bounds_to_string(*bounds.start(), *bounds.end()))
+        };
+
+        assert_eq!(text_at(0, 10).as_deref(), Some("https://example.org/a"));
+        assert_eq!(text_at(1, 5).as_deref(), Some("src/main.rs:12:5"));
+        assert_eq!(text_at(1, 27).as_deref(), Some("lib.rs:3"));
+        assert_eq!(text_at(2, 10).as_deref(), Some("123e4567-e89b-12d3-a456-426614174000"));
+        assert_eq!(text_at(3, 5).as_deref(), Some("fe80::1ff:fe23:4567:890a"));
+
+        // Words without a matching rule fall back to semantic selection.
+        assert_eq!(text_at(0, 1), None);
+        assert_eq!(text_at(3, 33), None);
+        assert_eq!(text_at(4, 7), None);
+        assert_eq!(text_at(4, 13), None);
+        assert_eq!(text_at(4, 22), None);
+    }
+
+    #[test]
+    fn command_arguments() {
+        let term = mock_term("src/main.rs:42:7");
+        let bounds = Point::new(Line(0), Column(0))..=Point::new(Line(0), Column(15));
+        let text = term.bounds_to_string(*bounds.start(), *bounds.end());
+
+        let hint: Hint = toml::from_str(
+            "regex = '(?P<path>[^:]+):([0-9]+)(:[0-9]+)?'\n\
+             placeholders = true\n\
+             command = { program = 'editor', args = ['+{2}', '{path}', '{3}{uri}', '{cwd}/{0}'] }",
+        )
+        .unwrap();
+        let mut hint_match = HintMatch {
+            action: hint.action.clone(),
+            bounds,
+            hyperlink: None,
+            regex: hint.content.regex.clone(),
+            placeholders: hint.placeholders,
+        };
+
+        let command = match &hint.action {
+            HintAction::Command(command) => command,
+            HintAction::Action(_) => unreachable!(),
+        };
+        let args = hint_match.command_args(command, text.clone(), Some(Path::new("/tmp")));
+        assert_eq!(args.unwrap(), ["+42", "src/main.rs", ":7", "/tmp/src/main.rs:42:7"]);
+
+        // Text which doesn't match the regex anymore can't be launched.
+        assert_eq!(hint_match.command_args(command, "src/main.rs".into(), None), None);
+
+        // Without placeholders, the text is appended.
+        hint_match.placeholders = false;
+        let args = hint_match.command_args(command, text, None).unwrap();
+        assert_eq!(args, ["+{2}", "{path}", "{3}{uri}", "{cwd}/{0}", "src/main.rs:42:7"]);
+    }
 }
@@ -0,0 +1,123 @@
+//! Conversion of terminal content to HTML.
+
+use alacritty_terminal::grid::Dimensions;
+use alacritty_terminal::html::{HtmlColors, HtmlWriter};
+use alacritty_terminal::index::{Column, Line};
+use alacritty_terminal::selection::SelectionRange;
+use alacritty_terminal::term::cell::{Cell, Flags};
+use alacritty_terminal::term::Term;
+use alacritty_terminal::vte::ansi::{Color, NamedColor, Rgb};
+
+use crate::display::color::ColorResolver;
+
+/// Convert the text within a selection range to an HTML `<pre>` block.
+///
+/// Colors are resolved like they are rendered, while text attributes and hyperlinks are
+/// converted to their HTML equivalent.
+pub fn range_to_html<T>(
+    term: &Term<T>,
+    range: SelectionRange,
+    colors: ColorResolver<'_>,
+) -> String {
+    let SelectionRange { start, end, is_block } = range;
+    let mut html = HtmlWriter::new(&colors);
+
+    let last_column = term.last_column();
+    for line in (start.line.0..=end.line.0).map(Line) {
+        let row = &term.grid()[line];
+        let (start_column, end_column) = if is_block {
+            (start.column, end.column)
+        } else {
+            let start_column = if line == start.line { start.column } else { Column(0) };
+            let end_column = if line == end.line { end.column } else { last_column };
+            (start_column, end_column)
+        };
+
+        let cells = &row[start_column..end_column + 1];
+
+        // Like in the plain text, blank cells at the end of the line are omitted.
+        let length = cells.iter().rposition(|cell| !is_blank(cell)).map_or(0, |index| index + 1);
+        html.push_cells(&cells[..length]);
+
+        // Wrapped lines are joined, unless only a block of them is selected.
+        let wrapped = !is_block && row[last_column].flags.contains(Flags::WRAPLINE);
+        if line != end.line && !wrapped {
+            html.push_newline();
+        }
+    }
+
+    html.finish()
+}
+
+impl HtmlColors for ColorResolver<'_> {
+    fn foreground(&self, color: Color, flags: Flags) -> Rgb {
+        ColorResolver::foreground(self, color, flags).0
+    }
+
+    fn background(&self, color: Color) -> Rgb {
+        ColorResolver::background(self, color).0
+    }
+}
+
+/// Check if a cell would not be visible at the end of a line.
+fn is_blank(cell: &Cell) -> bool {
+    matches!(cell.c, ' ' | '\t')
+        && cell.zerowidth().is_none()
+        && cell.hyperlink().is_none()
+        && cell.bg() == Color::Named(NamedColor::Background)
+        && !cell.flags.intersects(Flags::INVERSE | Flags::ALL_UNDERLINES | Flags::STRIKEOUT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alacritty_terminal::headless::Headless;
+    use alacritty_terminal::index::Point;
+
+    use crate::config::UiConfig;
+    use crate::display::color::List;
+
+    fn html(text: &str, start: Point, end: Point, is_block: bool) -> String {
+        let mut terminal = Headless::new(12, 3);
+        terminal.feed(text.as_bytes());
+
+        let config = UiConfig::default();
+        let list = List::from(&config.colors);
+        let term = terminal.term_mut();
+        let colors = ColorResolver::new(&config, &list, term.colors());
+
+        let html = range_to_html(term, SelectionRange::new(start, end, is_block), colors);
+        let content = html.strip_prefix("<pre style=\"").unwrap().split_once("\">").unwrap().1;
+        content.strip_suffix("</pre>").unwrap().to_owned()
+    }
+
+    #[test]
+    fn attributes() {
+        let start = Point::new(Line(0), Column(0));
+        let end = Point::new(Line(1), Column(11));
+
+        let text = "\x1b[1;31mred\x1b[0m <\x1b[4:3;9mb\x1b[0m>\r\n\x1b]8;;https://x\x1b\\link\x1b]8;;\x1b\\";
+        let config = UiConfig::default();
+        let red = config.colors.normal.red;
+        assert_eq!(
+            html(text, start, end, false),
+            format!(
+                "<span style=\"color:{};font-weight:bold\">red</span> &lt;<span \
+                 style=\"text-decoration:underline wavy line-through\">b</span>&gt;\n<a \
+                 href=\"https://x\">link</a>",
+                red
+            )
+        );
+    }
+
+    #[test]
+    fn wrapped_and_block_selections() {
+        let text = "abcdefghijklmnop\r\nqrstuvwx";
+        let start = Point::new(Line(0), Column(2));
+        let end = Point::new(Line(2), Column(3));
+
+        assert_eq!(html(text, start, end, false), "cdefghijklmnop\nqrst");
+        assert_eq!(html(text, start, end, true), "cd\nop\nst");
+    }
+}
@@ -1,6 +1,7 @@
 //! The display subsystem including window management, font rasterization, and
 //! GPU drawing.
 
+use std::borrow::Cow;
 use std::cmp;
 use std::fmt::{self, Formatter};
 use std::mem::{self, ManuallyDrop};
@@ -13,7 +14,6 @@ use glutin::prelude::*;
 use glutin::surface::{Surface, SwapInterval, WindowSurface};
 
 use log::{debug, info};
-use parking_lot::MutexGuard;
 use raw_window_handle::RawWindowHandle;
 use serde::{Deserialize, Serialize};
 use winit::dpi::PhysicalSize;
@@ -26,11 +26,12 @@ use unicode_width::UnicodeWidthChar;
 use alacritty_terminal::event::{EventListener, OnResize, WindowSize};
 use alacritty_terminal::grid::Dimensions as TermDimensions;
 use alacritty_terminal::index::{Column, Direction, Line, Point};
-use alacritty_terminal::selection::Selection;
+use alacritty_terminal::selection::{Selection, SelectionRange};
 use alacritty_terminal::term::cell::Flags;
+use alacritty_terminal::term::search::{SearchCase, SearchOptions};
+use alacritty_terminal::term::snapshot::{Snapshot, SnapshotBuffer, SnapshotDamage};
 use alacritty_terminal::term::{
-    self, point_to_viewport, LineDamageBounds, Term, TermDamage, TermMode, MIN_COLUMNS,
-    MIN_SCREEN_LINES,
+    self, point_to_viewport, LineDamageBounds, Term, TermMode, MIN_COLUMNS, MIN_SCREEN_LINES,
 };
 use alacritty_terminal::vte::ansi::{CursorShape, NamedColor};
 
@@ -40,12 +41,15 @@ use crate::config::window::Dimensions;
 use crate::config::window::StartupMode;
 use crate::config::UiConfig;
 use crate::display::bell::VisualBell;
+use crate::display::clipboard_picker::ClipboardPicker;
 use crate::display::color::{List, Rgb};
 use crate::display::content::{RenderableContent, RenderableCursor};
 use crate::display::cursor::IntoRects;
 use crate::display::damage::{damage_y_to_viewport_y, DamageTracker};
 use crate::display::hint::{HintMatch, HintState};
 use crate::display::meter::Meter;
+use crate::display::rows::RowCache;
+use crate::display::scrollbar::Scrollbar;
 use crate::display::window::Window;
 use crate::event::{Event, EventType, Mouse, SearchState};
 use crate::message_bar::{MessageBuffer, MessageType};
@@ -54,15 +58,19 @@ use crate::renderer::{self, GlyphCache, Renderer};
 use crate::scheduler::{Scheduler, TimerId, Topic};
 use crate::string::{ShortenDirection, StrShortener};
 
+pub mod clipboard_picker;
 pub mod color;
 pub mod content;
 pub mod cursor;
 pub mod hint;
+pub mod html;
 pub mod window;
 
 mod bell;
 mod damage;
 mod meter;
+mod rows;
+mod scrollbar;
 
 /// Label for the forward terminal search bar.
 const FORWARD_SEARCH_LABEL: &str = "Search: ";
@@ -73,6 +81,11 @@ const BACKWARD_SEARCH_LABEL: &str = "Backward Search: ";
 /// The character used to shorten the visible text like uri preview or search regex.
 const SHORTENER: char = '…';
 
+/// Indicator shown next to the cursor while a password is being entered.
+///
+/// The lock is a wide character, so it's followed by its spacer.
+const PASSWORD_INDICATOR: [char; 2] = ['🔒', ' '];
+
 /// Color which is used to highlight damaged rects when debugging.
 const DAMAGE_RECT_COLOR: Rgb = Rgb::new(255, 0, 255);
 
@@ -370,6 +383,9 @@ pub struct Display {
     /// State of the keyboard hints.
     pub hint_state: HintState,
 
+    /// State of the clipboard history picker.
+    pub clipboard_picker: ClipboardPicker,
+
     /// Unprocessed display updates.
     pub pending_update: DisplayUpdate,
 
@@ -379,6 +395,18 @@ pub struct Display {
     /// The ime on the given display.
     pub ime: Ime,
 
+    /// Child process is reading a password.
+    pub password_input: bool,
+
+    /// Terminal content drawn by the last frame.
+    snapshot: Option<Box<Snapshot>>,
+
+    /// Rows drawn by the last frame.
+    rows: RowCache,
+
+    /// Overlay scrollbar for the terminal history.
+    pub scrollbar: Scrollbar,
+
     /// The state of the timer for frame scheduling.
     pub frame_timer: FrameTimer,
 
@@ -534,6 +562,11 @@ impl Display {
             cursor_hidden: Default::default(),
             meter: Default::default(),
             ime: Default::default(),
+            password_input: Default::default(),
+            snapshot: Default::default(),
+            rows: Default::default(),
+            scrollbar: Default::default(),
+            clipboard_picker: Default::default(),
         })
     }
 
@@ -718,25 +751,54 @@ impl Display {
 
         self.renderer.resize(&self.size_info);
 
+        // Glyphs and their positions might have changed.
+        self.rows.invalidate();
+
         info!("Padding: {} x {}", self.size_info.padding_x(), self.size_info.padding_y());
         info!("Width: {}, Height: {}", self.size_info.width(), self.size_info.height());
     }
 
     /// Draw the screen.
     ///
-    /// A reference to Term whose state is being drawn must be provided.
+    /// The latest snapshot published to `snapshots` is drawn, or the previously drawn one if
+    /// nothing was published since.
     ///
     /// This call may block if vsync is enabled.
-    pub fn draw<T: EventListener>(
+    pub fn draw(
         &mut self,
-        mut terminal: MutexGuard<'_, Term<T>>,
+        snapshots: &SnapshotBuffer,
         scheduler: &mut Scheduler,
         message_buffer: &MessageBuffer,
         config: &UiConfig,
         search_state: &mut SearchState,
     ) {
-        // Collect renderable content before the terminal is dropped.
-        let mut content = RenderableContent::new(config, self, &terminal, search_state);
+        if let Some(snapshot) = snapshots.take() {
+            if let Some(previous) = self.snapshot.replace(snapshot) {
+                snapshots.recycle(previous);
+            }
+        }
+        let mut snapshot = match self.snapshot.take() {
+            Some(snapshot) => snapshot,
+            None => return,
+        };
+
+        let size_info = self.size_info;
+        let damage = mem::take(&mut snapshot.damage);
+        let resized = snapshot.columns != size_info.columns()
+            || snapshot.screen_lines != size_info.screen_lines();
+
+        // Find rows which changed since the last frame.
+        let mut rows = mem::take(&mut self.rows);
+        let highlighted_hints = [&self.highlighted_hint, &self.vi_highlighted_hint]
+            .map(|hint| hint.as_ref().map(|hint| hint.bounds().clone()));
+        let highlighted_matches = !snapshot.highlights.is_empty();
+        if resized {
+            rows.invalidate();
+        }
+        rows.update(&snapshot, &damage, highlighted_hints, highlighted_matches);
+
+        let mut content = RenderableContent::new(config, self, &snapshot, search_state)
+            .with_dirty_rows(rows.dirty());
         let mut grid_cells = Vec::new();
         for cell in &mut content {
             grid_cells.push(cell);
@@ -747,36 +809,33 @@ impl Display {
         let display_offset = content.display_offset();
         let cursor = content.cursor();
 
-        let cursor_point = terminal.grid().cursor.point;
-        let total_lines = terminal.grid().total_lines();
+        let cursor_point = snapshot.cursor_point;
+        let total_lines = snapshot.total_lines;
         let metrics = self.glyph_cache.font_metrics();
-        let size_info = self.size_info;
 
-        let vi_mode = terminal.mode().contains(TermMode::VI);
-        let vi_cursor_point = if vi_mode { Some(terminal.vi_mode_cursor.point) } else { None };
+        let vi_mode = snapshot.mode.contains(TermMode::VI);
+        let vi_cursor_point = if vi_mode { Some(snapshot.vi_cursor_point) } else { None };
 
         // Add damage from the terminal.
         if self.collect_damage() {
-            match terminal.damage() {
-                TermDamage::Full => self.damage_tracker.frame().mark_fully_damaged(),
-                TermDamage::Partial(damaged_lines) => {
+            match damage {
+                SnapshotDamage::Partial(damaged_lines) if !resized => {
                     for damage in damaged_lines {
                         self.damage_tracker.frame().damage_line(damage);
                     }
                 },
+                _ => self.damage_tracker.frame().mark_fully_damaged(),
             }
-            terminal.reset_damage();
         }
 
-        let graphics_queues = terminal.graphics_take_queues();
-
-        // Drop terminal as early as possible to free lock.
-        drop(terminal);
+        let graphics_queues = snapshot.graphics.take();
+        self.snapshot = Some(snapshot);
 
         // Add damage from alacritty's UI elements overlapping terminal.
         if self.collect_damage() {
             let requires_full_damage = self.visual_bell.intensity() != 0.
                 || self.hint_state.active()
+                || self.clipboard_picker.active()
                 || search_state.regex().is_some();
 
             if requires_full_damage {
@@ -817,11 +876,12 @@ impl Display {
             let glyph_cache = &mut self.glyph_cache;
             let highlighted_hint = &self.highlighted_hint;
             let vi_highlighted_hint = &self.vi_highlighted_hint;
-            let damage_tracker = &mut self.damage_tracker;
+            let dirty = rows.dirty().to_vec();
 
-            self.renderer.draw_cells(
+            self.renderer.draw_rows(
                 &size_info,
                 glyph_cache,
+                &dirty,
                 grid_cells.into_iter().map(|mut cell| {
                     // Underline hints hovered by mouse or vi mode cursor.
                     let point = term::viewport_to_point(display_offset, cell.point);
@@ -840,22 +900,31 @@ impl Display {
                         {
                             show_hint = true;
                             cell.flags.insert(Flags::UNDERLINE);
-                            // Damage hints for the current and next frames.
-                            damage_tracker.frame().damage_point(cell.point);
-                            damage_tracker.next_frame().damage_point(cell.point);
                         }
                     }
 
-                    // Update underline/strikeout.
-                    lines.update(&cell);
-
-                    // Track any graphic present in the cell.
-                    graphics_list.update(&cell, show_hint);
+                    // Keep cells with underline/strikeout and graphics for unchanged rows.
+                    rows.push_decoration(&cell, show_hint);
 
                     cell
                 }),
             );
+
+            for (cell, show_hint) in rows.decorations() {
+                if *show_hint {
+                    // Damage hints for the current and next frames.
+                    self.damage_tracker.frame().damage_point(cell.point);
+                    self.damage_tracker.next_frame().damage_point(cell.point);
+                }
+
+                // Update underline/strikeout.
+                lines.update(cell);
+
+                // Track any graphic present in the cell.
+                graphics_list.update(cell, *show_hint);
+            }
         }
+        self.rows = rows;
 
         let mut rects = lines.rects(&metrics, &size_info);
 
@@ -876,6 +945,18 @@ impl Display {
         // Draw cursor.
         rects.extend(cursor.rects(&size_info, config.cursor.thickness()));
 
+        // Draw scrollbar above the terminal content.
+        self.scrollbar.update(display_offset);
+        let history_size = total_lines.saturating_sub(size_info.screen_lines());
+        self.draw_scrollbar(
+            config,
+            scheduler,
+            &mut rects,
+            search_state,
+            selection_range,
+            history_size,
+        );
+
         // Push visual bell after url/underline/strikeout rects.
         let visual_bell_intensity = self.visual_bell.intensity();
         if visual_bell_intensity != 0. {
@@ -893,15 +974,24 @@ impl Display {
         // Handle IME positioning and search bar rendering.
         let ime_position = match search_state.regex() {
             Some(regex) => {
-                let search_label = match search_state.direction() {
-                    Direction::Right => FORWARD_SEARCH_LABEL,
-                    Direction::Left => BACKWARD_SEARCH_LABEL,
+                let search_label = match search_state.history_filter() {
+                    Some(filter) => Cow::Owned(format!("History [{}]: ", filter)),
+                    None => {
+                        Self::format_search_label(search_state.direction(), search_state.options())
+                    },
                 };
 
-                let search_text = Self::format_search(regex, search_label, size_info.columns());
+                let search_text = Self::format_search(regex, &search_label, size_info.columns());
 
-                // Render the search bar.
-                self.draw_search(config, &search_text);
+                // Render the search bar, with the match count on the right if it fits.
+                let num_cols = size_info.columns();
+                match Self::format_match_count(search_state, vi_cursor_point) {
+                    Some(count) if search_text.chars().count() + count.len() < num_cols => {
+                        let width = num_cols - count.len();
+                        self.draw_search(config, &format!("{:<2$}{}", search_text, count, width));
+                    },
+                    _ => self.draw_search(config, &search_text),
+                }
 
                 // Draw search bar cursor.
                 let line = size_info.screen_lines();
@@ -986,12 +1076,22 @@ impl Display {
 
         self.draw_render_timer(config);
 
+        // Draw password input indicator.
+        if self.password_input && search_state.regex().is_none() {
+            self.draw_password_indicator(config, cursor_point, display_offset);
+        }
+
         // Draw hyperlink uri preview.
         if has_highlighted_hint {
             let cursor_point = vi_cursor_point.or(Some(cursor_point));
             self.draw_hyperlink_preview(config, cursor_point, display_offset);
         }
 
+        // Draw clipboard history picker above all terminal content.
+        if self.clipboard_picker.active() {
+            self.draw_clipboard_picker(config);
+        }
+
         // Notify winit that we're about to present.
         self.window.pre_present_notify();
 
@@ -1027,6 +1127,7 @@ impl Display {
         self.damage_tracker.debug = config.debug.highlight_damage;
         self.visual_bell.update_config(&config.bell);
         self.colors = List::from(&config.colors);
+        self.rows.invalidate();
     }
 
     /// Update the mouse/vi mode cursor hint highlighting.
@@ -1172,6 +1273,57 @@ impl Display {
     }
 
     /// Format search regex to account for the cursor and fullwidth characters.
+    /// Format the search bar label, including all active search modifiers.
+    fn format_search_label(direction: Direction, options: SearchOptions) -> Cow<'static, str> {
+        let label = match direction {
+            Direction::Right => FORWARD_SEARCH_LABEL,
+            Direction::Left => BACKWARD_SEARCH_LABEL,
+        };
+
+        let mut modifiers = Vec::new();
+        if options.literal {
+            modifiers.push("literal");
+        }
+        match options.case {
+            SearchCase::Smart => (),
+            SearchCase::Sensitive => modifiers.push("match case"),
+            SearchCase::Insensitive => modifiers.push("ignore case"),
+        }
+        if options.whole_word {
+            modifiers.push("whole word");
+        }
+
+        if modifiers.is_empty() {
+            return Cow::Borrowed(label);
+        }
+
+        let label = label.strip_suffix(": ").unwrap_or(label);
+        Cow::Owned(format!("{} [{}]: ", label, modifiers.join(", ")))
+    }
+
+    /// Format the focused match's index and the total number of search matches.
+    ///
+    /// In vi mode, the index of the last match at or before the vi cursor is shown instead. The
+    /// total is suffixed with `+` while matches are still being counted.
+    fn format_match_count(
+        search_state: &SearchState,
+        vi_cursor_point: Option<Point>,
+    ) -> Option<String> {
+        let counter = search_state.match_counter()?;
+
+        let current = match (vi_cursor_point, search_state.focused_match()) {
+            (Some(point), _) => counter.preceding(point).to_string(),
+            (None, Some(focused_match)) => match counter.position(*focused_match.start()) {
+                Some(position) => position.to_string(),
+                None => String::from("?"),
+            },
+            (None, None) => String::from("0"),
+        };
+        let pending = if counter.is_done() { "" } else { "+" };
+
+        Some(format!(" {}/{}{} ", current, counter.total(), pending))
+    }
+
     fn format_search(search_regex: &str, search_label: &str, max_width: usize) -> String {
         let label_len = search_label.len();
 
@@ -1261,6 +1413,25 @@ impl Display {
         }
     }
 
+    /// Draw the clipboard history picker at the bottom of the terminal.
+    #[inline(never)]
+    fn draw_clipboard_picker(&mut self, config: &UiConfig) {
+        let num_cols = self.size_info.columns();
+        let num_lines = self.size_info.screen_lines();
+        let lines = self.clipboard_picker.lines(num_cols, num_lines);
+
+        let fg = config.colors.footer_bar_foreground();
+        let bg = config.colors.footer_bar_background();
+
+        let start_line = num_lines - lines.len();
+        for (i, (text, selected)) in lines.into_iter().enumerate() {
+            let (fg, bg) = if selected { (bg, fg) } else { (fg, bg) };
+            let point = Point::new(start_line + i, Column(0));
+            let glyph_cache = &mut self.glyph_cache;
+            self.renderer.draw_string(point, fg, bg, text.chars(), &self.size_info, glyph_cache);
+        }
+    }
+
     /// Draw current search regex.
     #[inline(never)]
     fn draw_search(&mut self, config: &UiConfig, text: &str) {
@@ -1283,6 +1454,51 @@ impl Display {
         );
     }
 
+    /// Draw the scrollbar and schedule a redraw for hiding it after inactivity.
+    fn draw_scrollbar(
+        &mut self,
+        config: &UiConfig,
+        scheduler: &mut Scheduler,
+        rects: &mut Vec<RenderRect>,
+        search_state: &SearchState,
+        selection: Option<SelectionRange>,
+        history_size: usize,
+    ) {
+        let scrollbar_config = &config.scrolling.scrollbar;
+        if !self.scrollbar.visible(scrollbar_config) {
+            return;
+        }
+
+        // Only show search matches while the search bar is open.
+        let counter = search_state.match_counter().filter(|_| search_state.regex().is_some());
+        let matches = counter.as_deref().map_or(&[][..], |counter| counter.matches());
+
+        let scrollbar_rects =
+            self.scrollbar.rects(config, &self.size_info, history_size, matches, selection);
+
+        // Damage the scrollbar for this and the next frame, in case it moves or disappears.
+        if let Some(thumb) = scrollbar_rects.first().filter(|_| self.collect_damage()) {
+            let size_info = self.size_info;
+            let x = thumb.x as i32;
+            let y = size_info.padding_y() as i32;
+            let width = size_info.width() as i32 - x;
+            let height = (size_info.screen_lines() as f32 * size_info.cell_height()) as i32;
+            self.damage_tracker.frame().add_viewport_rect(&size_info, x, y, width, height);
+            self.damage_tracker.next_frame().add_viewport_rect(&size_info, x, y, width, height);
+        }
+
+        rects.extend(scrollbar_rects);
+
+        // Redraw once the scrollbar should be hidden.
+        let timer_id = TimerId::new(Topic::HideScrollbar, self.window.id());
+        scheduler.unschedule(timer_id);
+        if let Some(deadline) = self.scrollbar.hide_deadline(scrollbar_config) {
+            let event = Event::new(EventType::HideScrollbar, self.window.id());
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            scheduler.schedule(event, timeout, false, timer_id);
+        }
+    }
+
     /// Draw render timer.
     #[inline(never)]
     fn draw_render_timer(&mut self, config: &UiConfig) {
@@ -1306,6 +1522,43 @@ impl Display {
         self.renderer.draw_string(point, fg, bg, timing.chars(), &self.size_info, glyph_cache);
     }
 
+    /// Draw a lock next to the cursor while a password is being entered.
+    #[inline(never)]
+    fn draw_password_indicator(
+        &mut self,
+        config: &UiConfig,
+        cursor_point: Point,
+        display_offset: usize,
+    ) {
+        let cursor_point = match term::point_to_viewport(display_offset, cursor_point) {
+            Some(point) if point.line < self.size_info.screen_lines() => point,
+            _ => return,
+        };
+
+        // Place the indicator after the cursor, unless there's no space left.
+        let indicator_width = PASSWORD_INDICATOR.len();
+        let column = if cursor_point.column + 1 + indicator_width <= self.size_info.columns() {
+            cursor_point.column + 1
+        } else {
+            Column(cursor_point.column.saturating_sub(indicator_width))
+        };
+        let point = Point::new(cursor_point.line, column);
+
+        if self.collect_damage() {
+            let damage = LineDamageBounds::new(point.line, column.0, column.0 + indicator_width);
+            self.damage_tracker.frame().damage_line(damage);
+            // Damage the indicator for the next frame in case it goes away.
+            self.damage_tracker.next_frame().damage_line(damage);
+        }
+
+        let fg = config.colors.primary.background;
+        let bg = config.colors.normal.yellow;
+
+        let glyph_cache = &mut self.glyph_cache;
+        let indicator = PASSWORD_INDICATOR.iter().copied();
+        self.renderer.draw_string(point, fg, bg, indicator, &self.size_info, glyph_cache);
+    }
+
     /// Draw an indicator for the position of a line in history.
     #[inline(never)]
     fn draw_line_indicator(
@@ -0,0 +1,197 @@
+//! Tracking of terminal rows which changed since the previous frame.
+
+use std::{cmp, mem};
+
+use alacritty_terminal::selection::SelectionRange;
+use alacritty_terminal::term;
+use alacritty_terminal::term::cell::Flags;
+use alacritty_terminal::term::search::Match;
+use alacritty_terminal::term::snapshot::{Snapshot, SnapshotDamage};
+
+use crate::display::content::RenderableCell;
+
+/// Rows of the terminal grid drawn by the previous frame.
+///
+/// The renderer caches the glyphs of every row, so only rows which changed since the previous
+/// frame have to be rebuilt from the terminal content.
+#[derive(Debug, Default)]
+pub struct RowCache {
+    /// Rows which have to be rebuilt for the current frame.
+    dirty: Vec<bool>,
+
+    /// Cells with underlines, strikeouts or graphics, for every row.
+    decorations: Vec<Vec<(RenderableCell, bool)>>,
+
+    /// Rebuild all rows in the next frame.
+    invalidated: bool,
+
+    /// Viewport line of the previous frame's cursor.
+    cursor_line: Option<usize>,
+
+    /// Selection of the previous frame.
+    selection: Option<SelectionRange>,
+
+    /// Display offset of the previous frame.
+    display_offset: usize,
+
+    /// Hints highlighted by the mouse and vi mode cursor in the previous frame.
+    highlighted_hints: [Option<Match>; 2],
+
+    /// Whether search matches or hint labels were drawn in the previous frame.
+    highlighted_matches: bool,
+}
+
+impl RowCache {
+    /// Rebuild all rows in the next frame.
+    pub fn invalidate(&mut self) {
+        self.invalidated = true;
+    }
+
+    /// Update the dirty rows for the next frame.
+    ///
+    /// Search matches and hint labels can change anywhere in the viewport, so all rows are rebuilt
+    /// while they are visible.
+    pub fn update(
+        &mut self,
+        snapshot: &Snapshot,
+        damage: &SnapshotDamage,
+        highlighted_hints: [Option<Match>; 2],
+        highlighted_matches: bool,
+    ) {
+        let screen_lines = snapshot.screen_lines;
+        let display_offset = snapshot.display_offset;
+        let cursor_point = term::point_to_viewport(display_offset, snapshot.cursor.point);
+        let cursor_line = cursor_point.map(|point| point.line);
+
+        let full = mem::take(&mut self.invalidated)
+            || self.dirty.len() != screen_lines
+            || self.display_offset != display_offset
+            || self.highlighted_matches
+            || highlighted_matches
+            || *damage == SnapshotDamage::Full;
+
+        self.dirty.clear();
+        self.dirty.resize(screen_lines, full);
+        self.decorations.resize_with(screen_lines, Vec::new);
+
+        if !full {
+            if let SnapshotDamage::Partial(lines) = damage {
+                for line in lines.iter().filter(|bounds| bounds.is_damaged()) {
+                    self.damage_line(line.line);
+                }
+            }
+
+            // Always rebuild the cursor, since its appearance does not depend on the terminal.
+            for line in self.cursor_line.into_iter().chain(cursor_line) {
+                self.damage_line(line);
+            }
+
+            if self.selection != snapshot.selection {
+                for selection in self.selection.into_iter().chain(snapshot.selection) {
+                    let start = selection.start.line;
+                    let end = selection.end.line;
+                    self.damage_lines(start.0, end.0, display_offset);
+                }
+            }
+
+            if self.highlighted_hints != highlighted_hints {
+                let old_hints =
+                    mem::replace(&mut self.highlighted_hints, highlighted_hints.clone());
+                for hint in old_hints.into_iter().chain(highlighted_hints.clone()).flatten() {
+                    self.damage_lines(hint.start().line.0, hint.end().line.0, display_offset);
+                }
+            }
+        }
+
+        let dirty_rows = self.decorations.iter_mut().zip(&self.dirty).filter(|(_, dirty)| **dirty);
+        for (decorations, _) in dirty_rows {
+            decorations.clear();
+        }
+
+        self.cursor_line = cursor_line;
+        self.selection = snapshot.selection;
+        self.display_offset = display_offset;
+        self.highlighted_hints = highlighted_hints;
+        self.highlighted_matches = highlighted_matches;
+    }
+
+    /// Rows which have to be rebuilt for the current frame.
+    #[inline]
+    pub fn dirty(&self) -> &[bool] {
+        &self.dirty
+    }
+
+    /// Store a rebuilt cell, if it is required for drawing lines or graphics.
+    #[inline]
+    pub fn push_decoration(&mut self, cell: &RenderableCell, show_hint: bool) {
+        let has_graphics = cell.extra.as_ref().is_some_and(|extra| extra.graphics.is_some());
+        if !has_graphics && !cell.flags.intersects(Flags::ALL_UNDERLINES | Flags::STRIKEOUT) {
+            return;
+        }
+
+        if let Some(decorations) = self.decorations.get_mut(cell.point.line) {
+            decorations.push((cell.clone(), show_hint));
+        }
+    }
+
+    /// Cells required for drawing lines and graphics, for all rows.
+    pub fn decorations(&self) -> impl Iterator<Item = &(RenderableCell, bool)> {
+        self.decorations.iter().flatten()
+    }
+
+    /// Mark a viewport line as dirty.
+    fn damage_line(&mut self, line: usize) {
+        if let Some(dirty) = self.dirty.get_mut(line) {
+            *dirty = true;
+        }
+    }
+
+    /// Mark all viewport lines within a range of terminal lines as dirty.
+    fn damage_lines(&mut self, start: i32, end: i32, display_offset: usize) {
+        let last_line = self.dirty.len() as i32 - 1;
+        let start = cmp::max(start + display_offset as i32, 0);
+        let end = cmp::min(end + display_offset as i32, last_line);
+        for line in start..=end {
+            self.damage_line(line as usize);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alacritty_terminal::headless::Headless;
+
+    #[test]
+    fn only_damaged_rows_are_dirty() {
+        let mut terminal = Headless::new(10, 5);
+        let mut rows = RowCache::default();
+
+        // Everything is rebuilt for the first frame.
+        let mut snapshot = Snapshot::new(terminal.term_mut());
+        let damage = mem::take(&mut snapshot.damage);
+        rows.update(&snapshot, &damage, Default::default(), false);
+        assert_eq!(rows.dirty(), [true; 5]);
+
+        // Moving the cursor damages the old and new cursor rows.
+        terminal.feed(b"\x1b[3Hab");
+        let mut snapshot = Snapshot::new(terminal.term_mut());
+        let damage = mem::take(&mut snapshot.damage);
+        rows.update(&snapshot, &damage, Default::default(), false);
+        assert_eq!(rows.dirty(), [true, false, true, false, false]);
+
+        // Search matches require rebuilding all rows, including after they're gone.
+        let snapshot = Snapshot::new(terminal.term_mut());
+        rows.update(&snapshot, &snapshot.damage, Default::default(), true);
+        assert_eq!(rows.dirty(), [true; 5]);
+        rows.update(&snapshot, &snapshot.damage, Default::default(), false);
+        assert_eq!(rows.dirty(), [true; 5]);
+        rows.update(&snapshot, &snapshot.damage, Default::default(), false);
+        assert_eq!(rows.dirty(), [false, false, true, false, false]);
+
+        rows.invalidate();
+        rows.update(&snapshot, &snapshot.damage, Default::default(), false);
+        assert_eq!(rows.dirty(), [true; 5]);
+    }
+}
@@ -0,0 +1,273 @@
+//! Overlay scrollbar indicating the viewport position in history.
+
+use std::time::Instant;
+
+use alacritty_terminal::grid::Dimensions;
+use alacritty_terminal::index::{Line, Point};
+use alacritty_terminal::selection::SelectionRange;
+
+use crate::config::scrolling::ScrollbarConfig;
+use crate::config::UiConfig;
+use crate::display::SizeInfo;
+use crate::renderer::rects::RenderRect;
+
+/// Minimum height of the scrollbar thumb in pixels.
+const MIN_THUMB_HEIGHT: f32 = 16.;
+
+/// Minimum height of the marks for search matches and the selection in pixels.
+const MIN_MARK_HEIGHT: f32 = 2.;
+
+/// Opacity of the scrollbar thumb.
+const THUMB_ALPHA: f32 = 0.5;
+
+/// Scrollbar drawn on top of the right edge of the terminal.
+#[derive(Debug, Default)]
+pub struct Scrollbar {
+    /// Last interaction with the scrollbar or change of the viewport position.
+    last_activity: Option<Instant>,
+
+    /// Display offset of the previous frame.
+    display_offset: usize,
+
+    /// Vertical distance between the mouse and the top of the thumb while it's dragged.
+    drag_offset: Option<f32>,
+}
+
+impl Scrollbar {
+    /// Reveal the scrollbar, restarting its inactivity timeout.
+    #[inline]
+    pub fn show(&mut self) {
+        self.last_activity = Some(Instant::now());
+    }
+
+    /// Reveal the scrollbar if the viewport was scrolled since the previous frame.
+    pub fn update(&mut self, display_offset: usize) {
+        if self.display_offset != display_offset {
+            self.display_offset = display_offset;
+            self.show();
+        }
+    }
+
+    /// Check if the scrollbar should be drawn.
+    pub fn visible(&self, config: &ScrollbarConfig) -> bool {
+        config.enabled
+            && !self.hide_deadline(config).is_some_and(|deadline| deadline <= Instant::now())
+    }
+
+    /// Time at which the scrollbar will be hidden due to inactivity.
+    pub fn hide_deadline(&self, config: &ScrollbarConfig) -> Option<Instant> {
+        let timeout = config.hide_timeout()?;
+        if self.drag_offset.is_some() {
+            return None;
+        }
+
+        Some(self.last_activity.map_or_else(Instant::now, |activity| activity + timeout))
+    }
+
+    /// Check if a mouse position is over the scrollbar, even if it's hidden.
+    pub fn hovered(
+        &self,
+        config: &ScrollbarConfig,
+        size_info: &SizeInfo,
+        history_size: usize,
+        x: f32,
+        y: f32,
+    ) -> bool {
+        config.enabled
+            && Geometry::new(size_info, 0, history_size).is_some_and(|geometry| {
+                x >= geometry.x
+                    && y >= geometry.track_y
+                    && y < geometry.track_y + geometry.track_height
+            })
+    }
+
+    /// Start dragging the thumb, returning the new display offset.
+    ///
+    /// Clicking outside of the thumb centers it on the mouse position.
+    pub fn start_drag(
+        &mut self,
+        size_info: &SizeInfo,
+        display_offset: usize,
+        history_size: usize,
+        y: f32,
+    ) -> usize {
+        let geometry = match Geometry::new(size_info, display_offset, history_size) {
+            Some(geometry) => geometry,
+            None => return display_offset,
+        };
+
+        let drag_offset = if y >= geometry.thumb_y && y < geometry.thumb_y + geometry.thumb_height {
+            y - geometry.thumb_y
+        } else {
+            geometry.thumb_height / 2.
+        };
+        self.drag_offset = Some(drag_offset);
+
+        geometry.display_offset(y - drag_offset)
+    }
+
+    /// Move the dragged thumb, returning the new display offset.
+    pub fn drag(&self, size_info: &SizeInfo, history_size: usize, y: f32) -> Option<usize> {
+        let drag_offset = self.drag_offset?;
+        let geometry = Geometry::new(size_info, 0, history_size)?;
+        Some(geometry.display_offset(y - drag_offset))
+    }
+
+    /// Stop dragging the thumb.
+    ///
+    /// Returns `true` if the thumb was being dragged.
+    pub fn stop_drag(&mut self) -> bool {
+        let dragging = self.drag_offset.take().is_some();
+        if dragging {
+            self.show();
+        }
+        dragging
+    }
+
+    /// Rects for the scrollbar thumb and the marks for search matches and the selection.
+    pub fn rects(
+        &self,
+        config: &UiConfig,
+        size_info: &SizeInfo,
+        history_size: usize,
+        matches: &[Point],
+        selection: Option<SelectionRange>,
+    ) -> Vec<RenderRect> {
+        let geometry = match Geometry::new(size_info, self.display_offset, history_size) {
+            Some(geometry) => geometry,
+            None => return Vec::new(),
+        };
+
+        let colors = &config.colors;
+        let thumb_color = colors.scrollbar.thumb.unwrap_or(colors.primary.foreground);
+        let selection_color = colors.scrollbar.selection.unwrap_or(colors.normal.blue);
+        let match_color = colors.scrollbar.matches.unwrap_or(colors.normal.yellow);
+
+        let mut rects = vec![RenderRect::new(
+            geometry.x,
+            geometry.thumb_y,
+            geometry.width,
+            geometry.thumb_height,
+            thumb_color,
+            THUMB_ALPHA,
+        )];
+
+        let mark_height = geometry.line_height().max(MIN_MARK_HEIGHT);
+
+        if let Some(selection) = selection {
+            let y = geometry.line_y(selection.start.line);
+            let height = geometry.line_y(selection.end.line) - y + mark_height;
+            rects.push(RenderRect::new(geometry.x, y, geometry.width, height, selection_color, 1.));
+        }
+
+        // Skip matches which would be drawn on the same pixel row as the previous one.
+        let mut last_y = None;
+        for start in matches {
+            let y = geometry.line_y(start.line).floor();
+            if last_y.replace(y) != Some(y) {
+                let rect =
+                    RenderRect::new(geometry.x, y, geometry.width, mark_height, match_color, 1.);
+                rects.push(rect);
+            }
+        }
+
+        rects
+    }
+}
+
+/// Position of the scrollbar in the window, in pixels.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Geometry {
+    x: f32,
+    width: f32,
+    track_y: f32,
+    track_height: f32,
+    thumb_y: f32,
+    thumb_height: f32,
+    history_size: usize,
+    total_lines: usize,
+}
+
+impl Geometry {
+    fn new(size_info: &SizeInfo, display_offset: usize, history_size: usize) -> Option<Self> {
+        if history_size == 0 {
+            return None;
+        }
+
+        let width = (size_info.cell_width() / 2.).round().max(1.);
+        let x = size_info.width() - width;
+
+        // The track covers all terminal lines, excluding the search and message bars.
+        let screen_lines = size_info.screen_lines();
+        let track_y = size_info.padding_y();
+        let track_height = screen_lines as f32 * size_info.cell_height();
+
+        let total_lines = history_size + screen_lines;
+        let visible_ratio = screen_lines as f32 / total_lines as f32;
+        let thumb_height = (track_height * visible_ratio).max(MIN_THUMB_HEIGHT).min(track_height);
+        let scrolled = 1. - display_offset as f32 / history_size as f32;
+        let thumb_y = track_y + (track_height - thumb_height) * scrolled;
+
+        Some(Self {
+            x,
+            width,
+            track_y,
+            track_height,
+            thumb_y,
+            thumb_height,
+            history_size,
+            total_lines,
+        })
+    }
+
+    /// Display offset which puts the top of the thumb at a vertical position.
+    fn display_offset(&self, thumb_y: f32) -> usize {
+        let range = self.track_height - self.thumb_height;
+        if range <= 0. {
+            return 0;
+        }
+
+        let scrolled = ((thumb_y - self.track_y) / range).clamp(0., 1.);
+        ((1. - scrolled) * self.history_size as f32).round() as usize
+    }
+
+    /// Vertical position of a terminal line on the track.
+    fn line_y(&self, line: Line) -> f32 {
+        let index = (line.0 + self.history_size as i32).max(0) as f32;
+        self.track_y + self.line_height() * index
+    }
+
+    /// Height of a single terminal line on the track.
+    fn line_height(&self) -> f32 {
+        self.track_height / self.total_lines as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drag_thumb() {
+        // 10 lines with a history of 30 lines, resulting in a 40px thumb on a 160px track.
+        let size_info = SizeInfo::new(100., 160., 10., 16., 0., 0., false);
+        let history_size = 30;
+
+        let geometry = Geometry::new(&size_info, history_size, history_size).unwrap();
+        assert_eq!(geometry.thumb_y, 0.);
+        assert_eq!(geometry.thumb_height, 40.);
+        let geometry = Geometry::new(&size_info, 0, history_size).unwrap();
+        assert_eq!(geometry.thumb_y, 120.);
+
+        // Grabbing the thumb does not scroll.
+        let mut scrollbar = Scrollbar::default();
+        assert_eq!(scrollbar.start_drag(&size_info, 0, history_size, 130.), 0);
+        assert_eq!(scrollbar.drag(&size_info, history_size, 70.), Some(15));
+        assert_eq!(scrollbar.drag(&size_info, history_size, -50.), Some(30));
+        assert!(scrollbar.stop_drag());
+        assert_eq!(scrollbar.drag(&size_info, history_size, 70.), None);
+
+        // Clicking the track centers the thumb on the mouse.
+        assert_eq!(scrollbar.start_drag(&size_info, 0, history_size, 20.), 30);
+    }
+}
@@ -10,13 +10,15 @@ use std::fmt::Debug;
 use std::os::unix::io::RawFd;
 use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
-use std::{env, f32, mem};
+use std::{env, f32, iter, mem};
 
 use ahash::RandomState;
 use crossfont::Size as FontSize;
 use glutin::display::{Display as GlutinDisplay, GetGlDisplay};
 use log::{debug, error, info, warn};
+use parking_lot::MappedMutexGuard;
 use raw_window_handle::HasRawDisplayHandle;
 use winit::event::{
     ElementState, Event as WinitEvent, Ime, Modifiers, MouseButton, StartCause,
@@ -31,27 +33,36 @@ use alacritty_terminal::event::{Event as TerminalEvent, EventListener, Notify};
 use alacritty_terminal::event_loop::Notifier;
 use alacritty_terminal::grid::{BidirectionalIterator, Dimensions, Scroll};
 use alacritty_terminal::index::{Boundary, Column, Direction, Line, Point, Side};
-use alacritty_terminal::selection::{Selection, SelectionType};
-use alacritty_terminal::term::search::{Match, RegexSearch};
+use alacritty_terminal::selection::{Selection, SelectionRange, SelectionType};
+use alacritty_terminal::sync::FairMutex;
+use alacritty_terminal::term::search::{self, Match, MatchCounter, RegexSearch, SearchOptions};
 use alacritty_terminal::term::{self, ClipboardType, Term, TermMode};
+use alacritty_terminal::vi_mode::ViMotion;
 
 #[cfg(unix)]
 use crate::cli::{IpcConfig, ParsedOptions};
 use crate::cli::{Options as CliOptions, WindowOptions};
-use crate::clipboard::Clipboard;
+use crate::clipboard::{Clipboard, RichText};
 use crate::config::ui_config::{HintAction, HintInternalAction};
 use crate::config::{self, UiConfig};
 #[cfg(not(windows))]
 use crate::daemon::foreground_process_path;
 use crate::daemon::spawn_daemon;
-use crate::display::color::Rgb;
-use crate::display::hint::HintMatch;
+use crate::display::color::{ColorResolver, Rgb};
+use crate::display::hint::{self, HintMatch};
+use crate::display::html;
 use crate::display::window::Window;
 use crate::display::{Display, Preedit, SizeInfo};
+use crate::input::vi_command::{
+    self, Parsed, ViCommand, ViCommandParser, ViInput, ViOperator, ViTarget,
+};
 use crate::input::{self, ActionContext as _, FONT_SIZE_STEP};
 use crate::logging::LOG_TARGET_CONFIG;
 use crate::message_bar::{Message, MessageBuffer};
 use crate::scheduler::{Scheduler, TimerId, Topic};
+use crate::search_count::SearchCount;
+use crate::search_history::{self, SearchHistory};
+use crate::session::{self, SessionState};
 use crate::window_context::WindowContext;
 
 /// Duration after the last user input until an unlimited search is performed.
@@ -60,9 +71,6 @@ pub const TYPING_SEARCH_DELAY: Duration = Duration::from_millis(500);
 /// Maximum number of lines for the blocking search while still typing the search regex.
 const MAX_SEARCH_WHILE_TYPING: Option<usize> = Some(1000);
 
-/// Maximum number of search terms stored in the history.
-const MAX_SEARCH_HISTORY_SIZE: usize = 255;
-
 /// Touch zoom speed.
 const TOUCH_ZOOM_FACTOR: f32 = 0.01;
 
@@ -95,13 +103,18 @@ pub enum EventType {
     ConfigReload(PathBuf),
     Message(Message),
     Scroll(Scroll),
-    CreateWindow(WindowOptions),
+    CreateWindow(Box<WindowOptions>),
     #[cfg(unix)]
     IpcConfig(IpcConfig),
     BlinkCursor,
     BlinkCursorTimeout,
     SearchNext,
+    SearchCount,
+    HideScrollbar,
     Frame,
+    SaveSession,
+    ToggleRecording,
+    RestartCommand,
 }
 
 impl From<TerminalEvent> for EventType {
@@ -137,6 +150,15 @@ pub struct SearchState {
 
     /// Compiled search automatons.
     dfas: Option<RegexSearch>,
+
+    /// Modifiers applied to the search regex.
+    options: SearchOptions,
+
+    /// Number of matches for the active search.
+    match_counter: Option<SearchCount>,
+
+    /// Fuzzy filter applied to the search history, while recalling history entries.
+    history_filter: Option<String>,
 }
 
 impl SearchState {
@@ -150,6 +172,39 @@ impl SearchState {
         self.direction
     }
 
+    /// Modifiers applied to the search regex.
+    pub fn options(&self) -> SearchOptions {
+        self.options
+    }
+
+    /// Number of matches for the active search.
+    pub fn match_counter(&self) -> Option<MappedMutexGuard<'_, MatchCounter>> {
+        self.match_counter.as_ref().map(SearchCount::counter)
+    }
+
+    /// Move the counted matches along with the terminal content.
+    ///
+    /// Lines which were not counted yet are counted on a background thread, which redraws the
+    /// window whenever it made progress.
+    pub fn update_match_counter(
+        &self,
+        term: &Term<EventProxy>,
+        terminal: &Arc<FairMutex<Term<EventProxy>>>,
+        event_proxy: &EventProxy,
+    ) {
+        if let (Some(counter), Some(dfas)) = (&self.match_counter, &self.dfas) {
+            let event_proxy = event_proxy.clone();
+            counter.update(term, terminal, dfas, move || {
+                event_proxy.send_event(EventType::SearchCount);
+            });
+        }
+    }
+
+    /// Fuzzy filter applied to the search history, while recalling history entries.
+    pub fn history_filter(&self) -> Option<&str> {
+        self.history_filter.as_deref()
+    }
+
     /// Focused match during vi-less search.
     pub fn focused_match(&self) -> Option<&Match> {
         self.focused_match.as_ref()
@@ -169,6 +224,14 @@ impl SearchState {
     fn regex_mut(&mut self) -> Option<&mut String> {
         self.history_index.and_then(move |index| self.history.get_mut(index))
     }
+
+    /// Check if a history entry matches the history filter.
+    fn history_matches(&self, index: usize) -> bool {
+        match &self.history_filter {
+            Some(filter) => search_history::fuzzy_match(filter, &self.history[index]),
+            None => true,
+        }
+    }
 }
 
 impl Default for SearchState {
@@ -181,6 +244,9 @@ impl Default for SearchState {
             history: Default::default(),
             origin: Default::default(),
             dfas: Default::default(),
+            options: Default::default(),
+            match_counter: Default::default(),
+            history_filter: Default::default(),
         }
     }
 }
@@ -210,6 +276,7 @@ pub struct ActionContext<'a, N, T> {
     pub notifier: &'a mut N,
     pub terminal: &'a mut Term<T>,
     pub clipboard: &'a mut Clipboard,
+    pub search_history: &'a mut SearchHistory,
     pub mouse: &'a mut Mouse,
     pub touch: &'a mut TouchPurpose,
     pub modifiers: &'a mut Modifiers,
@@ -222,6 +289,7 @@ pub struct ActionContext<'a, N, T> {
     pub scheduler: &'a mut Scheduler,
     pub search_state: &'a mut SearchState,
     pub inline_search_state: &'a mut InlineSearchState,
+    pub vi_command: &'a mut ViCommandParser,
     pub dirty: &'a mut bool,
     pub occluded: &'a mut bool,
     pub preserve_title: bool,
@@ -286,15 +354,22 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
 
     // Copy text selection.
     fn copy_selection(&mut self, ty: ClipboardType) {
-        let text = match self.terminal.selection_to_string().filter(|s| !s.is_empty()) {
-            Some(text) => text,
-            None => return,
-        };
+        self.store_selection(ty, false);
+    }
 
-        if ty == ClipboardType::Selection && self.config.selection.save_to_clipboard {
-            self.clipboard.store(ClipboardType::Clipboard, text.clone());
+    /// Copy text selection through a binding, storing it in the pending vi register if any.
+    fn copy(&mut self, ty: ClipboardType) {
+        self.store_selection(ty, true);
+    }
+
+    /// Copy text selection as HTML.
+    fn copy_rich_text(&mut self) {
+        let range = self.terminal.selection.as_ref().and_then(|s| s.to_range(self.terminal));
+        let text = self.terminal.selection_to_string().filter(|s| !s.is_empty());
+        if let Some((range, text)) = range.zip(text) {
+            let html = self.range_to_html(range);
+            self.clipboard.copy_rich_text(ClipboardType::Clipboard, RichText { text, html });
         }
-        self.clipboard.store(ty, text);
     }
 
     fn selection_is_empty(&self) -> bool {
@@ -317,6 +392,22 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         // Treat motion over message bar like motion over the last line.
         point.line = min(point.line, self.terminal.bottommost_line());
 
+        // Keep smart selections intact until the mouse leaves the selected match.
+        if let Some(bounds) = self.mouse.smart_selection.take() {
+            if bounds.contains(&point) {
+                self.mouse.smart_selection = Some(bounds);
+                self.terminal.selection = Some(selection);
+                return;
+            }
+
+            // Extend by words from the far end of the match, like a semantic selection.
+            selection = if point < *bounds.start() {
+                Selection::new(SelectionType::Semantic, *bounds.end(), Side::Right)
+            } else {
+                Selection::new(SelectionType::Semantic, *bounds.start(), Side::Left)
+            };
+        }
+
         // Update selection.
         selection.update(point, side);
 
@@ -332,11 +423,29 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
 
     fn start_selection(&mut self, ty: SelectionType, point: Point, side: Side) {
         self.terminal.selection = Some(Selection::new(ty, point, side));
+        self.mouse.smart_selection = None;
         *self.dirty = true;
 
         self.copy_selection(ClipboardType::Selection);
     }
 
+    fn start_smart_selection(&mut self, point: Point) -> bool {
+        let bounds = match hint::smart_selection_at(self.terminal, &self.config.selection, point) {
+            Some(bounds) => bounds,
+            None => return false,
+        };
+
+        let mut selection = Selection::new(SelectionType::Simple, *bounds.start(), Side::Left);
+        selection.update(*bounds.end(), Side::Right);
+        self.terminal.selection = Some(selection);
+        self.mouse.smart_selection = Some(bounds);
+        *self.dirty = true;
+
+        self.copy_selection(ClipboardType::Selection);
+
+        true
+    }
+
     fn toggle_selection(&mut self, ty: SelectionType, point: Point, side: Side) {
         match &mut self.terminal.selection {
             Some(selection) if selection.ty == ty && !selection.is_empty() => {
@@ -437,14 +546,15 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
             options.window_tabbing_id = tabbing_id;
         }
 
-        let _ = self.event_proxy.send_event(Event::new(EventType::CreateWindow(options), None));
+        let _ = self
+            .event_proxy
+            .send_event(Event::new(EventType::CreateWindow(Box::new(options)), None));
     }
 
     #[cfg(windows)]
     fn create_new_window(&mut self) {
-        let _ = self
-            .event_proxy
-            .send_event(Event::new(EventType::CreateWindow(WindowOptions::default()), None));
+        let _ =
+            self.event_proxy.send_event(Event::new(EventType::CreateWindow(Box::default()), None));
     }
 
     fn spawn_daemon<I, S>(&self, program: &str, args: I)
@@ -479,6 +589,18 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
             .set_font(self.config.font.clone().with_size(self.display.font_size));
     }
 
+    fn toggle_recording(&mut self) {
+        let window_id = self.display.window.id();
+        let event = Event::new(EventType::ToggleRecording, window_id);
+        let _ = self.event_proxy.send_event(event);
+    }
+
+    fn restart_command(&mut self) {
+        let window_id = self.display.window.id();
+        let event = Event::new(EventType::RestartCommand, window_id);
+        let _ = self.event_proxy.send_event(event);
+    }
+
     #[inline]
     fn pop_message(&mut self) {
         if !self.message_buffer.is_empty() {
@@ -489,11 +611,10 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
 
     #[inline]
     fn start_search(&mut self, direction: Direction) {
-        // Only create new history entry if the previous regex wasn't empty.
-        if self.search_state.history.front().map_or(true, |regex| !regex.is_empty()) {
-            self.search_state.history.push_front(String::new());
-            self.search_state.history.truncate(MAX_SEARCH_HISTORY_SIZE);
-        }
+        // Start with an empty regex, followed by the history of all windows.
+        let history = self.search_history.entries().iter().cloned();
+        self.search_state.history = iter::once(String::new()).chain(history).collect();
+        self.search_state.history_filter = None;
 
         self.search_state.history_index = Some(0);
         self.search_state.direction = direction;
@@ -501,6 +622,10 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
 
         // Store original search position as origin and reset location.
         if self.terminal.mode().contains(TermMode::VI) {
+            // Remember the position before the search, to allow jumping back to it.
+            let vi_point = self.terminal.vi_mode_cursor.point;
+            self.terminal.vi_marks.push_jump(vi_point);
+
             self.search_state.origin = self.terminal.vi_mode_cursor.point;
             self.search_state.display_offset_delta = 0;
 
@@ -572,6 +697,21 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
             },
             None => return,
         }
+
+        // Edit the history filter instead of the regex while recalling history entries.
+        if let Some(filter) = &mut self.search_state.history_filter {
+            match c {
+                '\x08' | '\x7f' => {
+                    let _ = filter.pop();
+                },
+                ' '..='~' | '\u{a0}'..='\u{10ffff}' => filter.push(c),
+                _ => return,
+            }
+
+            self.update_history_filter();
+            return;
+        }
+
         let regex = &mut self.search_state.history[0];
 
         match c {
@@ -595,7 +735,11 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
 
     #[inline]
     fn search_pop_word(&mut self) {
-        if let Some(regex) = self.search_state.regex_mut() {
+        if let Some(filter) = &mut self.search_state.history_filter {
+            *filter = filter.trim_end().to_owned();
+            filter.truncate(filter.rfind(' ').map_or(0, |i| i + 1));
+            self.update_history_filter();
+        } else if let Some(regex) = self.search_state.regex_mut() {
             *regex = regex.trim_end().to_owned();
             regex.truncate(regex.rfind(' ').map_or(0, |i| i + 1));
             self.update_search();
@@ -605,25 +749,85 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
     /// Go to the previous regex in the search history.
     #[inline]
     fn search_history_previous(&mut self) {
-        let index = match &mut self.search_state.history_index {
-            None => return,
-            Some(index) if *index + 1 >= self.search_state.history.len() => return,
+        let index = match self.search_state.history_index {
             Some(index) => index,
+            None => return,
         };
 
-        *index += 1;
-        self.update_search();
+        let history_len = self.search_state.history.len();
+        let previous = (index + 1..history_len).find(|&i| self.search_state.history_matches(i));
+        if previous.is_some() {
+            self.search_state.history_index = previous;
+            self.update_search();
+        }
     }
 
     /// Go to the previous regex in the search history.
     #[inline]
     fn search_history_next(&mut self) {
-        let index = match &mut self.search_state.history_index {
+        let index = match self.search_state.history_index {
             Some(0) | None => return,
             Some(index) => index,
         };
 
-        *index -= 1;
+        // Always allow going back to the user's input.
+        let next = (0..index).rev().find(|&i| i == 0 || self.search_state.history_matches(i));
+        self.search_state.history_index = next;
+        self.update_search();
+    }
+
+    /// Start recalling history entries matching the current search input.
+    #[inline]
+    fn search_history_filter(&mut self) {
+        if self.search_state.history_index.is_none() {
+            return;
+        }
+
+        // Go to the next matching entry if the filter is already active.
+        if self.search_state.history_filter.is_some() {
+            self.search_history_previous();
+            return;
+        }
+
+        let filter = self.search_state.regex().cloned().unwrap_or_default();
+        self.search_state.history_filter = Some(filter);
+        self.update_history_filter();
+    }
+
+    /// Stop recalling history entries.
+    ///
+    /// When `accept` is `true`, the recalled entry replaces the search input.
+    #[inline]
+    fn stop_search_history_filter(&mut self, accept: bool) {
+        if self.search_state.history_filter.take().is_none() {
+            return;
+        }
+
+        match self.search_state.history_index {
+            Some(index) if accept => {
+                self.search_state.history[0] = self.search_state.history[index].clone();
+            },
+            Some(_) => (),
+            None => return,
+        }
+
+        self.search_state.history_index = Some(0);
+        self.update_search();
+    }
+
+    #[inline]
+    fn search_history_filtering(&self) -> bool {
+        self.search_state.history_filter.is_some()
+    }
+
+    /// Change the search modifiers and update the active search.
+    #[inline]
+    fn update_search_options<F: FnOnce(&mut SearchOptions)>(&mut self, f: F) {
+        f(&mut self.search_state.options);
+
+        // Redraw the search bar, even if there is no regex to update.
+        *self.dirty = true;
+
         self.update_search();
     }
 
@@ -680,6 +884,44 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
             .and_then(|dfas| self.terminal.search_next(dfas, origin, direction, side, None))
     }
 
+    /// Search for the word below the vi mode cursor.
+    fn search_vi_word(&mut self, direction: Direction) {
+        let point = self.terminal.vi_mode_cursor.point;
+        let start = self.terminal.semantic_search_left(point);
+        let end = self.terminal.semantic_search_right(point);
+        let word = self.terminal.bounds_to_string(start, end);
+        if word.trim().is_empty() {
+            return;
+        }
+
+        // Ignore the user's search modifiers, since the regex is already escaped.
+        let regex = search::word_regex(&word);
+        let options = SearchOptions { case: self.search_state.options.case, ..Default::default() };
+        match RegexSearch::with_options(&regex, options) {
+            Ok(dfas) => self.search_state.dfas = Some(dfas),
+            Err(err) => {
+                warn!("Unable to search for {:?}: {}", word, err);
+                return;
+            },
+        }
+
+        self.search_state.direction = direction;
+        self.search_history.push(&self.config.search, regex);
+
+        // Skip the word below the cursor, like `SearchNext` and `SearchPrevious`.
+        let origin = match direction {
+            Direction::Right => end.add(self.terminal, Boundary::None, 1),
+            Direction::Left => start.sub(self.terminal, Boundary::None, 1),
+        };
+        if let Some(regex_match) = self.search_next(origin, direction, Side::Left) {
+            self.terminal.vi_jump(*regex_match.start());
+        }
+
+        // Highlight all matches of the new search.
+        self.display.damage_tracker.frame().mark_fully_damaged();
+        *self.dirty = true;
+    }
+
     #[inline]
     fn search_direction(&self) -> Direction {
         self.search_state.direction
@@ -741,13 +983,24 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         match &hint.action() {
             // Launch an external program.
             HintAction::Command(command) => {
-                let mut args = command.args().to_vec();
-                args.push(text);
-                self.spawn_daemon(command.program(), &args);
+                #[cfg(not(windows))]
+                let working_directory =
+                    foreground_process_path(self.master_fd, self.shell_pid).ok();
+                #[cfg(windows)]
+                let working_directory = None;
+
+                if let Some(args) = hint.command_args(command, text, working_directory.as_deref()) {
+                    self.spawn_daemon(command.program(), &args);
+                }
             },
             // Copy the text to the clipboard.
             HintAction::Action(HintInternalAction::Copy) => {
-                self.clipboard.store(ClipboardType::Clipboard, text);
+                self.clipboard.copy(ClipboardType::Clipboard, text);
+            },
+            HintAction::Action(HintInternalAction::CopyRichText) => {
+                let range = SelectionRange::new(*hint_bounds.start(), *hint_bounds.end(), false);
+                let html = self.range_to_html(range);
+                self.clipboard.copy_rich_text(ClipboardType::Clipboard, RichText { text, html });
             },
             // Write the text to the PTY/search.
             HintAction::Action(HintInternalAction::Paste) => self.paste(&text, true),
@@ -770,6 +1023,27 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         }
     }
 
+    /// Open the clipboard history picker.
+    fn start_clipboard_picker(&mut self) {
+        if self.display.clipboard_picker.start(self.clipboard) {
+            *self.dirty = true;
+        }
+    }
+
+    /// Paste the entry highlighted in the clipboard history picker.
+    ///
+    /// The entry also replaces the clipboard content, so it can be pasted again.
+    fn confirm_clipboard_picker(&mut self) {
+        let text = self.display.clipboard_picker.selected_text().map(String::from);
+        self.display.clipboard_picker.stop();
+        *self.dirty = true;
+
+        if let Some(text) = text {
+            self.clipboard.copy(ClipboardType::Clipboard, text.clone());
+            self.paste(&text, true);
+        }
+    }
+
     /// Expand the selection to the current mouse cursor position.
     #[inline]
     fn expand_selection(&mut self) {
@@ -878,9 +1152,11 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         }
 
         // We don't want IME in Vi mode.
-        self.window().set_ime_allowed(was_in_vi_mode);
+        let ime_allowed = was_in_vi_mode && !self.password_blocks_ime();
+        self.window().set_ime_allowed(ime_allowed);
 
         self.terminal.toggle_vi_mode();
+        self.vi_command.reset();
 
         *self.dirty = true;
     }
@@ -909,6 +1185,35 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         self.inline_search(direction);
     }
 
+    /// Move the vi mode cursor, or apply the pending operator to the motion.
+    fn vi_motion(&mut self, motion: ViMotion) {
+        let selecting = self.terminal.selection.is_some();
+        if let Parsed::Command(command) = self.vi_command.input(ViInput::Motion(motion), selecting)
+        {
+            self.execute_vi_command(command);
+        }
+    }
+
+    /// Pass a character to the pending vi command.
+    ///
+    /// Returns `false` if the character is not part of a vi command.
+    fn vi_command_input(&mut self, c: char) -> bool {
+        let selecting = self.terminal.selection.is_some();
+        match self.vi_command.input(ViInput::Char(c), selecting) {
+            Parsed::Ignored => false,
+            Parsed::Pending | Parsed::Aborted => true,
+            Parsed::Command(command) => {
+                self.execute_vi_command(command);
+                true
+            },
+        }
+    }
+
+    #[inline]
+    fn reset_vi_command(&mut self) {
+        self.vi_command.reset();
+    }
+
     fn message(&self) -> Option<&Message> {
         self.message_buffer.message()
     }
@@ -932,6 +1237,32 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
 }
 
 impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
+    /// Store the text selection in the clipboard.
+    ///
+    /// Explicit copies are recorded in the clipboard history, or stored in the pending vi register
+    /// instead.
+    fn store_selection(&mut self, ty: ClipboardType, explicit: bool) {
+        let text = match self.terminal.selection_to_string().filter(|s| !s.is_empty()) {
+            Some(text) => text,
+            None => return,
+        };
+
+        if let Some(register) = self.vi_command.register().filter(|_| explicit) {
+            self.clipboard.store_register(register, text);
+            return;
+        }
+
+        if ty == ClipboardType::Selection && self.config.selection.save_to_clipboard {
+            self.clipboard.store(ClipboardType::Clipboard, text.clone());
+        }
+
+        if explicit {
+            self.clipboard.copy(ty, text);
+        } else {
+            self.clipboard.store(ty, text);
+        }
+    }
+
     fn update_search(&mut self) {
         let regex = match self.search_state.regex() {
             Some(regex) => regex,
@@ -947,9 +1278,14 @@ impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
             // Stop search if there's nothing to search for.
             self.search_reset_state();
             self.search_state.dfas = None;
+            self.stop_match_count();
         } else {
             // Create search dfas for the new regex string.
-            self.search_state.dfas = RegexSearch::new(regex).ok();
+            let options = self.search_state.options;
+            self.search_state.dfas = RegexSearch::with_options(regex, options).ok();
+
+            // Recount all matches, cancelling the count for the previous regex.
+            self.start_match_count();
 
             // Update search highlighting.
             self.goto_match(MAX_SEARCH_WHILE_TYPING);
@@ -958,6 +1294,19 @@ impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
         *self.dirty = true;
     }
 
+    /// Preview the newest history entry matching the updated history filter.
+    fn update_history_filter(&mut self) {
+        self.search_state.history_index = Some(0);
+        self.search_history_previous();
+
+        // Show the user's input when nothing matches.
+        if self.search_state.history_index == Some(0) {
+            self.update_search();
+        }
+
+        *self.dirty = true;
+    }
+
     /// Reset terminal to the state before search was started.
     fn search_reset_state(&mut self) {
         // Unschedule pending timers.
@@ -982,6 +1331,23 @@ impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
         *self.dirty = true;
     }
 
+    /// Start counting all matches of the active search.
+    ///
+    /// The matches are counted in the background, once the window publishes its changes.
+    fn start_match_count(&mut self) {
+        if self.search_state.dfas.is_none() {
+            self.stop_match_count();
+            return;
+        }
+
+        self.search_state.match_counter = Some(SearchCount::new(self.terminal));
+    }
+
+    /// Cancel counting search matches.
+    fn stop_match_count(&mut self) {
+        self.search_state.match_counter = None;
+    }
+
     /// Jump to the first regex match from the search origin.
     fn goto_match(&mut self, mut limit: Option<usize>) {
         let dfas = match &mut self.search_state.dfas {
@@ -997,6 +1363,15 @@ impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
         let clamped_origin = self.search_state.origin.grid_clamp(self.terminal, Boundary::Grid);
         match self.terminal.search_next(dfas, clamped_origin, direction, Side::Left, limit) {
             Some(regex_match) => {
+                // Recount matches if the terminal content changed since they were counted.
+                let counter = self.search_state.match_counter();
+                if counter.is_some_and(|mut counter| {
+                    counter.update(self.terminal);
+                    counter.is_done() && counter.position(*regex_match.start()).is_none()
+                }) {
+                    self.start_match_count();
+                }
+
                 let old_offset = self.terminal.grid().display_offset() as i32;
 
                 if self.terminal.mode().contains(TermMode::VI) {
@@ -1036,14 +1411,27 @@ impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
         *self.dirty = true;
     }
 
+    /// Check if IME is disabled due to password input.
+    fn password_blocks_ime(&self) -> bool {
+        self.display.password_input && !self.config.terminal.password_ime
+    }
+
     /// Cleanup the search state.
     fn exit_search(&mut self) {
         let vi_mode = self.terminal.mode().contains(TermMode::VI);
-        self.window().set_ime_allowed(!vi_mode);
+        let ime_allowed = !vi_mode && !self.password_blocks_ime();
+        self.window().set_ime_allowed(ime_allowed);
 
         self.display.damage_tracker.frame().mark_fully_damaged();
         self.display.pending_update.dirty = true;
+        // Add the search to the history of all windows.
+        if let Some(regex) = self.search_state.regex().filter(|regex| !regex.is_empty()) {
+            self.search_history.push(&self.config.search, regex.clone());
+        }
+
         self.search_state.history_index = None;
+        self.search_state.history_filter = None;
+        self.stop_match_count();
 
         // Clear focused match.
         self.search_state.focused_match = None;
@@ -1102,6 +1490,84 @@ impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
         self.scheduler.schedule(event, blinking_timeout, false, timer_id);
     }
 
+    /// Convert terminal content to HTML, using the colors of this window.
+    fn range_to_html(&self, range: SelectionRange) -> String {
+        let colors = ColorResolver::new(self.config, &self.display.colors, self.terminal.colors());
+        html::range_to_html(self.terminal, range, colors)
+    }
+
+    /// Execute a complete vi command.
+    fn execute_vi_command(&mut self, command: ViCommand) {
+        match command {
+            ViCommand::Motion { motion, count } => {
+                for _ in 0..count {
+                    self.terminal.vi_motion(motion);
+                }
+            },
+            ViCommand::Operator { operator: ViOperator::Yank, target, count, register } => {
+                self.yank_vi_target(target, count, register);
+            },
+            ViCommand::Paste { register, count } => {
+                if let Some(text) = self.clipboard.register(register).map(|text| text.repeat(count))
+                {
+                    self.paste(&text, true);
+                }
+            },
+            ViCommand::SetMark(name) => {
+                let point = self.terminal.vi_mode_cursor.point;
+                self.terminal.vi_marks.set(name, point);
+            },
+            ViCommand::GotoMark { name, exact } => {
+                let mut point = match self.terminal.vi_marks.get(name) {
+                    Some(point) => point.grid_clamp(self.terminal, Boundary::Grid),
+                    None => return,
+                };
+
+                if !exact {
+                    let row = &self.terminal.grid()[point.line];
+                    let mut columns = (0..self.terminal.columns()).map(Column);
+                    point.column = columns.find(|&column| row[column].c != ' ').unwrap_or_default();
+                }
+
+                self.terminal.vi_jump(point);
+            },
+        }
+    }
+
+    /// Copy the text covered by a vi operator to a register or the clipboard.
+    fn yank_vi_target(&mut self, target: ViTarget, count: usize, register: Option<char>) {
+        let selection = match vi_command::target_selection(self.terminal, target, count) {
+            Some(selection) => selection,
+            None => return,
+        };
+        let ty = selection.ty;
+
+        // Copy the target without changing the active selection.
+        let active_selection = self.terminal.selection.replace(selection);
+        let range = self.terminal.selection.as_ref().and_then(|s| s.to_range(self.terminal));
+        let text = self.terminal.selection_to_string();
+        self.terminal.selection = active_selection;
+
+        match (text.filter(|text| !text.is_empty()), register) {
+            (Some(text), Some(register)) => self.clipboard.store_register(register, text),
+            (Some(text), None) => self.clipboard.copy(ClipboardType::Clipboard, text),
+            (None, _) => (),
+        }
+
+        // Like in vi, move the cursor to the start of the copied text.
+        let cursor = self.terminal.vi_mode_cursor.point;
+        let start = match range {
+            Some(range) if ty == SelectionType::Lines => {
+                Point::new(range.start.line, cursor.column)
+            },
+            Some(range) => range.start,
+            None => return,
+        };
+        if start < cursor {
+            self.terminal.vi_goto_point(start);
+        }
+    }
+
     /// Perform vi mode inline search in the specified direction.
     fn inline_search(&mut self, direction: Direction) {
         let c = match self.inline_search_state.character {
@@ -1216,6 +1682,8 @@ pub struct Mouse {
     pub lines_scrolled: f32,
     pub block_hint_launcher: bool,
     pub hint_highlight_dirty: bool,
+    /// Match selected by a smart selection rule, while the mouse button is held.
+    pub smart_selection: Option<Match>,
     pub inside_text_area: bool,
     pub x: usize,
     pub y: usize,
@@ -1233,6 +1701,7 @@ impl Default for Mouse {
             cell_side: Side::Left,
             hint_highlight_dirty: Default::default(),
             block_hint_launcher: Default::default(),
+            smart_selection: Default::default(),
             inside_text_area: Default::default(),
             lines_scrolled: Default::default(),
             accumulated_scroll: Default::default(),
@@ -1283,6 +1752,8 @@ impl input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>> {
         match event {
             WinitEvent::UserEvent(Event { payload, .. }) => match payload {
                 EventType::SearchNext => self.ctx.goto_match(None),
+                EventType::SearchCount => *self.ctx.dirty = true,
+                EventType::HideScrollbar => *self.ctx.dirty = true,
                 EventType::Scroll(scroll) => self.ctx.scroll(scroll),
                 EventType::BlinkCursor => {
                     // Only change state when timeout isn't reached, since we could get
@@ -1334,7 +1805,7 @@ impl input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>> {
                     },
                     TerminalEvent::ClipboardStore(clipboard_type, content) => {
                         if self.ctx.terminal.is_focused {
-                            self.ctx.clipboard.store(clipboard_type, content);
+                            self.ctx.clipboard.copy(clipboard_type, content);
                         }
                     },
                     TerminalEvent::ClipboardLoad(clipboard_type, format) => {
@@ -1356,6 +1827,17 @@ impl input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>> {
                     TerminalEvent::PtyWrite(text) => self.ctx.write_to_pty(text.into_bytes()),
                     TerminalEvent::MouseCursorDirty => self.reset_mouse_cursor(),
                     TerminalEvent::CursorBlinkingChange => self.ctx.update_cursor_blinking(),
+                    TerminalEvent::PasswordInput(active) => {
+                        self.ctx.display.password_input = active;
+
+                        if !self.ctx.config.terminal.password_ime {
+                            let vi_mode = self.ctx.terminal.mode().contains(TermMode::VI);
+                            let ime_allowed = self.ctx.search_active() || (!active && !vi_mode);
+                            self.ctx.window().set_ime_allowed(ime_allowed);
+                        }
+
+                        *self.ctx.dirty = true;
+                    },
                     TerminalEvent::Exit | TerminalEvent::ChildExit(_) | TerminalEvent::Wakeup => (),
                 },
                 #[cfg(unix)]
@@ -1363,6 +1845,9 @@ impl input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>> {
                 EventType::Message(_)
                 | EventType::ConfigReload(_)
                 | EventType::CreateWindow(_)
+                | EventType::SaveSession
+                | EventType::ToggleRecording
+                | EventType::RestartCommand
                 | EventType::Frame => (),
             },
             WinitEvent::WindowEvent { event, .. } => {
@@ -1535,14 +2020,16 @@ impl Processor {
         event_loop: &EventLoopWindowTarget<Event>,
         proxy: EventLoopProxy<Event>,
         options: WindowOptions,
-    ) -> Result<(), Box<dyn Error>> {
+        session: Option<SessionState>,
+    ) -> Result<WindowId, Box<dyn Error>> {
         let window_context =
-            WindowContext::initial(event_loop, proxy, self.config.clone(), options)?;
+            WindowContext::initial(event_loop, proxy, self.config.clone(), options, session)?;
 
+        let window_id = window_context.id();
         self.gl_display = Some(window_context.display.gl_context().display());
-        self.windows.insert(window_context.id(), window_context);
+        self.windows.insert(window_id, window_context);
 
-        Ok(())
+        Ok(window_id)
     }
 
     /// Create a new terminal window.
@@ -1551,7 +2038,8 @@ impl Processor {
         event_loop: &EventLoopWindowTarget<Event>,
         proxy: EventLoopProxy<Event>,
         options: WindowOptions,
-    ) -> Result<(), Box<dyn Error>> {
+        session: Option<SessionState>,
+    ) -> Result<WindowId, Box<dyn Error>> {
         let window = self.windows.iter().next().as_ref().unwrap().1;
 
         // Overide config with CLI/IPC options.
@@ -1563,10 +2051,22 @@ impl Processor {
 
         #[allow(unused_mut)]
         let mut window_context =
-            window.additional(event_loop, proxy, config, options, config_overrides)?;
+            window.additional(event_loop, proxy, config, options, config_overrides, session)?;
 
-        self.windows.insert(window_context.id(), window_context);
-        Ok(())
+        let window_id = window_context.id();
+        self.windows.insert(window_id, window_context);
+        Ok(window_id)
+    }
+
+    /// Schedule periodic session saves for a window.
+    fn schedule_session_save(&self, scheduler: &mut Scheduler, window_id: WindowId) {
+        let timer_id = TimerId::new(Topic::SaveSession, window_id);
+        scheduler.unschedule(timer_id);
+
+        if let Some(interval) = self.config.session.save_interval() {
+            let event = Event::new(EventType::SaveSession, window_id);
+            scheduler.schedule(event, interval, true, timer_id);
+        }
     }
 
     /// Run the event loop.
@@ -1589,8 +2089,10 @@ impl Processor {
         // SAFETY: Since this takes a pointer to the winit event loop, it MUST be dropped first,
         // which is done by `move` into event loop.
         let mut clipboard = unsafe { Clipboard::new(event_loop.raw_display_handle()) };
+        let mut search_history = SearchHistory::new(&self.config.search);
         let result = event_loop.run(move |event, event_loop| {
-            if self.config.debug.print_events {
+            // Never log keystrokes which might be part of a password.
+            if self.config.debug.print_events && !self.is_password_input(&event) {
                 info!("winit event: {:?}", event);
             }
 
@@ -1610,14 +2112,36 @@ impl Processor {
                         None => return,
                     };
 
-                    if let Err(err) = self.create_initial_window(
+                    let sessions =
+                        if self.config.session.restore { session::take_all() } else { Vec::new() };
+                    let mut sessions = sessions.into_iter();
+
+                    match self.create_initial_window(
                         event_loop,
                         proxy.clone(),
                         initial_window_options,
+                        sessions.next(),
                     ) {
-                        *initial_window_error_loop = Err(err);
-                        event_loop.exit();
-                        return;
+                        Ok(window_id) => self.schedule_session_save(&mut scheduler, window_id),
+                        Err(err) => {
+                            *initial_window_error_loop = Err(err);
+                            event_loop.exit();
+                            return;
+                        },
+                    }
+
+                    // Open a separate window for every other restored session.
+                    for session in sessions {
+                        for window_context in self.windows.values_mut() {
+                            window_context.display.make_not_current();
+                        }
+
+                        let options = WindowOptions::default();
+                        match self.create_window(event_loop, proxy.clone(), options, Some(session))
+                        {
+                            Ok(window_id) => self.schedule_session_save(&mut scheduler, window_id),
+                            Err(err) => error!("Could not restore window: {:?}", err),
+                        }
                     }
 
                     info!("Initialisation complete");
@@ -1645,10 +2169,7 @@ impl Processor {
                     payload: EventType::Terminal(TerminalEvent::Wakeup),
                 }) => {
                     if let Some(window_context) = self.windows.get_mut(&window_id) {
-                        window_context.dirty = true;
-                        if window_context.display.window.has_frame {
-                            window_context.display.window.request_redraw();
-                        }
+                        window_context.wakeup();
                     }
                 },
                 // NOTE: This event bypasses batching to minimize input latency.
@@ -1669,7 +2190,7 @@ impl Processor {
                     payload: EventType::Terminal(TerminalEvent::Exit),
                 }) => {
                     // Remove the closed terminal.
-                    let window_context = match self.windows.remove(&window_id) {
+                    let mut window_context = match self.windows.remove(&window_id) {
                         Some(window_context) => window_context,
                         None => return,
                     };
@@ -1684,7 +2205,16 @@ impl Processor {
                             window_context.write_ref_test_results();
                         }
 
+                        // Keep the last window's content around for the next startup.
+                        if self.config.session.persist {
+                            window_context.save_session(true);
+                        } else {
+                            window_context.remove_session();
+                        }
+
                         event_loop.exit();
+                    } else {
+                        window_context.remove_session();
                     }
                 },
                 WinitEvent::WindowEvent { window_id, event: WindowEvent::RedrawRequested } => {
@@ -1697,12 +2227,49 @@ impl Processor {
                         event_loop,
                         &proxy,
                         &mut clipboard,
+                        &mut search_history,
                         &mut scheduler,
                         event,
                     );
 
                     window_context.draw(&mut scheduler);
                 },
+                // Periodically persist the terminal content.
+                WinitEvent::UserEvent(Event {
+                    window_id: Some(window_id),
+                    payload: EventType::SaveSession,
+                }) => {
+                    if let Some(window_context) = self.windows.get_mut(&window_id) {
+                        window_context.save_session(false);
+                    }
+                },
+                // Start or stop recording the terminal output.
+                WinitEvent::UserEvent(Event {
+                    window_id: Some(window_id),
+                    payload: EventType::ToggleRecording,
+                }) => {
+                    if let Some(window_context) = self.windows.get_mut(&window_id) {
+                        window_context.toggle_recording();
+                    }
+                },
+                // Respawn the child process of a held window.
+                WinitEvent::UserEvent(Event {
+                    window_id: Some(window_id),
+                    payload: EventType::RestartCommand,
+                }) => {
+                    if let Some(window_context) = self.windows.get_mut(&window_id) {
+                        window_context.restart_command();
+                    }
+                },
+                // Show the child's exit status in held windows.
+                WinitEvent::UserEvent(Event {
+                    window_id: Some(window_id),
+                    payload: EventType::Terminal(TerminalEvent::ChildExit(status)),
+                }) => {
+                    if let Some(window_context) = self.windows.get_mut(&window_id) {
+                        window_context.child_exit(status);
+                    }
+                },
                 // Process all pending events.
                 WinitEvent::AboutToWait => {
                     // Dispatch event to all windows.
@@ -1711,6 +2278,7 @@ impl Processor {
                             event_loop,
                             &proxy,
                             &mut clipboard,
+                            &mut search_history,
                             &mut scheduler,
                             WinitEvent::AboutToWait,
                         );
@@ -1741,6 +2309,10 @@ impl Processor {
                         for window_context in self.windows.values_mut() {
                             window_context.update_config(self.config.clone());
                         }
+
+                        for window_id in self.windows.keys() {
+                            self.schedule_session_save(&mut scheduler, *window_id);
+                        }
                     }
                 },
                 // Process IPC config update.
@@ -1786,8 +2358,9 @@ impl Processor {
                         window_context.display.make_not_current();
                     }
 
-                    if let Err(err) = self.create_window(event_loop, proxy.clone(), options) {
-                        error!("Could not open window: {:?}", err);
+                    match self.create_window(event_loop, proxy.clone(), *options, None) {
+                        Ok(window_id) => self.schedule_session_save(&mut scheduler, window_id),
+                        Err(err) => error!("Could not open window: {:?}", err),
                     }
                 },
                 // Process events affecting all windows.
@@ -1797,6 +2370,7 @@ impl Processor {
                             event_loop,
                             &proxy,
                             &mut clipboard,
+                            &mut search_history,
                             &mut scheduler,
                             event.clone().into(),
                         );
@@ -1810,6 +2384,7 @@ impl Processor {
                             event_loop,
                             &proxy,
                             &mut clipboard,
+                            &mut search_history,
                             &mut scheduler,
                             event,
                         );
@@ -1826,6 +2401,17 @@ impl Processor {
         }
     }
 
+    /// Check if an event is keyboard input for a window reading a password.
+    fn is_password_input(&self, event: &WinitEvent<Event>) -> bool {
+        match event {
+            WinitEvent::WindowEvent {
+                window_id,
+                event: WindowEvent::KeyboardInput { .. } | WindowEvent::Ime(_),
+            } => self.windows.get(window_id).is_some_and(|window| window.display.password_input),
+            _ => false,
+        }
+    }
+
     /// Check if an event is irrelevant and can be skipped.
     fn skip_event(event: &WinitEvent<Event>) -> bool {
         match event {
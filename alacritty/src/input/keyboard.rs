@@ -43,6 +43,12 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
             return;
         }
 
+        // All key bindings are disabled while a clipboard history entry is being picked.
+        if self.ctx.display().clipboard_picker.active() {
+            self.clipboard_picker_input(&key, text);
+            return;
+        }
+
         // First key after inline search is captured.
         let inline_state = self.ctx.inline_search_state();
         if mem::take(&mut inline_state.char_pending) {
@@ -60,8 +66,21 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
         // Reset search delay when the user is still typing.
         self.reset_search_delay();
 
+        // Counts, operators and text objects of vi commands take precedence over bindings.
+        let vi_command = mode.contains(TermMode::VI) && !self.ctx.search_active();
+        if vi_command && self.vi_command_input(text, mods) {
+            return;
+        }
+
         // Key bindings suppress the character input.
-        if self.process_key_bindings(&key) {
+        let suppress_chars = self.process_key_bindings(&key);
+
+        // Discard vi commands which weren't completed by a motion binding.
+        if vi_command && !text.is_empty() {
+            self.ctx.reset_vi_command();
+        }
+
+        if suppress_chars {
             return;
         }
 
@@ -130,6 +149,41 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
         }
     }
 
+    /// Navigate the clipboard history picker.
+    fn clipboard_picker_input(&mut self, key: &KeyEvent, text: &str) {
+        let picker = &mut self.ctx.display().clipboard_picker;
+        match (key.logical_key.as_ref(), text) {
+            (Key::Named(NamedKey::ArrowDown), _) | (_, "j") => picker.select_next(),
+            (Key::Named(NamedKey::ArrowUp), _) | (_, "k") => picker.select_previous(),
+            (Key::Named(NamedKey::Enter), _) => self.ctx.confirm_clipboard_picker(),
+            (Key::Named(NamedKey::Escape), _) => picker.stop(),
+            // Entries can be picked directly by typing their label.
+            (_, text) if !text.is_empty() => {
+                if picker.label_input(text) {
+                    self.ctx.confirm_clipboard_picker();
+                }
+            },
+            _ => return,
+        }
+
+        self.ctx.mark_dirty();
+    }
+
+    /// Pass text input to the pending vi command.
+    ///
+    /// Returns `true` if the input was consumed by the vi command.
+    fn vi_command_input(&mut self, text: &str, mods: ModifiersState) -> bool {
+        if mods.intersects(ModifiersState::CONTROL | ModifiersState::ALT | ModifiersState::SUPER) {
+            return false;
+        }
+
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => self.ctx.vi_command_input(c),
+            _ => false,
+        }
+    }
+
     /// Check whether we should try to build escape sequence for the [`KeyEvent`].
     fn should_build_sequence(
         key: &KeyEvent,
@@ -30,7 +30,7 @@ use alacritty_terminal::event::EventListener;
 use alacritty_terminal::grid::{Dimensions, Scroll};
 use alacritty_terminal::index::{Boundary, Column, Direction, Point, Side};
 use alacritty_terminal::selection::SelectionType;
-use alacritty_terminal::term::search::Match;
+use alacritty_terminal::term::search::{Match, SearchCase, SearchOptions};
 use alacritty_terminal::term::{ClipboardType, Term, TermMode};
 use alacritty_terminal::vi_mode::ViMotion;
 use alacritty_terminal::vte::ansi::{ClearMode, Handler};
@@ -47,6 +47,7 @@ use crate::message_bar::{self, Message};
 use crate::scheduler::{Scheduler, TimerId, Topic};
 
 pub mod keyboard;
+pub mod vi_command;
 
 /// Font size change interval in px.
 pub const FONT_SIZE_STEP: f32 = 1.;
@@ -80,7 +81,12 @@ pub trait ActionContext<T: EventListener> {
     fn mark_dirty(&mut self) {}
     fn size_info(&self) -> SizeInfo;
     fn copy_selection(&mut self, _ty: ClipboardType) {}
+    fn copy(&mut self, _ty: ClipboardType) {}
+    fn copy_rich_text(&mut self) {}
     fn start_selection(&mut self, _ty: SelectionType, _point: Point, _side: Side) {}
+    fn start_smart_selection(&mut self, _point: Point) -> bool {
+        false
+    }
     fn toggle_selection(&mut self, _ty: SelectionType, _point: Point, _side: Side) {}
     fn update_selection(&mut self, _point: Point, _side: Side) {}
     fn clear_selection(&mut self) {}
@@ -101,6 +107,8 @@ pub trait ActionContext<T: EventListener> {
     fn create_new_window(&mut self) {}
     fn change_font_size(&mut self, _delta: f32) {}
     fn reset_font_size(&mut self) {}
+    fn toggle_recording(&mut self) {}
+    fn restart_command(&mut self) {}
     fn pop_message(&mut self) {}
     fn message(&self) -> Option<&Message>;
     fn config(&self) -> &UiConfig;
@@ -116,7 +124,14 @@ pub trait ActionContext<T: EventListener> {
     fn search_pop_word(&mut self) {}
     fn search_history_previous(&mut self) {}
     fn search_history_next(&mut self) {}
+    fn search_history_filter(&mut self) {}
+    fn stop_search_history_filter(&mut self, _accept: bool) {}
+    fn search_history_filtering(&self) -> bool {
+        false
+    }
+    fn update_search_options<F: FnOnce(&mut SearchOptions)>(&mut self, _f: F) {}
     fn search_next(&mut self, origin: Point, direction: Direction, side: Side) -> Option<Match>;
+    fn search_vi_word(&mut self, _direction: Direction) {}
     fn advance_search_origin(&mut self, _direction: Direction) {}
     fn search_direction(&self) -> Direction;
     fn search_active(&self) -> bool;
@@ -126,8 +141,15 @@ pub trait ActionContext<T: EventListener> {
     fn start_inline_search(&mut self, _direction: Direction, _stop_short: bool) {}
     fn inline_search_next(&mut self) {}
     fn inline_search_previous(&mut self) {}
+    fn vi_motion(&mut self, _motion: ViMotion) {}
+    fn vi_command_input(&mut self, _c: char) -> bool {
+        false
+    }
+    fn reset_vi_command(&mut self) {}
     fn hint_input(&mut self, _character: char) {}
     fn trigger_hint(&mut self, _hint: &HintMatch) {}
+    fn start_clipboard_picker(&mut self) {}
+    fn confirm_clipboard_picker(&mut self) {}
     fn expand_selection(&mut self) {}
     fn on_terminal_input_start(&mut self) {}
     fn paste(&mut self, _text: &str, _bracketed: bool) {}
@@ -179,7 +201,7 @@ impl<T: EventListener> Execute<T> for Action {
             },
             Action::ViMotion(motion) => {
                 ctx.on_typing_start();
-                ctx.terminal_mut().vi_motion(*motion);
+                ctx.vi_motion(*motion);
                 ctx.mark_dirty();
             },
             Action::Vi(ViAction::ToggleNormalSelection) => {
@@ -214,7 +236,7 @@ impl<T: EventListener> Execute<T> for Action {
                 };
 
                 if let Some(regex_match) = ctx.search_next(origin, direction, Side::Left) {
-                    ctx.terminal_mut().vi_goto_point(*regex_match.start());
+                    ctx.terminal_mut().vi_jump(*regex_match.start());
                     ctx.mark_dirty();
                 }
             },
@@ -230,10 +252,18 @@ impl<T: EventListener> Execute<T> for Action {
                 };
 
                 if let Some(regex_match) = ctx.search_next(origin, direction, Side::Left) {
-                    ctx.terminal_mut().vi_goto_point(*regex_match.start());
+                    ctx.terminal_mut().vi_jump(*regex_match.start());
                     ctx.mark_dirty();
                 }
             },
+            Action::Vi(ViAction::SearchWordForward) => {
+                ctx.on_typing_start();
+                ctx.search_vi_word(Direction::Right);
+            },
+            Action::Vi(ViAction::SearchWordBackward) => {
+                ctx.on_typing_start();
+                ctx.search_vi_word(Direction::Left);
+            },
             Action::Vi(ViAction::SearchStart) => {
                 let terminal = ctx.terminal();
                 let origin = terminal.vi_mode_cursor.point.sub(terminal, Boundary::None, 1);
@@ -275,9 +305,28 @@ impl<T: EventListener> Execute<T> for Action {
             },
             Action::Vi(ViAction::InlineSearchNext) => ctx.inline_search_next(),
             Action::Vi(ViAction::InlineSearchPrevious) => ctx.inline_search_previous(),
+            Action::Vi(ViAction::JumpBackward) => {
+                ctx.on_typing_start();
+                ctx.terminal_mut().vi_jump_backward();
+                ctx.mark_dirty();
+            },
+            Action::Vi(ViAction::JumpForward) => {
+                ctx.on_typing_start();
+                ctx.terminal_mut().vi_jump_forward();
+                ctx.mark_dirty();
+            },
             action @ Action::Search(_) if !ctx.search_active() => {
                 debug!("Ignoring {action:?}: Search mode inactive");
             },
+            // Use the recalled history entry instead of moving between matches.
+            Action::Search(
+                SearchAction::SearchConfirm
+                | SearchAction::SearchFocusNext
+                | SearchAction::SearchFocusPrevious,
+            ) if ctx.search_history_filtering() => ctx.stop_search_history_filter(true),
+            Action::Search(SearchAction::SearchCancel) if ctx.search_history_filtering() => {
+                ctx.stop_search_history_filter(false);
+            },
             Action::Search(SearchAction::SearchFocusNext) => {
                 ctx.advance_search_origin(ctx.search_direction());
             },
@@ -295,12 +344,29 @@ impl<T: EventListener> Execute<T> for Action {
             Action::Search(SearchAction::SearchDeleteWord) => ctx.search_pop_word(),
             Action::Search(SearchAction::SearchHistoryPrevious) => ctx.search_history_previous(),
             Action::Search(SearchAction::SearchHistoryNext) => ctx.search_history_next(),
+            Action::Search(SearchAction::SearchHistoryFilter) => ctx.search_history_filter(),
+            Action::Search(SearchAction::SearchToggleLiteral) => {
+                ctx.update_search_options(|options| options.literal = !options.literal);
+            },
+            Action::Search(SearchAction::SearchToggleCase) => {
+                ctx.update_search_options(|options| {
+                    options.case = match options.case {
+                        SearchCase::Smart => SearchCase::Sensitive,
+                        SearchCase::Sensitive => SearchCase::Insensitive,
+                        SearchCase::Insensitive => SearchCase::Smart,
+                    };
+                });
+            },
+            Action::Search(SearchAction::SearchToggleWholeWord) => {
+                ctx.update_search_options(|options| options.whole_word = !options.whole_word);
+            },
             Action::Mouse(MouseAction::ExpandSelection) => ctx.expand_selection(),
             Action::SearchForward => ctx.start_search(Direction::Right),
             Action::SearchBackward => ctx.start_search(Direction::Left),
-            Action::Copy => ctx.copy_selection(ClipboardType::Clipboard),
+            Action::Copy => ctx.copy(ClipboardType::Clipboard),
+            Action::CopyRichText => ctx.copy_rich_text(),
             #[cfg(not(any(target_os = "macos", windows)))]
-            Action::CopySelection => ctx.copy_selection(ClipboardType::Selection),
+            Action::CopySelection => ctx.copy(ClipboardType::Selection),
             Action::ClearSelection => ctx.clear_selection(),
             Action::Paste => {
                 let text = ctx.clipboard_mut().load(ClipboardType::Clipboard);
@@ -310,6 +376,7 @@ impl<T: EventListener> Execute<T> for Action {
                 let text = ctx.clipboard_mut().load(ClipboardType::Selection);
                 ctx.paste(&text, true);
             },
+            Action::ClipboardHistory => ctx.start_clipboard_picker(),
             Action::ToggleFullscreen => ctx.window().toggle_fullscreen(),
             Action::ToggleMaximized => ctx.window().toggle_maximized(),
             #[cfg(target_os = "macos")]
@@ -378,6 +445,8 @@ impl<T: EventListener> Execute<T> for Action {
             },
             Action::ClearHistory => ctx.terminal_mut().clear_screen(ClearMode::Saved),
             Action::ClearLogNotice => ctx.pop_message(),
+            Action::ToggleRecording => ctx.toggle_recording(),
+            Action::RestartCommand => ctx.restart_command(),
             #[cfg(not(target_os = "macos"))]
             Action::CreateNewWindow => ctx.create_new_window(),
             Action::SpawnNewInstance => ctx.spawn_new_instance(),
@@ -428,6 +497,11 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
 
         let (x, y) = position.into();
 
+        // Scroll instead of selecting while the scrollbar is dragged.
+        if self.scrollbar_mouse_moved(x as f32, y as f32) {
+            return;
+        }
+
         let lmb_pressed = self.ctx.mouse().left_button_state == ElementState::Pressed;
         let rmb_pressed = self.ctx.mouse().right_button_state == ElementState::Pressed;
         if !self.ctx.selection_is_empty() && (lmb_pressed || rmb_pressed) {
@@ -488,6 +562,69 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
         }
     }
 
+    /// Drag the scrollbar, or reveal it while the mouse is hovering over it.
+    ///
+    /// Returns `true` if the scrollbar is being dragged.
+    fn scrollbar_mouse_moved(&mut self, x: f32, y: f32) -> bool {
+        let config = self.ctx.config().scrolling.scrollbar;
+        if !config.enabled {
+            return false;
+        }
+
+        let size_info = self.ctx.size_info();
+        let display_offset = self.ctx.terminal().grid().display_offset();
+        let history_size = self.ctx.terminal().grid().history_size();
+
+        let scrollbar = &mut self.ctx.display().scrollbar;
+        if let Some(new_offset) = scrollbar.drag(&size_info, history_size, y) {
+            self.ctx.scroll(Scroll::Delta(new_offset as i32 - display_offset as i32));
+            return true;
+        }
+
+        if !scrollbar.visible(&config) && scrollbar.hovered(&config, &size_info, history_size, x, y)
+        {
+            scrollbar.show();
+            self.ctx.mark_dirty();
+        }
+
+        false
+    }
+
+    /// Handle left mouse button presses and releases on the scrollbar.
+    ///
+    /// Returns `true` if the input was handled by the scrollbar.
+    fn scrollbar_input(&mut self, state: ElementState) -> bool {
+        let config = self.ctx.config().scrolling.scrollbar;
+        if !config.enabled {
+            return false;
+        }
+
+        let size_info = self.ctx.size_info();
+        let display_offset = self.ctx.terminal().grid().display_offset();
+        let history_size = self.ctx.terminal().grid().history_size();
+        let (x, y) = (self.ctx.mouse().x as f32, self.ctx.mouse().y as f32);
+
+        let scrollbar = &mut self.ctx.display().scrollbar;
+        match state {
+            ElementState::Pressed
+                if scrollbar.visible(&config)
+                    && scrollbar.hovered(&config, &size_info, history_size, x, y) =>
+            {
+                let new_offset = scrollbar.start_drag(&size_info, display_offset, history_size, y);
+                self.ctx.scroll(Scroll::Delta(new_offset as i32 - display_offset as i32));
+                self.ctx.mark_dirty();
+                true
+            },
+            ElementState::Released if scrollbar.stop_drag() => {
+                // Redraw to start the inactivity timeout.
+                self.ctx.mark_dirty();
+                true
+            },
+            ElementState::Released => false,
+            ElementState::Pressed => false,
+        }
+    }
+
     /// Check which side of a cell an X coordinate lies on.
     fn cell_side(&self, x: usize) -> Side {
         let size_info = self.ctx.size_info();
@@ -648,7 +785,9 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
             },
             ClickState::DoubleClick => {
                 self.ctx.mouse_mut().block_hint_launcher = true;
-                self.ctx.start_selection(SelectionType::Semantic, point, side);
+                if !self.ctx.start_smart_selection(point) {
+                    self.ctx.start_selection(SelectionType::Semantic, point, side);
+                }
             },
             ClickState::TripleClick => {
                 self.ctx.mouse_mut().block_hint_launcher = true;
@@ -688,6 +827,8 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
         self.ctx.scheduler_mut().unschedule(timer_id);
 
         if let MouseButton::Left | MouseButton::Right = button {
+            self.ctx.mouse_mut().smart_selection = None;
+
             // Copy selection on release, to prevent flooding the display server.
             self.ctx.copy_selection(ClipboardType::Selection);
         }
@@ -949,6 +1090,11 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
             _ => (),
         }
 
+        // Skip normal mouse events if the scrollbar has been clicked.
+        if button == MouseButton::Left && self.scrollbar_input(state) {
+            return;
+        }
+
         // Skip normal mouse events if the message bar has been clicked.
         if self.message_bar_cursor_state() == Some(CursorIcon::Pointer)
             && state == ElementState::Pressed
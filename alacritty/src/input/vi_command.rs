@@ -0,0 +1,727 @@
+//! Vi mode commands composed of counts, operators, motions and text objects.
+
+use std::cmp::{max, min};
+
+use alacritty_terminal::event::EventListener;
+use alacritty_terminal::grid::{BidirectionalIterator, Dimensions, Scroll};
+use alacritty_terminal::index::{Boundary, Column, Direction, Line, Point, Side};
+use alacritty_terminal::selection::{Selection, SelectionType};
+use alacritty_terminal::term::cell::Flags;
+use alacritty_terminal::term::Term;
+use alacritty_terminal::vi_mode::ViMotion;
+
+use crate::display::hint::MAX_SEARCH_LINES;
+
+/// Largest count accepted for a single command.
+const MAX_COUNT: usize = 9999;
+
+/// Input for the vi command parser.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ViInput {
+    /// Character typed in vi mode.
+    Char(char),
+    /// Motion triggered by a key binding.
+    Motion(ViMotion),
+}
+
+/// Operator applied to the text covered by a motion or text object.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ViOperator {
+    /// Copy the text to the clipboard.
+    Yank,
+}
+
+impl ViOperator {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            'y' => Some(Self::Yank),
+            _ => None,
+        }
+    }
+}
+
+/// Text surrounding the vi mode cursor.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextObject {
+    /// Semantically separated word.
+    Word,
+    /// Whitespace separated word.
+    WhitespaceWord,
+    /// Text enclosed by a quote character.
+    Quote(char),
+    /// Text enclosed by a pair of brackets.
+    Bracket(char, char),
+    /// Lines up to the surrounding blank lines.
+    Paragraph,
+}
+
+impl TextObject {
+    fn from_char(c: char) -> Option<Self> {
+        let object = match c {
+            'w' => Self::Word,
+            'W' => Self::WhitespaceWord,
+            '"' | '\'' | '`' => Self::Quote(c),
+            '(' | ')' | 'b' => Self::Bracket('(', ')'),
+            '[' | ']' => Self::Bracket('[', ']'),
+            '{' | '}' | 'B' => Self::Bracket('{', '}'),
+            '<' | '>' => Self::Bracket('<', '>'),
+            'p' => Self::Paragraph,
+            _ => return None,
+        };
+        Some(object)
+    }
+}
+
+/// Text covered by an operator.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ViTarget {
+    /// Text between the vi mode cursor and the destination of a motion.
+    Motion(ViMotion),
+    /// Text object, excluding its delimiters and surrounding whitespace when `inner` is set.
+    TextObject { object: TextObject, inner: bool },
+    /// Lines starting at the vi mode cursor, selected by repeating the operator.
+    Lines,
+}
+
+/// Complete vi command.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ViCommand {
+    /// Repeated motion of the vi mode cursor.
+    Motion { motion: ViMotion, count: usize },
+    /// Operator applied to a motion or text object, using a register instead of the clipboard.
+    Operator { operator: ViOperator, target: ViTarget, count: usize, register: Option<char> },
+    /// Paste the content of a register.
+    Paste { register: char, count: usize },
+    /// Mark the vi mode cursor position.
+    SetMark(char),
+    /// Jump to a mark, or to the first occupied cell of its line unless `exact` is set.
+    GotoMark { name: char, exact: bool },
+}
+
+/// Mark command waiting for the name of the mark.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum PendingMark {
+    Set,
+    Goto { exact: bool },
+}
+
+/// Result of passing input to the vi command parser.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Parsed {
+    /// Input is not part of a vi command.
+    Ignored,
+    /// Input was consumed, but the command is not complete yet.
+    Pending,
+    /// Input was invalid for the pending command, which was discarded.
+    Aborted,
+    /// Input completed a command.
+    Command(ViCommand),
+}
+
+/// Parser composing vi commands from individual keys.
+///
+/// Motions are triggered through key bindings, while counts, operators and text objects are
+/// passed to the parser as characters.
+#[derive(Debug, Default)]
+pub struct ViCommandParser {
+    /// Count typed before the pending operator or motion.
+    count: Option<usize>,
+
+    /// Pending operator, with the count typed before it.
+    operator: Option<(ViOperator, Option<usize>)>,
+
+    /// Pending text object, with `true` for inner objects.
+    text_object: Option<bool>,
+
+    /// Pending mark command.
+    mark: Option<PendingMark>,
+
+    /// Register used by the pending command, with `None` while waiting for its name.
+    register: Option<Option<char>>,
+}
+
+impl ViCommandParser {
+    /// Discard the pending command.
+    #[inline]
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Register selected for the pending command.
+    ///
+    /// This is used by key bindings operating on the selection, like `Copy`.
+    #[inline]
+    pub fn register(&self) -> Option<char> {
+        self.register.flatten()
+    }
+
+    /// Pass the next input to the parser.
+    ///
+    /// Operators are not started while `selecting`, since their key bindings apply them to the
+    /// active selection instead.
+    pub fn input(&mut self, input: ViInput, selecting: bool) -> Parsed {
+        let c = match input {
+            ViInput::Motion(_)
+                if self.text_object.is_some()
+                    || self.mark.is_some()
+                    || self.register == Some(None) =>
+            {
+                return self.abort();
+            },
+            ViInput::Motion(motion) if self.operator.is_none() => {
+                let count = self.count.take().unwrap_or(1);
+                self.register = None;
+                return Parsed::Command(ViCommand::Motion { motion, count });
+            },
+            ViInput::Motion(motion) => return self.complete(ViTarget::Motion(motion)),
+            ViInput::Char(c) => c,
+        };
+
+        if let Some(inner) = self.text_object {
+            return match TextObject::from_char(c) {
+                Some(object) => self.complete(ViTarget::TextObject { object, inner }),
+                None => self.abort(),
+            };
+        }
+
+        if self.register == Some(None) {
+            if !c.is_ascii_alphabetic() {
+                return self.abort();
+            }
+
+            self.register = Some(Some(c));
+            return Parsed::Pending;
+        }
+
+        if let Some(mark) = self.mark {
+            self.reset();
+            return match mark {
+                _ if !c.is_ascii_lowercase() => Parsed::Aborted,
+                PendingMark::Set => Parsed::Command(ViCommand::SetMark(c)),
+                PendingMark::Goto { exact } => {
+                    Parsed::Command(ViCommand::GotoMark { name: c, exact })
+                },
+            };
+        }
+
+        match (c, self.operator) {
+            ('0'..='9', _) if c != '0' || self.count.is_some() => {
+                let digit = c as usize - '0' as usize;
+                let count = self.count.unwrap_or(0).saturating_mul(10).saturating_add(digit);
+                self.count = Some(min(count, MAX_COUNT));
+                Parsed::Pending
+            },
+            ('i' | 'a', Some(_)) => {
+                self.text_object = Some(c == 'i');
+                Parsed::Pending
+            },
+            ('"', None) => {
+                self.register = Some(None);
+                Parsed::Pending
+            },
+            ('p', None) if self.register().is_some() => {
+                let register = self.register().unwrap_or_default();
+                let count = self.count.unwrap_or(1);
+                self.reset();
+                Parsed::Command(ViCommand::Paste { register, count })
+            },
+            ('m', None) => {
+                self.mark = Some(PendingMark::Set);
+                Parsed::Pending
+            },
+            ('\'' | '`', None) => {
+                self.mark = Some(PendingMark::Goto { exact: c == '`' });
+                Parsed::Pending
+            },
+            _ => match (ViOperator::from_char(c), self.operator) {
+                (Some(operator), Some((pending, _))) if operator == pending => {
+                    self.complete(ViTarget::Lines)
+                },
+                (Some(_), Some(_)) => self.abort(),
+                (Some(operator), None) if !selecting => {
+                    self.operator = Some((operator, self.count.take()));
+                    Parsed::Pending
+                },
+                _ => Parsed::Ignored,
+            },
+        }
+    }
+
+    /// Complete the pending operator.
+    fn complete(&mut self, target: ViTarget) -> Parsed {
+        let (operator, operator_count) = match self.operator.take() {
+            Some(operator) => operator,
+            None => return self.abort(),
+        };
+
+        // Counts before the operator and before its target are multiplied, like `2y3w`.
+        let count = operator_count.unwrap_or(1).saturating_mul(self.count.unwrap_or(1));
+        let register = self.register();
+        self.reset();
+
+        let count = min(count, MAX_COUNT);
+        Parsed::Command(ViCommand::Operator { operator, target, count, register })
+    }
+
+    fn abort(&mut self) -> Parsed {
+        self.reset();
+        Parsed::Aborted
+    }
+}
+
+/// Selection covering the target of an operator.
+///
+/// Returns `None` if the target does not cover any text.
+pub fn target_selection<T: EventListener>(
+    term: &mut Term<T>,
+    target: ViTarget,
+    count: usize,
+) -> Option<Selection> {
+    let cursor = term.vi_mode_cursor.point;
+
+    let (ty, start, end) = match target {
+        ViTarget::Motion(motion) => return motion_selection(term, motion, count),
+        ViTarget::Lines => {
+            let end = min(cursor.line + (count as i32 - 1), term.bottommost_line());
+            (SelectionType::Lines, cursor, Point::new(end, cursor.column))
+        },
+        ViTarget::TextObject { object: TextObject::Paragraph, inner } => {
+            let (start, end) = paragraph(term, cursor.line, inner);
+            (SelectionType::Lines, Point::new(start, Column(0)), Point::new(end, Column(0)))
+        },
+        ViTarget::TextObject { object: TextObject::Word, inner } => {
+            let separators = term.semantic_escape_chars().to_owned();
+            let (start, end) = word(term, cursor, &separators, inner);
+            (SelectionType::Simple, start, end)
+        },
+        ViTarget::TextObject { object: TextObject::WhitespaceWord, inner } => {
+            let (start, end) = word(term, cursor, "", inner);
+            (SelectionType::Simple, start, end)
+        },
+        ViTarget::TextObject { object: TextObject::Quote(quote), inner } => {
+            let (start, end) = quotes(term, cursor, quote, inner)?;
+            (SelectionType::Simple, start, end)
+        },
+        ViTarget::TextObject { object: TextObject::Bracket(open, close), inner } => {
+            let (start, end) = brackets(term, cursor, open, close, inner)?;
+            (SelectionType::Simple, start, end)
+        },
+    };
+
+    let mut selection = Selection::new(ty, start, Side::Left);
+    selection.update(end, Side::Right);
+    Some(selection)
+}
+
+/// Selection between the vi mode cursor and the destination of a repeated motion.
+fn motion_selection<T: EventListener>(
+    term: &mut Term<T>,
+    motion: ViMotion,
+    count: usize,
+) -> Option<Selection> {
+    let cursor = term.vi_mode_cursor;
+
+    // Motions scroll to their destination, which shouldn't affect the viewport.
+    let display_offset = term.grid().display_offset();
+    let destination = (0..count).fold(cursor, |destination, _| destination.motion(term, motion));
+    let delta = display_offset as i32 - term.grid().display_offset() as i32;
+    term.scroll_display(Scroll::Delta(delta));
+
+    let start = min(cursor.point, destination.point);
+    let end = max(cursor.point, destination.point);
+
+    let (ty, end_side) = match motion {
+        ViMotion::Up | ViMotion::Down | ViMotion::High | ViMotion::Middle | ViMotion::Low => {
+            (SelectionType::Lines, Side::Right)
+        },
+        ViMotion::Last
+        | ViMotion::SemanticRightEnd
+        | ViMotion::WordRightEnd
+        | ViMotion::Bracket => (SelectionType::Simple, Side::Right),
+        // The destination of exclusive motions is not part of the selection.
+        _ if start == end => return None,
+        _ => (SelectionType::Simple, Side::Left),
+    };
+
+    let mut selection = Selection::new(ty, start, Side::Left);
+    selection.update(end, end_side);
+    Some(selection)
+}
+
+/// Lines of the paragraph at `line`.
+///
+/// A paragraph is a block of either blank or non-blank lines. Outer paragraphs include the blank
+/// lines after the paragraph, or before it when there are none after it.
+fn paragraph<T>(term: &Term<T>, line: Line, inner: bool) -> (Line, Line) {
+    let blank = is_blank(term, line);
+    let mut start = expand_lines(term, line, -1, |line| is_blank(term, line) == blank);
+    let mut end = expand_lines(term, line, 1, |line| is_blank(term, line) == blank);
+
+    if inner {
+        return (start, end);
+    }
+
+    // Blank paragraphs include the following text instead.
+    if blank {
+        if end < term.bottommost_line() {
+            end = expand_lines(term, end + 1, 1, |line| !is_blank(term, line));
+        }
+    } else if end < term.bottommost_line() && is_blank(term, end + 1) {
+        end = expand_lines(term, end + 1, 1, |line| is_blank(term, line));
+    } else if start > term.topmost_line() && is_blank(term, start - 1) {
+        start = expand_lines(term, start - 1, -1, |line| is_blank(term, line));
+    }
+
+    (start, end)
+}
+
+/// Move from `line` in a direction until `f` is `false` for the next line.
+fn expand_lines<T, F>(term: &Term<T>, mut line: Line, step: i32, f: F) -> Line
+where
+    F: Fn(Line) -> bool,
+{
+    loop {
+        let next = line + step;
+        if next < term.topmost_line() || next > term.bottommost_line() || !f(next) {
+            return line;
+        }
+        line = next;
+    }
+}
+
+/// Check if a line contains only whitespace.
+fn is_blank<T>(term: &Term<T>, line: Line) -> bool {
+    let row = &term.grid()[line];
+    (0..term.columns()).all(|column| row[Column(column)].c.is_whitespace())
+}
+
+/// Start and end of the word at `point`.
+///
+/// Whitespace between words is treated as separate word. Outer words include the whitespace after
+/// the word, or before it when there is none after it.
+fn word<T>(term: &Term<T>, point: Point, separators: &str, inner: bool) -> (Point, Point) {
+    let class = CharClass::new(term, point, separators);
+    let same_class = |point| CharClass::new(term, point, separators) == class;
+    let mut start = expand(term, point, Direction::Left, same_class);
+    let mut end = expand(term, point, Direction::Right, same_class);
+
+    if inner {
+        return (start, end);
+    }
+
+    let is_whitespace = |point| CharClass::new(term, point, separators) == CharClass::Whitespace;
+    let after = adjacent(term, end, Direction::Right);
+    let before = adjacent(term, start, Direction::Left);
+
+    if class == CharClass::Whitespace {
+        // Include the word after the whitespace.
+        if let Some(after) = after {
+            let class = CharClass::new(term, after, separators);
+            end = expand(term, after, Direction::Right, |point| {
+                CharClass::new(term, point, separators) == class
+            });
+        }
+    } else if let Some(after) = after.filter(|&point| is_whitespace(point)) {
+        end = expand(term, after, Direction::Right, is_whitespace);
+    } else if let Some(before) = before.filter(|&point| is_whitespace(point)) {
+        start = expand(term, before, Direction::Left, is_whitespace);
+    }
+
+    (start, end)
+}
+
+/// Start and end of the text enclosed by `quote`.
+///
+/// Quotes are paired from the start of the logical line, ignoring quotes escaped by a backslash.
+/// When the cursor is not inside of quotes, the next quoted text in the line is used.
+fn quotes<T>(term: &Term<T>, point: Point, quote: char, inner: bool) -> Option<(Point, Point)> {
+    let grid = term.grid();
+
+    let mut quotes = Vec::new();
+    let mut escaped = false;
+    let mut next = Some(expand(term, point, Direction::Left, |_| true));
+    while let Some(point) = next {
+        let c = grid[point].c;
+        if c == quote && !escaped {
+            quotes.push(point);
+        }
+        escaped = c == '\\' && !escaped;
+
+        next = adjacent(term, point, Direction::Right);
+    }
+
+    let (open, close) =
+        quotes.chunks_exact(2).map(|pair| (pair[0], pair[1])).find(|&(_, close)| close >= point)?;
+
+    if inner {
+        inner_range(term, open, close)
+    } else {
+        Some((open, close))
+    }
+}
+
+/// Start and end of the text enclosed by the brackets surrounding `point`.
+fn brackets<T>(
+    term: &Term<T>,
+    point: Point,
+    open: char,
+    close: char,
+    inner: bool,
+) -> Option<(Point, Point)> {
+    let grid = term.grid();
+    let topmost_line = max(point.line - MAX_SEARCH_LINES, term.topmost_line());
+    let bottommost_line = min(point.line + MAX_SEARCH_LINES, term.bottommost_line());
+
+    // Find the unmatched opening bracket before the cursor.
+    let mut depth = 0;
+    let mut iter = grid.iter_from(point);
+    let open_point = if grid[point].c == open {
+        point
+    } else {
+        loop {
+            let cell = iter.prev().filter(|cell| cell.point.line >= topmost_line)?;
+            match cell.c {
+                c if c == open && depth == 0 => break cell.point,
+                c if c == open => depth -= 1,
+                c if c == close => depth += 1,
+                _ => (),
+            }
+        }
+    };
+
+    // Find the matching closing bracket.
+    let mut depth = 0;
+    let mut iter = grid.iter_from(open_point);
+    let close_point = loop {
+        let cell = iter.next().filter(|cell| cell.point.line <= bottommost_line)?;
+        match cell.c {
+            c if c == close && depth == 0 => break cell.point,
+            c if c == close => depth -= 1,
+            c if c == open => depth += 1,
+            _ => (),
+        }
+    };
+
+    if inner {
+        inner_range(term, open_point, close_point)
+    } else {
+        Some((open_point, close_point))
+    }
+}
+
+/// Text between two delimiters, excluding the delimiters themselves.
+fn inner_range<T>(term: &Term<T>, open: Point, close: Point) -> Option<(Point, Point)> {
+    let start = open.add(term, Boundary::Grid, 1);
+    let end = close.sub(term, Boundary::Grid, 1);
+    (start <= end && start != close).then_some((start, end))
+}
+
+/// Move from `point` in a direction within its logical line, until `f` is `false` for the next
+/// cell.
+fn expand<T, F>(term: &Term<T>, mut point: Point, direction: Direction, f: F) -> Point
+where
+    F: Fn(Point) -> bool,
+{
+    while let Some(next) = adjacent(term, point, direction).filter(|&next| f(next)) {
+        point = next;
+    }
+    point
+}
+
+/// Neighboring cell within the same logical line.
+fn adjacent<T>(term: &Term<T>, point: Point, direction: Direction) -> Option<Point> {
+    let last_column = term.last_column();
+    match direction {
+        Direction::Right if point.column < last_column => Some(point.add(term, Boundary::None, 1)),
+        Direction::Right => is_wrap(term, point).then(|| Point::new(point.line + 1, Column(0))),
+        Direction::Left if point.column > 0 => Some(point.sub(term, Boundary::None, 1)),
+        Direction::Left => {
+            let wrap_point = Point::new(point.line - 1, last_column);
+            (point.line > term.topmost_line() && is_wrap(term, wrap_point)).then_some(wrap_point)
+        },
+    }
+}
+
+/// Check if a line is continued by the next line.
+fn is_wrap<T>(term: &Term<T>, point: Point) -> bool {
+    point.line < term.bottommost_line() && term.grid()[point].flags.contains(Flags::WRAPLINE)
+}
+
+/// Kind of character, used for finding word boundaries.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum CharClass {
+    Whitespace,
+    Separator,
+    Word,
+}
+
+impl CharClass {
+    fn new<T>(term: &Term<T>, mut point: Point, separators: &str) -> Self {
+        // Spacers belong to the wide char before them.
+        let flags = term.grid()[point].flags;
+        if flags.contains(Flags::WIDE_CHAR_SPACER) && point.column > 0 {
+            point.column -= 1;
+        } else if flags.contains(Flags::LEADING_WIDE_CHAR_SPACER) {
+            return Self::Whitespace;
+        }
+
+        match term.grid()[point].c {
+            c if c.is_whitespace() || c == '\0' => Self::Whitespace,
+            c if separators.contains(c) => Self::Separator,
+            _ => Self::Word,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alacritty_terminal::headless::Headless;
+
+    fn parse(parser: &mut ViCommandParser, keys: &str) -> Vec<Parsed> {
+        keys.chars()
+            .map(|c| match c {
+                'j' => parser.input(ViInput::Motion(ViMotion::Down), false),
+                'l' => parser.input(ViInput::Motion(ViMotion::Right), false),
+                c => parser.input(ViInput::Char(c), false),
+            })
+            .collect()
+    }
+
+    fn yank(target: ViTarget, count: usize) -> Parsed {
+        let register = None;
+        Parsed::Command(ViCommand::Operator { operator: ViOperator::Yank, target, count, register })
+    }
+
+    #[test]
+    fn parse_counts() {
+        let mut parser = ViCommandParser::default();
+
+        let motion = ViCommand::Motion { motion: ViMotion::Down, count: 1 };
+        assert_eq!(parse(&mut parser, "j"), [Parsed::Command(motion)]);
+
+        let motion = ViCommand::Motion { motion: ViMotion::Down, count: 10 };
+        assert_eq!(
+            parse(&mut parser, "10j"),
+            [Parsed::Pending, Parsed::Pending, Parsed::Command(motion)]
+        );
+
+        // A leading zero is not a count.
+        assert_eq!(parse(&mut parser, "0"), [Parsed::Ignored]);
+
+        parse(&mut parser, "99999999");
+        let motion = ViCommand::Motion { motion: ViMotion::Down, count: MAX_COUNT };
+        assert_eq!(parse(&mut parser, "j"), [Parsed::Command(motion)]);
+    }
+
+    #[test]
+    fn parse_operators() {
+        let mut parser = ViCommandParser::default();
+
+        assert_eq!(parse(&mut parser, "yy")[1], yank(ViTarget::Lines, 1));
+        assert_eq!(parse(&mut parser, "3yy")[2], yank(ViTarget::Lines, 3));
+        assert_eq!(parse(&mut parser, "2y3l")[3], yank(ViTarget::Motion(ViMotion::Right), 6));
+
+        // Operators are left to the bindings while selecting.
+        assert_eq!(parser.input(ViInput::Char('y'), true), Parsed::Ignored);
+
+        // Unknown characters keep the pending command.
+        assert_eq!(parse(&mut parser, "yxj")[2], yank(ViTarget::Motion(ViMotion::Down), 1));
+    }
+
+    #[test]
+    fn parse_text_objects() {
+        let mut parser = ViCommandParser::default();
+
+        let inner_word = ViTarget::TextObject { object: TextObject::Word, inner: true };
+        assert_eq!(parse(&mut parser, "yiw")[2], yank(inner_word, 1));
+
+        let around_parens =
+            ViTarget::TextObject { object: TextObject::Bracket('(', ')'), inner: false };
+        assert_eq!(parse(&mut parser, "ya)")[2], yank(around_parens, 1));
+
+        let inner_quotes = ViTarget::TextObject { object: TextObject::Quote('"'), inner: true };
+        assert_eq!(parse(&mut parser, "yi\"")[2], yank(inner_quotes, 1));
+
+        // Invalid text objects abort the command.
+        assert_eq!(parse(&mut parser, "yix"), [Parsed::Pending, Parsed::Pending, Parsed::Aborted]);
+        assert_eq!(parse(&mut parser, "i"), [Parsed::Ignored]);
+    }
+
+    #[test]
+    fn parse_marks() {
+        let mut parser = ViCommandParser::default();
+
+        assert_eq!(parse(&mut parser, "ma")[1], Parsed::Command(ViCommand::SetMark('a')));
+
+        let goto = ViCommand::GotoMark { name: 'b', exact: false };
+        assert_eq!(parse(&mut parser, "'b")[1], Parsed::Command(goto));
+
+        let goto = ViCommand::GotoMark { name: 'b', exact: true };
+        assert_eq!(parse(&mut parser, "`b")[1], Parsed::Command(goto));
+
+        assert_eq!(parse(&mut parser, "m1"), [Parsed::Pending, Parsed::Aborted]);
+        assert_eq!(parse(&mut parser, "y'"), [Parsed::Pending, Parsed::Ignored]);
+    }
+
+    #[test]
+    fn parse_registers() {
+        let mut parser = ViCommandParser::default();
+
+        let operator = ViOperator::Yank;
+        let yank = ViCommand::Operator {
+            operator,
+            target: ViTarget::Lines,
+            count: 2,
+            register: Some('a'),
+        };
+        assert_eq!(parse(&mut parser, "\"a2yy")[4], Parsed::Command(yank));
+
+        let paste = ViCommand::Paste { register: 'b', count: 3 };
+        assert_eq!(parse(&mut parser, "3\"bp")[3], Parsed::Command(paste));
+
+        // Selection bindings use the register until the parser is reset.
+        assert_eq!(parse(&mut parser, "\"C"), [Parsed::Pending, Parsed::Pending]);
+        assert_eq!(parser.input(ViInput::Char('y'), true), Parsed::Ignored);
+        assert_eq!(parser.register(), Some('C'));
+
+        parser.reset();
+        assert_eq!(parse(&mut parser, "p"), [Parsed::Ignored]);
+        assert_eq!(parse(&mut parser, "\"1"), [Parsed::Pending, Parsed::Aborted]);
+    }
+
+    fn text_object(text: &str, cursor: Point, object: TextObject, inner: bool) -> String {
+        let mut terminal = Headless::new(20, 4);
+        terminal.feed(text.replace('\n', "\r\n").as_bytes());
+
+        let term = terminal.term_mut();
+        term.vi_mode_cursor.point = cursor;
+        let selection = target_selection(term, ViTarget::TextObject { object, inner }, 1);
+        term.selection = selection;
+        term.selection_to_string().unwrap_or_default()
+    }
+
+    #[test]
+    fn text_objects() {
+        let cursor = Point::new(Line(0), Column(6));
+        assert_eq!(text_object("echo foo  bar", cursor, TextObject::Word, true), "foo");
+        assert_eq!(text_object("echo foo  bar", cursor, TextObject::Word, false), "foo  ");
+
+        let text = "a \"b\" \"c d\" e";
+        let cursor = Point::new(Line(0), Column(8));
+        assert_eq!(text_object(text, cursor, TextObject::Quote('"'), true), "c d");
+        assert_eq!(text_object(text, cursor, TextObject::Quote('"'), false), "\"c d\"");
+
+        let text = "f(a, (b), c)\nnext";
+        let cursor = Point::new(Line(0), Column(3));
+        assert_eq!(text_object(text, cursor, TextObject::Bracket('(', ')'), true), "a, (b), c");
+        let cursor = Point::new(Line(0), Column(7));
+        assert_eq!(text_object(text, cursor, TextObject::Bracket('(', ')'), false), "(b)");
+
+        let text = "one\ntwo\n\nthree";
+        let cursor = Point::new(Line(1), Column(0));
+        assert_eq!(text_object(text, cursor, TextObject::Paragraph, true), "one\ntwo\n");
+        assert_eq!(text_object(text, cursor, TextObject::Paragraph, false), "one\ntwo\n\n");
+    }
+}
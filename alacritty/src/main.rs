@@ -44,7 +44,12 @@ mod migrate;
 #[cfg(windows)]
 mod panic;
 mod renderer;
+#[cfg(not(windows))]
+mod replay;
 mod scheduler;
+mod search_count;
+mod search_history;
+mod session;
 mod string;
 mod window_context;
 
@@ -200,6 +200,26 @@ impl Renderer {
         }
     }
 
+    /// Draw the terminal grid, only rebuilding the glyphs of dirty rows.
+    ///
+    /// The `cells` must contain all cells of the rows marked as dirty and no others.
+    pub fn draw_rows<I: Iterator<Item = RenderableCell>>(
+        &mut self,
+        size_info: &SizeInfo,
+        glyph_cache: &mut GlyphCache,
+        dirty: &[bool],
+        cells: I,
+    ) {
+        match &mut self.text_renderer {
+            TextRendererProvider::Gles2(renderer) => {
+                renderer.draw_rows(size_info, glyph_cache, dirty, cells)
+            },
+            TextRendererProvider::Glsl3(renderer) => {
+                renderer.draw_rows(size_info, glyph_cache, dirty, cells)
+            },
+        }
+    }
+
     /// Draw a string in a variable location. Used for printing the render timer, warnings and
     /// errors.
     pub fn draw_string(
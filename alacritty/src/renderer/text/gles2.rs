@@ -15,8 +15,8 @@ use crate::renderer::{cstr, Error, GlExtensions};
 
 use super::atlas::{Atlas, ATLAS_SIZE};
 use super::{
-    glsl3, Glyph, LoadGlyph, LoaderApi, RenderingGlyphFlags, RenderingPass, TextRenderApi,
-    TextRenderBatch, TextRenderer, TextShader,
+    glsl3, Glyph, LoadGlyph, LoaderApi, RenderingGlyphFlags, RenderingPass, RowInstances,
+    TextRenderApi, TextRenderBatch, TextRenderer, TextShader,
 };
 
 // Shader source.
@@ -31,6 +31,7 @@ pub struct Gles2Renderer {
     ebo: GLuint,
     atlas: Vec<Atlas>,
     batch: Batch,
+    rows: RowInstances<[TextVertex; 4]>,
     current_atlas: usize,
     active_tex: GLuint,
     dual_source_blending: bool,
@@ -151,6 +152,7 @@ impl Gles2Renderer {
             ebo,
             atlas: vec![Atlas::new(ATLAS_SIZE, is_gles_context)],
             batch: Batch::new(),
+            rows: Default::default(),
             current_atlas: 0,
             active_tex: 0,
             dual_source_blending,
@@ -192,6 +194,7 @@ impl<'a> TextRenderer<'a> for Gles2Renderer {
         let res = func(RenderApi {
             active_tex: &mut self.active_tex,
             batch: &mut self.batch,
+            rows: &mut self.rows,
             atlas: &mut self.atlas,
             current_atlas: &mut self.current_atlas,
             program: &mut self.program,
@@ -261,6 +264,8 @@ impl Batch {
 }
 
 impl TextRenderBatch for Batch {
+    type Instance = [TextVertex; 4];
+
     #[inline]
     fn tex(&self) -> GLuint {
         self.tex
@@ -276,11 +281,7 @@ impl TextRenderBatch for Batch {
         self.len() == 0
     }
 
-    fn add_item(&mut self, cell: &RenderableCell, glyph: &Glyph, size_info: &SizeInfo) {
-        if self.is_empty() {
-            self.tex = glyph.tex_id;
-        }
-
+    fn instance(cell: &RenderableCell, glyph: &Glyph, size_info: &SizeInfo) -> [TextVertex; 4] {
         // Calculate the cell position.
         let x = cell.point.column.0 as i16 * size_info.cell_width() as i16;
         let y = cell.point.line as i16 * size_info.cell_height() as i16;
@@ -297,7 +298,7 @@ impl TextRenderBatch for Batch {
 
         let is_wide = if cell.flags.contains(Flags::WIDE_CHAR) { 2 } else { 1 };
 
-        let mut vertex = TextVertex {
+        let mut vertices = [TextVertex {
             x,
             y: y + size_info.cell_height() as i16,
 
@@ -314,29 +315,39 @@ impl TextRenderBatch for Batch {
             bg_g: cell.bg.g,
             bg_b: cell.bg.b,
             bg_a: (cell.bg_alpha * 255.0) as u8,
-        };
-
-        self.vertices.push(vertex);
+        }; 4];
 
+        let vertex = &mut vertices[1];
         vertex.y = y;
         vertex.glyph_y = glyph_y;
         vertex.u = glyph.uv_left;
         vertex.v = glyph.uv_bot;
-        self.vertices.push(vertex);
 
+        let vertex = &mut vertices[2];
         vertex.x = x + is_wide * size_info.cell_width() as i16;
+        vertex.y = y;
         vertex.glyph_x = glyph_x + glyph.width;
+        vertex.glyph_y = glyph_y;
         vertex.u = glyph.uv_left + glyph.uv_width;
         vertex.v = glyph.uv_bot;
-        self.vertices.push(vertex);
 
+        let vertex = &mut vertices[3];
         vertex.x = x + is_wide * size_info.cell_width() as i16;
         vertex.y = y + size_info.cell_height() as i16;
         vertex.glyph_x = glyph_x + glyph.width;
         vertex.glyph_y = glyph_y + glyph.height;
         vertex.u = glyph.uv_left + glyph.uv_width;
         vertex.v = glyph.uv_bot + glyph.uv_height;
-        self.vertices.push(vertex);
+
+        vertices
+    }
+
+    fn add_instance(&mut self, tex: GLuint, instance: [TextVertex; 4]) {
+        if self.is_empty() {
+            self.tex = tex;
+        }
+
+        self.vertices.extend_from_slice(&instance);
     }
 }
 
@@ -344,6 +355,7 @@ impl TextRenderBatch for Batch {
 pub struct RenderApi<'a> {
     active_tex: &'a mut GLuint,
     batch: &'a mut Batch,
+    rows: &'a mut RowInstances<[TextVertex; 4]>,
     atlas: &'a mut Vec<Atlas>,
     current_atlas: &'a mut usize,
     program: &'a mut TextShaderProgram,
@@ -373,6 +385,10 @@ impl<'a> TextRenderApi<Batch> for RenderApi<'a> {
         self.batch
     }
 
+    fn rows(&mut self) -> &mut RowInstances<[TextVertex; 4]> {
+        self.rows
+    }
+
     fn render_batch(&mut self) {
         unsafe {
             gl::BufferSubData(
@@ -429,7 +445,7 @@ impl<'a> TextRenderApi<Batch> for RenderApi<'a> {
 
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
-struct TextVertex {
+pub struct TextVertex {
     // Cell coordinates.
     x: i16,
     y: i16,
@@ -15,7 +15,7 @@ use crate::renderer::{cstr, Error};
 
 use super::atlas::{Atlas, ATLAS_SIZE};
 use super::{
-    Glyph, LoadGlyph, LoaderApi, RenderingGlyphFlags, RenderingPass, TextRenderApi,
+    Glyph, LoadGlyph, LoaderApi, RenderingGlyphFlags, RenderingPass, RowInstances, TextRenderApi,
     TextRenderBatch, TextRenderer, TextShader,
 };
 
@@ -36,6 +36,7 @@ pub struct Glsl3Renderer {
     current_atlas: usize,
     active_tex: GLuint,
     batch: Batch,
+    rows: RowInstances<InstanceData>,
 }
 
 impl Glsl3Renderer {
@@ -141,6 +142,7 @@ impl Glsl3Renderer {
             current_atlas: 0,
             active_tex: 0,
             batch: Batch::new(),
+            rows: Default::default(),
         })
     }
 }
@@ -167,6 +169,7 @@ impl<'a> TextRenderer<'a> for Glsl3Renderer {
         let res = func(RenderApi {
             active_tex: &mut self.active_tex,
             batch: &mut self.batch,
+            rows: &mut self.rows,
             atlas: &mut self.atlas,
             current_atlas: &mut self.current_atlas,
             program: &mut self.program,
@@ -214,6 +217,7 @@ impl Drop for Glsl3Renderer {
 pub struct RenderApi<'a> {
     active_tex: &'a mut GLuint,
     batch: &'a mut Batch,
+    rows: &'a mut RowInstances<InstanceData>,
     atlas: &'a mut Vec<Atlas>,
     current_atlas: &'a mut usize,
     program: &'a mut TextShaderProgram,
@@ -224,6 +228,10 @@ impl<'a> TextRenderApi<Batch> for RenderApi<'a> {
         self.batch
     }
 
+    fn rows(&mut self) -> &mut RowInstances<InstanceData> {
+        self.rows
+    }
+
     fn render_batch(&mut self) {
         unsafe {
             gl::BufferSubData(
@@ -283,9 +291,9 @@ impl<'a> Drop for RenderApi<'a> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 #[repr(C)]
-struct InstanceData {
+pub struct InstanceData {
     // Coords.
     col: u16,
     row: u16,
@@ -328,6 +336,8 @@ pub struct Batch {
 }
 
 impl TextRenderBatch for Batch {
+    type Instance = InstanceData;
+
     #[inline]
     fn tex(&self) -> GLuint {
         self.tex
@@ -343,16 +353,12 @@ impl TextRenderBatch for Batch {
         self.len() == 0
     }
 
-    fn add_item(&mut self, cell: &RenderableCell, glyph: &Glyph, _: &SizeInfo) {
-        if self.is_empty() {
-            self.tex = glyph.tex_id;
-        }
-
+    fn instance(cell: &RenderableCell, glyph: &Glyph, _: &SizeInfo) -> InstanceData {
         let mut cell_flags = RenderingGlyphFlags::empty();
         cell_flags.set(RenderingGlyphFlags::COLORED, glyph.multicolor);
         cell_flags.set(RenderingGlyphFlags::WIDE_CHAR, cell.flags.contains(Flags::WIDE_CHAR));
 
-        self.instances.push(InstanceData {
+        InstanceData {
             col: cell.point.column.0 as u16,
             row: cell.point.line as u16,
 
@@ -375,7 +381,15 @@ impl TextRenderBatch for Batch {
             bg_g: cell.bg.g,
             bg_b: cell.bg.b,
             bg_a: (cell.bg_alpha * 255.0) as u8,
-        });
+        }
+    }
+
+    fn add_instance(&mut self, tex: GLuint, instance: InstanceData) {
+        if self.is_empty() {
+            self.tex = tex;
+        }
+
+        self.instances.push(instance);
     }
 }
 
@@ -1,3 +1,5 @@
+use std::mem;
+
 use bitflags::bitflags;
 use crossfont::{GlyphKey, RasterizedGlyph};
 
@@ -68,6 +70,25 @@ pub trait TextRenderer<'a> {
         })
     }
 
+    /// Draw the terminal grid, reusing the glyphs of rows which did not change.
+    ///
+    /// The `cells` must contain all cells of the rows marked as dirty and no others.
+    fn draw_rows<'b: 'a, I: Iterator<Item = RenderableCell>>(
+        &'b mut self,
+        size_info: &'b SizeInfo,
+        glyph_cache: &'a mut GlyphCache,
+        dirty: &[bool],
+        cells: I,
+    ) {
+        self.with_api(size_info, |mut api| {
+            api.rows().invalidate(dirty);
+            for cell in cells {
+                api.cache_cell(cell, glyph_cache, size_info);
+            }
+            api.draw_cached_rows();
+        })
+    }
+
     fn with_api<'b: 'a, F, T>(&'b mut self, size_info: &'b SizeInfo, func: F) -> T
     where
         F: FnOnce(Self::RenderApi) -> T;
@@ -97,6 +118,8 @@ pub trait TextRenderer<'a> {
 }
 
 pub trait TextRenderBatch {
+    /// Data required to render a single glyph.
+    type Instance: Copy;
     /// Check if `Batch` is empty.
     fn is_empty(&self) -> bool;
 
@@ -106,8 +129,11 @@ pub trait TextRenderBatch {
     /// Get texture `Batch` is using.
     fn tex(&self) -> GLuint;
 
-    /// Add item to the batch.
-    fn add_item(&mut self, cell: &RenderableCell, glyph: &Glyph, size_info: &SizeInfo);
+    /// Create the render data for a glyph.
+    fn instance(cell: &RenderableCell, glyph: &Glyph, size_info: &SizeInfo) -> Self::Instance;
+
+    /// Add glyph render data to the batch.
+    fn add_instance(&mut self, tex: GLuint, instance: Self::Instance);
 }
 
 pub trait TextRenderApi<T: TextRenderBatch>: LoadGlyph {
@@ -117,15 +143,24 @@ pub trait TextRenderApi<T: TextRenderBatch>: LoadGlyph {
     /// Render the underlying data.
     fn render_batch(&mut self);
 
+    /// Get the cached glyphs of every row.
+    fn rows(&mut self) -> &mut RowInstances<T::Instance>;
+
     /// Add item to the rendering queue.
     #[inline]
     fn add_render_item(&mut self, cell: &RenderableCell, glyph: &Glyph, size_info: &SizeInfo) {
+        self.add_instance(glyph.tex_id, T::instance(cell, glyph, size_info));
+    }
+
+    /// Add glyph render data to the rendering queue.
+    #[inline]
+    fn add_instance(&mut self, tex: GLuint, instance: T::Instance) {
         // Flush batch if tex changing.
-        if !self.batch().is_empty() && self.batch().tex() != glyph.tex_id {
+        if !self.batch().is_empty() && self.batch().tex() != tex {
             self.render_batch();
         }
 
-        self.batch().add_item(cell, glyph, size_info);
+        self.batch().add_instance(tex, instance);
 
         // Render batch and clear if it's full.
         if self.batch().full() {
@@ -136,10 +171,42 @@ pub trait TextRenderApi<T: TextRenderBatch>: LoadGlyph {
     /// Draw cell.
     fn draw_cell(
         &mut self,
-        mut cell: RenderableCell,
+        cell: RenderableCell,
+        glyph_cache: &mut GlyphCache,
+        size_info: &SizeInfo,
+    ) {
+        self.load_glyphs(cell, glyph_cache, |api, cell, glyph| {
+            api.add_render_item(cell, glyph, size_info);
+        });
+    }
+
+    /// Add a cell's glyphs to the cache of its row.
+    fn cache_cell(
+        &mut self,
+        cell: RenderableCell,
         glyph_cache: &mut GlyphCache,
         size_info: &SizeInfo,
     ) {
+        self.load_glyphs(cell, glyph_cache, |api, cell, glyph| {
+            let instance = T::instance(cell, glyph, size_info);
+            api.rows().push(cell.point.line, glyph.tex_id, instance);
+        });
+    }
+
+    /// Draw the cached glyphs of all rows.
+    fn draw_cached_rows(&mut self) {
+        let rows = mem::take(self.rows());
+        for &(tex, instance) in rows.iter() {
+            self.add_instance(tex, instance);
+        }
+        *self.rows() = rows;
+    }
+
+    /// Load all glyphs required to draw a cell.
+    fn load_glyphs<F>(&mut self, mut cell: RenderableCell, glyph_cache: &mut GlyphCache, mut f: F)
+    where
+        F: FnMut(&mut Self, &RenderableCell, &Glyph),
+    {
         // Get font key for cell.
         let font_key = match cell.flags & Flags::BOLD_ITALIC {
             Flags::BOLD_ITALIC => glyph_cache.bold_italic_key,
@@ -159,7 +226,7 @@ pub trait TextRenderApi<T: TextRenderBatch>: LoadGlyph {
 
         // Add cell to batch.
         let glyph = glyph_cache.get(glyph_key, self, true);
-        self.add_render_item(&cell, &glyph, size_info);
+        f(self, &cell, &glyph);
 
         // Render visible zero-width characters.
         if let Some(zerowidth) =
@@ -168,12 +235,49 @@ pub trait TextRenderApi<T: TextRenderBatch>: LoadGlyph {
             for character in zerowidth {
                 glyph_key.character = character;
                 let glyph = glyph_cache.get(glyph_key, self, false);
-                self.add_render_item(&cell, &glyph, size_info);
+                f(self, &cell, &glyph);
             }
         }
     }
 }
 
+/// Glyphs of every row in the terminal grid.
+///
+/// This allows drawing rows which did not change since the last frame, without having to look up
+/// their glyphs and build their render data again.
+#[derive(Debug)]
+pub struct RowInstances<T> {
+    rows: Vec<Vec<(GLuint, T)>>,
+}
+
+impl<T> Default for RowInstances<T> {
+    fn default() -> Self {
+        Self { rows: Vec::new() }
+    }
+}
+
+impl<T> RowInstances<T> {
+    /// Remove the cached glyphs of all dirty rows.
+    fn invalidate(&mut self, dirty: &[bool]) {
+        self.rows.resize_with(dirty.len(), Vec::new);
+        for (row, _) in self.rows.iter_mut().zip(dirty).filter(|(_, dirty)| **dirty) {
+            row.clear();
+        }
+    }
+
+    /// Add a glyph to a row.
+    fn push(&mut self, line: usize, tex: GLuint, instance: T) {
+        if let Some(row) = self.rows.get_mut(line) {
+            row.push((tex, instance));
+        }
+    }
+
+    /// Iterate over the glyphs of all rows.
+    fn iter(&self) -> impl Iterator<Item = &(GLuint, T)> {
+        self.rows.iter().flatten()
+    }
+}
+
 pub trait TextShader {
     fn id(&self) -> GLuint;
 
@@ -0,0 +1,228 @@
+//! Playback of asciicast v2 recordings.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use log::debug;
+use serde::Deserialize;
+use serde_json as json;
+
+use alacritty_terminal::thread;
+
+/// Key toggling the playback pause.
+const PAUSE_KEY: u8 = b' ';
+
+/// Key doubling the playback speed.
+const FASTER_KEY: u8 = b'+';
+
+/// Key halving the playback speed.
+const SLOWER_KEY: u8 = b'-';
+
+/// Bounds of the playback speed.
+const MIN_SPEED: f64 = 1. / 16.;
+const MAX_SPEED: f64 = 64.;
+
+/// Header line of an asciicast recording.
+#[derive(Deserialize)]
+struct Header {
+    version: u32,
+    #[serde(default)]
+    idle_time_limit: Option<f64>,
+}
+
+/// Terminal output of an asciicast recording.
+#[derive(Debug, Default, PartialEq)]
+pub struct Recording {
+    /// Output with its playback time in seconds.
+    output: Vec<(f64, String)>,
+}
+
+impl Recording {
+    /// Load a recording from disk.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)
+            .map_err(|err| io::Error::new(err.kind(), format!("{:?}: {}", path, err)))?;
+        Self::parse(BufReader::new(file))
+    }
+
+    /// Parse a recording.
+    ///
+    /// Only output events are kept, since neither input nor resizes can be replayed.
+    fn parse<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut lines = reader.lines();
+
+        let header = lines.next().transpose()?.unwrap_or_default();
+        let header: Header = json::from_str(&header)?;
+        if header.version != 2 {
+            let msg = format!("Unsupported asciicast version {}", header.version);
+            return Err(io::Error::new(ErrorKind::InvalidData, msg));
+        }
+
+        // Shorten pauses exceeding the idle time limit.
+        let idle_time_limit = header.idle_time_limit.unwrap_or(f64::INFINITY);
+        let mut recorded_time = 0.;
+        let mut playback_time = 0.;
+
+        let mut output = Vec::new();
+        for line in lines {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (time, code, data): (f64, String, String) = json::from_str(&line)?;
+            playback_time += (time - recorded_time).clamp(0., idle_time_limit);
+            recorded_time = time;
+
+            if code == "o" {
+                output.push((playback_time, data));
+            }
+        }
+
+        Ok(Self { output })
+    }
+}
+
+/// Start replaying a recording.
+///
+/// Returns the socket the recorded output is written to. Playback is controlled by writing to
+/// this socket, space toggles the pause while `+` and `-` change the playback speed.
+///
+/// The socket is closed once the replay is done.
+pub fn spawn(recording: Recording, speed: f64) -> io::Result<UnixStream> {
+    let (terminal, mut player) = UnixStream::pair()?;
+
+    thread::spawn_named("replay", move || {
+        let mut playback = Playback::new(speed);
+        if let Err(err) = playback.play(&mut player, recording) {
+            debug!("Replay stopped: {}", err);
+        }
+    });
+
+    Ok(terminal)
+}
+
+/// Playback position of a replay.
+struct Playback {
+    /// Position in the recording in seconds.
+    position: f64,
+
+    /// Last time the position was updated.
+    updated: Instant,
+
+    speed: f64,
+    paused: bool,
+}
+
+impl Playback {
+    fn new(speed: f64) -> Self {
+        Self {
+            speed: speed.clamp(MIN_SPEED, MAX_SPEED),
+            updated: Instant::now(),
+            position: 0.,
+            paused: false,
+        }
+    }
+
+    /// Write all recorded output at its recorded time.
+    fn play(&mut self, stream: &mut UnixStream, recording: Recording) -> io::Result<()> {
+        let mut keys = [0; 64];
+        for (time, data) in recording.output {
+            loop {
+                self.update();
+
+                // Wait for the next output, or for keyboard input while paused.
+                let timeout = if self.paused {
+                    None
+                } else if self.position >= time {
+                    break;
+                } else {
+                    let timeout = Duration::from_secs_f64((time - self.position) / self.speed);
+                    Some(timeout.max(Duration::from_millis(1)))
+                };
+
+                stream.set_read_timeout(timeout)?;
+                match stream.read(&mut keys) {
+                    // The terminal was closed.
+                    Ok(0) => return Ok(()),
+                    Ok(read) => {
+                        self.update();
+                        for key in &keys[..read] {
+                            self.handle_key(*key);
+                        }
+                    },
+                    Err(err) => match err.kind() {
+                        ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted => (),
+                        _ => return Err(err),
+                    },
+                }
+            }
+
+            stream.write_all(data.as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Advance the playback position to the current time.
+    fn update(&mut self) {
+        let now = Instant::now();
+        if !self.paused {
+            self.position += now.duration_since(self.updated).as_secs_f64() * self.speed;
+        }
+        self.updated = now;
+    }
+
+    fn handle_key(&mut self, key: u8) {
+        match key {
+            PAUSE_KEY => self.paused = !self.paused,
+            FASTER_KEY => self.speed = (self.speed * 2.).min(MAX_SPEED),
+            SLOWER_KEY => self.speed = (self.speed / 2.).max(MIN_SPEED),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECORDING: &str = "{\"version\": 2, \"width\": 80, \"height\": 24, \
+                             \"idle_time_limit\": 0.5}\n[0.1, \"o\", \"a\"]\n[0.2, \"i\", \
+                             \"x\"]\n[5.0, \"o\", \"b\\r\\n\"]\n[5.1, \"r\", \"100x30\"]\n";
+
+    #[test]
+    fn parse_recording() {
+        let recording = Recording::parse(RECORDING.as_bytes()).unwrap();
+
+        assert_eq!(recording.output.len(), 2);
+        assert_eq!(recording.output[0], (0.1, String::from("a")));
+        assert_eq!(recording.output[1].1, "b\r\n");
+        assert!((recording.output[1].0 - 0.7).abs() < 1e-9);
+
+        let v1 = "{\"version\": 1, \"width\": 80, \"height\": 24}\n";
+        assert!(Recording::parse(v1.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn replay_and_pause() {
+        let recording = Recording::parse(RECORDING.as_bytes()).unwrap();
+        let mut terminal = spawn(recording, MAX_SPEED).unwrap();
+
+        // Pause before anything could be written.
+        terminal.write_all(b" ").unwrap();
+        terminal.set_read_timeout(Some(Duration::from_millis(50))).unwrap();
+        let mut output = Vec::new();
+        let err = terminal.read_to_end(&mut output).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+
+        // Resume until the recording is done.
+        terminal.write_all(b" ").unwrap();
+        terminal.set_read_timeout(None).unwrap();
+        terminal.read_to_end(&mut output).unwrap();
+        assert_eq!(output, b"ab\r\n");
+    }
+}
@@ -26,9 +26,11 @@ impl TimerId {
 pub enum Topic {
     SelectionScrolling,
     DelayedSearch,
+    HideScrollbar,
     BlinkCursor,
     BlinkTimeout,
     Frame,
+    SaveSession,
 }
 
 /// Event scheduled to be emitted at a specific time.
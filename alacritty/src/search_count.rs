@@ -0,0 +1,142 @@
+//! Counting search matches on a background thread.
+
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+
+use parking_lot::{MappedMutexGuard, Mutex, MutexGuard};
+
+use alacritty_terminal::sync::FairMutex;
+use alacritty_terminal::term::search::{MatchCounter, RegexSearch};
+use alacritty_terminal::term::Term;
+use alacritty_terminal::thread;
+
+/// Minimum time between progress notifications of the counting thread.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Search matches counted on a background thread.
+///
+/// The thread locks the terminal separately for every chunk of lines, so neither the PTY nor the
+/// UI are blocked for long. It stops once all lines are counted or the count is dropped.
+pub struct SearchCount {
+    state: Arc<Mutex<CountState>>,
+}
+
+struct CountState {
+    counter: MatchCounter,
+
+    /// Whether a thread is counting the remaining lines.
+    counting: bool,
+}
+
+impl SearchCount {
+    /// Prepare counting the matches in a terminal.
+    ///
+    /// Counting only starts with the first [`SearchCount::update`].
+    pub fn new<T>(term: &Term<T>) -> Self {
+        let state = CountState { counter: MatchCounter::new(term), counting: false };
+        Self { state: Arc::new(Mutex::new(state)) }
+    }
+
+    /// Matches counted so far.
+    pub fn counter(&self) -> MappedMutexGuard<'_, MatchCounter> {
+        MutexGuard::map(self.state.lock(), |state| &mut state.counter)
+    }
+
+    /// Continue counting after the terminal has changed.
+    ///
+    /// The counted matches are moved along with the lines scrolled into the history, then a thread
+    /// is started for counting all lines which have not been searched yet. The thread calls
+    /// `notify` whenever it made progress.
+    pub fn update<T, F>(
+        &self,
+        term: &Term<T>,
+        terminal: &Arc<FairMutex<Term<T>>>,
+        regex: &RegexSearch,
+        notify: F,
+    ) where
+        T: Send + 'static,
+        F: Fn() + Send + 'static,
+    {
+        let mut state = self.state.lock();
+        state.counter.update(term);
+        if state.counting || state.counter.is_done() {
+            return;
+        }
+        state.counting = true;
+
+        let state = Arc::downgrade(&self.state);
+        let terminal = terminal.clone();
+        let regex = regex.clone();
+        thread::spawn_named("search counter", move || count(state, terminal, regex, notify));
+    }
+}
+
+/// Count the matches chunk by chunk, until all lines are searched.
+fn count<T, F: Fn()>(
+    state: Weak<Mutex<CountState>>,
+    terminal: Arc<FairMutex<Term<T>>>,
+    mut regex: RegexSearch,
+    notify: F,
+) {
+    let mut last_notified = Instant::now();
+
+    loop {
+        let terminal = terminal.lock();
+        let shared = match state.upgrade() {
+            Some(shared) => shared,
+            None => return,
+        };
+        let mut state = shared.lock();
+
+        // Count a single chunk, to release the terminal lock as soon as possible.
+        let done = state.counter.count(&terminal, &mut regex, Instant::now());
+        state.counting = !done;
+        drop(state);
+        drop(terminal);
+
+        if done || last_notified.elapsed() >= PROGRESS_INTERVAL {
+            last_notified = Instant::now();
+            notify();
+        }
+
+        if done {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::mpsc;
+
+    use alacritty_terminal::event::VoidListener;
+    use alacritty_terminal::term::test::TermSize;
+    use alacritty_terminal::term::Config;
+    use alacritty_terminal::vte::ansi::Handler;
+
+    #[test]
+    fn count_in_background() {
+        let size = TermSize::new(10, 5);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        for _ in 0..5000 {
+            term.input('a');
+            term.carriage_return();
+            term.linefeed();
+        }
+        let terminal = Arc::new(FairMutex::new(term));
+
+        let count = SearchCount::new(&terminal.lock());
+        let regex = RegexSearch::new("a").unwrap();
+        let (tx, rx) = mpsc::channel();
+        count.update(&terminal.lock(), &terminal, &regex, move || {
+            let _ = tx.send(());
+        });
+
+        while !count.counter().is_done() {
+            rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        }
+        assert_eq!(count.counter().total(), 5000);
+    }
+}
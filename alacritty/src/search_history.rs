@@ -0,0 +1,374 @@
+//! Search history shared by all windows and persisted across restarts.
+
+use std::collections::{HashSet, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::iter;
+#[cfg(windows)]
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use log::warn;
+
+use alacritty_terminal::thread;
+
+use crate::config::search::SearchConfig;
+
+/// Search terms of all windows, from newest to oldest.
+#[derive(Debug, Default)]
+pub struct SearchHistory {
+    entries: VecDeque<String>,
+
+    /// Entries sent to the writer, whose merged history was not picked up yet.
+    pending: VecDeque<String>,
+
+    /// Location of the history file.
+    path: Option<PathBuf>,
+
+    /// Writer saving the history in the background, started by the first persisted entry.
+    writer: Option<HistoryWriter>,
+}
+
+impl SearchHistory {
+    /// Load the persisted search history.
+    pub fn new(config: &SearchConfig) -> Self {
+        Self::with_path(config, history_path())
+    }
+
+    /// Load the search history persisted at `path`.
+    fn with_path(config: &SearchConfig, path: Option<PathBuf>) -> Self {
+        let mut entries = VecDeque::new();
+        if let Some(path) = path.as_ref().filter(|_| config.persist_history) {
+            match read_entries(path) {
+                Ok(saved) => entries = merge(saved, Vec::new(), config.history_size),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+                Err(err) => warn!("Unable to load search history {:?}: {}", path, err),
+            }
+        }
+
+        Self { entries, path, pending: VecDeque::new(), writer: None }
+    }
+
+    /// Search terms, from newest to oldest.
+    ///
+    /// This includes the entries other instances saved alongside the ones of this instance.
+    #[inline]
+    pub fn entries(&mut self) -> &VecDeque<String> {
+        self.update_saved();
+        &self.entries
+    }
+
+    /// Add a search term to the history.
+    ///
+    /// Duplicates are moved to the front of the history instead of being stored twice.
+    pub fn push(&mut self, config: &SearchConfig, regex: String) {
+        self.update_saved();
+
+        let entries = self.entries.drain(..).rev().collect();
+        self.entries = merge(entries, vec![regex.clone()], config.history_size);
+
+        let path = match self.path.as_ref().filter(|_| config.persist_history) {
+            Some(path) => path,
+            None => return,
+        };
+
+        let writer = self.writer.get_or_insert_with(|| HistoryWriter::new(path.clone()));
+        writer.save(regex.clone(), config.history_size);
+        self.pending.push_back(regex);
+    }
+
+    /// Replace the entries with the last history written to disk.
+    fn update_saved(&mut self) {
+        let saved = match self.writer.as_ref().and_then(HistoryWriter::take_saved) {
+            Some(saved) => saved,
+            None => return,
+        };
+
+        // Keep the entries the writer didn't see yet.
+        self.pending.drain(..saved.count);
+        if let Some(entries) = saved.entries {
+            let pending = self.pending.iter().cloned().collect();
+            let entries = entries.into_iter().rev().collect();
+            self.entries = merge(entries, pending, saved.max_size);
+        }
+    }
+}
+
+/// Background thread merging new entries with the history file.
+///
+/// Blocking on the lock of the history file and rewriting it is kept out of the event loop.
+#[derive(Debug)]
+struct HistoryWriter {
+    sender: Option<Sender<(String, usize)>>,
+
+    /// History merged by the last write, until it's picked up.
+    saved: Arc<Mutex<Option<SavedHistory>>>,
+
+    thread: Option<JoinHandle<()>>,
+}
+
+impl HistoryWriter {
+    fn new(path: PathBuf) -> Self {
+        let (sender, receiver) = mpsc::channel::<(String, usize)>();
+        let saved = Arc::new(Mutex::new(None));
+
+        let thread_saved = saved.clone();
+        let thread = thread::spawn_named("search history writer", move || {
+            while let Ok(entry) = receiver.recv() {
+                // Write all entries which were added while the last write was in progress at once.
+                let (unsaved, max_sizes): (Vec<_>, Vec<_>) =
+                    iter::once(entry).chain(receiver.try_iter()).unzip();
+                let max_size = max_sizes.last().copied().unwrap_or_default();
+                let count = unsaved.len();
+
+                let entries = match update_file(&path, |saved| merge(saved, unsaved, max_size)) {
+                    Ok(entries) => Some(entries),
+                    Err(err) => {
+                        warn!("Unable to save search history {:?}: {}", path, err);
+                        None
+                    },
+                };
+
+                let mut saved = thread_saved.lock().unwrap();
+                let previous: Option<SavedHistory> = saved.take();
+                let count = count + previous.as_ref().map_or(0, |previous| previous.count);
+                let entries = entries.or_else(|| previous.and_then(|previous| previous.entries));
+                *saved = Some(SavedHistory { entries, count, max_size });
+            }
+        });
+
+        Self { sender: Some(sender), saved, thread: Some(thread) }
+    }
+
+    /// Queue an entry for saving.
+    fn save(&self, entry: String, max_size: usize) {
+        if let Some(sender) = &self.sender {
+            let _ = sender.send((entry, max_size));
+        }
+    }
+
+    /// Take the history merged by the last write.
+    fn take_saved(&self) -> Option<SavedHistory> {
+        self.saved.lock().unwrap().take()
+    }
+}
+
+impl Drop for HistoryWriter {
+    fn drop(&mut self) {
+        // Finish writing the queued entries before shutdown.
+        drop(self.sender.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// History written to disk by the writer.
+#[derive(Debug)]
+struct SavedHistory {
+    /// Merged history, from newest to oldest, unless writing failed.
+    entries: Option<VecDeque<String>>,
+
+    /// Number of queued entries included in the history.
+    count: usize,
+
+    max_size: usize,
+}
+
+/// Combine search histories, with the newest entries last.
+///
+/// Only the latest occurrence of every search term is kept. The result is ordered from newest to
+/// oldest.
+fn merge(saved: Vec<String>, unsaved: Vec<String>, max_size: usize) -> VecDeque<String> {
+    let mut seen = HashSet::new();
+    saved
+        .into_iter()
+        .chain(unsaved)
+        .rev()
+        .filter(|entry| !entry.is_empty() && seen.insert(entry.clone()))
+        .take(max_size)
+        .collect()
+}
+
+/// Read all entries of a history file, from oldest to newest.
+fn read_entries(path: &Path) -> io::Result<Vec<String>> {
+    Ok(fs::read_to_string(path)?.lines().map(String::from).collect())
+}
+
+/// Replace the history file's entries, while no other instance is modifying it.
+fn update_file<F>(path: &Path, update: F) -> io::Result<VecDeque<String>>
+where
+    F: FnOnce(Vec<String>) -> VecDeque<String>,
+{
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+
+    // Hold the lock until the new history replaced the old one.
+    let lock_file = private_options().truncate(false).open(path.with_extension("lock"))?;
+    lock(&lock_file)?;
+
+    let saved = match read_entries(path) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+        saved => saved?,
+    };
+    let entries = update(saved);
+
+    // Write to a temporary file first, so a crash never truncates the history.
+    let tmp_path = path.with_extension("tmp");
+    let mut writer = BufWriter::new(private_options().truncate(true).open(&tmp_path)?);
+    for entry in entries.iter().rev() {
+        writeln!(writer, "{}", entry)?;
+    }
+    writer.flush()?;
+    drop(writer);
+
+    fs::rename(&tmp_path, path)?;
+
+    Ok(entries)
+}
+
+/// Options for creating a file only readable by the current user.
+fn private_options() -> OpenOptions {
+    let mut options = OpenOptions::new();
+    options.create(true).write(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    options
+}
+
+/// Check if all characters of the query appear in the text, in the same order.
+///
+/// Characters are compared ignoring case.
+pub fn fuzzy_match(query: &str, text: &str) -> bool {
+    let mut text = text.chars().flat_map(char::to_lowercase);
+    query.chars().flat_map(char::to_lowercase).all(|c| text.any(|text_c| text_c == c))
+}
+
+/// Wait for an exclusive lock on a file, which is released once the file is closed.
+#[cfg(unix)]
+fn lock(file: &File) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    match unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// Wait for an exclusive lock on a file, which is released once the file is closed.
+#[cfg(windows)]
+fn lock(file: &File) -> io::Result<()> {
+    use std::os::windows::io::AsRawHandle;
+
+    use windows_sys::Win32::Storage::FileSystem::{LockFileEx, LOCKFILE_EXCLUSIVE_LOCK};
+    use windows_sys::Win32::System::IO::OVERLAPPED;
+
+    let handle = file.as_raw_handle() as _;
+    let mut overlapped: OVERLAPPED = unsafe { mem::zeroed() };
+    let result = unsafe {
+        LockFileEx(handle, LOCKFILE_EXCLUSIVE_LOCK, 0, u32::MAX, u32::MAX, &mut overlapped)
+    };
+
+    match result {
+        0 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+/// Location of the persisted search history.
+#[cfg(not(windows))]
+fn history_path() -> Option<PathBuf> {
+    let xdg = xdg::BaseDirectories::with_prefix("alacritty").ok()?;
+    Some(xdg.get_state_home().join("search_history"))
+}
+
+/// Location of the persisted search history.
+#[cfg(windows)]
+fn history_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|path| path.join("alacritty").join("search_history"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(entries: &[&str]) -> Vec<String> {
+        entries.iter().map(|entry| entry.to_string()).collect()
+    }
+
+    #[test]
+    fn merge_histories() {
+        // Entries of this instance are newer than the ones on disk.
+        let merged = merge(entries(&["a", "b", "c"]), entries(&["d", "a"]), 10);
+        assert_eq!(merged, entries(&["a", "d", "c", "b"]));
+
+        // Only the newest entries are kept.
+        let merged = merge(entries(&["a", "b", "", "c"]), entries(&["d"]), 2);
+        assert_eq!(merged, entries(&["d", "c"]));
+    }
+
+    #[test]
+    fn update_history_file() {
+        let directory = std::env::temp_dir().join(format!("alacritty-{}", std::process::id()));
+        let path = directory.join("search_history");
+
+        update_file(&path, |saved| merge(saved, entries(&["a", "b"]), 10)).unwrap();
+        let merged = update_file(&path, |saved| merge(saved, entries(&["c", "a"]), 10)).unwrap();
+        assert_eq!(merged, entries(&["a", "c", "b"]));
+        assert_eq!(read_entries(&path).unwrap(), entries(&["b", "c", "a"]));
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn background_writer() {
+        let directory =
+            std::env::temp_dir().join(format!("alacritty-writer-{}", std::process::id()));
+        let path = directory.join("search_history");
+        let config = SearchConfig::default();
+
+        let mut history = SearchHistory::with_path(&config, Some(path.clone()));
+        history.push(&config, "a".into());
+        history.push(&config, "b".into());
+        assert_eq!(history.entries(), &entries(&["b", "a"]));
+
+        // Queued entries are written before the history is dropped.
+        drop(history);
+        assert_eq!(read_entries(&path).unwrap(), entries(&["a", "b"]));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // Entries saved by other instances are picked up after the next write.
+        let mut history = SearchHistory::with_path(&config, Some(path.clone()));
+        update_file(&path, |saved| merge(saved, entries(&["c"]), 10)).unwrap();
+        history.push(&config, "d".into());
+        while history.writer.as_ref().unwrap().saved.lock().unwrap().is_none() {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        assert_eq!(history.entries(), &entries(&["d", "c", "b", "a"]));
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn fuzzy() {
+        assert!(fuzzy_match("", "anything"));
+        assert!(fuzzy_match("grp", "git grep"));
+        assert!(fuzzy_match("GG", "git grep"));
+        assert!(!fuzzy_match("gpg", "git grep"));
+    }
+}
@@ -0,0 +1,388 @@
+//! Persistence of terminal content across restarts.
+
+use std::cmp::min;
+use std::collections::VecDeque;
+use std::fs::{self, DirBuilder, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use serde_json as json;
+
+use alacritty_terminal::event::EventListener;
+use alacritty_terminal::grid::{Dimensions, Row};
+use alacritty_terminal::index::Line;
+use alacritty_terminal::term::cell::Cell;
+use alacritty_terminal::term::Term;
+use alacritty_terminal::vte::ansi::{Attr, Handler};
+
+use crate::config::scrolling::MAX_SCROLLBACK_LINES;
+
+/// Label written into the separator between restored content and the new shell.
+const MARKER_LABEL: &str = " Restored session ";
+
+/// Counter for unique session file names within this process.
+static SESSION_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Serializable state of a single terminal window.
+///
+/// Lines never change after they were scrolled into the history, so the state is updated
+/// incrementally instead of copying the entire grid for every save.
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct SessionState {
+    pub title: Option<String>,
+    pub working_directory: Option<PathBuf>,
+
+    /// Scrollback history, oldest line first.
+    history: VecDeque<Row<Cell>>,
+
+    /// Visible lines up to the cursor.
+    screen: Vec<Row<Cell>>,
+
+    /// Grid history rotations at the time of the last update.
+    #[serde(skip)]
+    rotations: usize,
+
+    /// Grid dimensions at the time of the last update.
+    #[serde(skip)]
+    dimensions: (usize, usize),
+}
+
+impl SessionState {
+    /// Update the terminal content.
+    ///
+    /// Only the lines scrolled into the history since the last update and the visible lines up to
+    /// the cursor are copied.
+    ///
+    /// Since the state is kept in memory, history which exceeds the in-memory scrollback limit is
+    /// not persisted.
+    pub fn update<T>(&mut self, term: &Term<T>) {
+        let grid = term.grid();
+        let history_size = min(grid.history_size(), MAX_SCROLLBACK_LINES as usize);
+        let rotations = grid.history_rotations();
+        let dimensions = (grid.columns(), grid.screen_lines());
+
+        // Drop history lines which were removed from the grid since the last update.
+        let mut new_lines = rotations.wrapping_sub(self.rotations);
+        let kept_lines = min(self.history.len(), history_size.saturating_sub(new_lines));
+        self.history.drain(..self.history.len() - kept_lines);
+
+        // Copy the entire history if it can't be tracked, like after a resize or screen switch.
+        if dimensions != self.dimensions || kept_lines + new_lines != history_size {
+            self.history.clear();
+            new_lines = history_size;
+        }
+
+        // Read history moved to disk without keeping it cached in the grid.
+        let new_lines = (1..=new_lines as i32).rev().map(|line| grid.row_ref(Line(-line)).clone());
+        self.history.extend(new_lines);
+
+        let cursor_line = grid.cursor.point.line;
+        self.screen.clear();
+        self.screen.extend((0..=cursor_line.0).map(|line| grid[Line(line)].clone()));
+
+        self.rotations = rotations;
+        self.dimensions = dimensions;
+    }
+}
+
+/// On-disk location of a window's session.
+#[derive(Clone, Debug)]
+pub struct SessionFile {
+    path: PathBuf,
+}
+
+impl SessionFile {
+    /// Allocate a new session file path for a window.
+    pub fn new() -> Option<Self> {
+        let id = SESSION_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let name = format!("{}-{}.json", process::id(), id);
+        session_directory().map(|directory| Self { path: directory.join(name) })
+    }
+
+    /// Write the session state to disk.
+    ///
+    /// The state is written to a temporary file first, to make sure a crash never leaves a
+    /// partially written session behind. Since sessions contain terminal content, they are only
+    /// accessible by the current user.
+    pub fn save(&self, state: &SessionState) -> io::Result<()> {
+        if let Some(directory) = self.path.parent() {
+            let mut builder = DirBuilder::new();
+            builder.recursive(true);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::DirBuilderExt;
+                builder.mode(0o700);
+            }
+            builder.create(directory)?;
+        }
+
+        // Remove leftovers of a crash, since permissions are only applied to new files.
+        let tmp_path = self.path.with_extension("json.tmp");
+        match fs::remove_file(&tmp_path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => (),
+        }
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let mut writer = BufWriter::new(options.open(&tmp_path)?);
+        json::to_writer(&mut writer, state)?;
+        writer.flush()?;
+        drop(writer);
+
+        fs::rename(&tmp_path, &self.path)
+    }
+
+    /// Delete the session from disk.
+    pub fn remove(&self) {
+        match fs::remove_file(&self.path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                warn!("Unable to remove session {:?}: {}", self.path, err);
+            },
+            _ => (),
+        }
+    }
+}
+
+/// Load all sessions left behind by previous Alacritty instances.
+///
+/// Sessions are returned oldest first and removed from disk, since they're going to be
+/// persisted again by the windows which restore them.
+pub fn take_all() -> Vec<SessionState> {
+    let directory = match session_directory() {
+        Some(directory) => directory,
+        None => return Vec::new(),
+    };
+
+    let entries = match fs::read_dir(&directory) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut files: Vec<_> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? != "json" || owner_alive(&path) {
+                return None;
+            }
+            let modified = path.metadata().and_then(|metadata| metadata.modified()).ok()?;
+            Some((modified, path))
+        })
+        .collect();
+    files.sort();
+
+    files
+        .into_iter()
+        .filter_map(|(_, path)| {
+            let state = File::open(&path)
+                .map_err(json::Error::io)
+                .and_then(|file| json::from_reader(BufReader::new(file)));
+
+            let _ = fs::remove_file(&path);
+
+            match state {
+                Ok(state) => Some(state),
+                Err(err) => {
+                    warn!("Unable to load session {:?}: {}", path, err);
+                    None
+                },
+            }
+        })
+        .collect()
+}
+
+/// Write restored session content into a new terminal.
+///
+/// All lines up to the last non-empty line before the saved cursor are written into the terminal,
+/// followed by a dim separator row. This must happen before the shell is spawned.
+pub fn restore<T: EventListener>(term: &mut Term<T>, state: SessionState) {
+    let SessionState { history, screen, title, .. } = state;
+    let mut rows: Vec<_> = history.into_iter().chain(screen).collect();
+
+    // Ignore trailing empty lines, like a pending prompt of an exited shell.
+    while rows.last().is_some_and(|row| row.is_clear()) {
+        rows.pop();
+    }
+    if rows.is_empty() {
+        return;
+    }
+
+    debug!("Restoring {} lines of session content", rows.len());
+
+    let columns = term.columns();
+    for mut row in rows {
+        if row.len() < columns {
+            row.grow(columns);
+        } else {
+            row.shrink(columns);
+        }
+
+        term.grid_mut().intern_styles(&mut row);
+
+        let cursor_line = term.grid().cursor.point.line;
+        term.grid_mut()[cursor_line] = row;
+        term.linefeed();
+    }
+
+    // Separate the restored content from the new shell.
+    term.carriage_return();
+    term.terminal_attribute(Attr::Dim);
+    let marker = MARKER_LABEL.chars().chain(std::iter::repeat('─'));
+    for c in std::iter::once('─').chain(marker).take(columns) {
+        term.input(c);
+    }
+    term.terminal_attribute(Attr::Reset);
+    term.carriage_return();
+    term.linefeed();
+
+    if title.is_some() {
+        term.set_title(title);
+    }
+}
+
+/// Directory containing all persisted sessions.
+#[cfg(not(windows))]
+fn session_directory() -> Option<PathBuf> {
+    let xdg = xdg::BaseDirectories::with_prefix("alacritty").ok()?;
+    Some(xdg.get_state_home().join("sessions"))
+}
+
+/// Directory containing all persisted sessions.
+#[cfg(windows)]
+fn session_directory() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|path| path.join("alacritty").join("sessions"))
+}
+
+/// Check if the Alacritty instance which wrote a session is still running.
+#[cfg(unix)]
+fn owner_alive(path: &Path) -> bool {
+    let pid = path
+        .file_stem()
+        .and_then(|stem| stem.to_str()?.split('-').next()?.parse::<libc::pid_t>().ok());
+
+    match pid {
+        Some(pid) if pid as u32 != process::id() => unsafe { libc::kill(pid, 0) == 0 },
+        _ => false,
+    }
+}
+
+/// Check if the Alacritty instance which wrote a session is still running.
+#[cfg(not(unix))]
+fn owner_alive(_path: &Path) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alacritty_terminal::event::VoidListener;
+    use alacritty_terminal::index::Column;
+    use alacritty_terminal::term::cell::Flags;
+    use alacritty_terminal::term::test::{mock_term, TermSize};
+    use alacritty_terminal::term::Config;
+
+    #[test]
+    fn restore_above_marker() {
+        let mut saved = mock_term("first\r\nsecond\r\n\r");
+        saved.grid_mut().cursor.point.line = Line(2);
+        let mut state = SessionState { title: Some("saved".into()), ..Default::default() };
+        state.update(&saved);
+
+        let size = TermSize::new(10, 5);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        restore(&mut term, state);
+
+        let grid = term.grid();
+        assert_eq!(grid[Line(0)][Column(0)].c, 'f');
+        assert_eq!(grid[Line(1)][Column(0)].c, 's');
+        assert_eq!(grid[Line(2)][Column(0)].c, '─');
+        assert_eq!(grid[Line(2)][Column(2)].c, 'R');
+        assert!(grid[Line(2)][Column(9)].flags.contains(Flags::DIM));
+        assert_eq!(grid.cursor.point.line, Line(3));
+        assert_eq!(grid.cursor.point.column, Column(0));
+        assert!(grid.cursor.template.flags.is_empty());
+    }
+
+    #[test]
+    fn update_incrementally() {
+        let size = TermSize::new(10, 3);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        let mut state = SessionState::default();
+        let text = |rows: &mut dyn Iterator<Item = &Row<Cell>>| -> Vec<char> {
+            rows.map(|row| row[Column(0)].c).collect()
+        };
+
+        for c in ['a', 'b', 'c', 'd'] {
+            term.input(c);
+            term.carriage_return();
+            term.linefeed();
+        }
+        state.update(&term);
+        assert_eq!(text(&mut state.history.iter()), ['a', 'b']);
+
+        // Only new lines are copied from the history.
+        state.history[0][Column(0)].c = 'x';
+        for c in ['e', 'f'] {
+            term.input(c);
+            term.carriage_return();
+            term.linefeed();
+        }
+        state.update(&term);
+        assert_eq!(text(&mut state.history.iter()), ['x', 'b', 'c', 'd']);
+        assert_eq!(text(&mut state.screen.iter()), ['e', 'f', ' ']);
+
+        // Cleared history is dropped.
+        term.grid_mut().clear_history();
+        state.update(&term);
+        assert!(state.history.is_empty());
+    }
+
+    #[test]
+    fn save_private() {
+        let directory = std::env::temp_dir().join(format!("alacritty-session-{}", process::id()));
+        let session_file = SessionFile { path: directory.join("session.json") };
+
+        // Stale temporary files must not leak their permissions into the session.
+        fs::create_dir_all(&directory).unwrap();
+        File::create(session_file.path.with_extension("json.tmp")).unwrap();
+
+        let mut state = SessionState::default();
+        state.update(&mock_term("saved"));
+        session_file.save(&state).unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&session_file.path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn restore_empty() {
+        let mut saved = mock_term("   \r\n   \r\n   \r");
+        saved.grid_mut().cursor.point.line = Line(2);
+        let mut state = SessionState::default();
+        state.update(&saved);
+
+        let size = TermSize::new(10, 5);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        restore(&mut term, state);
+
+        assert_eq!(term.grid().cursor.point.line, Line(0));
+        assert!(term.grid()[Line(0)].is_clear());
+    }
+}
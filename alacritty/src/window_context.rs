@@ -2,45 +2,66 @@
 
 use std::error::Error;
 use std::fs::File;
-use std::io::Write;
-use std::mem;
+use std::io::{self, Write};
 #[cfg(not(windows))]
 use std::os::unix::io::{AsRawFd, RawFd};
+#[cfg(not(windows))]
+use std::os::unix::process::ExitStatusExt;
+use std::path::PathBuf;
+#[cfg(not(windows))]
+use std::process;
+use std::process::ExitStatus;
 use std::rc::Rc;
 use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::{env, iter, mem};
 
 use glutin::config::GetGlConfig;
 use glutin::display::GetGlDisplay;
 #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
 use glutin::platform::x11::X11GlConfigExt;
-use log::info;
+use log::{error, info, warn};
 use raw_window_handle::HasRawDisplayHandle;
 use serde_json as json;
 use winit::event::{Event as WinitEvent, Modifiers, WindowEvent};
 use winit::event_loop::{EventLoopProxy, EventLoopWindowTarget};
 use winit::window::WindowId;
 
-use alacritty_terminal::event::Event as TerminalEvent;
-use alacritty_terminal::event_loop::{EventLoop as PtyEventLoop, Msg, Notifier};
+use alacritty_terminal::asciicast::Recorder;
+use alacritty_terminal::event::{Event as TerminalEvent, EventListener, OnResize};
+use alacritty_terminal::event_loop::{EventLoop as PtyEventLoop, EventLoopSender, Msg, Notifier};
 use alacritty_terminal::grid::{Dimensions, Scroll};
 use alacritty_terminal::index::Direction;
 use alacritty_terminal::sync::FairMutex;
+use alacritty_terminal::term::snapshot::SnapshotBuffer;
 use alacritty_terminal::term::test::TermSize;
 use alacritty_terminal::term::{Term, TermMode};
-use alacritty_terminal::tty;
+use alacritty_terminal::thread;
+#[cfg(not(windows))]
+use alacritty_terminal::tty::device::Device;
+use alacritty_terminal::tty::{self, EventedPty, Options as PtyOptions};
+use alacritty_terminal::vte::ansi::{Attr, Handler};
 
 use crate::cli::{ParsedOptions, WindowOptions};
 use crate::clipboard::Clipboard;
 use crate::config::UiConfig;
+#[cfg(not(windows))]
+use crate::daemon::foreground_process_path;
 use crate::display::window::Window;
-use crate::display::Display;
+use crate::display::{hint, Display};
 use crate::event::{
     ActionContext, Event, EventProxy, InlineSearchState, Mouse, SearchState, TouchPurpose,
 };
+use crate::input::vi_command::ViCommandParser;
 #[cfg(unix)]
 use crate::logging::LOG_TARGET_IPC_CONFIG;
 use crate::message_bar::MessageBuffer;
+#[cfg(not(windows))]
+use crate::replay::{self, Recording};
 use crate::scheduler::Scheduler;
+use crate::search_history::SearchHistory;
+use crate::session::{self, SessionFile, SessionState};
 use crate::{input, renderer};
 
 /// Event context for one individual Alacritty window.
@@ -50,9 +71,11 @@ pub struct WindowContext {
     pub dirty: bool,
     event_queue: Vec<WinitEvent<Event>>,
     terminal: Arc<FairMutex<Term<EventProxy>>>,
+    snapshots: Arc<SnapshotBuffer>,
     cursor_blink_timed_out: bool,
     modifiers: Modifiers,
     inline_search_state: InlineSearchState,
+    vi_command: ViCommandParser,
     search_state: SearchState,
     notifier: Notifier,
     mouse: Mouse,
@@ -65,6 +88,24 @@ pub struct WindowContext {
     shell_pid: u32,
     window_config: ParsedOptions,
     config: Rc<UiConfig>,
+    session_file: Option<SessionFile>,
+    session_state: SessionState,
+    session_writer: Option<JoinHandle<SessionState>>,
+    recording: Option<PathBuf>,
+    event_proxy: EventProxy,
+
+    /// PTY configuration used to restart the child process.
+    ///
+    /// This is `None` for children which cannot be restarted.
+    restart_config: Option<PtyOptions>,
+
+    /// Command line of the child process.
+    command: String,
+
+    /// Start of the running child process.
+    ///
+    /// This is `None` once the child has exited.
+    child_start: Option<Instant>,
 }
 
 impl WindowContext {
@@ -74,6 +115,7 @@ impl WindowContext {
         proxy: EventLoopProxy<Event>,
         config: Rc<UiConfig>,
         options: WindowOptions,
+        session: Option<SessionState>,
     ) -> Result<Self, Box<dyn Error>> {
         let raw_display_handle = event_loop.raw_display_handle();
 
@@ -114,7 +156,7 @@ impl WindowContext {
 
         let display = Display::new(window, gl_context, &config, false)?;
 
-        Self::new(display, config, options, proxy)
+        Self::new(display, config, options, proxy, session)
     }
 
     /// Create additional context with the graphics platform other windows are using.
@@ -125,6 +167,7 @@ impl WindowContext {
         config: Rc<UiConfig>,
         options: WindowOptions,
         config_overrides: ParsedOptions,
+        session: Option<SessionState>,
     ) -> Result<Self, Box<dyn Error>> {
         // Get any window and take its GL config and display to build a new context.
         let (gl_display, gl_config) = {
@@ -161,7 +204,7 @@ impl WindowContext {
 
         let display = Display::new(window, gl_context, &config, tabbed)?;
 
-        let mut window_context = Self::new(display, config, options, proxy)?;
+        let mut window_context = Self::new(display, config, options, proxy, session)?;
 
         // Set the config overrides at startup.
         //
@@ -177,10 +220,40 @@ impl WindowContext {
         config: Rc<UiConfig>,
         options: WindowOptions,
         proxy: EventLoopProxy<Event>,
+        session: Option<SessionState>,
     ) -> Result<Self, Box<dyn Error>> {
         let mut pty_config = config.pty_config();
+
+        // Start restored sessions in their previous working directory.
+        let session_directory =
+            session.as_ref().and_then(|session| session.working_directory.as_ref());
+        if let Some(working_directory) = session_directory.filter(|directory| directory.is_dir()) {
+            pty_config.working_directory = Some(working_directory.clone());
+        }
+
         options.terminal_options.override_pty_config(&mut pty_config);
 
+        let mut command = command_line(&pty_config);
+        let mut restart_config = Some(pty_config.clone());
+
+        // Feed recordings to the terminal like any other device.
+        //
+        // The replay's socket only needs to stay open until the device has duplicated it, so
+        // replays cannot be restarted.
+        #[cfg(not(windows))]
+        let _replay = match &options.terminal_options.replay {
+            Some(path) => {
+                let speed = options.terminal_options.replay_speed.unwrap_or(1.);
+                let replay = replay::spawn(Recording::load(path)?, speed)?;
+                pty_config.device = Some(Device::Fd(replay.as_raw_fd()));
+                pty_config.hold = true;
+                command = path.display().to_string();
+                restart_config = None;
+                Some(replay)
+            },
+            None => None,
+        };
+
         let preserve_title = options.window_identity.title.is_some();
 
         info!(
@@ -196,41 +269,19 @@ impl WindowContext {
         // This object contains all of the state about what's being displayed. It's
         // wrapped in a clonable mutex since both the I/O loop and display need to
         // access it.
-        let terminal = Term::new(config.term_options(), &display.size_info, event_proxy.clone());
-        let terminal = Arc::new(FairMutex::new(terminal));
-
-        // Create the PTY.
-        //
-        // The PTY forks a process to run the shell on the slave side of the
-        // pseudoterminal. A file descriptor for the master side is retained for
-        // reading/writing to the shell.
-        let pty = tty::new(&pty_config, display.size_info.into(), display.window.id().into())?;
+        let mut terminal =
+            Term::new(config.term_options(), &display.size_info, event_proxy.clone());
 
-        #[cfg(not(windows))]
-        let master_fd = pty.file().as_raw_fd();
-        #[cfg(not(windows))]
-        let shell_pid = pty.child().id();
-
-        // Create the pseudoterminal I/O loop.
-        //
-        // PTY I/O is ran on another thread as to not occupy cycles used by the
-        // renderer and input processing. Note that access to the terminal state is
-        // synchronized since the I/O loop updates the state, and the display
-        // consumes it periodically.
-        let event_loop = PtyEventLoop::new(
-            Arc::clone(&terminal),
-            event_proxy.clone(),
-            pty,
-            pty_config.hold,
-            config.debug.ref_test,
-        )?;
+        // Restore the previous session before the shell can write anything.
+        if let Some(session) = session {
+            session::restore(&mut terminal, session);
+        }
 
-        // The event loop channel allows write requests from the event processor
-        // to be sent to the pty loop and ultimately written to the pty.
-        let loop_tx = event_loop.channel();
+        let snapshots = Arc::new(SnapshotBuffer::new());
+        snapshots.publish(&mut terminal);
+        let terminal = Arc::new(FairMutex::new(terminal));
 
-        // Kick off the I/O thread.
-        let _io_thread = event_loop.spawn();
+        let pty = spawn_pty(&terminal, &snapshots, &display, &event_proxy, &pty_config, &config)?;
 
         // Start cursor blinking, in case `Focused` isn't sent on startup.
         if config.cursor.style().blinking {
@@ -238,18 +289,25 @@ impl WindowContext {
         }
 
         // Create context for the Alacritty window.
-        Ok(WindowContext {
+        let mut window_context = WindowContext {
             preserve_title,
             terminal,
+            snapshots,
             display,
             #[cfg(not(windows))]
-            master_fd,
+            master_fd: pty.master_fd,
             #[cfg(not(windows))]
-            shell_pid,
+            shell_pid: pty.shell_pid,
+            child_start: Some(Instant::now()),
+            session_file: SessionFile::new(),
+            restart_config,
+            event_proxy,
+            command,
             config,
-            notifier: Notifier(loop_tx),
+            notifier: Notifier(pty.loop_tx),
             cursor_blink_timed_out: Default::default(),
             inline_search_state: Default::default(),
+            vi_command: Default::default(),
             message_buffer: Default::default(),
             window_config: Default::default(),
             search_state: Default::default(),
@@ -257,9 +315,109 @@ impl WindowContext {
             modifiers: Default::default(),
             occluded: Default::default(),
             mouse: Default::default(),
+            session_state: Default::default(),
+            session_writer: Default::default(),
             touch: Default::default(),
             dirty: Default::default(),
-        })
+            recording: Default::default(),
+        };
+
+        if let Some(path) = options.terminal_options.record {
+            window_context.start_recording(path, options.terminal_options.record_input);
+        }
+
+        Ok(window_context)
+    }
+
+    /// Show the exit status of the child process.
+    ///
+    /// This is only relevant with `hold` enabled, since the window is closed otherwise.
+    pub fn child_exit(&mut self, status: Option<ExitStatus>) {
+        let elapsed = match self.child_start.take() {
+            Some(child_start) => child_start.elapsed(),
+            None => return,
+        };
+
+        info!("Child process exited with {:?} after {:?}", status, elapsed);
+
+        let banner = exit_banner(status, elapsed, &self.command);
+        write_banner(&mut self.terminal.lock(), &banner);
+        self.publish_changes();
+        self.dirty = true;
+    }
+
+    /// Restart the child process after it has exited.
+    ///
+    /// The terminal content including scrollback is kept, only the terminal modes are reset.
+    pub fn restart_command(&mut self) {
+        let pty_config = match &self.restart_config {
+            Some(pty_config) if self.child_start.is_none() => pty_config,
+            _ => return,
+        };
+
+        self.terminal.lock().reset_modes();
+
+        let (terminal, snapshots) = (&self.terminal, &self.snapshots);
+        let (display, event_proxy) = (&self.display, &self.event_proxy);
+        let pty =
+            match spawn_pty(terminal, snapshots, display, event_proxy, pty_config, &self.config) {
+                Ok(pty) => pty,
+                Err(err) => {
+                    error!("Unable to restart command: {}", err);
+                    return;
+                },
+            };
+
+        #[cfg(not(windows))]
+        {
+            self.master_fd = pty.master_fd;
+            self.shell_pid = pty.shell_pid;
+        }
+        self.notifier = Notifier(pty.loop_tx);
+        self.child_start = Some(Instant::now());
+
+        // Recordings are owned by the previous PTY's event loop.
+        self.recording = None;
+
+        self.publish_changes();
+        self.dirty = true;
+    }
+
+    /// Start or stop recording the terminal output.
+    pub fn toggle_recording(&mut self) {
+        if self.recording.take().is_some() {
+            let _ = self.notifier.0.send(Msg::StopRecording);
+            info!("Recording stopped");
+            return;
+        }
+
+        #[cfg(not(windows))]
+        let directory = foreground_process_path(self.master_fd, self.shell_pid).ok();
+        #[cfg(windows)]
+        let directory = None;
+        let directory = directory.or_else(|| env::current_dir().ok()).unwrap_or_default();
+
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let path = directory.join(format!("alacritty-{}.cast", timestamp.as_secs()));
+
+        self.start_recording(path, false);
+    }
+
+    /// Start recording the terminal output to an asciicast file.
+    fn start_recording(&mut self, path: PathBuf, record_input: bool) {
+        let size_info = self.display.size_info;
+        let recorder = File::create(&path).and_then(|file| {
+            Recorder::new(file, size_info.columns(), size_info.screen_lines(), record_input)
+        });
+
+        match recorder {
+            Ok(recorder) => {
+                let _ = self.notifier.0.send(Msg::StartRecording(recorder));
+                info!("Recording to {:?}", path);
+                self.recording = Some(path);
+            },
+            Err(err) => error!("Unable to record to {:?}: {}", path, err),
+        }
     }
 
     /// Update the terminal window to the latest config.
@@ -271,6 +429,7 @@ impl WindowContext {
 
         self.display.update_config(&self.config);
         self.terminal.lock().set_options(self.config.term_options());
+        self.publish_changes();
 
         // Reload cursor if its thickness has changed.
         if (old_config.cursor.thickness() - self.config.cursor.thickness()).abs() > f32::EPSILON {
@@ -386,14 +545,41 @@ impl WindowContext {
         }
 
         // Redraw the window.
-        let terminal = self.terminal.lock();
+        //
+        // Both the PTY and the UI publish a snapshot whenever they change the terminal, so
+        // drawing never needs the terminal lock.
         self.display.draw(
-            terminal,
+            &self.snapshots,
             scheduler,
             &self.message_buffer,
             &self.config,
             &mut self.search_state,
         );
+
+        // Allow the PTY to request the next frame.
+        let _ = self.notifier.0.send(Msg::FrameDrawn);
+    }
+
+    /// Request a redraw for new PTY output.
+    ///
+    /// While search or hint matches are highlighted, the PTY leaves publishing snapshots to the
+    /// UI, so the visible matches are updated here.
+    pub fn wakeup(&mut self) {
+        if self.snapshots.highlighting() {
+            let mut terminal = self.terminal.lock();
+            Self::publish_snapshot(
+                &mut terminal,
+                &self.snapshots,
+                &mut self.display,
+                &mut self.search_state,
+            );
+            self.search_state.update_match_counter(&terminal, &self.terminal, &self.event_proxy);
+        }
+
+        self.dirty = true;
+        if self.display.window.has_frame {
+            self.display.window.request_redraw();
+        }
     }
 
     /// Process events for this terminal window.
@@ -402,6 +588,7 @@ impl WindowContext {
         event_loop: &EventLoopWindowTarget<Event>,
         event_proxy: &EventLoopProxy<Event>,
         clipboard: &mut Clipboard,
+        search_history: &mut SearchHistory,
         scheduler: &mut Scheduler,
         event: WinitEvent<Event>,
     ) {
@@ -429,6 +616,7 @@ impl WindowContext {
             cursor_blink_timed_out: &mut self.cursor_blink_timed_out,
             message_buffer: &mut self.message_buffer,
             inline_search_state: &mut self.inline_search_state,
+            vi_command: &mut self.vi_command,
             search_state: &mut self.search_state,
             modifiers: &mut self.modifiers,
             notifier: &mut self.notifier,
@@ -447,6 +635,7 @@ impl WindowContext {
             event_proxy,
             event_loop,
             clipboard,
+            search_history,
             scheduler,
         };
         let mut processor = input::Processor::new(context);
@@ -479,6 +668,19 @@ impl WindowContext {
             self.mouse.hint_highlight_dirty = false;
         }
 
+        // Publish the changes made by the UI for the next frame.
+        if self.dirty {
+            Self::publish_snapshot(
+                &mut terminal,
+                &self.snapshots,
+                &mut self.display,
+                &mut self.search_state,
+            );
+        }
+
+        // Count the matches of a new search, or of lines scrolled onto the screen.
+        self.search_state.update_match_counter(&terminal, &self.terminal, &self.event_proxy);
+
         // Don't call `request_redraw` when event is `RedrawRequested` since the `dirty` flag
         // represents the current frame, but redraw is for the next frame.
         if self.dirty
@@ -523,6 +725,97 @@ impl WindowContext {
             .expect("write config.json");
     }
 
+    /// Persist the terminal content to disk.
+    ///
+    /// Unless `blocking` is set, the session is written on a background thread.
+    pub fn save_session(&mut self, blocking: bool) {
+        let session_file = match &self.session_file {
+            Some(session_file) => session_file.clone(),
+            None => return,
+        };
+
+        #[cfg(not(windows))]
+        let working_directory = foreground_process_path(self.master_fd, self.shell_pid).ok();
+        #[cfg(windows)]
+        let working_directory = None;
+
+        // Wait for the previous save, to avoid concurrent writes to the same file.
+        self.wait_for_session_writer();
+
+        let mut state = mem::take(&mut self.session_state);
+        state.title = Some(self.display.window.title().to_owned());
+        state.working_directory = working_directory;
+        state.update(&self.terminal.lock());
+
+        let save = move || {
+            if let Err(err) = session_file.save(&state) {
+                warn!("Unable to save session: {}", err);
+            }
+            state
+        };
+
+        if blocking {
+            self.session_state = save();
+        } else {
+            self.session_writer = Some(thread::spawn_named("session writer", save));
+        }
+    }
+
+    /// Remove the persisted session from disk.
+    pub fn remove_session(&mut self) {
+        self.wait_for_session_writer();
+
+        if let Some(session_file) = &self.session_file {
+            session_file.remove();
+        }
+    }
+
+    /// Block until the background session writer is done.
+    fn wait_for_session_writer(&mut self) {
+        if let Some(session_writer) = self.session_writer.take() {
+            // The session is copied entirely with the next save if the writer panicked.
+            self.session_state = session_writer.join().unwrap_or_default();
+        }
+    }
+
+    /// Publish terminal changes made by the UI outside of event handling.
+    fn publish_changes(&mut self) {
+        let mut terminal = self.terminal.lock();
+        Self::publish_snapshot(
+            &mut terminal,
+            &self.snapshots,
+            &mut self.display,
+            &mut self.search_state,
+        );
+    }
+
+    /// Publish the terminal for rendering, together with the visible search and hint matches.
+    fn publish_snapshot(
+        terminal: &mut Term<EventProxy>,
+        snapshots: &SnapshotBuffer,
+        display: &mut Display,
+        search_state: &mut SearchState,
+    ) {
+        let hint_state = &mut display.hint_state;
+        if hint_state.active() {
+            hint_state.update_matches(terminal);
+        }
+
+        // Stop the PTY from publishing snapshots without the highlights.
+        snapshots.set_highlighting(search_state.dfas().is_some() || hint_state.active());
+
+        snapshots.publish_with(terminal, |terminal, highlights| {
+            if let Some(dfas) = search_state.dfas() {
+                highlights.search.extend(hint::visible_regex_match_iter(terminal, dfas));
+            }
+
+            if hint_state.active() {
+                highlights.hints.extend_from_slice(hint_state.matches());
+                highlights.hint_labels.extend_from_slice(hint_state.labels());
+            }
+        });
+    }
+
     /// Submit the pending changes to the `Display`.
     fn submit_display_update(
         terminal: &mut Term<EventProxy>,
@@ -563,3 +856,190 @@ impl Drop for WindowContext {
         let _ = self.notifier.0.send(Msg::Shutdown);
     }
 }
+
+/// Handles of a running PTY.
+struct Pty {
+    loop_tx: EventLoopSender,
+    #[cfg(not(windows))]
+    master_fd: RawFd,
+    #[cfg(not(windows))]
+    shell_pid: u32,
+}
+
+/// Create the PTY and start its event loop.
+///
+/// The PTY forks a process to run the shell on the slave side of the pseudoterminal. A file
+/// descriptor for the master side is retained for reading/writing to the shell.
+///
+/// Devices have no child process, so Alacritty's own process is used to determine the working
+/// directory of new windows instead.
+fn spawn_pty(
+    terminal: &Arc<FairMutex<Term<EventProxy>>>,
+    snapshots: &Arc<SnapshotBuffer>,
+    display: &Display,
+    event_proxy: &EventProxy,
+    pty_config: &PtyOptions,
+    config: &UiConfig,
+) -> io::Result<Pty> {
+    let window_id = display.window.id().into();
+
+    #[cfg(not(windows))]
+    if let Some(device) = &pty_config.device {
+        let pty = tty::device::new(device)?;
+        let master_fd = pty.file().as_raw_fd();
+        let loop_tx = spawn_pty_loop(terminal, snapshots, event_proxy, pty, pty_config, config)?;
+        return Ok(Pty { loop_tx, master_fd, shell_pid: process::id() });
+    }
+
+    let pty = tty::new(pty_config, display.size_info.into(), window_id)?;
+    #[cfg(not(windows))]
+    let (master_fd, shell_pid) = (pty.file().as_raw_fd(), pty.child().id());
+    let loop_tx = spawn_pty_loop(terminal, snapshots, event_proxy, pty, pty_config, config)?;
+
+    Ok(Pty {
+        loop_tx,
+        #[cfg(not(windows))]
+        master_fd,
+        #[cfg(not(windows))]
+        shell_pid,
+    })
+}
+
+/// Command line of the child process described by a PTY configuration.
+fn command_line(pty_config: &PtyOptions) -> String {
+    #[cfg(not(windows))]
+    if let Some(device) = &pty_config.device {
+        return device.to_string();
+    }
+
+    match &pty_config.shell {
+        Some(shell) => {
+            let args = shell.args().iter().map(String::as_str);
+            iter::once(shell.program()).chain(args).collect::<Vec<_>>().join(" ")
+        },
+        None => env::var("SHELL").unwrap_or_else(|_| String::from("shell")),
+    }
+}
+
+/// Text of the banner shown after the child process exited.
+fn exit_banner(status: Option<ExitStatus>, elapsed: Duration, command: &str) -> String {
+    #[cfg(not(windows))]
+    let signal = status.and_then(|status| status.signal());
+    #[cfg(windows)]
+    let signal: Option<i32> = None;
+
+    let exit = match (status.and_then(|status| status.code()), signal) {
+        (Some(code), _) => format!("exited with code {}", code),
+        (None, Some(signal)) => format!("was terminated by signal {}", signal),
+        (None, None) => String::from("exited"),
+    };
+
+    let secs = elapsed.as_secs();
+    let elapsed = match (secs / 3600, secs / 60 % 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {}s", m, s),
+        (h, m, s) => format!("{}h {}m {}s", h, m, s),
+    };
+
+    format!("Process {} after {}: {}", exit, elapsed, command)
+}
+
+/// Write a banner to the terminal on its own line.
+fn write_banner<T: EventListener>(term: &mut Term<T>, banner: &str) {
+    if term.grid().cursor.point.column > 0 {
+        term.carriage_return();
+        term.linefeed();
+    }
+
+    term.terminal_attribute(Attr::Reset);
+    term.terminal_attribute(Attr::Reverse);
+    for c in iter::once(' ').chain(banner.chars()).chain(iter::once(' ')) {
+        term.input(c);
+    }
+    term.terminal_attribute(Attr::Reset);
+    term.carriage_return();
+    term.linefeed();
+}
+
+/// Start the pseudoterminal I/O loop.
+///
+/// PTY I/O is ran on another thread as to not occupy cycles used by the
+/// renderer and input processing. Note that access to the terminal state is
+/// synchronized since the I/O loop updates the state, and the display
+/// consumes it periodically.
+///
+/// The returned channel allows write requests from the event processor
+/// to be sent to the pty loop and ultimately written to the pty.
+fn spawn_pty_loop<P>(
+    terminal: &Arc<FairMutex<Term<EventProxy>>>,
+    snapshots: &Arc<SnapshotBuffer>,
+    event_proxy: &EventProxy,
+    pty: P,
+    pty_config: &PtyOptions,
+    config: &UiConfig,
+) -> io::Result<EventLoopSender>
+where
+    P: EventedPty + OnResize + Send + 'static,
+{
+    let mut event_loop = PtyEventLoop::new(
+        Arc::clone(terminal),
+        event_proxy.clone(),
+        pty,
+        pty_config.hold,
+        config.debug.ref_test,
+    )?;
+    event_loop.publish_snapshots(Arc::clone(snapshots));
+    let loop_tx = event_loop.channel();
+
+    // Kick off the I/O thread.
+    let _io_thread = event_loop.spawn();
+
+    Ok(loop_tx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alacritty_terminal::event::VoidListener;
+    use alacritty_terminal::index::{Column, Line, Point};
+    use alacritty_terminal::term::cell::Flags;
+    use alacritty_terminal::term::Config;
+
+    #[cfg(windows)]
+    use std::os::windows::process::ExitStatusExt;
+
+    #[test]
+    fn banner_text() {
+        let elapsed = Duration::from_secs(3725);
+        let banner = exit_banner(Some(ExitStatus::from_raw(0)), elapsed, "sh -c true");
+        assert_eq!(banner, "Process exited with code 0 after 1h 2m 5s: sh -c true");
+
+        let banner = exit_banner(None, Duration::from_millis(1500), "/dev/ttyUSB0");
+        assert_eq!(banner, "Process exited after 1s: /dev/ttyUSB0");
+
+        // Signals are stored in the lower bits of the raw wait status.
+        #[cfg(not(windows))]
+        {
+            let banner = exit_banner(Some(ExitStatus::from_raw(9)), elapsed, "vim");
+            assert_eq!(banner, "Process was terminated by signal 9 after 1h 2m 5s: vim");
+        }
+    }
+
+    #[test]
+    fn banner_on_new_line() {
+        let size = TermSize::new(10, 3);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        for c in "$ exit".chars() {
+            term.input(c);
+        }
+
+        write_banner(&mut term, "done");
+
+        let banner_line = &term.grid()[Line(1)];
+        let text: String = (0..6).map(|column| banner_line[Column(column)].c).collect();
+        assert_eq!(text, " done ");
+        assert!(banner_line[Column(0)].flags.contains(Flags::INVERSE));
+        assert_eq!(term.grid().cursor.point, Point::new(Line(2), Column(0)));
+    }
+}
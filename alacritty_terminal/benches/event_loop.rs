@@ -0,0 +1,181 @@
+//! Throughput and input latency of the PTY event loop while the PTY is flooded with output.
+//!
+//! Run with `cargo bench -p alacritty_terminal --bench event_loop`.
+
+#[cfg(unix)]
+fn main() {
+    unix::main();
+}
+
+#[cfg(not(unix))]
+fn main() {
+    println!("The event loop benchmarks are only supported on unix");
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::borrow::Cow;
+    use std::io::{Read, Write};
+    use std::os::unix::io::AsRawFd;
+    use std::os::unix::net::UnixStream;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::{mpsc, Arc};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use alacritty_terminal::event::{Event, EventListener};
+    use alacritty_terminal::event_loop::{EventLoop, EventLoopSender, Msg, State};
+    use alacritty_terminal::sync::FairMutex;
+    use alacritty_terminal::term::test::TermSize;
+    use alacritty_terminal::term::{Config, Term};
+    use alacritty_terminal::tty::device::{self, Device, DevicePty};
+
+    /// Bytes written to the PTY for the throughput benchmark.
+    const THROUGHPUT_BYTES: usize = 256 * 1024 * 1024;
+
+    /// Number of keystrokes sent for the latency benchmark.
+    const KEYSTROKES: usize = 200;
+
+    /// Time between keystrokes.
+    const KEYSTROKE_INTERVAL: Duration = Duration::from_millis(5);
+
+    /// Time the simulated renderer takes to draw a frame.
+    const FRAME_TIME: Duration = Duration::from_millis(8);
+
+    /// Primary device attributes request, used to detect when all output was parsed.
+    const SENTINEL: &[u8] = b"\x1b[c";
+
+    pub fn main() {
+        let mut line = b"lorem ipsum dolor sit amet ".repeat(5);
+        line.extend_from_slice(b"\x1b[31merror\x1b[0m\r\n");
+
+        let bench = Bench::new();
+        throughput(&bench, &line);
+        latency(&bench, &line);
+        bench.shutdown();
+    }
+
+    /// Measure how fast a flood of output is parsed.
+    fn throughput(bench: &Bench, line: &[u8]) {
+        let start = Instant::now();
+        let drawn = bench.frames.load(Ordering::Relaxed);
+
+        let mut written = 0;
+        let mut remote = bench.remote.try_clone().unwrap();
+        while written < THROUGHPUT_BYTES {
+            remote.write_all(line).unwrap();
+            written += line.len();
+        }
+        remote.write_all(SENTINEL).unwrap();
+        bench.parsed.recv().unwrap();
+
+        let elapsed = start.elapsed();
+        let frames = bench.frames.load(Ordering::Relaxed) - drawn;
+        let throughput = written as f64 / elapsed.as_secs_f64() / 1024. / 1024.;
+        println!("throughput: {:.0} MiB/s, {} frames drawn in {:?}", throughput, frames, elapsed);
+    }
+
+    /// Measure how long keystrokes take to reach the PTY while it is flooded with output.
+    fn latency(bench: &Bench, line: &[u8]) {
+        // Flood the PTY until all keystrokes have been received.
+        let flooding = Arc::new(AtomicBool::new(true));
+        let mut remote = bench.remote.try_clone().unwrap();
+        let flood = {
+            let flooding = flooding.clone();
+            let line = line.to_vec();
+            thread::spawn(move || {
+                while flooding.load(Ordering::Relaxed) {
+                    remote.write_all(&line).unwrap();
+                }
+                remote.write_all(SENTINEL).unwrap();
+            })
+        };
+
+        let mut remote = bench.remote.try_clone().unwrap();
+        let mut latencies = Vec::with_capacity(KEYSTROKES);
+        for _ in 0..KEYSTROKES {
+            thread::sleep(KEYSTROKE_INTERVAL);
+
+            let sent = Instant::now();
+            bench.sender.send(Msg::Input(Cow::Borrowed(b"x"))).unwrap();
+            remote.read_exact(&mut [0]).unwrap();
+            latencies.push(sent.elapsed());
+        }
+
+        flooding.store(false, Ordering::Relaxed);
+        flood.join().unwrap();
+        bench.parsed.recv().unwrap();
+
+        latencies.sort_unstable();
+        let percentile = |p: usize| latencies[(latencies.len() - 1) * p / 100];
+        println!(
+            "input latency: p50 {:?}, p99 {:?}, max {:?}",
+            percentile(50),
+            percentile(99),
+            percentile(100)
+        );
+    }
+
+    /// Event loop with a simulated renderer.
+    struct Bench {
+        remote: UnixStream,
+        sender: EventLoopSender,
+        parsed: mpsc::Receiver<()>,
+        frames: Arc<AtomicUsize>,
+        event_loop: thread::JoinHandle<(EventLoop<DevicePty, Listener>, State)>,
+    }
+
+    impl Bench {
+        fn new() -> Self {
+            let (pty, remote) = UnixStream::pair().unwrap();
+            let pty = device::new(&Device::Fd(pty.as_raw_fd())).unwrap();
+
+            let (event_tx, event_rx) = mpsc::channel();
+            let listener = Listener(event_tx);
+
+            let size = TermSize::new(200, 50);
+            let terminal = Term::new(Config::default(), &size, listener.clone());
+            let terminal = Arc::new(FairMutex::new(terminal));
+
+            let event_loop = EventLoop::new(terminal.clone(), listener, pty, false, false).unwrap();
+            let sender = event_loop.channel();
+            let event_loop = event_loop.spawn();
+
+            // Draw a frame for every wakeup, acknowledging it to the event loop.
+            let frames = Arc::new(AtomicUsize::new(0));
+            let (parsed_tx, parsed) = mpsc::channel();
+            let renderer_frames = frames.clone();
+            let renderer_sender = sender.clone();
+            thread::spawn(move || {
+                for event in event_rx {
+                    match event {
+                        Event::Wakeup => {
+                            let _terminal = terminal.lock();
+                            thread::sleep(FRAME_TIME);
+                            renderer_frames.fetch_add(1, Ordering::Relaxed);
+                            let _ = renderer_sender.send(Msg::FrameDrawn);
+                        },
+                        Event::PtyWrite(_) => parsed_tx.send(()).unwrap(),
+                        _ => (),
+                    }
+                }
+            });
+
+            Self { remote, sender, parsed, frames, event_loop }
+        }
+
+        fn shutdown(self) {
+            self.sender.send(Msg::Shutdown).unwrap();
+            let _ = self.event_loop.join().unwrap();
+        }
+    }
+
+    #[derive(Clone)]
+    struct Listener(mpsc::Sender<Event>);
+
+    impl EventListener for Listener {
+        fn send_event(&self, event: Event) {
+            let _ = self.0.send(event);
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! Throughput of writing printable ASCII into the terminal.
+//!
+//! Run with `cargo bench -p alacritty_terminal --bench input`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use alacritty_terminal::event::VoidListener;
+use alacritty_terminal::term::test::TermSize;
+use alacritty_terminal::term::{Config, Term};
+use alacritty_terminal::vte::ansi::{Handler, Processor};
+
+/// Bytes written for every measurement.
+const BYTES: usize = 64 * 1024 * 1024;
+
+/// Terminal dimensions.
+const COLUMNS: usize = 200;
+const SCREEN_LINES: usize = 50;
+
+fn main() {
+    let line = "lorem ipsum dolor sit amet ".repeat(6);
+    let line = line.as_bytes();
+
+    let parser = bench("parser", |term| {
+        let mut parser: Processor = Processor::new();
+        write_lines(line, |bytes| {
+            for byte in bytes {
+                parser.advance(term, *byte);
+            }
+            term.carriage_return();
+            term.linefeed();
+        })
+    });
+
+    let input = bench("Handler::input", |term| {
+        write_lines(line, |bytes| {
+            for byte in bytes {
+                term.input(*byte as char);
+            }
+            term.carriage_return();
+            term.linefeed();
+        })
+    });
+
+    let input_ascii = bench("Term::input_ascii", |term| {
+        write_lines(line, |bytes| {
+            term.input_ascii(bytes);
+            term.carriage_return();
+            term.linefeed();
+        })
+    });
+
+    println!();
+    println!("speedup over parser: {:.1}x", parser.as_secs_f64() / input_ascii.as_secs_f64());
+    println!("speedup over input: {:.1}x", input.as_secs_f64() / input_ascii.as_secs_f64());
+}
+
+/// Write lines until the byte limit is reached.
+fn write_lines<F: FnMut(&[u8])>(line: &[u8], mut f: F) {
+    for _ in 0..BYTES / line.len() {
+        f(black_box(line));
+    }
+}
+
+/// Time how long it takes to write all lines into a new terminal.
+fn bench<F>(name: &str, f: F) -> Duration
+where
+    F: FnOnce(&mut Term<VoidListener>),
+{
+    let size = TermSize::new(COLUMNS, SCREEN_LINES);
+    let mut term = Term::new(Config::default(), &size, VoidListener);
+
+    let start = Instant::now();
+    f(&mut term);
+    let elapsed = start.elapsed();
+
+    let throughput = BYTES as f64 / elapsed.as_secs_f64() / 1024. / 1024.;
+    println!("{:<20} {:>8.0} MiB/s", name, throughput);
+
+    elapsed
+}
@@ -0,0 +1,99 @@
+//! Memory usage of terminals with large scrollback histories.
+//!
+//! Run with `cargo bench -p alacritty_terminal --bench memory`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use alacritty_terminal::event::VoidListener;
+use alacritty_terminal::term::test::TermSize;
+use alacritty_terminal::term::{Config, Term};
+use alacritty_terminal::vte::ansi::Processor;
+
+/// Number of lines in the scrollback history.
+const HISTORY: usize = 100_000;
+
+/// Terminal dimensions.
+const COLUMNS: usize = 200;
+const SCREEN_LINES: usize = 50;
+
+/// Number of bytes currently allocated.
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+/// Allocator keeping track of the total size of all live allocations.
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATED.fetch_add(new_size, Ordering::Relaxed);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn main() {
+    bench("plain", |i| format!("{i:>8} {}", "lorem ipsum ".repeat(15)));
+
+    bench("256 colors", |i| {
+        let mut line = String::new();
+        for word in 0..30 {
+            line.push_str(&format!("\x1b[38;5;{}m{i:>6}", (i + word) % 256));
+        }
+        line.push_str("\x1b[0m");
+        line
+    });
+
+    bench("truecolor", |i| {
+        let mut line = String::new();
+        for column in 0..COLUMNS {
+            let (r, g, b) = ((i % 256) as u8, (column % 256) as u8, (i / 256 % 256) as u8);
+            line.push_str(&format!("\x1b[48;2;{r};{g};{b}m "));
+        }
+        line.push_str("\x1b[0m");
+        line
+    });
+}
+
+/// Fill the scrollback history with the generated lines and report the memory used.
+fn bench<F: Fn(usize) -> String>(name: &str, line: F) {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let start = Instant::now();
+
+    let config = Config { scrolling_history: HISTORY, ..Default::default() };
+    let size = TermSize::new(COLUMNS, SCREEN_LINES);
+    let mut term = Term::new(config, &size, VoidListener);
+    let mut parser: Processor = Processor::new();
+
+    for i in 0..HISTORY + SCREEN_LINES {
+        for byte in line(i).bytes().chain(*b"\r\n") {
+            parser.advance(&mut term, byte);
+        }
+    }
+
+    let elapsed = start.elapsed();
+    let used = ALLOCATED.load(Ordering::Relaxed) - before;
+    let cells = (HISTORY + SCREEN_LINES) * COLUMNS;
+
+    println!(
+        "{name:<12} {:>8.1} MiB {:>6.2} bytes/cell {:>8.2?}",
+        used as f64 / 1024. / 1024.,
+        used as f64 / cells as f64,
+        elapsed,
+    );
+
+    drop(term);
+}
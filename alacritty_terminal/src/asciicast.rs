@@ -0,0 +1,236 @@
+//! Recording of terminal sessions in the asciicast v2 format.
+//!
+//! The recording consists of a JSON header line, followed by one JSON array per line for every
+//! event, see <https://docs.asciinema.org/manual/asciicast/v2/>.
+
+use std::fmt::{self, Debug, Formatter, Write as _};
+use std::io::{self, BufWriter, Write};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use std::{env, str};
+
+/// Writer for asciicast v2 recordings.
+///
+/// Events are buffered until the recording is flushed or dropped.
+pub struct Recorder {
+    writer: BufWriter<Box<dyn Write + Send>>,
+    start: Instant,
+    record_input: bool,
+
+    /// Incomplete UTF-8 sequences from the end of the last chunk.
+    pending_output: Vec<u8>,
+    pending_input: Vec<u8>,
+}
+
+impl Recorder {
+    /// Start a new recording and write its header.
+    ///
+    /// Input is only recorded when `record_input` is set, since it might contain passwords.
+    pub fn new<W>(
+        mut writer: W,
+        columns: usize,
+        lines: usize,
+        record_input: bool,
+    ) -> io::Result<Self>
+    where
+        W: Write + Send + 'static,
+    {
+        let mut header = format!("{{\"version\": 2, \"width\": {}, \"height\": {}", columns, lines);
+        if let Ok(timestamp) = SystemTime::now().duration_since(UNIX_EPOCH) {
+            let _ = write!(header, ", \"timestamp\": {}", timestamp.as_secs());
+        }
+        if let Ok(term) = env::var("TERM") {
+            header.push_str(", \"env\": {\"TERM\": ");
+            push_json_string(&mut header, &term);
+            header.push('}');
+        }
+        header.push_str("}\n");
+        writer.write_all(header.as_bytes())?;
+
+        Ok(Self {
+            writer: BufWriter::new(Box::new(writer)),
+            start: Instant::now(),
+            record_input,
+            pending_output: Default::default(),
+            pending_input: Default::default(),
+        })
+    }
+
+    /// Record bytes read from the PTY.
+    pub fn output(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let text = take_utf8(&mut self.pending_output, bytes);
+        self.write_event("o", &text)
+    }
+
+    /// Record bytes written to the PTY.
+    pub fn input(&mut self, bytes: &[u8]) -> io::Result<()> {
+        if !self.record_input {
+            return Ok(());
+        }
+
+        let text = take_utf8(&mut self.pending_input, bytes);
+        self.write_event("i", &text)
+    }
+
+    /// Record a change of the terminal dimensions.
+    pub fn resize(&mut self, columns: usize, lines: usize) -> io::Result<()> {
+        self.write_event("r", &format!("{}x{}", columns, lines))
+    }
+
+    /// Write all buffered events.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    fn write_event(&mut self, code: &str, data: &str) -> io::Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let time = self.start.elapsed().as_secs_f64();
+        let mut line = format!("[{:.6}, \"{}\", ", time, code);
+        push_json_string(&mut line, data);
+        line.push_str("]\n");
+
+        self.writer.write_all(line.as_bytes())
+    }
+}
+
+impl Debug for Recorder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recorder")
+            .field("start", &self.start)
+            .field("record_input", &self.record_input)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Decode all complete UTF-8 sequences, keeping an incomplete trailing sequence for later.
+///
+/// Invalid sequences are replaced with `U+FFFD`.
+fn take_utf8(pending: &mut Vec<u8>, bytes: &[u8]) -> String {
+    pending.extend_from_slice(bytes);
+
+    let mut text = String::new();
+    let mut rest = &pending[..];
+    loop {
+        match str::from_utf8(rest) {
+            Ok(valid) => {
+                text.push_str(valid);
+                rest = &[];
+                break;
+            },
+            Err(err) => {
+                let (valid, invalid) = rest.split_at(err.valid_up_to());
+                text.push_str(str::from_utf8(valid).unwrap());
+
+                match err.error_len() {
+                    Some(len) => {
+                        text.push(char::REPLACEMENT_CHARACTER);
+                        rest = &invalid[len..];
+                    },
+                    None => {
+                        rest = invalid;
+                        break;
+                    },
+                }
+            },
+        }
+    }
+
+    let consumed = pending.len() - rest.len();
+    pending.drain(..consumed);
+
+    text
+}
+
+/// Append a string to `json` as quoted JSON string.
+fn push_json_string(json: &mut String, text: &str) {
+    json.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c < ' ' || c == '\x7f' => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            },
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    /// Writer which can be inspected after being moved into the recorder.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn record_events() {
+        let buffer = SharedBuffer::default();
+        let mut recorder = Recorder::new(buffer.clone(), 80, 24, false).unwrap();
+
+        // Split in the middle of `é`.
+        recorder.output(b"\x1b[1mcaf\xc3").unwrap();
+        recorder.output(b"\xa9\"\r\n").unwrap();
+        recorder.input(b"secret").unwrap();
+        recorder.resize(100, 30).unwrap();
+        recorder.flush().unwrap();
+
+        let recording = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<_> = recording.lines().collect();
+        assert_eq!(lines.len(), 4);
+
+        let header: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(header["version"], 2);
+        assert_eq!(header["width"], 80);
+        assert_eq!(header["height"], 24);
+
+        let events: Vec<(f64, String, String)> =
+            lines[1..].iter().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(events[0].1, "o");
+        assert_eq!(events[0].2, "\x1b[1mcaf");
+        assert_eq!(events[1].2, "é\"\r\n");
+        assert_eq!((events[2].1.as_str(), events[2].2.as_str()), ("r", "100x30"));
+        assert!(events[0].0 <= events[1].0 && events[1].0 <= events[2].0);
+    }
+
+    #[test]
+    fn record_input() {
+        let buffer = SharedBuffer::default();
+        let mut recorder = Recorder::new(buffer.clone(), 80, 24, true).unwrap();
+        recorder.input(b"ls\r").unwrap();
+        recorder.flush().unwrap();
+
+        let recording = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let event = recording.lines().nth(1).unwrap();
+        let (_, code, data): (f64, String, String) = serde_json::from_str(event).unwrap();
+        assert_eq!((code.as_str(), data.as_str()), ("i", "ls\r"));
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let mut pending = Vec::new();
+        assert_eq!(take_utf8(&mut pending, b"a\xffb\xe6\xbc"), "a\u{fffd}b");
+        assert_eq!(pending, b"\xe6\xbc");
+        assert_eq!(take_utf8(&mut pending, b"\xa2"), "漢");
+        assert!(pending.is_empty());
+    }
+}
@@ -0,0 +1,702 @@
+//! Runtime-agnostic asynchronous alternative to [`EventLoop::spawn`].
+//!
+//! Instead of dedicating a thread to every terminal, [`AsyncEventLoop`] is a [`Future`] which
+//! performs all pending PTY I/O whenever it is polled and resolves once the terminal has shut
+//! down. It can be spawned on any executor:
+//!
+//! ```no_run
+//! # use std::sync::Arc;
+//! # use alacritty_terminal::async_event_loop::{self, AsyncEventLoop};
+//! # use alacritty_terminal::event::{Event, WindowSize};
+//! # use alacritty_terminal::event_loop::Notifier;
+//! # use alacritty_terminal::sync::FairMutex;
+//! # use alacritty_terminal::term::test::TermSize;
+//! # use alacritty_terminal::term::{Config, Term};
+//! # use alacritty_terminal::tty;
+//! # fn spawn<F: std::future::Future>(_: F) {}
+//! # let window_size = WindowSize { num_lines: 24, num_cols: 80, cell_width: 1, cell_height: 1 };
+//! let (sender, mut events) = async_event_loop::event_channel();
+//! let size = TermSize::new(80, 24);
+//! let terminal = Arc::new(FairMutex::new(Term::new(Config::default(), &size, sender.clone())));
+//! let pty = tty::new(&tty::Options::default(), window_size, 0).unwrap();
+//!
+//! let event_loop = AsyncEventLoop::new(terminal.clone(), sender, pty, false).unwrap();
+//! let notifier = Notifier(event_loop.channel());
+//! spawn(event_loop);
+//!
+//! spawn(async move {
+//!     while let Some(event) = events.recv().await {
+//!         if let Event::Wakeup = event {
+//!             // Read the new output from `terminal`.
+//!         }
+//!     }
+//! });
+//! ```
+//!
+//! Messages are sent through the same [`Notifier`]/[`Msg`] channel used by [`EventLoop`] and
+//! terminal updates are reported through the [`EventListener`]. The listener created by
+//! [`event_channel`] turns them into an asynchronous stream of events, which can be awaited with
+//! [`EventReceiver::recv`] or polled like a `Stream` with [`EventReceiver::poll_recv`].
+//!
+//! # Reactor thread
+//!
+//! Polling an [`AsyncEventLoop`] for the first time starts a single reactor thread, named
+//! `PTY reactor`, which is shared by all event loops of the process and runs until it exits. It
+//! waits for the descriptors of all pending event loops, so that a ready PTY or message channel
+//! wakes up the task of its event loop, and it keeps track of the [`AsyncEventLoop::deadline`] of
+//! every event loop. The event loops themselves only ever run on the executor polling them.
+//!
+//! Executors which can watch file descriptors themselves, like tokio's `AsyncFd`, can avoid the
+//! reactor thread entirely by never polling the future. Instead, [`AsyncEventLoop::process`] is
+//! called whenever the descriptor returned by [`AsRawFd::as_raw_fd`] is readable or the
+//! deadline is reached.
+//!
+//! [`Notifier`]: crate::event_loop::Notifier
+//! [`Msg`]: crate::event_loop::Msg
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::future::Future;
+use std::io;
+use std::num::NonZeroUsize;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+use log::error;
+use parking_lot::Mutex;
+use polling::{Event as PollingEvent, Events, PollMode, Poller};
+
+use crate::event::{self, Event, EventListener};
+use crate::event_loop::{EventLoop, EventLoopSender, State};
+use crate::sync::FairMutex;
+use crate::term::Term;
+use crate::{thread, tty};
+
+/// Max bytes to read from the PTY before forced terminal synchronization.
+///
+/// This is smaller than the threaded event loop's buffer, since it is allocated for every
+/// terminal instead of living on a thread's stack.
+const READ_BUFFER_SIZE: usize = 0x1_0000;
+
+/// Asynchronous PTY event loop.
+pub struct AsyncEventLoop<T: tty::EventedPty, U: EventListener> {
+    event_loop: EventLoop<T, U>,
+    state: State,
+    buf: Box<[u8]>,
+    events: Events,
+    interest: PollingEvent,
+    registration: Option<Arc<Registration>>,
+    finished: bool,
+}
+
+impl<T, U> AsyncEventLoop<T, U>
+where
+    T: tty::EventedPty + event::OnResize + Send + 'static,
+    U: EventListener + Send + 'static,
+{
+    /// Create a new asynchronous event loop.
+    pub fn new(
+        terminal: Arc<FairMutex<Term<U>>>,
+        event_proxy: U,
+        pty: T,
+        hold: bool,
+    ) -> io::Result<Self> {
+        let mut event_loop = EventLoop::new(terminal, event_proxy, pty, hold, false)?;
+
+        let interest = PollingEvent::readable(0);
+        unsafe { event_loop.pty.register(&event_loop.poll, interest, PollMode::Level)? };
+
+        Ok(Self {
+            event_loop,
+            interest,
+            state: State::default(),
+            buf: vec![0; READ_BUFFER_SIZE].into_boxed_slice(),
+            events: Events::with_capacity(NonZeroUsize::new(1024).unwrap()),
+            registration: None,
+            finished: false,
+        })
+    }
+
+    /// Channel for sending messages to the event loop.
+    pub fn channel(&self) -> EventLoopSender {
+        self.event_loop.channel()
+    }
+
+    /// Perform all pending I/O without blocking.
+    ///
+    /// Returns `false` once the terminal has shut down, after which this should not be called
+    /// again.
+    pub fn process(&mut self) -> bool {
+        if self.finished {
+            return false;
+        }
+
+        self.events.clear();
+        if let Err(err) = self.event_loop.poll.wait(&mut self.events, Some(Duration::ZERO)) {
+            if err.kind() != io::ErrorKind::Interrupted {
+                error!("Event loop polling error: {}", err);
+                return self.finish();
+            }
+        }
+
+        // Handle synchronized update timeout.
+        if self.state.sync_deadline().is_some_and(|deadline| deadline <= Instant::now()) {
+            self.event_loop.stop_sync(&mut self.state);
+        }
+
+        let running = self.event_loop.handle_events(
+            &mut self.state,
+            &self.events,
+            &mut self.buf,
+            None::<&mut File>,
+            &mut self.interest,
+        );
+
+        running || self.finish()
+    }
+
+    /// Time at which [`Self::process`] must be called, even without any I/O.
+    ///
+    /// This is used to end synchronized updates which did not terminate in time, to send
+    /// deferred redraw requests and to continue parsing output which exceeded the time budget.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.state.deadline()
+    }
+
+    /// Stop processing PTY events.
+    ///
+    /// Always returns `false`.
+    fn finish(&mut self) -> bool {
+        if let Some(registration) = self.registration.take() {
+            Reactor::get().deregister(&registration, self.as_fd());
+        }
+        let _ = self.event_loop.pty.deregister(&self.event_loop.poll);
+        self.finished = true;
+
+        false
+    }
+}
+
+impl<T, U> Future for AsyncEventLoop<T, U>
+where
+    T: tty::EventedPty + event::OnResize + Send + Unpin + 'static,
+    U: EventListener + Send + Unpin + 'static,
+{
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = self.get_mut();
+
+        if !this.process() {
+            return Poll::Ready(());
+        }
+
+        // Wake up once the PTY or channel is ready, or the deadline has been reached.
+        let reactor = Reactor::get();
+        let deadline = this.deadline();
+        let result = match &this.registration {
+            Some(registration) => reactor.rearm(registration, this.as_fd(), cx.waker(), deadline),
+            None => reactor.register(this.as_fd(), cx.waker(), deadline).map(|registration| {
+                this.registration = Some(registration);
+            }),
+        };
+
+        match result {
+            Ok(()) => Poll::Pending,
+            Err(err) => {
+                error!("Event loop registration error: {}", err);
+                this.finish();
+                Poll::Ready(())
+            },
+        }
+    }
+}
+
+impl<T: tty::EventedPty, U: EventListener> AsRawFd for AsyncEventLoop<T, U> {
+    /// Descriptor which becomes readable whenever [`AsyncEventLoop::process`] has work to do.
+    fn as_raw_fd(&self) -> RawFd {
+        self.event_loop.poll.as_raw_fd()
+    }
+}
+
+impl<T: tty::EventedPty, U: EventListener> AsFd for AsyncEventLoop<T, U> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.event_loop.poll.as_fd()
+    }
+}
+
+impl<T: tty::EventedPty, U: EventListener> Drop for AsyncEventLoop<T, U> {
+    fn drop(&mut self) {
+        if let Some(registration) = self.registration.take() {
+            Reactor::get().deregister(&registration, self.event_loop.poll.as_fd());
+        }
+    }
+}
+
+/// Create a listener whose events can be received asynchronously.
+///
+/// The [`EventSender`] is passed to the terminal and its event loop, while the
+/// [`EventReceiver`] yields all their events. Consecutive [`Event::Wakeup`]s which were not
+/// received yet are merged into one.
+pub fn event_channel() -> (EventSender, EventReceiver) {
+    let state = ChannelState { senders: 1, ..Default::default() };
+    let channel = Arc::new(Mutex::new(state));
+    (EventSender { channel: channel.clone() }, EventReceiver { channel })
+}
+
+/// Event listener forwarding all events to an [`EventReceiver`].
+pub struct EventSender {
+    channel: Arc<Mutex<ChannelState>>,
+}
+
+impl EventListener for EventSender {
+    fn send_event(&self, event: Event) {
+        let waker = {
+            let mut channel = self.channel.lock();
+            if let Event::Wakeup = event {
+                if channel.wakeup_queued {
+                    return;
+                }
+                channel.wakeup_queued = true;
+            }
+
+            channel.events.push_back(event);
+            channel.waker.take()
+        };
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl Clone for EventSender {
+    fn clone(&self) -> Self {
+        self.channel.lock().senders += 1;
+        Self { channel: self.channel.clone() }
+    }
+}
+
+impl Drop for EventSender {
+    fn drop(&mut self) {
+        let waker = {
+            let mut channel = self.channel.lock();
+            channel.senders -= 1;
+            if channel.senders > 0 {
+                return;
+            }
+            channel.waker.take()
+        };
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// Asynchronous receiver for the events of an [`EventSender`].
+pub struct EventReceiver {
+    channel: Arc<Mutex<ChannelState>>,
+}
+
+impl EventReceiver {
+    /// Wait for the next event.
+    ///
+    /// Returns `None` once all senders were dropped and every event has been received.
+    pub async fn recv(&mut self) -> Option<Event> {
+        std::future::poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    /// Poll for the next event.
+    ///
+    /// This matches the signature of `Stream::poll_next`, so the receiver can be wrapped into any
+    /// stream implementation. While no event is available, the waker of `cx` is woken once the
+    /// next event is sent.
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<Event>> {
+        let mut channel = self.channel.lock();
+        match channel.pop() {
+            Some(event) => Poll::Ready(Some(event)),
+            None if channel.senders == 0 => Poll::Ready(None),
+            None => {
+                channel.waker = Some(cx.waker().clone());
+                Poll::Pending
+            },
+        }
+    }
+
+    /// Receive the next event without waiting.
+    pub fn try_recv(&mut self) -> Option<Event> {
+        self.channel.lock().pop()
+    }
+}
+
+/// Events shared between the senders and the receiver of an event channel.
+#[derive(Default)]
+struct ChannelState {
+    events: VecDeque<Event>,
+
+    /// Whether a wakeup is waiting to be received.
+    wakeup_queued: bool,
+
+    /// Task waiting for the next event.
+    waker: Option<Waker>,
+
+    /// Number of live senders.
+    senders: usize,
+}
+
+impl ChannelState {
+    fn pop(&mut self) -> Option<Event> {
+        let event = self.events.pop_front()?;
+        if let Event::Wakeup = event {
+            self.wakeup_queued = false;
+        }
+        Some(event)
+    }
+}
+
+/// Wakeup state of a single event loop.
+struct Registration {
+    key: usize,
+    wakeup: Mutex<Wakeup>,
+}
+
+#[derive(Default)]
+struct Wakeup {
+    waker: Option<Waker>,
+    deadline: Option<Instant>,
+}
+
+impl Registration {
+    /// Wake the event loop's task.
+    fn wake(&self) {
+        let waker = {
+            let mut wakeup = self.wakeup.lock();
+            wakeup.deadline = None;
+            wakeup.waker.take()
+        };
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// Shared thread waking up event loops when their descriptors are ready.
+struct Reactor {
+    poller: Poller,
+    registrations: Mutex<HashMap<usize, Arc<Registration>>>,
+    next_key: AtomicUsize,
+}
+
+impl Reactor {
+    /// Get the global reactor, starting it if necessary.
+    fn get() -> &'static Reactor {
+        static REACTOR: OnceLock<Reactor> = OnceLock::new();
+
+        REACTOR.get_or_init(|| {
+            thread::spawn_named("PTY reactor", || Reactor::get().run());
+
+            Reactor {
+                poller: Poller::new().expect("create reactor poller"),
+                registrations: Default::default(),
+                next_key: Default::default(),
+            }
+        })
+    }
+
+    fn register(
+        &self,
+        fd: BorrowedFd<'_>,
+        waker: &Waker,
+        deadline: Option<Instant>,
+    ) -> io::Result<Arc<Registration>> {
+        let key = self.next_key.fetch_add(1, Ordering::Relaxed);
+        let wakeup = Wakeup { waker: Some(waker.clone()), deadline };
+        let registration = Arc::new(Registration { key, wakeup: Mutex::new(wakeup) });
+        self.registrations.lock().insert(key, registration.clone());
+
+        unsafe { self.poller.add(fd.as_raw_fd(), PollingEvent::readable(key))? };
+
+        if deadline.is_some() {
+            self.poller.notify()?;
+        }
+
+        Ok(registration)
+    }
+
+    fn rearm(
+        &self,
+        registration: &Registration,
+        fd: BorrowedFd<'_>,
+        waker: &Waker,
+        deadline: Option<Instant>,
+    ) -> io::Result<()> {
+        {
+            let mut wakeup = registration.wakeup.lock();
+            wakeup.waker = Some(waker.clone());
+            wakeup.deadline = deadline;
+        }
+
+        self.poller.modify(fd, PollingEvent::readable(registration.key))?;
+
+        // Make sure the reactor picks up the new deadline.
+        if deadline.is_some() {
+            self.poller.notify()?;
+        }
+
+        Ok(())
+    }
+
+    fn deregister(&self, registration: &Registration, fd: BorrowedFd<'_>) {
+        self.registrations.lock().remove(&registration.key);
+        let _ = self.poller.delete(fd);
+    }
+
+    fn run(&self) {
+        let mut events = Events::new();
+
+        loop {
+            // Wait until the next sync timeout is reached.
+            let deadline = self
+                .registrations
+                .lock()
+                .values()
+                .filter_map(|registration| registration.wakeup.lock().deadline)
+                .min();
+            let timeout =
+                deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+
+            events.clear();
+            if let Err(err) = self.poller.wait(&mut events, timeout) {
+                if err.kind() != io::ErrorKind::Interrupted {
+                    error!("Reactor polling error: {}", err);
+                }
+                continue;
+            }
+
+            // Collect tasks first, to avoid waking them while holding the lock.
+            let now = Instant::now();
+            let ready: Vec<_> = {
+                let registrations = self.registrations.lock();
+                let expired = registrations.values().filter(|registration| {
+                    registration.wakeup.lock().deadline.is_some_and(|deadline| deadline <= now)
+                });
+                events
+                    .iter()
+                    .filter_map(|event| registrations.get(&event.key))
+                    .chain(expired)
+                    .cloned()
+                    .collect()
+            };
+
+            for registration in ready {
+                registration.wake();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixStream;
+    use std::sync::atomic::AtomicBool;
+    use std::task::Wake;
+    use std::thread::Thread;
+
+    use crate::event::{Event, Notify, OnResize, VoidListener, WindowSize};
+    use crate::event_loop::{Msg, Notifier};
+    use crate::index::{Column, Line};
+    use crate::term::test::TermSize;
+    use crate::term::Config;
+    use crate::tty::{ChildEvent, EventedPty, EventedReadWrite};
+
+    /// PTY replacement backed by a socket.
+    struct MockPty(UnixStream);
+
+    impl EventedReadWrite for MockPty {
+        type Reader = UnixStream;
+        type Writer = UnixStream;
+
+        unsafe fn register(
+            &mut self,
+            poll: &Arc<Poller>,
+            mut interest: PollingEvent,
+            mode: PollMode,
+        ) -> io::Result<()> {
+            interest.key = tty::PTY_READ_WRITE_TOKEN;
+            unsafe { poll.add_with_mode(&self.0, interest, mode) }
+        }
+
+        fn reregister(
+            &mut self,
+            poll: &Arc<Poller>,
+            mut interest: PollingEvent,
+            mode: PollMode,
+        ) -> io::Result<()> {
+            interest.key = tty::PTY_READ_WRITE_TOKEN;
+            poll.modify_with_mode(&self.0, interest, mode)
+        }
+
+        fn deregister(&mut self, poll: &Arc<Poller>) -> io::Result<()> {
+            poll.delete(&self.0)
+        }
+
+        fn reader(&mut self) -> &mut UnixStream {
+            &mut self.0
+        }
+
+        fn writer(&mut self) -> &mut UnixStream {
+            &mut self.0
+        }
+    }
+
+    impl EventedPty for MockPty {
+        fn next_child_event(&mut self) -> Option<ChildEvent> {
+            None
+        }
+    }
+
+    impl OnResize for MockPty {
+        fn on_resize(&mut self, _: WindowSize) {}
+    }
+
+    struct ThreadWaker {
+        thread: Thread,
+        woken: AtomicBool,
+    }
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.woken.store(true, Ordering::Release);
+            self.thread.unpark();
+        }
+    }
+
+    /// Minimal executor driving a single future.
+    fn block_on<F: Future + Unpin>(mut future: F) -> F::Output {
+        let thread = std::thread::current();
+        let thread_waker = Arc::new(ThreadWaker { thread, woken: AtomicBool::new(true) });
+        let waker = Waker::from(thread_waker.clone());
+        let mut context = Context::from_waker(&waker);
+
+        loop {
+            if thread_waker.woken.swap(false, Ordering::AcqRel) {
+                if let Poll::Ready(output) = Pin::new(&mut future).poll(&mut context) {
+                    return output;
+                }
+            } else {
+                std::thread::park_timeout(Duration::from_secs(5));
+            }
+        }
+    }
+
+    #[test]
+    fn read_write_shutdown() {
+        let (pty, mut remote) = UnixStream::pair().unwrap();
+        pty.set_nonblocking(true).unwrap();
+
+        let size = TermSize::new(20, 5);
+        let terminal = Arc::new(FairMutex::new(Term::new(Config::default(), &size, VoidListener)));
+        let event_loop =
+            AsyncEventLoop::new(terminal.clone(), VoidListener, MockPty(pty), false).unwrap();
+        let notifier = Notifier(event_loop.channel());
+
+        let remote = std::thread::spawn(move || {
+            remote.write_all(b"hello").unwrap();
+
+            notifier.notify(&b"ping"[..]);
+            let mut input = [0; 4];
+            remote.read_exact(&mut input).unwrap();
+
+            notifier.0.send(Msg::Shutdown).unwrap();
+            input
+        });
+
+        block_on(event_loop);
+
+        assert_eq!(&remote.join().unwrap(), b"ping");
+        let terminal = terminal.lock();
+        let text: String = (0..5).map(|i| terminal.grid()[Line(0)][Column(i)].c).collect();
+        assert_eq!(text, "hello");
+    }
+
+    /// Listener counting redraw requests.
+    #[derive(Clone, Default)]
+    struct WakeupCounter(Arc<AtomicUsize>);
+
+    impl EventListener for WakeupCounter {
+        fn send_event(&self, event: Event) {
+            if let Event::Wakeup = event {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    #[test]
+    fn event_channel() {
+        let (pty, mut remote) = UnixStream::pair().unwrap();
+        pty.set_nonblocking(true).unwrap();
+
+        let (sender, mut events) = super::event_channel();
+        let size = TermSize::new(20, 5);
+        let terminal = Term::new(Config::default(), &size, sender.clone());
+        let terminal = Arc::new(FairMutex::new(terminal));
+        let mut event_loop = AsyncEventLoop::new(terminal, sender, MockPty(pty), false).unwrap();
+
+        // Pending wakeups are merged.
+        remote.write_all(b"a").unwrap();
+        event_loop.process();
+        remote.write_all(b"b").unwrap();
+        event_loop.process();
+        assert!(matches!(block_on(Box::pin(events.recv())), Some(Event::Wakeup)));
+        assert!(events.try_recv().is_none());
+
+        // The stream ends once the terminal and its event loop are gone.
+        drop(event_loop);
+        assert!(block_on(Box::pin(events.recv())).is_none());
+    }
+
+    #[test]
+    fn wakeups_wait_for_drawn_frames() {
+        let (pty, mut remote) = UnixStream::pair().unwrap();
+        pty.set_nonblocking(true).unwrap();
+
+        let size = TermSize::new(20, 5);
+        let wakeups = WakeupCounter::default();
+        let terminal = Term::new(Config::default(), &size, wakeups.clone());
+        let terminal = Arc::new(FairMutex::new(terminal));
+        let mut event_loop =
+            AsyncEventLoop::new(terminal, wakeups.clone(), MockPty(pty), false).unwrap();
+        let sender = event_loop.channel();
+        let wakeups = wakeups.0;
+
+        // Without frame acknowledgements, every read requests a redraw.
+        remote.write_all(b"a").unwrap();
+        event_loop.process();
+        remote.write_all(b"b").unwrap();
+        event_loop.process();
+        assert_eq!(wakeups.load(Ordering::Relaxed), 2);
+
+        // Once frames are acknowledged, redraws are held back until the frame was drawn.
+        sender.send(Msg::FrameDrawn).unwrap();
+        remote.write_all(b"c").unwrap();
+        event_loop.process();
+        remote.write_all(b"d").unwrap();
+        event_loop.process();
+        assert_eq!(wakeups.load(Ordering::Relaxed), 3);
+        assert!(event_loop.deadline().is_some());
+
+        sender.send(Msg::FrameDrawn).unwrap();
+        event_loop.process();
+        assert_eq!(wakeups.load(Ordering::Relaxed), 4);
+        assert_eq!(event_loop.deadline(), None);
+    }
+}
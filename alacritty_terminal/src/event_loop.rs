@@ -6,17 +6,20 @@ use std::fmt::{self, Display, Formatter};
 use std::fs::File;
 use std::io::{self, ErrorKind, Read, Write};
 use std::marker::Send;
+use std::mem;
 use std::num::NonZeroUsize;
 use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
 use std::sync::Arc;
 use std::thread::JoinHandle;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use log::error;
 use polling::{Event as PollingEvent, Events, PollMode};
 
+use crate::asciicast::Recorder;
 use crate::event::{self, Event, EventListener, WindowSize};
 use crate::sync::FairMutex;
+use crate::term::snapshot::SnapshotBuffer;
 use crate::term::Term;
 use crate::vte::ansi;
 use crate::{thread, tty};
@@ -27,6 +30,15 @@ pub(crate) const READ_BUFFER_SIZE: usize = 0x10_0000;
 /// Max bytes to read from the PTY while the terminal is locked.
 const MAX_LOCKED_READ: usize = u16::MAX as usize;
 
+/// Max time spent parsing PTY output while the terminal is locked.
+const MAX_LOCKED_TIME: Duration = Duration::from_millis(2);
+
+/// Bytes parsed between checks of the locked time.
+const PARSE_CHUNK_SIZE: usize = 0x1000;
+
+/// Max time a redraw is held back while the UI is still drawing the previous frame.
+const MAX_FRAME_DELAY: Duration = Duration::from_millis(100);
+
 /// Messages that may be sent to the `EventLoop`.
 #[derive(Debug)]
 pub enum Msg {
@@ -38,6 +50,18 @@ pub enum Msg {
 
     /// Instruction to resize the PTY.
     Resize(WindowSize),
+
+    /// Record all PTY I/O until the recording is stopped.
+    StartRecording(Recorder),
+
+    /// Stop the active recording.
+    StopRecording,
+
+    /// Indicates that the UI has drawn the terminal.
+    ///
+    /// Once this has been received, redraws are only requested after the previous frame was
+    /// drawn, dropping intermediate frames when rendering can't keep up with the PTY.
+    FrameDrawn,
 }
 
 /// The main event loop.
@@ -45,14 +69,17 @@ pub enum Msg {
 /// Handles all the PTY I/O and runs the PTY parser which updates terminal
 /// state.
 pub struct EventLoop<T: tty::EventedPty, U: EventListener> {
-    poll: Arc<polling::Poller>,
-    pty: T,
+    pub(crate) poll: Arc<polling::Poller>,
+    pub(crate) pty: T,
     rx: PeekableReceiver<Msg>,
     tx: Sender<Msg>,
     terminal: Arc<FairMutex<Term<U>>>,
     event_proxy: U,
     hold: bool,
     ref_test: bool,
+    recorder: Option<Recorder>,
+    password_input: bool,
+    snapshots: Option<Arc<SnapshotBuffer>>,
 }
 
 impl<T, U> EventLoop<T, U>
@@ -79,6 +106,9 @@ where
             event_proxy,
             hold,
             ref_test,
+            recorder: None,
+            password_input: false,
+            snapshots: None,
         })
     }
 
@@ -86,14 +116,52 @@ where
         EventLoopSender { sender: self.tx.clone(), poller: self.poll.clone() }
     }
 
+    /// Publish a snapshot of the terminal whenever PTY output requires a redraw.
+    ///
+    /// This allows rendering the latest terminal content without waiting for the terminal lock.
+    /// While [`SnapshotBuffer::highlighting`] is set, publishing is left to the event listener.
+    pub fn publish_snapshots(&mut self, snapshots: Arc<SnapshotBuffer>) {
+        self.snapshots = Some(snapshots);
+    }
+
     /// Drain the channel.
     ///
     /// Returns `false` when a shutdown message was received.
     fn drain_recv_channel(&mut self, state: &mut State) -> bool {
         while let Some(msg) = self.rx.recv() {
             match msg {
-                Msg::Input(input) => state.write_list.push_back(input),
-                Msg::Resize(window_size) => self.pty.on_resize(window_size),
+                Msg::Input(input) => {
+                    // Never record keystrokes while a password is being entered.
+                    let pty = &self.pty;
+                    record(&mut self.recorder, |recorder| {
+                        if pty.is_reading_password() {
+                            Ok(())
+                        } else {
+                            recorder.input(&input)
+                        }
+                    });
+                    state.write_list.push_back(input);
+                },
+                Msg::Resize(window_size) => {
+                    let (columns, lines) = (window_size.num_cols, window_size.num_lines);
+                    record(&mut self.recorder, |recorder| {
+                        recorder.resize(columns.into(), lines.into())
+                    });
+                    self.pty.on_resize(window_size);
+                },
+                Msg::StartRecording(recorder) => self.recorder = Some(recorder),
+                Msg::StopRecording => {
+                    record(&mut self.recorder, Recorder::flush);
+                    self.recorder = None;
+                },
+                Msg::FrameDrawn => {
+                    state.frames.acknowledged = true;
+                    state.frames.pending = None;
+
+                    if state.frames.deferred {
+                        self.wakeup(state);
+                    }
+                },
                 Msg::Shutdown => return false,
             }
         }
@@ -111,28 +179,45 @@ where
     where
         X: Write,
     {
-        let mut unprocessed = 0;
+        // Continue with bytes which exceeded the previous read's time budget, parsing them before
+        // reading more from the PTY.
+        let mut unprocessed = mem::take(&mut state.unprocessed);
+        let mut skip_read = unprocessed > 0;
+        let mut read_error = None;
         let mut processed = 0;
 
         // Reserve the next terminal lock for PTY reading.
         let _terminal_lease = Some(self.terminal.lease());
         let mut terminal = None;
+        let mut locked_since = None;
 
         loop {
             // Read from the PTY.
-            match self.pty.reader().read(&mut buf[unprocessed..]) {
+            let read = if mem::take(&mut skip_read) || read_error.is_some() {
+                None
+            } else {
+                Some(self.pty.reader().read(&mut buf[unprocessed..]))
+            };
+            match read {
                 // This is received on Windows/macOS when no more data is readable from the PTY.
-                Ok(0) if unprocessed == 0 => break,
-                Ok(got) => unprocessed += got,
-                Err(err) => match err.kind() {
+                Some(Ok(0)) if unprocessed == 0 => break,
+                Some(Ok(got)) => unprocessed += got,
+                Some(Err(err)) => match err.kind() {
                     ErrorKind::Interrupted | ErrorKind::WouldBlock => {
                         // Go back to mio if we're caught up on parsing and the PTY would block.
                         if unprocessed == 0 {
                             break;
                         }
                     },
-                    _ => return Err(err),
+                    // Parse the remaining bytes before reporting the error, so no output is lost.
+                    _ => {
+                        read_error = Some(err);
+                        if unprocessed == 0 {
+                            break;
+                        }
+                    },
                 },
+                None => (),
             }
 
             // Attempt to lock the terminal.
@@ -140,37 +225,90 @@ where
                 Some(terminal) => terminal,
                 None => terminal.insert(match self.terminal.try_lock_unfair() {
                     // Force block if we are at the buffer size limit.
-                    None if unprocessed >= READ_BUFFER_SIZE => self.terminal.lock_unfair(),
+                    None if unprocessed >= buf.len() => self.terminal.lock_unfair(),
                     None => continue,
                     Some(terminal) => terminal,
                 }),
             };
+            let locked_since = *locked_since.get_or_insert_with(Instant::now);
+
+            // Parse the incoming bytes, until the time budget is exhausted.
+            let mut parsed = 0;
+            for chunk in buf[..unprocessed].chunks(PARSE_CHUNK_SIZE) {
+                // Write a copy of the bytes to the ref test file.
+                if let Some(writer) = &mut writer {
+                    writer.write_all(chunk).unwrap();
+                }
 
-            // Write a copy of the bytes to the ref test file.
-            if let Some(writer) = &mut writer {
-                writer.write_all(&buf[..unprocessed]).unwrap();
-            }
+                record(&mut self.recorder, |recorder| recorder.output(chunk));
 
-            // Parse the incoming bytes.
-            for byte in &buf[..unprocessed] {
-                state.parser.advance(&mut **terminal, *byte);
-            }
+                parse(&mut state.parser, terminal, chunk);
 
-            processed += unprocessed;
-            unprocessed = 0;
+                parsed += chunk.len();
+                if locked_since.elapsed() >= MAX_LOCKED_TIME {
+                    break;
+                }
+            }
 
-            // Assure we're not blocking the terminal too long unnecessarily.
-            if processed >= MAX_LOCKED_READ {
+            // Keep the remaining bytes for the next lock acquisition.
+            buf.copy_within(parsed..unprocessed, 0);
+            unprocessed -= parsed;
+            processed += parsed;
+
+            // Assure we're not blocking the terminal too long unnecessarily, and release it
+            // immediately when there is new input which should be written to the PTY.
+            if processed >= MAX_LOCKED_READ
+                || locked_since.elapsed() >= MAX_LOCKED_TIME
+                || self.rx.peek().is_some()
+                || (read_error.is_some() && unprocessed == 0)
+            {
                 break;
             }
         }
 
+        state.unprocessed = unprocessed;
+
         // Queue terminal redraw unless all processed bytes were synchronized.
         if state.parser.sync_bytes_count() < processed && processed > 0 {
-            self.event_proxy.send_event(Event::Wakeup);
+            if let (Some(snapshots), Some(terminal)) = (self.publisher(), &mut terminal) {
+                snapshots.publish(&mut **terminal);
+            }
+
+            self.wakeup(state);
         }
 
-        Ok(())
+        // Write the recorded output once the terminal is unlocked.
+        drop(terminal);
+        record(&mut self.recorder, Recorder::flush);
+
+        // Treat read errors as the end of the PTY output, once all bytes have been parsed.
+        match read_error {
+            Some(err) if state.unprocessed == 0 => Err(err),
+            _ => Ok(()),
+        }
+    }
+
+    /// Read from the PTY, logging unrecoverable errors.
+    ///
+    /// Returns `false` when the event loop should shut down.
+    fn try_pty_read<X>(&mut self, state: &mut State, buf: &mut [u8], writer: Option<&mut X>) -> bool
+    where
+        X: Write,
+    {
+        match self.pty_read(state, buf, writer) {
+            Ok(()) => true,
+            // On Linux, a `read` on the master side of a PTY can fail
+            // with `EIO` if the client side hangs up.  In that case,
+            // just loop back round for the inevitable `Exited` event.
+            // This sucks, but checking the process is either racy or
+            // blocking.
+            #[cfg(target_os = "linux")]
+            Err(err) if err.raw_os_error() == Some(libc::EIO) => true,
+            Err(err) => {
+                error!("Error reading from PTY in event loop: {}", err);
+                false
+            },
+        }
     }
 
     #[inline]
@@ -205,6 +343,135 @@ where
         Ok(())
     }
 
+    /// Flush a pending synchronized update.
+    pub(crate) fn stop_sync(&mut self, state: &mut State) {
+        let mut terminal = self.terminal.lock();
+        state.parser.stop_sync(&mut *terminal);
+
+        if let Some(snapshots) = self.publisher() {
+            snapshots.publish(&mut *terminal);
+        }
+        drop(terminal);
+
+        self.wakeup(state);
+    }
+
+    /// Snapshot buffer, if snapshots should be published by the event loop.
+    fn publisher(&self) -> Option<&SnapshotBuffer> {
+        self.snapshots.as_deref().filter(|snapshots| !snapshots.highlighting())
+    }
+
+    /// Request a terminal redraw.
+    ///
+    /// While the UI has not drawn the previous frame yet, the request is deferred instead. This
+    /// drops intermediate frames when the renderer can't keep up with the PTY.
+    fn wakeup(&self, state: &mut State) {
+        let frames = &mut state.frames;
+        if frames.pending.is_some_and(|requested| requested.elapsed() < MAX_FRAME_DELAY) {
+            frames.deferred = true;
+            return;
+        }
+
+        frames.deferred = false;
+        frames.pending = frames.acknowledged.then(Instant::now);
+        self.event_proxy.send_event(Event::Wakeup);
+    }
+
+    /// Notify the UI when the child starts or stops reading a password.
+    fn update_password_input(&mut self) {
+        let password_input = self.pty.is_reading_password();
+        if password_input != self.password_input {
+            self.password_input = password_input;
+            self.event_proxy.send_event(Event::PasswordInput(password_input));
+        }
+    }
+
+    /// Handle pending channel messages and PTY events.
+    ///
+    /// Returns `false` when the event loop should shut down.
+    pub(crate) fn handle_events<X>(
+        &mut self,
+        state: &mut State,
+        events: &Events,
+        buf: &mut [u8],
+        mut pipe: Option<&mut X>,
+        interest: &mut PollingEvent,
+    ) -> bool
+    where
+        X: Write,
+    {
+        // Handle channel events, if there are any.
+        if !self.drain_recv_channel(state) {
+            return false;
+        }
+
+        // Stop waiting for a frame which is taking too long to draw.
+        if state.frames.deadline().is_some_and(|deadline| deadline <= Instant::now()) {
+            self.wakeup(state);
+        }
+
+        for event in events.iter() {
+            match event.key {
+                tty::PTY_CHILD_EVENT_TOKEN => {
+                    if let Some(tty::ChildEvent::Exited(status)) = self.pty.next_child_event() {
+                        if self.hold {
+                            // With hold enabled, make sure the PTY is drained.
+                            while self.pty_read(state, buf, pipe.as_mut()).is_ok()
+                                && state.unprocessed > 0
+                            {}
+                        } else {
+                            // Without hold, shutdown the terminal.
+                            self.terminal.lock().exit();
+                        }
+
+                        // Notify about the exit only after all output has been processed.
+                        self.event_proxy.send_event(Event::ChildExit(status));
+                        self.event_proxy.send_event(Event::Wakeup);
+                        return false;
+                    }
+                },
+
+                tty::PTY_READ_WRITE_TOKEN => {
+                    if event.is_interrupt() {
+                        // Don't try to do I/O on a dead PTY.
+                        continue;
+                    }
+
+                    // Write pending input first, to keep it responsive while the PTY is flooded.
+                    if event.writable || state.needs_write() {
+                        if let Err(err) = self.pty_write(state) {
+                            error!("Error writing to PTY in event loop: {}", err);
+                            return false;
+                        }
+                    }
+
+                    if event.readable && !self.try_pty_read(state, buf, pipe.as_mut()) {
+                        return false;
+                    }
+
+                    self.update_password_input();
+                },
+                _ => (),
+            }
+        }
+
+        // Continue parsing bytes which exceeded the last read's time budget.
+        if state.unprocessed > 0 && !self.try_pty_read(state, buf, pipe.as_mut()) {
+            return false;
+        }
+
+        // Register write interest if necessary.
+        let needs_write = state.needs_write();
+        if needs_write != interest.writable {
+            interest.writable = needs_write;
+
+            // Re-register with new interest.
+            self.pty.reregister(&self.poll, *interest, PollMode::Level).unwrap();
+        }
+
+        true
+    }
+
     pub fn spawn(mut self) -> JoinHandle<(Self, State)> {
         thread::spawn_named("PTY reader", move || {
             let mut state = State::default();
@@ -228,10 +495,9 @@ where
             };
 
             'event_loop: loop {
-                // Wakeup the event loop when a synchronized update timeout was reached.
-                let handler = state.parser.sync_timeout();
+                // Wakeup the event loop when a timeout was reached or parsing is incomplete.
                 let timeout =
-                    handler.sync_timeout().map(|st| st.saturating_duration_since(Instant::now()));
+                    state.deadline().map(|st| st.saturating_duration_since(Instant::now()));
 
                 events.clear();
                 if let Err(err) = self.poll.wait(&mut events, timeout) {
@@ -245,80 +511,14 @@ where
                 }
 
                 // Handle synchronized update timeout.
-                if events.is_empty() && self.rx.peek().is_none() {
-                    state.parser.stop_sync(&mut *self.terminal.lock());
-                    self.event_proxy.send_event(Event::Wakeup);
-                    continue;
+                if state.sync_deadline().is_some_and(|deadline| deadline <= Instant::now()) {
+                    self.stop_sync(&mut state);
                 }
 
-                // Handle channel events, if there are any.
-                if !self.drain_recv_channel(&mut state) {
+                let pipe = pipe.as_mut();
+                if !self.handle_events(&mut state, &events, &mut buf, pipe, &mut interest) {
                     break;
                 }
-
-                for event in events.iter() {
-                    match event.key {
-                        tty::PTY_CHILD_EVENT_TOKEN => {
-                            if let Some(tty::ChildEvent::Exited(code)) = self.pty.next_child_event()
-                            {
-                                if let Some(code) = code {
-                                    self.event_proxy.send_event(Event::ChildExit(code));
-                                }
-                                if self.hold {
-                                    // With hold enabled, make sure the PTY is drained.
-                                    let _ = self.pty_read(&mut state, &mut buf, pipe.as_mut());
-                                } else {
-                                    // Without hold, shutdown the terminal.
-                                    self.terminal.lock().exit();
-                                }
-                                self.event_proxy.send_event(Event::Wakeup);
-                                break 'event_loop;
-                            }
-                        },
-
-                        tty::PTY_READ_WRITE_TOKEN => {
-                            if event.is_interrupt() {
-                                // Don't try to do I/O on a dead PTY.
-                                continue;
-                            }
-
-                            if event.readable {
-                                if let Err(err) = self.pty_read(&mut state, &mut buf, pipe.as_mut())
-                                {
-                                    // On Linux, a `read` on the master side of a PTY can fail
-                                    // with `EIO` if the client side hangs up.  In that case,
-                                    // just loop back round for the inevitable `Exited` event.
-                                    // This sucks, but checking the process is either racy or
-                                    // blocking.
-                                    #[cfg(target_os = "linux")]
-                                    if err.raw_os_error() == Some(libc::EIO) {
-                                        continue;
-                                    }
-
-                                    error!("Error reading from PTY in event loop: {}", err);
-                                    break 'event_loop;
-                                }
-                            }
-
-                            if event.writable {
-                                if let Err(err) = self.pty_write(&mut state) {
-                                    error!("Error writing to PTY in event loop: {}", err);
-                                    break 'event_loop;
-                                }
-                            }
-                        },
-                        _ => (),
-                    }
-                }
-
-                // Register write interest if necessary.
-                let needs_write = state.needs_write();
-                if needs_write != interest.writable {
-                    interest.writable = needs_write;
-
-                    // Re-register with new interest.
-                    self.pty.reregister(&self.poll, interest, poll_opts).unwrap();
-                }
             }
 
             // The evented instances are not dropped here so deregister them explicitly.
@@ -329,6 +529,55 @@ where
     }
 }
 
+/// Parse PTY output.
+///
+/// Once the parser is known to be in its ground state, runs of printable ASCII are written to the
+/// terminal directly instead of being parsed one byte at a time.
+fn parse<U: EventListener>(parser: &mut ansi::Processor, terminal: &mut Term<U>, bytes: &[u8]) {
+    let is_printable = |byte: &u8| (b' '..=b'~').contains(byte);
+
+    // Ignore characters printed outside of this function, since the synchronized update bytes
+    // replayed after a timeout might have ended in the middle of an escape sequence.
+    terminal.take_printed();
+
+    let mut ground = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        if ground {
+            let len = bytes[i..].iter().position(|byte| !is_printable(byte));
+            let len = len.unwrap_or(bytes.len() - i);
+
+            // Leave the last character to the parser, so it can be repeated using `REP`.
+            if len > 1 {
+                terminal.input_ascii(&bytes[i..i + len - 1]);
+                i += len - 1;
+            }
+        }
+
+        // Bytes buffered by a synchronized update might be replayed by any byte, like when the
+        // buffer is full, leaving the parser in an unknown state.
+        let syncing = parser.sync_bytes_count() > 0;
+
+        parser.advance(terminal, bytes[i]);
+        i += 1;
+
+        ground = terminal.take_printed() && !syncing;
+    }
+}
+
+/// Write an event to the active recording.
+///
+/// Recording is stopped if writing the event fails.
+fn record<F>(recorder: &mut Option<Recorder>, f: F)
+where
+    F: FnOnce(&mut Recorder) -> io::Result<()>,
+{
+    if let Some(Err(err)) = recorder.as_mut().map(f) {
+        error!("Unable to write recording, stopping: {}", err);
+        *recorder = None;
+    }
+}
+
 /// Helper type which tracks how much of a buffer has been written.
 struct Writing {
     source: Cow<'static, [u8]>,
@@ -407,6 +656,10 @@ pub struct State {
     write_list: VecDeque<Cow<'static, [u8]>>,
     writing: Option<Writing>,
     parser: ansi::Processor,
+    frames: Frames,
+
+    /// Bytes at the start of the read buffer which have not been parsed yet.
+    unprocessed: usize,
 }
 
 impl State {
@@ -427,6 +680,24 @@ impl State {
         self.writing.take()
     }
 
+    /// Deadline for the currently pending synchronized update.
+    #[inline]
+    pub(crate) fn sync_deadline(&self) -> Option<Instant> {
+        self.parser.sync_timeout().sync_timeout()
+    }
+
+    /// Time at which the event loop must run, even without any I/O.
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        if self.unprocessed > 0 {
+            return Some(Instant::now());
+        }
+
+        match (self.sync_deadline(), self.frames.deadline()) {
+            (Some(sync), Some(frame)) => Some(sync.min(frame)),
+            (sync, frame) => sync.or(frame),
+        }
+    }
+
     #[inline]
     fn needs_write(&self) -> bool {
         self.writing.is_some() || !self.write_list.is_empty()
@@ -460,6 +731,26 @@ impl Writing {
     }
 }
 
+/// Pacing of terminal redraw requests.
+#[derive(Default)]
+struct Frames {
+    /// Whether the UI reports drawn frames using [`Msg::FrameDrawn`].
+    acknowledged: bool,
+
+    /// Time of the last redraw request which has not been drawn yet.
+    pending: Option<Instant>,
+
+    /// Whether a redraw request is held back until the pending frame was drawn.
+    deferred: bool,
+}
+
+impl Frames {
+    /// Time at which a deferred redraw request is sent regardless of the pending frame.
+    fn deadline(&self) -> Option<Instant> {
+        self.pending.filter(|_| self.deferred).map(|pending| pending + MAX_FRAME_DELAY)
+    }
+}
+
 struct PeekableReceiver<T> {
     rx: Receiver<T>,
     peeked: Option<T>,
@@ -489,3 +780,118 @@ impl<T> PeekableReceiver<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use polling::Poller;
+
+    use crate::event::{OnResize, VoidListener};
+    use crate::index::{Column, Line};
+    use crate::term::test::TermSize;
+    use crate::term::Config;
+    use crate::tty::{ChildEvent, EventedPty, EventedReadWrite};
+    use crate::vte::ansi::{Color, NamedColor};
+
+    /// Reader returning its remaining output once, before failing like a closed PTY.
+    struct ClosedReader(&'static [u8]);
+
+    impl Read for ClosedReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Err(io::Error::new(ErrorKind::BrokenPipe, "PTY closed"));
+            }
+
+            let len = self.0.len().min(buf.len());
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    /// PTY replacement whose child wrote its output and hung up.
+    struct ClosedPty(ClosedReader, io::Sink);
+
+    impl EventedReadWrite for ClosedPty {
+        type Reader = ClosedReader;
+        type Writer = io::Sink;
+
+        unsafe fn register(
+            &mut self,
+            _: &Arc<Poller>,
+            _: PollingEvent,
+            _: PollMode,
+        ) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn reregister(&mut self, _: &Arc<Poller>, _: PollingEvent, _: PollMode) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn deregister(&mut self, _: &Arc<Poller>) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn reader(&mut self) -> &mut ClosedReader {
+            &mut self.0
+        }
+
+        fn writer(&mut self) -> &mut io::Sink {
+            &mut self.1
+        }
+    }
+
+    impl EventedPty for ClosedPty {
+        fn next_child_event(&mut self) -> Option<ChildEvent> {
+            None
+        }
+    }
+
+    impl OnResize for ClosedPty {
+        fn on_resize(&mut self, _: WindowSize) {}
+    }
+
+    #[test]
+    fn parse_output_of_closed_pty() {
+        let size = TermSize::new(20, 5);
+        let terminal = Arc::new(FairMutex::new(Term::new(Config::default(), &size, VoidListener)));
+        let pty = ClosedPty(ClosedReader(b" world"), io::sink());
+        let mut event_loop =
+            EventLoop::new(terminal.clone(), VoidListener, pty, false, false).unwrap();
+
+        // Bytes which exceeded the previous read's time budget are parsed before reading again.
+        let mut buf = [0; 0x100];
+        buf[..5].copy_from_slice(b"hello");
+        let mut state = State { unprocessed: 5, ..Default::default() };
+
+        // The read error is only reported once all bytes were parsed.
+        let result = event_loop.pty_read(&mut state, &mut buf, None::<&mut File>);
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::BrokenPipe);
+        assert_eq!(state.unprocessed, 0);
+        assert_eq!(state.deadline(), None);
+
+        let terminal = terminal.lock();
+        let text: String = (0..11).map(|i| terminal.grid()[Line(0)][Column(i)].c).collect();
+        assert_eq!(text, "hello world");
+    }
+
+    #[test]
+    fn parse_after_sync_timeout() {
+        let size = TermSize::new(20, 5);
+        let mut terminal = Term::new(Config::default(), &size, VoidListener);
+        let mut parser = ansi::Processor::new();
+
+        // The synchronized update times out in the middle of an escape sequence.
+        parse(&mut parser, &mut terminal, b"\x1b[?2026habc\x1b[3");
+        parser.stop_sync(&mut terminal);
+
+        parse(&mut parser, &mut terminal, b"1mdef");
+
+        let text: String = (0..7).map(|i| terminal.grid()[Line(0)][Column(i)].c).collect();
+        assert_eq!(text, "abcdef ");
+        assert_eq!(terminal.grid()[Line(0)][Column(2)].fg(), Color::Named(NamedColor::Foreground));
+        assert_eq!(terminal.grid()[Line(0)][Column(3)].fg(), Color::Named(NamedColor::Red));
+    }
+}
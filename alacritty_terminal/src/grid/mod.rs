@@ -1,22 +1,27 @@
 //! A specialized 2D grid implementation optimized for use in a terminal.
 
 use std::cmp::{max, min};
+use std::mem;
 use std::ops::{Bound, Deref, Index, IndexMut, Range, RangeBounds};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use crate::index::{Column, Line, Point};
-use crate::term::cell::{Flags, ResetDiscriminant};
+use crate::term::cell::{Cell, Flags, ResetDiscriminant};
+use crate::term::style::StyleTable;
 use crate::vte::ansi::{CharsetIndex, StandardCharset};
 
 pub mod resize;
 mod row;
+mod spill;
 mod storage;
 #[cfg(test)]
 mod tests;
 
 pub use self::row::Row;
+pub(crate) use self::spill::{read_varint, write_varint};
+pub use self::spill::{RowRef, SpillCell, SpillConfig};
 use self::storage::Storage;
 
 pub trait GridCell: Sized {
@@ -135,6 +140,17 @@ pub struct Grid<T> {
 
     /// Maximum number of lines in history.
     max_scroll_limit: usize,
+
+    /// Number of lines scrolled into the history, wrapping around on overflow.
+    ///
+    /// This allows tracking points across scrolling, since all lines move up by one for every
+    /// line scrolled into the history.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    history_rotations: usize,
+
+    /// Styles written to this grid.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    styles: StyleTable,
 }
 
 impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
@@ -147,6 +163,8 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
             cursor: Cursor::default(),
             lines,
             columns,
+            history_rotations: 0,
+            styles: Default::default(),
         }
     }
 
@@ -154,13 +172,29 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
     pub fn update_history(&mut self, history_size: usize) {
         let current_history_size = self.history_size();
         if current_history_size > history_size {
-            self.raw.shrink_lines(current_history_size - history_size);
+            self.raw.shrink_history(current_history_size - history_size);
         }
         self.display_offset = min(self.display_offset, history_size);
         self.max_scroll_limit = history_size;
     }
 
+    /// Update the budgets for moving scrollback history exceeding the memory budget to disk.
+    ///
+    /// Passing `None` keeps the entire history in memory and drops all lines stored on disk.
+    pub fn update_spill(&mut self, config: Option<SpillConfig>)
+    where
+        T: SpillCell,
+    {
+        if self.raw.spill_config() != config {
+            self.raw.set_spill(config);
+            self.display_offset = min(self.display_offset, self.history_size());
+        }
+    }
+
     pub fn scroll_display(&mut self, scroll: Scroll) {
+        // Release history read back from disk while scrolling through it.
+        self.raw.trim_cache();
+
         self.display_offset = match scroll {
             Scroll::Delta(count) => {
                 min(max((self.display_offset as i32) + count, 0) as usize, self.history_size())
@@ -173,10 +207,7 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
     }
 
     fn increase_scroll_limit(&mut self, count: usize) {
-        let count = min(count, self.max_scroll_limit - self.history_size());
-        if count != 0 {
-            self.raw.initialize(count, self.columns);
-        }
+        self.raw.grow_history(count, self.max_scroll_limit, self.columns);
     }
 
     fn decrease_scroll_limit(&mut self, count: usize) {
@@ -287,6 +318,7 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
 
             // Rotate the entire line buffer upward.
             self.raw.rotate(-(positions as isize));
+            self.history_rotations = self.history_rotations.wrapping_add(positions);
 
             // Swap the fixed lines at the bottom back into position.
             let screen_lines = self.screen_lines() as i32;
@@ -379,10 +411,22 @@ impl<T> Grid<T> {
         }
     }
 
+    /// Number of lines scrolled into the history, wrapping around on overflow.
+    #[inline]
+    pub fn history_rotations(&self) -> usize {
+        self.history_rotations
+    }
+
+    /// Table for sharing styles between the cells of this grid.
+    #[inline]
+    pub(crate) fn style_table(&mut self) -> &mut StyleTable {
+        &mut self.styles
+    }
+
     #[inline]
     pub fn clear_history(&mut self) {
         // Explicitly purge all lines from history.
-        self.raw.shrink_lines(self.history_size());
+        self.raw.shrink_history(self.history_size());
 
         // Reset display offset.
         self.display_offset = 0;
@@ -407,6 +451,21 @@ impl<T> Grid<T> {
         self.raw.truncate();
     }
 
+    /// Get a line without keeping its decompressed history block cached.
+    ///
+    /// Indexing the grid caches history read back from disk until the grid is modified, so this
+    /// should be preferred when reading through large parts of the history.
+    #[inline]
+    pub fn row_ref(&self, line: Line) -> RowRef<'_, T> {
+        self.raw.row_ref(line)
+    }
+
+    /// Number of decompressed history blocks held in memory.
+    #[cfg(test)]
+    pub(crate) fn resident_blocks(&self) -> usize {
+        self.raw.resident_blocks()
+    }
+
     /// Iterate over all cells in the grid starting at a specific point.
     #[inline]
     pub fn iter_from(&self, point: Point) -> GridIterator<'_, T> {
@@ -440,6 +499,18 @@ impl<T> Grid<T> {
     }
 }
 
+impl Grid<Cell> {
+    /// Share the styles of a line created outside of the grid with the other lines.
+    ///
+    /// Lines which were not written by the terminal, like deserialized ones, usually use a
+    /// separate style for every cell.
+    pub fn intern_styles(&mut self, row: &mut Row<Cell>) {
+        for cell in &mut row[..] {
+            cell.style = self.styles.intern(mem::take(&mut cell.style));
+        }
+    }
+}
+
 impl<T: PartialEq> PartialEq for Grid<T> {
     fn eq(&self, other: &Self) -> bool {
         // Compare struct fields and check result of grid comparison.
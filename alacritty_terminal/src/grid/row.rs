@@ -106,8 +106,15 @@ impl<T: Clone + Default> Row<T> {
         }
 
         // Reset every dirty cell in the row.
-        for item in &mut self.inner[0..self.occ] {
-            item.reset(template);
+        //
+        // All cells after the first one are reset based on the first, which allows them to share
+        // its attributes.
+        let mut cells = self.inner[0..self.occ].iter_mut();
+        if let Some(first) = cells.next() {
+            first.reset(template);
+            for item in cells {
+                item.reset(first);
+            }
         }
 
         self.occ = 0;
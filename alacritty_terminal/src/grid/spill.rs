@@ -0,0 +1,858 @@
+//! Compressed on-disk storage for scrollback history exceeding the memory budget.
+//!
+//! Lines which are rotated out of the in-memory ring buffer are collected into blocks of
+//! [`BLOCK_ROWS`] lines, run-length encoded and appended to an anonymous temporary file. Blocks
+//! are decompressed on demand when they are indexed and kept in a small least recently used
+//! cache.
+//!
+//! The only compression applied is the run-length encoding of identical consecutive cells, which
+//! mostly shrinks the blank tails of lines. There is no general purpose codec, so lines full of
+//! text take about as much space on disk as their binary cell representation.
+//!
+//! Indexing hands out references into the cache, so blocks evicted while reading through a shared
+//! reference can only be released with the next mutable access. Readers scanning large parts of
+//! the history should use [`ColdStorage::get_shared`] instead, which keeps at most one block alive
+//! for itself.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt::{self, Debug, Formatter};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::ops::Deref;
+#[cfg(not(unix))]
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::{env, mem, process};
+
+use log::{error, warn};
+
+use super::Row;
+
+/// Number of lines compressed together into a single block on disk.
+pub(crate) const BLOCK_ROWS: usize = 256;
+
+/// Maximum number of decompressed blocks kept in memory.
+const MAX_CACHED_BLOCKS: usize = 8;
+
+/// Counter for unique temporary file names within this process.
+static FILE_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Budgets for storing scrollback history outside of memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpillConfig {
+    /// Approximate number of bytes of uncompressed history kept in memory.
+    pub memory_budget: usize,
+
+    /// Maximum number of bytes of compressed history stored on disk.
+    pub disk_budget: usize,
+}
+
+/// Binary encoding for cells of lines written to disk.
+pub trait SpillCell: Sized {
+    /// Append the binary representation of the cell to `buf`.
+    fn encode(&self, buf: &mut Vec<u8>);
+
+    /// Read a cell from the start of `buf`, advancing it past the consumed bytes.
+    ///
+    /// The previously decoded cell is passed along, so data can be shared between them.
+    fn decode(buf: &mut &[u8], previous: Option<&Self>) -> Option<Self>;
+}
+
+/// Location of a compressed block inside the spill file.
+#[derive(Debug, Clone, Copy)]
+struct Block {
+    offset: u64,
+    size: usize,
+}
+
+/// Decompressed block held in memory.
+struct CachedBlock<T> {
+    id: usize,
+    rows: Box<[Row<T>]>,
+    dirty: bool,
+}
+
+/// Line of the grid which might be shared with a decompressed history block.
+///
+/// Unlike references obtained by indexing the grid, this keeps the block alive on its own, so
+/// blocks which are no longer in use are released immediately.
+pub struct RowRef<'a, T> {
+    inner: RowRefInner<'a, T>,
+}
+
+enum RowRefInner<'a, T> {
+    Borrowed(&'a Row<T>),
+    Shared(Arc<[Row<T>]>, usize, PhantomData<&'a Row<T>>),
+}
+
+impl<'a, T> RowRef<'a, T> {
+    #[inline]
+    pub(crate) fn borrowed(row: &'a Row<T>) -> Self {
+        Self { inner: RowRefInner::Borrowed(row) }
+    }
+}
+
+impl<'a, T> Deref for RowRef<'a, T> {
+    type Target = Row<T>;
+
+    #[inline]
+    fn deref(&self) -> &Row<T> {
+        match &self.inner {
+            RowRefInner::Borrowed(row) => row,
+            RowRefInner::Shared(rows, index, _) => &rows[*index],
+        }
+    }
+}
+
+/// Decompressed block shared with [`RowRef`]s.
+struct SharedBlock<T> {
+    id: usize,
+    rows: Arc<[Row<T>]>,
+}
+
+/// Temporary file which is removed once dropped.
+struct SpillFile {
+    file: File,
+    #[cfg(not(unix))]
+    path: PathBuf,
+
+    /// Current end of the file.
+    len: u64,
+}
+
+impl SpillFile {
+    fn new() -> io::Result<Self> {
+        let id = FILE_ID.fetch_add(1, Ordering::Relaxed);
+        let path = env::temp_dir().join(format!("alacritty-scrollback-{}-{id}", process::id()));
+        let file = OpenOptions::new().read(true).write(true).create_new(true).open(&path)?;
+
+        // Unlink the file immediately, so it can't outlive the process.
+        #[cfg(unix)]
+        let _ = fs::remove_file(&path);
+
+        Ok(Self {
+            file,
+            #[cfg(not(unix))]
+            path,
+            len: 0,
+        })
+    }
+
+    fn append(&mut self, bytes: &[u8]) -> io::Result<u64> {
+        let offset = self.len;
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.write_all(bytes)?;
+        self.len += bytes.len() as u64;
+        Ok(offset)
+    }
+
+    fn read(&self, block: Block) -> io::Result<Vec<u8>> {
+        let mut bytes = vec![0; block.size];
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(block.offset))?;
+        file.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.len = 0;
+        self.file.set_len(0)
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        #[cfg(not(unix))]
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Scrollback history stored outside of the in-memory ring buffer.
+///
+/// Lines are indexed starting at the most recent line, which is the one directly above the
+/// topmost line of the ring buffer.
+pub struct ColdStorage<T> {
+    config: SpillConfig,
+
+    /// Backing file, created when the first block is written.
+    file: Option<SpillFile>,
+
+    /// Blocks written to disk, from oldest to newest.
+    blocks: VecDeque<Block>,
+
+    /// Unique ID of the oldest block.
+    first_block: usize,
+
+    /// Number of lines removed from the start of the oldest block.
+    skipped: usize,
+
+    /// Most recent lines which do not fill a complete block yet.
+    pending: Vec<Row<T>>,
+
+    /// Blocks which have been read back from disk, from least to most recently used.
+    cache: RefCell<Vec<CachedBlock<T>>>,
+
+    /// Blocks evicted from the cache while their lines might still be borrowed.
+    ///
+    /// These are released with the next mutable access to the storage.
+    retired: RefCell<Vec<CachedBlock<T>>>,
+
+    /// Block most recently read by [`Self::get_shared`].
+    shared: RefCell<Option<SharedBlock<T>>>,
+
+    /// Bytes of the file used by blocks which are no longer referenced.
+    dead_bytes: u64,
+
+    encode: fn(&T, &mut Vec<u8>),
+    decode: fn(&mut &[u8], Option<&T>) -> Option<T>,
+    template: fn() -> T,
+}
+
+impl<T> ColdStorage<T> {
+    pub fn new(config: SpillConfig) -> Self
+    where
+        T: SpillCell + Default,
+    {
+        Self {
+            config,
+            file: None,
+            blocks: VecDeque::new(),
+            first_block: 0,
+            skipped: 0,
+            pending: Vec::new(),
+            cache: RefCell::new(Vec::new()),
+            retired: RefCell::new(Vec::new()),
+            shared: RefCell::new(None),
+            dead_bytes: 0,
+            encode: T::encode,
+            decode: T::decode,
+            template: T::default,
+        }
+    }
+
+    #[inline]
+    pub fn config(&self) -> SpillConfig {
+        self.config
+    }
+
+    /// Update the storage budgets.
+    pub fn set_config(&mut self, config: SpillConfig) {
+        self.config = config;
+        self.enforce_disk_budget();
+    }
+
+    /// Number of lines in the storage.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.blocks.len() * BLOCK_ROWS - self.skipped + self.pending.len()
+    }
+
+    /// Number of bytes used for the compressed history on disk.
+    pub fn disk_usage(&self) -> usize {
+        self.blocks.iter().map(|block| block.size).sum()
+    }
+
+    /// Maximum number of lines kept in memory before they're moved into the cold storage.
+    pub fn memory_lines(&self, columns: usize) -> usize {
+        self.config.memory_budget / (columns * mem::size_of::<T>()).max(1)
+    }
+
+    /// Add a new line as the most recent line of the storage.
+    pub fn push(&mut self, row: Row<T>) {
+        self.release();
+        self.pending.push(row);
+
+        if self.pending.len() == BLOCK_ROWS {
+            let rows = mem::take(&mut self.pending);
+            match self.write_block(&rows) {
+                Ok(block) => self.blocks.push_back(block),
+                Err(err) => {
+                    error!("Unable to write scrollback history to disk: {err}");
+
+                    // Drop all history, since it would no longer be contiguous.
+                    self.clear();
+                },
+            }
+
+            self.enforce_disk_budget();
+        }
+    }
+
+    /// Remove the most recent line from the storage.
+    pub fn pop(&mut self, columns: usize) -> Option<Row<T>> {
+        self.release();
+
+        if self.pending.is_empty() {
+            let block = self.blocks.pop_back()?;
+            let id = self.first_block + self.blocks.len();
+
+            let rows = self.take_cached(id).unwrap_or_else(|| self.read_block(block, columns));
+            self.dead_bytes += block.size as u64;
+            self.pending = rows.into_vec();
+
+            // Remove lines which were already dropped from the oldest block.
+            if self.blocks.is_empty() {
+                self.pending.drain(..self.skipped);
+                self.skipped = 0;
+            }
+        }
+
+        self.pending.pop()
+    }
+
+    /// Remove the `count` oldest lines from the storage.
+    pub fn truncate_front(&mut self, count: usize) {
+        let count = count.min(self.len());
+        self.skipped += count;
+
+        // Drop all blocks which don't contain any lines anymore.
+        while self.skipped >= BLOCK_ROWS && !self.blocks.is_empty() {
+            let block = self.blocks.pop_front().unwrap();
+            self.dead_bytes += block.size as u64;
+            self.first_block += 1;
+            self.skipped -= BLOCK_ROWS;
+        }
+
+        if self.blocks.is_empty() {
+            self.pending.drain(..self.skipped);
+            self.skipped = 0;
+        }
+
+        let first_block = self.first_block;
+        self.cache.get_mut().retain(|cached| cached.id >= first_block);
+        self.release();
+
+        self.compact();
+    }
+
+    /// Remove all lines from the storage.
+    pub fn clear(&mut self) {
+        self.first_block += self.blocks.len();
+        self.blocks.clear();
+        self.pending.clear();
+        self.cache.get_mut().clear();
+        self.release();
+        self.skipped = 0;
+        self.dead_bytes = 0;
+
+        if let Some(file) = &mut self.file {
+            if let Err(err) = file.clear() {
+                warn!("Unable to truncate scrollback history file: {err}");
+            }
+        }
+    }
+
+    /// Get a line by its distance from the most recent line.
+    pub fn get(&self, index: usize, columns: usize) -> &Row<T> {
+        debug_assert!(index < self.len());
+
+        if index < self.pending.len() {
+            return &self.pending[self.pending.len() - index - 1];
+        }
+
+        let (id, row) = self.block_position(index);
+        let rows = self.load(id, columns);
+        &rows[row]
+    }
+
+    /// Get a mutable line by its distance from the most recent line.
+    pub fn get_mut(&mut self, index: usize, columns: usize) -> &mut Row<T> {
+        debug_assert!(index < self.len());
+
+        // Avoid keeping an outdated copy of the block around.
+        self.release();
+
+        if index < self.pending.len() {
+            let len = self.pending.len();
+            return &mut self.pending[len - index - 1];
+        }
+
+        let (id, row) = self.block_position(index);
+        self.load(id, columns);
+
+        let cache = self.cache.get_mut();
+        let cached = cache.iter_mut().find(|cached| cached.id == id).unwrap();
+        cached.dirty = true;
+        &mut cached.rows[row]
+    }
+
+    /// Release decompressed blocks which exceed the cache limit.
+    pub fn trim_cache(&mut self) {
+        self.release();
+
+        let excess = self.cache.get_mut().len().saturating_sub(MAX_CACHED_BLOCKS);
+        if excess == 0 {
+            return;
+        }
+
+        for cached in self.cache.get_mut().drain(..excess).collect::<Vec<_>>() {
+            if cached.dirty {
+                self.write_back(cached);
+            }
+        }
+    }
+
+    /// Drop all decompressed blocks.
+    ///
+    /// This should be called whenever the number of columns changes, since lines are resized to
+    /// the current width while they're read back from disk.
+    pub fn clear_cache(&mut self) {
+        self.release();
+
+        for cached in mem::take(self.cache.get_mut()) {
+            if cached.dirty {
+                self.write_back(cached);
+            }
+        }
+    }
+
+    /// Get a line by its distance from the most recent line, without growing the cache.
+    ///
+    /// Blocks which are not cached already are decompressed separately and dropped once they're
+    /// no longer used by any of the returned lines.
+    pub fn get_shared(&self, index: usize, columns: usize) -> RowRef<'_, T> {
+        debug_assert!(index < self.len());
+
+        if index < self.pending.len() {
+            return RowRef::borrowed(&self.pending[self.pending.len() - index - 1]);
+        }
+
+        let (id, row) = self.block_position(index);
+        if let Some(rows) = self.find_cached(id) {
+            // SAFETY: Cached and retired blocks are only released through mutable access, which
+            // can't happen while the returned reference is borrowing `self`.
+            return RowRef::borrowed(unsafe { &(*rows)[row] });
+        }
+
+        let mut shared = self.shared.borrow_mut();
+        let rows = match &*shared {
+            Some(block) if block.id == id => block.rows.clone(),
+            _ => {
+                let block = self.blocks[id - self.first_block];
+                let rows: Arc<[Row<T>]> = self.read_block(block, columns).into();
+                *shared = Some(SharedBlock { id, rows: rows.clone() });
+                rows
+            },
+        };
+
+        RowRef { inner: RowRefInner::Shared(rows, row, PhantomData) }
+    }
+
+    /// Number of decompressed blocks held by the storage.
+    #[cfg(test)]
+    pub fn resident_blocks(&self) -> usize {
+        let shared = usize::from(self.shared.borrow().is_some());
+        self.cache.borrow().len() + self.retired.borrow().len() + shared
+    }
+
+    /// Release all blocks which might still be borrowed through a shared reference.
+    fn release(&mut self) {
+        self.retired.get_mut().clear();
+        *self.shared.get_mut() = None;
+    }
+
+    /// Find a block in the cache or in the list of retired blocks.
+    fn find_cached(&self, id: usize) -> Option<*const [Row<T>]> {
+        let cache = self.cache.borrow();
+        let retired = self.retired.borrow();
+        cache
+            .iter()
+            .chain(retired.iter())
+            .find(|cached| cached.id == id)
+            .map(|cached| &*cached.rows as *const [Row<T>])
+    }
+
+    /// Get block ID and row offset within the block for a line.
+    fn block_position(&self, index: usize) -> (usize, usize) {
+        let index = index - self.pending.len();
+        let block = self.blocks.len() - index / BLOCK_ROWS - 1;
+        (self.first_block + block, BLOCK_ROWS - index % BLOCK_ROWS - 1)
+    }
+
+    /// Get the lines of a block, reading it from disk if necessary.
+    ///
+    /// The block becomes the most recently used one, evicting the least recently used unmodified
+    /// block once the cache is full.
+    fn load(&self, id: usize, columns: usize) -> &[Row<T>] {
+        let mut cache = self.cache.borrow_mut();
+
+        match cache.iter().position(|cached| cached.id == id) {
+            Some(index) => {
+                let cached = cache.remove(index);
+                cache.push(cached);
+            },
+            None => {
+                let mut retired = self.retired.borrow_mut();
+                let cached = match retired.iter().position(|cached| cached.id == id) {
+                    Some(index) => retired.swap_remove(index),
+                    None => {
+                        let block = self.blocks[id - self.first_block];
+                        CachedBlock { id, rows: self.read_block(block, columns), dirty: false }
+                    },
+                };
+                cache.push(cached);
+
+                // Modified blocks can only be written back to disk through mutable access.
+                let evicted = cache[..cache.len() - 1].iter().position(|cached| !cached.dirty);
+                if let Some(index) = evicted.filter(|_| cache.len() > MAX_CACHED_BLOCKS) {
+                    retired.push(cache.remove(index));
+                }
+            },
+        }
+
+        let rows: *const [Row<T>] = &*cache[cache.len() - 1].rows;
+
+        // SAFETY: The rows are boxed, so their location doesn't change when the cache is
+        // modified. Evicted blocks are retired and only released through mutable access, which
+        // can't happen while the returned reference is borrowing `self`.
+        unsafe { &*rows }
+    }
+
+    /// Remove a block from the cache.
+    fn take_cached(&mut self, id: usize) -> Option<Box<[Row<T>]>> {
+        let cache = self.cache.get_mut();
+        if let Some(index) = cache.iter().position(|cached| cached.id == id) {
+            return Some(cache.remove(index).rows);
+        }
+
+        let retired = self.retired.get_mut();
+        let index = retired.iter().position(|cached| cached.id == id)?;
+        Some(retired.swap_remove(index).rows)
+    }
+
+    /// Write a modified block back to disk.
+    fn write_back(&mut self, cached: CachedBlock<T>) {
+        let index = match cached.id.checked_sub(self.first_block) {
+            Some(index) if index < self.blocks.len() => index,
+            _ => return,
+        };
+
+        match self.write_block(&cached.rows) {
+            Ok(block) => {
+                let old_block = mem::replace(&mut self.blocks[index], block);
+                self.dead_bytes += old_block.size as u64;
+            },
+            Err(err) => error!("Unable to update scrollback history on disk: {err}"),
+        }
+    }
+
+    /// Compress lines and append them to the spill file.
+    fn write_block(&mut self, rows: &[Row<T>]) -> io::Result<Block> {
+        let mut buf = Vec::new();
+        for row in rows {
+            encode_row(row, self.encode, &mut buf);
+        }
+
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => self.file.insert(SpillFile::new()?),
+        };
+
+        let offset = file.append(&buf)?;
+        Ok(Block { offset, size: buf.len() })
+    }
+
+    /// Read and decompress a block from the spill file.
+    fn read_block(&self, block: Block, columns: usize) -> Box<[Row<T>]> {
+        let bytes = self.file.as_ref().map(|file| file.read(block));
+
+        let mut rows = Vec::with_capacity(BLOCK_ROWS);
+        match bytes {
+            Some(Ok(bytes)) => {
+                let mut buf = bytes.as_slice();
+                while rows.len() < BLOCK_ROWS {
+                    let previous = rows.last().and_then(|row: &Row<T>| row[..].last());
+                    match decode_row(&mut buf, self.decode, previous, self.template, columns) {
+                        Some(row) => rows.push(row),
+                        None => {
+                            error!("Corrupt scrollback history block at offset {}", block.offset);
+                            break;
+                        },
+                    }
+                }
+            },
+            Some(Err(err)) => error!("Unable to read scrollback history from disk: {err}"),
+            None => (),
+        }
+
+        // Replace unreadable lines with empty ones.
+        rows.resize_with(BLOCK_ROWS, || {
+            let mut cells = Vec::with_capacity(columns);
+            cells.resize_with(columns, self.template);
+            Row::from_vec(cells, 0)
+        });
+
+        rows.into_boxed_slice()
+    }
+
+    /// Drop the oldest blocks until the disk budget is satisfied.
+    fn enforce_disk_budget(&mut self) {
+        let mut usage = self.disk_usage();
+        let mut dropped = 0;
+        while usage > self.config.disk_budget && self.blocks.len() > dropped {
+            usage -= self.blocks[dropped].size;
+            dropped += 1;
+        }
+
+        if dropped > 0 {
+            self.truncate_front(dropped * BLOCK_ROWS - self.skipped);
+        }
+    }
+
+    /// Rewrite the spill file once most of it is occupied by unused blocks.
+    fn compact(&mut self) {
+        let live_bytes = self.disk_usage() as u64;
+        if self.dead_bytes < live_bytes.max(self.config.disk_budget as u64 / 4) {
+            return;
+        }
+
+        // Remove all stale data when no blocks are left.
+        if self.blocks.is_empty() {
+            if let Some(Err(err)) = self.file.as_mut().map(SpillFile::clear) {
+                warn!("Unable to truncate scrollback history file: {err}");
+            }
+            self.dead_bytes = 0;
+            return;
+        }
+
+        let file = match self.file.take() {
+            Some(file) => file,
+            None => return,
+        };
+
+        let compacted = SpillFile::new().and_then(|mut new_file| {
+            for block in self.blocks.iter_mut() {
+                let bytes = file.read(*block)?;
+                block.offset = new_file.append(&bytes)?;
+            }
+            Ok(new_file)
+        });
+
+        match compacted {
+            Ok(new_file) => {
+                self.file = Some(new_file);
+                self.dead_bytes = 0;
+            },
+            Err(err) => {
+                warn!("Unable to compact scrollback history file: {err}");
+                self.file = Some(file);
+            },
+        }
+    }
+}
+
+impl<T: Clone> Clone for ColdStorage<T> {
+    fn clone(&self) -> Self {
+        let mut blocks = VecDeque::with_capacity(self.blocks.len());
+
+        let file = self.file.as_ref().and_then(|file| {
+            let copy = SpillFile::new().and_then(|mut new_file| {
+                for block in &self.blocks {
+                    let bytes = file.read(*block)?;
+                    let offset = new_file.append(&bytes)?;
+                    blocks.push_back(Block { offset, size: block.size });
+                }
+                Ok(new_file)
+            });
+
+            match copy {
+                Ok(new_file) => Some(new_file),
+                Err(err) => {
+                    error!("Unable to copy scrollback history: {err}");
+                    blocks.clear();
+                    None
+                },
+            }
+        });
+
+        let skipped = if blocks.is_empty() { 0 } else { self.skipped };
+
+        Self {
+            file,
+            blocks,
+            skipped,
+            config: self.config,
+            first_block: 0,
+            pending: self.pending.clone(),
+            cache: Default::default(),
+            retired: Default::default(),
+            shared: Default::default(),
+            dead_bytes: 0,
+            encode: self.encode,
+            decode: self.decode,
+            template: self.template,
+        }
+    }
+}
+
+impl<T> Debug for ColdStorage<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ColdStorage")
+            .field("config", &self.config)
+            .field("len", &self.len())
+            .field("blocks", &self.blocks.len())
+            .field("pending", &self.pending.len())
+            .finish()
+    }
+}
+
+/// Run-length encode a line.
+///
+/// Every line starts with the number of cells, followed by pairs of repetition count and cell.
+/// Cells are considered identical if their binary representation is the same.
+fn encode_row<T>(row: &Row<T>, encode: fn(&T, &mut Vec<u8>), buf: &mut Vec<u8>) {
+    let cells = &row[..];
+    write_varint(buf, cells.len() as u64);
+
+    let mut run = 0;
+    let mut previous = Vec::new();
+    let mut current = Vec::new();
+    for cell in cells {
+        current.clear();
+        encode(cell, &mut current);
+
+        if run > 0 && current != previous {
+            write_varint(buf, run);
+            buf.extend_from_slice(&previous);
+            run = 0;
+        }
+
+        mem::swap(&mut previous, &mut current);
+        run += 1;
+    }
+
+    if run > 0 {
+        write_varint(buf, run);
+        buf.extend_from_slice(&previous);
+    }
+}
+
+/// Decode a line, adjusting it to the desired number of columns.
+fn decode_row<T>(
+    buf: &mut &[u8],
+    decode: fn(&mut &[u8], Option<&T>) -> Option<T>,
+    previous: Option<&T>,
+    template: fn() -> T,
+    columns: usize,
+) -> Option<Row<T>> {
+    let len = read_varint(buf)? as usize;
+
+    let mut cells = Vec::with_capacity(columns.max(len));
+    while cells.len() < len {
+        let run = read_varint(buf)? as usize;
+        if run == 0 || cells.len() + run > len {
+            return None;
+        }
+
+        // Decode the same bytes for every repetition, to avoid requiring `Clone`.
+        let start = *buf;
+        for _ in 0..run {
+            *buf = start;
+            let cell = decode(buf, cells.last().or(previous))?;
+            cells.push(cell);
+        }
+    }
+
+    cells.truncate(columns);
+    cells.resize_with(columns, template);
+
+    let occ = cells.len();
+    Some(Row::from_vec(cells, occ))
+}
+
+/// Append a LEB128 encoded integer.
+pub(crate) fn write_varint(buf: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            buf.push(byte);
+            return;
+        }
+
+        buf.push(byte | 0x80);
+    }
+}
+
+/// Read a LEB128 encoded integer.
+pub(crate) fn read_varint(buf: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = buf.split_first()?;
+        *buf = rest;
+
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::index::Column;
+    use crate::term::cell::Cell;
+
+    #[test]
+    fn evict_least_recently_used() {
+        let config = SpillConfig { memory_budget: 0, disk_budget: usize::MAX };
+        let mut storage = ColdStorage::<Cell>::new(config);
+        let blocks = MAX_CACHED_BLOCKS + 2;
+        for i in 0..blocks * BLOCK_ROWS {
+            let c = char::from_u32(0x4e00 + i as u32).unwrap();
+            storage.push(Row::from_vec(vec![Cell { c, ..Cell::default() }], 1));
+        }
+
+        // Keep the most recent block in use, while reading all others.
+        let newest = storage.get(0, 1);
+        for block in 0..blocks {
+            storage.get(block * BLOCK_ROWS, 1);
+            storage.get(0, 1);
+        }
+
+        assert_eq!(storage.cache.borrow().len(), MAX_CACHED_BLOCKS);
+        assert_eq!(storage.cache.borrow().last().unwrap().id, blocks - 1);
+        assert_eq!(storage.retired.borrow().len(), 2);
+        assert_eq!(
+            newest[Column(0)].c,
+            char::from_u32(0x4e00 + (blocks * BLOCK_ROWS - 1) as u32).unwrap()
+        );
+
+        storage.trim_cache();
+        assert!(storage.retired.get_mut().is_empty());
+    }
+
+    #[test]
+    fn shared_rows_release_blocks() {
+        let config = SpillConfig { memory_budget: 0, disk_budget: usize::MAX };
+        let mut storage = ColdStorage::<Cell>::new(config);
+        let blocks = MAX_CACHED_BLOCKS * 2;
+        for i in 0..blocks * BLOCK_ROWS {
+            let c = char::from_u32(0x4e00 + i as u32).unwrap();
+            storage.push(Row::from_vec(vec![Cell { c, ..Cell::default() }], 1));
+        }
+
+        // Keep the most recent line alive while reading all others.
+        let newest = storage.get_shared(0, 1);
+        for index in 0..storage.len() {
+            let row = storage.get_shared(index, 1);
+            let expected = char::from_u32(0x4e00 + (storage.len() - index - 1) as u32).unwrap();
+            assert_eq!(row[Column(0)].c, expected);
+            assert!(storage.resident_blocks() <= 1);
+        }
+
+        assert_eq!(storage.shared.borrow().as_ref().unwrap().id, 0);
+        assert_eq!(
+            newest[Column(0)].c,
+            char::from_u32(0x4e00 + (blocks * BLOCK_ROWS - 1) as u32).unwrap()
+        );
+    }
+}
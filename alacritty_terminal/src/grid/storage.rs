@@ -1,4 +1,4 @@
-use std::cmp::{max, PartialEq};
+use std::cmp::{max, min, PartialEq};
 use std::mem;
 use std::mem::MaybeUninit;
 use std::ops::{Index, IndexMut};
@@ -6,6 +6,7 @@ use std::ops::{Index, IndexMut};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use super::spill::{ColdStorage, RowRef, SpillCell, SpillConfig};
 use super::Row;
 use crate::index::Line;
 
@@ -50,6 +51,12 @@ pub struct Storage<T> {
     /// As long as `len` is bigger than `inner`, it is also possible to grow the scrollback buffer
     /// without any additional insertions.
     len: usize,
+
+    /// History lines which exceeded the memory budget.
+    ///
+    /// All of these lines are older than the topmost line of the ring buffer.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    cold: Option<Box<ColdStorage<T>>>,
 }
 
 impl<T: PartialEq> PartialEq for Storage<T> {
@@ -72,7 +79,27 @@ impl<T> Storage<T> {
         let mut inner = Vec::with_capacity(visible_lines);
         inner.resize_with(visible_lines, || Row::new(columns));
 
-        Storage { inner, zero: 0, visible_lines, len: visible_lines }
+        Storage { inner, zero: 0, visible_lines, len: visible_lines, cold: None }
+    }
+
+    /// Update the budgets for moving history lines to disk.
+    ///
+    /// Passing `None` disables spilling history and removes all lines which were stored on disk.
+    pub fn set_spill(&mut self, config: Option<SpillConfig>)
+    where
+        T: SpillCell + Default,
+    {
+        match (config, &mut self.cold) {
+            (Some(config), Some(cold)) => cold.set_config(config),
+            (Some(config), None) => self.cold = Some(Box::new(ColdStorage::new(config))),
+            (None, _) => self.cold = None,
+        }
+    }
+
+    /// Current budgets for moving history lines to disk.
+    #[inline]
+    pub fn spill_config(&self) -> Option<SpillConfig> {
+        self.cold.as_ref().map(|cold| cold.config())
     }
 
     /// Increase the number of lines in the buffer.
@@ -85,6 +112,11 @@ impl<T> Storage<T> {
         let additional_lines = next - self.visible_lines;
 
         let columns = self[Line(0)].len();
+
+        // Make sure all lines which will become visible are stored in memory.
+        let memory_history = self.len - self.visible_lines;
+        self.unspill(additional_lines.saturating_sub(memory_history), columns);
+
         self.initialize(additional_lines, columns);
 
         // Update visible lines.
@@ -102,7 +134,28 @@ impl<T> Storage<T> {
         self.visible_lines = next;
     }
 
-    /// Shrink the number of lines in the buffer.
+    /// Remove the oldest lines from the scrollback history.
+    ///
+    /// Unlike [`Storage::shrink_lines`], this will remove lines stored on disk first.
+    pub fn shrink_history(&mut self, shrinkage: usize) {
+        let cold_shrinkage = match &mut self.cold {
+            Some(cold) => {
+                let cold_shrinkage = min(cold.len(), shrinkage);
+                cold.truncate_front(cold_shrinkage);
+                cold_shrinkage
+            },
+            None => 0,
+        };
+
+        if shrinkage > cold_shrinkage {
+            self.shrink_lines(shrinkage - cold_shrinkage);
+        }
+    }
+
+    /// Shrink the number of lines in the in-memory buffer.
+    ///
+    /// This always removes the topmost lines of the ring buffer, lines stored on disk are not
+    /// affected.
     #[inline]
     pub fn shrink_lines(&mut self, shrinkage: usize) {
         self.len -= shrinkage;
@@ -137,9 +190,84 @@ impl<T> Storage<T> {
         self.len += additional_rows;
     }
 
+    /// Grow the scrollback history in preparation for rotating the buffer upward.
+    ///
+    /// The history will be grown by up to `additional_rows` lines without exceeding
+    /// `max_history`. Once the memory budget is exhausted, the topmost lines of the ring buffer
+    /// which will be rotated out by the upcoming rotation are moved to disk instead.
+    pub fn grow_history(&mut self, additional_rows: usize, max_history: usize, columns: usize)
+    where
+        T: Clone + Default,
+    {
+        let history = self.len() - self.visible_lines;
+        let growth = min(additional_rows, max_history.saturating_sub(history));
+
+        let mut cold = match self.cold.take() {
+            Some(cold) => cold,
+            None => {
+                if growth != 0 {
+                    self.initialize(growth, columns);
+                }
+                return;
+            },
+        };
+
+        cold.trim_cache();
+
+        // Grow the in-memory history until the memory budget is exhausted.
+        let memory_lines = max(cold.memory_lines(columns), self.visible_lines);
+        let memory_history = self.len - self.visible_lines;
+        let memory_growth = min(growth, memory_lines.saturating_sub(memory_history));
+
+        // Move the lines which will be rotated out of memory to disk, skipping the oldest ones
+        // if there isn't enough space left in the history.
+        let rotated_out = min(additional_rows - memory_growth, self.len);
+        let cold_space = max_history.saturating_sub(memory_history + memory_growth);
+        let spilled = min(rotated_out, cold_space);
+        for offset in (self.len - rotated_out..self.len - rotated_out + spilled).rev() {
+            let index = self.ring_index(offset);
+            cold.push(self.inner[index].clone());
+        }
+
+        // Drop the oldest lines on disk once the history limit is reached.
+        cold.truncate_front(cold.len().saturating_sub(cold_space));
+
+        self.cold = Some(cold);
+
+        if memory_growth != 0 {
+            self.initialize(memory_growth, columns);
+        }
+    }
+
+    /// Total number of lines, including the lines stored on disk.
     #[inline]
     pub fn len(&self) -> usize {
-        self.len
+        self.len + self.cold.as_ref().map_or(0, |cold| cold.len())
+    }
+
+    /// Release decompressed history lines which exceed the cache limit.
+    #[inline]
+    pub fn trim_cache(&mut self) {
+        if let Some(cold) = &mut self.cold {
+            cold.trim_cache();
+        }
+    }
+
+    /// Get a line without growing the cache of decompressed history lines.
+    #[inline]
+    pub fn row_ref(&self, line: Line) -> RowRef<'_, T> {
+        let offset = self.line_offset(line);
+        if let Some(cold) = self.cold.as_ref().filter(|_| offset >= self.len) {
+            return cold.get_shared(offset - self.len, self.columns());
+        }
+
+        RowRef::borrowed(&self[line])
+    }
+
+    /// Number of decompressed history blocks held in memory.
+    #[cfg(test)]
+    pub fn resident_blocks(&self) -> usize {
+        self.cold.as_ref().map_or(0, |cold| cold.resident_blocks())
     }
 
     /// Swap implementation for Row<T>.
@@ -202,9 +330,16 @@ impl<T> Storage<T> {
         self.zero = 0;
     }
 
-    /// Remove all rows from storage.
+    /// Remove all rows from the in-memory storage.
+    ///
+    /// Lines stored on disk are not affected, they are resized to the current number of columns
+    /// without reflow whenever they're read back into memory.
     #[inline]
     pub fn take_all(&mut self) -> Vec<Row<T>> {
+        if let Some(cold) = &mut self.cold {
+            cold.clear_cache();
+        }
+
         self.truncate();
 
         let mut buffer = Vec::new();
@@ -215,15 +350,50 @@ impl<T> Storage<T> {
         buffer
     }
 
-    /// Compute actual index in underlying storage given the requested index.
+    /// Move the most recent lines stored on disk back to the top of the ring buffer.
+    fn unspill(&mut self, count: usize, columns: usize)
+    where
+        T: Clone + Default,
+    {
+        for _ in 0..count {
+            let row = match self.cold.as_mut().and_then(|cold| cold.pop(columns)) {
+                Some(row) => row,
+                None => return,
+            };
+
+            self.initialize(1, columns);
+            let index = self.ring_index(self.len - 1);
+            self.inner[index] = row;
+        }
+    }
+
+    /// Compute the distance of the requested line from the bottom of the storage.
     #[inline]
-    fn compute_index(&self, requested: Line) -> usize {
+    fn line_offset(&self, requested: Line) -> usize {
         debug_assert!(requested.0 < self.visible_lines as i32);
 
-        let positive = -(requested - self.visible_lines).0 as usize - 1;
+        -(requested - self.visible_lines).0 as usize - 1
+    }
+
+    /// Get the current number of columns, which is used for resizing lines read from disk.
+    #[inline]
+    fn columns(&self) -> usize {
+        self.inner[self.ring_index(0)].len()
+    }
+
+    /// Compute actual index in underlying storage given the requested index.
+    #[inline]
+    fn compute_index(&self, requested: Line) -> usize {
+        let positive = self.line_offset(requested);
 
         debug_assert!(positive < self.len);
 
+        self.ring_index(positive)
+    }
+
+    /// Compute actual index in underlying storage given a line's distance from the bottom.
+    #[inline]
+    fn ring_index(&self, positive: usize) -> usize {
         let zeroed = self.zero + positive;
 
         // Use if/else instead of remainder here to improve performance.
@@ -254,19 +424,46 @@ impl<T> Index<Line> for Storage<T> {
 
     #[inline]
     fn index(&self, index: Line) -> &Self::Output {
-        let index = self.compute_index(index);
-        &self.inner[index]
+        let offset = self.line_offset(index);
+        if offset >= self.len && self.cold.is_some() {
+            return cold_row(self, offset - self.len);
+        }
+
+        debug_assert!(offset < self.len);
+
+        &self.inner[self.ring_index(offset)]
     }
 }
 
 impl<T> IndexMut<Line> for Storage<T> {
     #[inline]
     fn index_mut(&mut self, index: Line) -> &mut Self::Output {
-        let index = self.compute_index(index);
+        let offset = self.line_offset(index);
+        if offset >= self.len && self.cold.is_some() {
+            return cold_row_mut(self, offset - self.len);
+        }
+
+        debug_assert!(offset < self.len);
+
+        let index = self.ring_index(offset);
         &mut self.inner[index]
     }
 }
 
+/// Read a history line from disk.
+#[cold]
+fn cold_row<T>(storage: &Storage<T>, index: usize) -> &Row<T> {
+    let columns = storage.columns();
+    storage.cold.as_ref().unwrap().get(index, columns)
+}
+
+/// Read a history line from disk for modification.
+#[cold]
+fn cold_row_mut<T>(storage: &mut Storage<T>, index: usize) -> &mut Row<T> {
+    let columns = storage.columns();
+    storage.cold.as_mut().unwrap().get_mut(index, columns)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::grid::row::Row;
@@ -358,6 +555,7 @@ mod tests {
             zero: 0,
             visible_lines: 3,
             len: 3,
+            cold: None,
         };
 
         // Grow buffer.
@@ -369,6 +567,7 @@ mod tests {
             zero: 0,
             visible_lines: 4,
             len: 4,
+            cold: None,
         };
         expected.inner.append(&mut vec![filled_row('\0'); MAX_CACHE_SIZE]);
 
@@ -399,6 +598,7 @@ mod tests {
             zero: 1,
             visible_lines: 3,
             len: 3,
+            cold: None,
         };
 
         // Grow buffer.
@@ -410,6 +610,7 @@ mod tests {
             zero: 0,
             visible_lines: 4,
             len: 4,
+            cold: None,
         };
         expected.inner.append(&mut vec![filled_row('\0'); MAX_CACHE_SIZE]);
 
@@ -437,6 +638,7 @@ mod tests {
             zero: 1,
             visible_lines: 3,
             len: 3,
+            cold: None,
         };
 
         // Shrink buffer.
@@ -448,6 +650,7 @@ mod tests {
             zero: 1,
             visible_lines: 2,
             len: 2,
+            cold: None,
         };
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
@@ -473,6 +676,7 @@ mod tests {
             zero: 0,
             visible_lines: 3,
             len: 3,
+            cold: None,
         };
 
         // Shrink buffer.
@@ -484,6 +688,7 @@ mod tests {
             zero: 0,
             visible_lines: 2,
             len: 2,
+            cold: None,
         };
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
@@ -522,6 +727,7 @@ mod tests {
             zero: 2,
             visible_lines: 6,
             len: 6,
+            cold: None,
         };
 
         // Shrink buffer.
@@ -540,6 +746,7 @@ mod tests {
             zero: 2,
             visible_lines: 2,
             len: 2,
+            cold: None,
         };
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
@@ -574,6 +781,7 @@ mod tests {
             zero: 2,
             visible_lines: 1,
             len: 2,
+            cold: None,
         };
 
         // Truncate buffer.
@@ -585,6 +793,7 @@ mod tests {
             zero: 0,
             visible_lines: 1,
             len: 2,
+            cold: None,
         };
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
@@ -609,6 +818,7 @@ mod tests {
             zero: 2,
             visible_lines: 1,
             len: 2,
+            cold: None,
         };
 
         // Truncate buffer.
@@ -620,6 +830,7 @@ mod tests {
             zero: 0,
             visible_lines: 1,
             len: 2,
+            cold: None,
         };
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
@@ -666,6 +877,7 @@ mod tests {
             zero: 2,
             visible_lines: 0,
             len: 6,
+            cold: None,
         };
 
         // Shrink buffer.
@@ -684,6 +896,7 @@ mod tests {
             zero: 2,
             visible_lines: 0,
             len: 3,
+            cold: None,
         };
         assert_eq!(storage.inner, shrinking_expected.inner);
         assert_eq!(storage.zero, shrinking_expected.zero);
@@ -705,6 +918,7 @@ mod tests {
             zero: 2,
             visible_lines: 0,
             len: 4,
+            cold: None,
         };
 
         assert_eq!(storage.inner, growing_expected.inner);
@@ -727,6 +941,7 @@ mod tests {
             zero: 2,
             visible_lines: 0,
             len: 6,
+            cold: None,
         };
 
         // Initialize additional lines.
@@ -744,7 +959,8 @@ mod tests {
         ];
         let expected_init_size = std::cmp::max(init_size, MAX_CACHE_SIZE);
         expected_inner.append(&mut vec![filled_row('\0'); expected_init_size]);
-        let expected_storage = Storage { inner: expected_inner, zero: 0, visible_lines: 0, len: 9 };
+        let expected_storage =
+            Storage { inner: expected_inner, zero: 0, visible_lines: 0, len: 9, cold: None };
 
         assert_eq!(storage.len, expected_storage.len);
         assert_eq!(storage.zero, expected_storage.zero);
@@ -758,6 +974,7 @@ mod tests {
             zero: 2,
             visible_lines: 0,
             len: 3,
+            cold: None,
         };
 
         storage.rotate(2);
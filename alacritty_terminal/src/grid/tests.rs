@@ -3,6 +3,7 @@
 use super::*;
 
 use crate::term::cell::Cell;
+use crate::vte::ansi::Color;
 
 impl GridCell for usize {
     fn is_empty(&self) -> bool {
@@ -361,3 +362,81 @@ fn wrap_cell(c: char) -> Cell {
     cell.flags.insert(Flags::WRAPLINE);
     cell
 }
+
+#[test]
+fn spill_history() {
+    let mut grid = Grid::<Cell>::new(2, 1, 2000);
+    grid.update_spill(Some(SpillConfig { memory_budget: 0, disk_budget: usize::MAX }));
+    fill_history(&mut grid, 3000);
+
+    assert_eq!(grid.history_size(), 2000);
+    assert!(grid.raw.spill_config().is_some());
+    assert_history(&grid, 3000);
+
+    // Lines read back from disk can be modified.
+    grid[Line(-1500)][Column(0)].c = 'x';
+    grid.scroll_display(Scroll::Top);
+    assert_eq!(grid[Line(-1500)][Column(0)].c, 'x');
+}
+
+#[test]
+fn spill_history_shrink() {
+    let mut grid = Grid::<Cell>::new(2, 1, 2000);
+    grid.update_spill(Some(SpillConfig { memory_budget: 0, disk_budget: usize::MAX }));
+    fill_history(&mut grid, 3000);
+
+    grid.update_history(500);
+    assert_eq!(grid.history_size(), 500);
+    assert_history(&grid, 3000);
+
+    grid.clear_history();
+    assert_eq!(grid.history_size(), 0);
+}
+
+#[test]
+fn spill_history_grow_lines() {
+    let mut grid = Grid::<Cell>::new(2, 1, 2000);
+    grid.update_spill(Some(SpillConfig { memory_budget: 0, disk_budget: usize::MAX }));
+    fill_history(&mut grid, 3000);
+
+    grid.resize::<Color>(true, 10, 1);
+    assert_eq!(grid.screen_lines(), 10);
+    assert_eq!(grid.history_size() + grid.screen_lines(), 2002);
+
+    // All lines above the two blank lines at the bottom are still in order.
+    let blank = Line(grid.screen_lines() as i32 - 2);
+    let first = 3000 - (grid.history_size() + grid.screen_lines() - 2);
+    for (index, line) in (first..).zip((grid.topmost_line().0..blank.0).map(Line)) {
+        assert_eq!(grid[line][Column(0)], history_cell(index));
+    }
+}
+
+#[test]
+fn spill_history_disk_budget() {
+    let mut grid = Grid::<Cell>::new(2, 1, 2000);
+    grid.update_spill(Some(SpillConfig { memory_budget: 0, disk_budget: 4096 }));
+    fill_history(&mut grid, 3000);
+
+    assert!(grid.history_size() < 2000);
+    assert_history(&grid, 3000);
+}
+
+/// Write `count` unique lines into the grid, scrolling each one into history.
+fn fill_history(grid: &mut Grid<Cell>, count: usize) {
+    let region = Line(0)..Line(grid.screen_lines() as i32);
+    for i in 0..count {
+        grid[Line(0)][Column(0)] = history_cell(i);
+        grid.scroll_up::<Color>(&region, 1);
+    }
+}
+
+/// Assert that the history contains the last lines written by [`fill_history`].
+fn assert_history(grid: &Grid<Cell>, count: usize) {
+    for offset in 1..=grid.history_size() {
+        assert_eq!(grid[Line(-(offset as i32))][Column(0)], history_cell(count - offset));
+    }
+}
+
+fn history_cell(index: usize) -> Cell {
+    cell(char::from_u32(0x4e00 + index as u32).unwrap())
+}
@@ -0,0 +1,135 @@
+//! Headless terminal without PTY or renderer.
+//!
+//! This is intended for tests and other tools which need to run raw terminal output through the
+//! emulation and inspect the result:
+//!
+//! ```rust
+//! use alacritty_terminal::headless::{Format, Headless, Region};
+//!
+//! let mut terminal = Headless::new(20, 3);
+//! terminal.feed(b"\x1b[1mhello\x1b[0m world");
+//!
+//! assert_eq!(terminal.snapshot(Region::Screen, Format::Text), "hello world\n\n\n");
+//! ```
+
+use crate::event::{EventListener, VoidListener};
+use crate::term::test::TermSize;
+use crate::term::{Config, Term};
+use crate::vte::ansi::Processor;
+
+mod snapshot;
+
+pub use snapshot::{snapshot, Format, Region};
+
+/// Terminal driven directly by bytes, instead of a PTY.
+pub struct Headless<T = VoidListener> {
+    term: Term<T>,
+    parser: Processor,
+}
+
+impl Headless<VoidListener> {
+    /// Create a headless terminal with the default config, discarding all terminal events.
+    pub fn new(columns: usize, screen_lines: usize) -> Self {
+        Self::with_config(Config::default(), columns, screen_lines, VoidListener)
+    }
+}
+
+impl<T: EventListener> Headless<T> {
+    /// Create a headless terminal.
+    ///
+    /// Terminal events, like responses to device status requests, are sent to `event_proxy`.
+    pub fn with_config(
+        config: Config,
+        columns: usize,
+        screen_lines: usize,
+        event_proxy: T,
+    ) -> Self {
+        let size = TermSize::new(columns, screen_lines);
+        Self { term: Term::new(config, &size, event_proxy), parser: Processor::new() }
+    }
+
+    /// Process terminal output.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.parser.advance(&mut self.term, *byte);
+        }
+    }
+
+    /// Resize the terminal.
+    pub fn resize(&mut self, columns: usize, screen_lines: usize) {
+        self.term.resize(TermSize::new(columns, screen_lines));
+    }
+
+    /// Render the terminal content.
+    pub fn snapshot(&self, region: Region, format: Format) -> String {
+        snapshot(&self.term, region, format)
+    }
+
+    /// Access the underlying terminal.
+    #[inline]
+    pub fn term(&self) -> &Term<T> {
+        &self.term
+    }
+
+    /// Mutable access to the underlying terminal.
+    #[inline]
+    pub fn term_mut(&mut self) -> &mut Term<T> {
+        &mut self.term
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_snapshot() {
+        let mut terminal = Headless::new(5, 2);
+        terminal.feed(b"\x1b[31mhello world");
+
+        let screen = terminal.snapshot(Region::Screen, Format::Text);
+        assert_eq!(screen, " worl\nd\n");
+
+        let history = terminal.snapshot(Region::History, Format::Text);
+        assert_eq!(history, "hello\n worl\nd\n");
+
+        terminal.feed(b"\r\n\x1b[0m\xe6\xbc\xa2\tx");
+        let screen = terminal.snapshot(Region::Screen, Format::Text);
+        assert_eq!(screen, "d\n漢  x\n");
+    }
+
+    #[test]
+    fn ansi_snapshot() {
+        let mut terminal = Headless::new(20, 2);
+        terminal.feed(b"a\x1b[1;31mb\x1b[38;5;100;48;2;1;2;3mc\x1b[0m d");
+        terminal
+            .feed(b"\r\n\x1b]8;id=x;https://example.org\x1b\\link\x1b]8;;\x1b\\ \x1b[4m \x1b[0m");
+
+        let screen = terminal.snapshot(Region::Screen, Format::Ansi);
+        assert_eq!(
+            screen,
+            "a\x1b[0;1;31mb\x1b[0;1;38;5;100;48;2;1;2;3mc\x1b[0m d\n\x1b]8;id=x;https://example.\
+             org\x1b\\link\x1b]8;;\x1b\\ \x1b[0;4m \x1b[0m\n"
+        );
+
+        // Feeding the snapshot back must reproduce the same snapshot.
+        let mut replay = Headless::new(20, 2);
+        replay.feed(screen.trim_end().replace('\n', "\r\n").as_bytes());
+        assert_eq!(replay.snapshot(Region::Screen, Format::Ansi), screen);
+    }
+
+    #[test]
+    fn html_snapshot() {
+        let mut terminal = Headless::new(10, 1);
+        terminal.feed(b"<\x1b[1;32mok\x1b[0m \x1b[7m&\x1b]8;;file:///\x1b\\x");
+
+        let html = terminal.snapshot(Region::Screen, Format::Html);
+        assert_eq!(
+            html,
+            "<pre style=\"font-family:monospace;color:#d8d8d8;background-color:#181818\">&lt;<span \
+             style=\"color:#90a959;font-weight:bold\">ok</span> <span \
+             style=\"color:#181818;background-color:#d8d8d8\">&amp;</span><a href=\"file:///\" \
+             style=\"color:#181818;background-color:#d8d8d8\">x</a>\n</pre>\n"
+        );
+    }
+}
@@ -0,0 +1,337 @@
+//! Stable text representations of the terminal content.
+
+use std::fmt::Write;
+
+use crate::grid::{Dimensions, Row};
+use crate::html::{HtmlColors, HtmlWriter};
+use crate::index::{Column, Line};
+use crate::term::cell::{Cell, Flags, Hyperlink};
+use crate::term::color::Colors;
+use crate::term::Term;
+use crate::vte::ansi::{Color, NamedColor, Rgb};
+
+/// Flags which affect the appearance of a cell.
+const STYLE_FLAGS: Flags = Flags::INVERSE
+    .union(Flags::BOLD)
+    .union(Flags::ITALIC)
+    .union(Flags::ALL_UNDERLINES)
+    .union(Flags::DIM)
+    .union(Flags::HIDDEN)
+    .union(Flags::STRIKEOUT);
+
+/// Factor for dimming colors, matching Alacritty's default.
+const DIM_FACTOR: f32 = 0.66;
+
+/// Default colors for the 16 named ANSI colors, matching Alacritty's default theme.
+const ANSI_COLORS: [Rgb; 16] = [
+    Rgb { r: 0x18, g: 0x18, b: 0x18 },
+    Rgb { r: 0xac, g: 0x42, b: 0x42 },
+    Rgb { r: 0x90, g: 0xa9, b: 0x59 },
+    Rgb { r: 0xf4, g: 0xbf, b: 0x75 },
+    Rgb { r: 0x6a, g: 0x9f, b: 0xb5 },
+    Rgb { r: 0xaa, g: 0x75, b: 0x9f },
+    Rgb { r: 0x75, g: 0xb5, b: 0xaa },
+    Rgb { r: 0xd8, g: 0xd8, b: 0xd8 },
+    Rgb { r: 0x6b, g: 0x6b, b: 0x6b },
+    Rgb { r: 0xc5, g: 0x55, b: 0x55 },
+    Rgb { r: 0xaa, g: 0xc4, b: 0x74 },
+    Rgb { r: 0xfe, g: 0xca, b: 0x88 },
+    Rgb { r: 0x82, g: 0xb8, b: 0xc8 },
+    Rgb { r: 0xc2, g: 0x8c, b: 0xb8 },
+    Rgb { r: 0x93, g: 0xd3, b: 0xc3 },
+    Rgb { r: 0xf8, g: 0xf8, b: 0xf8 },
+];
+
+/// Default foreground color.
+const FOREGROUND: Rgb = Rgb { r: 0xd8, g: 0xd8, b: 0xd8 };
+
+/// Default background color.
+const BACKGROUND: Rgb = Rgb { r: 0x18, g: 0x18, b: 0x18 };
+
+/// Part of the terminal included in a snapshot.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Region {
+    /// Lines currently visible in the viewport.
+    Screen,
+
+    /// Entire scrollback history, followed by the screen.
+    History,
+}
+
+/// Output format of a snapshot.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Plain text without any styling.
+    Text,
+
+    /// Text with SGR and OSC 8 escapes reconstructing colors, attributes and hyperlinks.
+    Ansi,
+
+    /// HTML `<pre>` block with inline styles.
+    ///
+    /// Colors which were not changed through escape sequences use Alacritty's default theme.
+    Html,
+}
+
+/// Render the terminal content.
+///
+/// Every grid line is rendered as a separate line terminated by `\n`, including wrapped and
+/// empty lines, so the number of output lines always matches the number of lines in the region.
+/// Trailing blank cells are omitted.
+pub fn snapshot<T>(term: &Term<T>, region: Region, format: Format) -> String {
+    let grid = term.grid();
+    let (start, end) = match region {
+        Region::Screen => {
+            let top = -(grid.display_offset() as i32);
+            (top, top + grid.screen_lines() as i32)
+        },
+        Region::History => (grid.topmost_line().0, grid.screen_lines() as i32),
+    };
+
+    // Avoid caching the entire history when it was moved to disk.
+    let rows = (start..end).map(|line| grid.row_ref(Line(line)));
+    match format {
+        Format::Text => rows.map(|row| text_line(&row) + "\n").collect(),
+        Format::Ansi => rows.map(|row| ansi_line(&row)).collect(),
+        Format::Html => {
+            let colors = SnapshotColors(term.colors());
+            let mut html = HtmlWriter::new(&colors);
+            for row in rows {
+                html.push_cells(cells(&row, true));
+                html.push_newline();
+            }
+            html.finish() + "\n"
+        },
+    }
+}
+
+/// Appearance of a cell.
+#[derive(Clone, PartialEq, Eq)]
+struct Pen {
+    fg: Color,
+    bg: Color,
+    flags: Flags,
+    underline_color: Option<Color>,
+    hyperlink: Option<Hyperlink>,
+}
+
+impl Default for Pen {
+    fn default() -> Self {
+        Self {
+            fg: Color::Named(NamedColor::Foreground),
+            bg: Color::Named(NamedColor::Background),
+            flags: Flags::empty(),
+            underline_color: None,
+            hyperlink: None,
+        }
+    }
+}
+
+impl From<&Cell> for Pen {
+    fn from(cell: &Cell) -> Self {
+        Self {
+            fg: cell.fg(),
+            bg: cell.bg(),
+            flags: cell.flags & STYLE_FLAGS,
+            underline_color: cell.underline_color(),
+            hyperlink: cell.hyperlink(),
+        }
+    }
+}
+
+impl Pen {
+    /// Check if everything except the foreground would be visible on an empty cell.
+    fn is_visible_blank(&self) -> bool {
+        self.bg != Color::Named(NamedColor::Background)
+            || self.hyperlink.is_some()
+            || self.flags.intersects(Flags::INVERSE | Flags::ALL_UNDERLINES | Flags::STRIKEOUT)
+    }
+
+    /// SGR sequence switching from the default appearance to this pen.
+    fn sgr(&self) -> String {
+        let mut sgr = String::from("\x1b[0");
+
+        let attributes = [
+            (Flags::BOLD, "1"),
+            (Flags::DIM, "2"),
+            (Flags::ITALIC, "3"),
+            (Flags::UNDERLINE, "4"),
+            (Flags::DOUBLE_UNDERLINE, "4:2"),
+            (Flags::UNDERCURL, "4:3"),
+            (Flags::DOTTED_UNDERLINE, "4:4"),
+            (Flags::DASHED_UNDERLINE, "4:5"),
+            (Flags::INVERSE, "7"),
+            (Flags::HIDDEN, "8"),
+            (Flags::STRIKEOUT, "9"),
+        ];
+        for (flag, attribute) in attributes {
+            if self.flags.contains(flag) {
+                sgr.push(';');
+                sgr.push_str(attribute);
+            }
+        }
+
+        push_sgr_color(&mut sgr, self.fg, 30, 90, 38);
+        push_sgr_color(&mut sgr, self.bg, 40, 100, 48);
+        if let Some(color) = self.underline_color {
+            push_sgr_color(&mut sgr, color, 0, 0, 58);
+        }
+
+        sgr.push('m');
+        sgr
+    }
+}
+
+/// Add a color to an SGR sequence.
+///
+/// A `normal` or `bright` base of `0` forces the extended color syntax.
+fn push_sgr_color(sgr: &mut String, color: Color, normal: u8, bright: u8, extended: u8) {
+    match color {
+        Color::Named(named) if (named as usize) < 8 && normal != 0 => {
+            let _ = write!(sgr, ";{}", normal + named as u8);
+        },
+        Color::Named(named) if (named as usize) < 16 && bright != 0 => {
+            let _ = write!(sgr, ";{}", bright + named as u8 - 8);
+        },
+        Color::Named(named) if (named as usize) < 16 => {
+            let _ = write!(sgr, ";{extended};5;{}", named as u8);
+        },
+        Color::Indexed(index) => {
+            let _ = write!(sgr, ";{extended};5;{index}");
+        },
+        Color::Spec(Rgb { r, g, b }) => {
+            let _ = write!(sgr, ";{extended};2;{r};{g};{b}");
+        },
+        Color::Named(_) => (),
+    }
+}
+
+/// Cells which should be rendered for a row.
+///
+/// With `styled`, trailing blank cells are kept when their style would be visible.
+fn cells(row: &Row<Cell>, styled: bool) -> &[Cell] {
+    let cells = &row[Column(0)..];
+    let length = cells
+        .iter()
+        .rposition(|cell| {
+            let blank = matches!(cell.c, ' ' | '\t') && cell.zerowidth().is_none();
+            !blank || (styled && Pen::from(cell).is_visible_blank())
+        })
+        .map_or(0, |index| index + 1);
+    &cells[..length]
+}
+
+/// Characters displayed by a single cell.
+fn push_cell(text: &mut String, cell: &Cell) {
+    if cell.flags.intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER) {
+        return;
+    }
+
+    text.push(if cell.c == '\t' { ' ' } else { cell.c });
+    text.extend(cell.zerowidth().into_iter().flatten());
+}
+
+fn text_line(row: &Row<Cell>) -> String {
+    let mut text = String::new();
+    for cell in cells(row, false) {
+        push_cell(&mut text, cell);
+    }
+    text
+}
+
+fn ansi_line(row: &Row<Cell>) -> String {
+    let mut text = String::new();
+    let mut pen = Pen::default();
+
+    for cell in cells(row, true) {
+        let cell_pen = Pen::from(cell);
+
+        if cell_pen.hyperlink != pen.hyperlink {
+            match &cell_pen.hyperlink {
+                Some(hyperlink) if hyperlink.has_generated_id() => {
+                    let _ = write!(text, "\x1b]8;;{}\x1b\\", hyperlink.uri());
+                },
+                Some(hyperlink) => {
+                    let _ = write!(text, "\x1b]8;id={};{}\x1b\\", hyperlink.id(), hyperlink.uri());
+                },
+                None => text.push_str("\x1b]8;;\x1b\\"),
+            }
+        }
+
+        let style_changed = cell_pen.fg != pen.fg
+            || cell_pen.bg != pen.bg
+            || cell_pen.flags != pen.flags
+            || cell_pen.underline_color != pen.underline_color;
+        if style_changed {
+            text.push_str(&cell_pen.sgr());
+        }
+
+        pen = cell_pen;
+        push_cell(&mut text, cell);
+    }
+
+    if pen.hyperlink.is_some() {
+        text.push_str("\x1b]8;;\x1b\\");
+    }
+    if pen != Pen::default() {
+        text.push_str("\x1b[0m");
+    }
+
+    text.push('\n');
+    text
+}
+
+/// Colors of the terminal, falling back to Alacritty's default theme.
+struct SnapshotColors<'a>(&'a Colors);
+
+impl HtmlColors for SnapshotColors<'_> {
+    fn foreground(&self, color: Color, flags: Flags) -> Rgb {
+        let rgb = resolve(self.0, color);
+        if flags.contains(Flags::DIM) {
+            rgb * DIM_FACTOR
+        } else {
+            rgb
+        }
+    }
+
+    fn background(&self, color: Color) -> Rgb {
+        resolve(self.0, color)
+    }
+}
+
+/// Resolve a color to RGB, using the terminal's overrides or Alacritty's default theme.
+fn resolve(colors: &Colors, color: Color) -> Rgb {
+    let index = match color {
+        Color::Spec(rgb) => return rgb,
+        Color::Indexed(index) => index as usize,
+        Color::Named(named) => named as usize,
+    };
+
+    if let Some(rgb) = colors[index] {
+        return rgb;
+    }
+
+    match index {
+        0..=15 => ANSI_COLORS[index],
+        16..=231 => {
+            let index = index - 16;
+            let component = |value: usize| if value == 0 { 0 } else { value as u8 * 40 + 55 };
+            Rgb { r: component(index / 36), g: component(index / 6 % 6), b: component(index % 6) }
+        },
+        232..=255 => {
+            let value = (index - 232) as u8 * 10 + 8;
+            Rgb { r: value, g: value, b: value }
+        },
+        _ => match color {
+            Color::Named(NamedColor::Background) => BACKGROUND,
+            Color::Named(NamedColor::DimForeground) => FOREGROUND * DIM_FACTOR,
+            Color::Named(named)
+                if (NamedColor::DimBlack as usize..=NamedColor::DimWhite as usize)
+                    .contains(&(named as usize)) =>
+            {
+                ANSI_COLORS[named as usize - NamedColor::DimBlack as usize] * DIM_FACTOR
+            },
+            _ => FOREGROUND,
+        },
+    }
+}
@@ -0,0 +1,187 @@
+//! Conversion of terminal cells to HTML.
+
+use std::fmt::Write;
+
+use crate::term::cell::{Cell, Flags};
+use crate::vte::ansi::{Color, NamedColor, Rgb};
+
+/// Resolution of cell colors to RGB.
+pub trait HtmlColors {
+    /// Foreground color of a cell, including the effect of its flags like dim text.
+    fn foreground(&self, color: Color, flags: Flags) -> Rgb;
+
+    /// Background color of a cell.
+    fn background(&self, color: Color) -> Rgb;
+}
+
+/// Writer for an HTML `<pre>` block.
+///
+/// Cells with identical appearance are merged into a single `<span>`, while text attributes and
+/// hyperlinks are converted to their HTML equivalent.
+pub struct HtmlWriter<'a, C> {
+    html: String,
+    colors: &'a C,
+    foreground: Rgb,
+    background: Rgb,
+}
+
+impl<'a, C: HtmlColors> HtmlWriter<'a, C> {
+    /// Start a block using the default foreground and background colors.
+    pub fn new(colors: &'a C) -> Self {
+        let foreground = colors.foreground(Color::Named(NamedColor::Foreground), Flags::empty());
+        let background = colors.background(Color::Named(NamedColor::Background));
+
+        let html = format!(
+            "<pre style=\"font-family:monospace;color:{};background-color:{}\">",
+            hex(foreground),
+            hex(background)
+        );
+
+        Self { html, colors, foreground, background }
+    }
+
+    /// Write cells of a single line.
+    pub fn push_cells<'c>(&mut self, cells: impl IntoIterator<Item = &'c Cell>) {
+        let mut run = String::new();
+        let mut run_style = None;
+        for cell in cells {
+            if cell.flags.intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER) {
+                continue;
+            }
+
+            let style = self.style(cell);
+            if run_style.as_ref() != Some(&style) {
+                self.push_run(run_style.as_ref(), &run);
+                run_style = Some(style);
+                run.clear();
+            }
+
+            run.push(if cell.c == '\t' { ' ' } else { cell.c });
+            run.extend(cell.zerowidth().into_iter().flatten());
+        }
+        self.push_run(run_style.as_ref(), &run);
+    }
+
+    /// Start a new line.
+    pub fn push_newline(&mut self) {
+        self.html.push('\n');
+    }
+
+    /// Close the block and return its HTML.
+    pub fn finish(mut self) -> String {
+        self.html.push_str("</pre>");
+        self.html
+    }
+
+    /// Appearance of a cell in HTML.
+    fn style(&self, cell: &Cell) -> CellStyle {
+        let mut fg = self.colors.foreground(cell.fg(), cell.flags);
+        let mut bg = self.colors.background(cell.bg());
+        if cell.flags.contains(Flags::INVERSE) {
+            std::mem::swap(&mut fg, &mut bg);
+        }
+        if cell.flags.contains(Flags::HIDDEN) {
+            fg = bg;
+        }
+
+        let mut css = String::new();
+        if fg != self.foreground {
+            let _ = write!(css, "color:{};", hex(fg));
+        }
+        if bg != self.background {
+            let _ = write!(css, "background-color:{};", hex(bg));
+        }
+        if cell.flags.contains(Flags::BOLD) {
+            css.push_str("font-weight:bold;");
+        }
+        if cell.flags.contains(Flags::ITALIC) {
+            css.push_str("font-style:italic;");
+        }
+
+        let mut decorations = Vec::new();
+        let underline = match cell.flags & Flags::ALL_UNDERLINES {
+            Flags::UNDERLINE => Some("underline"),
+            Flags::DOUBLE_UNDERLINE => Some("underline double"),
+            Flags::UNDERCURL => Some("underline wavy"),
+            Flags::DOTTED_UNDERLINE => Some("underline dotted"),
+            Flags::DASHED_UNDERLINE => Some("underline dashed"),
+            _ => None,
+        };
+        decorations.extend(underline);
+        if cell.flags.contains(Flags::STRIKEOUT) {
+            decorations.push("line-through");
+        }
+        if !decorations.is_empty() {
+            let _ = write!(css, "text-decoration:{};", decorations.join(" "));
+        }
+        if let (Some(_), Some(color)) = (underline, cell.underline_color()) {
+            let color = self.colors.foreground(color, cell.flags);
+            let _ = write!(css, "text-decoration-color:{};", hex(color));
+        }
+
+        // Strip the trailing separator.
+        css.pop();
+
+        let hyperlink = cell.hyperlink().map(|hyperlink| hyperlink.uri().to_owned());
+
+        CellStyle { css, hyperlink }
+    }
+
+    /// Write text with identical appearance.
+    fn push_run(&mut self, style: Option<&CellStyle>, text: &str) {
+        let style = match style {
+            Some(style) if !text.is_empty() => style,
+            _ => return,
+        };
+
+        let html = &mut self.html;
+        match (&style.hyperlink, style.css.is_empty()) {
+            (Some(uri), true) => {
+                let _ = write!(html, "<a href=\"{}\">", escape(uri));
+            },
+            (Some(uri), false) => {
+                let _ = write!(html, "<a href=\"{}\" style=\"{}\">", escape(uri), style.css);
+            },
+            (None, false) => {
+                let _ = write!(html, "<span style=\"{}\">", style.css);
+            },
+            (None, true) => (),
+        }
+
+        html.push_str(&escape(text));
+
+        if style.hyperlink.is_some() {
+            html.push_str("</a>");
+        } else if !style.css.is_empty() {
+            html.push_str("</span>");
+        }
+    }
+}
+
+/// Appearance of a cell in HTML.
+#[derive(Debug, PartialEq, Eq)]
+struct CellStyle {
+    /// Inline CSS, empty for the default appearance.
+    css: String,
+    hyperlink: Option<String>,
+}
+
+/// Format a color as CSS hex color.
+fn hex(rgb: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", rgb.r, rgb.g, rgb.b)
+}
+
+/// Escape text for use in HTML content and attributes.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -4,10 +4,15 @@
 #![deny(clippy::all, clippy::if_not_else, clippy::enum_glob_use)]
 #![cfg_attr(clippy, deny(warnings))]
 
+pub mod asciicast;
+#[cfg(unix)]
+pub mod async_event_loop;
 pub mod event;
 pub mod event_loop;
 pub mod graphics;
 pub mod grid;
+pub mod headless;
+pub mod html;
 pub mod index;
 pub mod selection;
 pub mod sync;
@@ -1,3 +1,4 @@
+use std::hash::{Hash, Hasher};
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 
@@ -6,9 +7,10 @@ use bitflags::bitflags;
 use serde::{Deserialize, Serialize};
 
 use crate::graphics::GraphicsCell;
-use crate::grid::{self, GridCell};
+use crate::grid::{self, read_varint, write_varint, GridCell, SpillCell};
 use crate::index::Column;
-use crate::vte::ansi::{Color, Hyperlink as VteHyperlink, NamedColor};
+use crate::term::style::{self, Style, StyleData};
+use crate::vte::ansi::{Color, Hyperlink as VteHyperlink, NamedColor, Rgb};
 
 bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -41,6 +43,9 @@ bitflags! {
 /// Counter for hyperlinks without explicit ID.
 static HYPERLINK_ID_SUFFIX: AtomicU32 = AtomicU32::new(0);
 
+/// Suffix of IDs generated for hyperlinks without explicit ID.
+const GENERATED_ID_SUFFIX: &str = "_alacritty";
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Hyperlink {
@@ -60,6 +65,11 @@ impl Hyperlink {
     pub fn uri(&self) -> &str {
         &self.inner.uri
     }
+
+    /// Check if the ID was generated by Alacritty.
+    pub(crate) fn has_generated_id(&self) -> bool {
+        self.inner.id.ends_with(GENERATED_ID_SUFFIX)
+    }
 }
 
 impl From<VteHyperlink> for Hyperlink {
@@ -90,7 +100,7 @@ impl HyperlinkInner {
             Some(id) => id.to_string(),
             None => {
                 let mut id = HYPERLINK_ID_SUFFIX.fetch_add(1, Ordering::Relaxed).to_string();
-                id.push_str("_alacritty");
+                id.push_str(GENERATED_ID_SUFFIX);
                 id
             },
         };
@@ -113,15 +123,14 @@ impl<T: Copy> ResetDiscriminant<T> for T {
 
 impl ResetDiscriminant<Color> for Cell {
     fn discriminant(&self) -> Color {
-        self.bg
+        self.bg()
     }
 }
 
-/// Dynamically allocated cell content.
+/// Rarely used cell content.
 ///
-/// This storage is reserved for cell attributes which are rarely set. This allows reducing the
-/// allocation required ahead of time for every cell, with some additional overhead when the extra
-/// storage is actually required.
+/// This is stored as part of the cell's shared style, so it should only contain attributes
+/// which are unlikely to differ between most cells.
 #[derive(Default, Debug, Clone, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct CellExtra {
@@ -135,67 +144,143 @@ pub struct CellExtra {
     graphics: Option<GraphicsCell>,
 }
 
+impl CellExtra {
+    /// Check if none of the attributes are set.
+    fn is_empty(&self) -> bool {
+        self.zerowidth.is_empty()
+            && self.underline_color.is_none()
+            && self.hyperlink.is_none()
+            && self.graphics.is_none()
+    }
+}
+
+impl Hash for CellExtra {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.zerowidth.hash(state);
+        self.underline_color.is_some().hash(state);
+        if let Some(color) = self.underline_color {
+            style::hash_color(color, state);
+        }
+        self.hyperlink.hash(state);
+        self.graphics.as_ref().map(|graphics| graphics.len()).hash(state);
+    }
+}
+
 /// Content and attributes of a single cell in the terminal grid.
+///
+/// Colors and other attributes which are shared by many cells are stored in a reference counted
+/// style, see [`Cell::fg`], [`Cell::bg`] and the other accessors.
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "SerdeCell", into = "SerdeCell"))]
 pub struct Cell {
     pub c: char,
-    pub fg: Color,
-    pub bg: Color,
     pub flags: Flags,
-    pub extra: Option<Arc<CellExtra>>,
+    pub(crate) style: Style,
 }
 
 impl Default for Cell {
     #[inline]
     fn default() -> Cell {
-        Cell {
-            c: ' ',
-            bg: Color::Named(NamedColor::Background),
-            fg: Color::Named(NamedColor::Foreground),
-            flags: Flags::empty(),
-            extra: None,
-        }
+        Cell { c: ' ', flags: Flags::empty(), style: Style::default() }
     }
 }
 
 impl Cell {
+    /// Create a new cell.
+    #[inline]
+    pub fn new(c: char, fg: Color, bg: Color, flags: Flags) -> Self {
+        let style = Style::new(StyleData { fg, bg, extra: None });
+        Self { c, flags, style }
+    }
+
+    /// Foreground color.
+    #[inline]
+    pub fn fg(&self) -> Color {
+        self.style.fg
+    }
+
+    /// Background color.
+    #[inline]
+    pub fn bg(&self) -> Color {
+        self.style.bg
+    }
+
+    /// Set the foreground color.
+    #[inline]
+    pub fn set_fg(&mut self, fg: Color) {
+        if self.style.fg != fg {
+            self.style.update(|style| style.fg = fg);
+        }
+    }
+
+    /// Set the background color.
+    #[inline]
+    pub fn set_bg(&mut self, bg: Color) {
+        if self.style.bg != bg {
+            self.style.update(|style| style.bg = bg);
+        }
+    }
+
+    /// Blank cell cleared using the attributes of this cell.
+    ///
+    /// Only the background color is kept.
+    #[inline]
+    pub(crate) fn blank(&self) -> Self {
+        Self { style: self.style.blank(), ..Self::default() }
+    }
+
+    /// Copy all colors and attributes from another cell.
+    #[inline]
+    pub fn set_style_from(&mut self, other: &Cell) {
+        if self.style != other.style {
+            self.style = other.style.clone();
+        }
+    }
+
+    /// Modify the extra storage, dropping it if no attributes are left.
+    fn update_extra<F: FnOnce(&mut CellExtra)>(&mut self, f: F) {
+        self.style.update(|style| {
+            let extra = style.extra.get_or_insert_with(Default::default);
+            f(extra);
+
+            if extra.is_empty() {
+                style.extra = None;
+            }
+        });
+    }
+
     /// Zerowidth characters stored in this cell.
     #[inline]
     pub fn zerowidth(&self) -> Option<&[char]> {
-        self.extra.as_ref().map(|extra| extra.zerowidth.as_slice())
+        let extra = self.style.extra.as_ref()?;
+        (!extra.zerowidth.is_empty()).then_some(extra.zerowidth.as_slice())
     }
 
     /// Write a new zerowidth character to this cell.
     #[inline]
     pub fn push_zerowidth(&mut self, character: char) {
-        let extra = self.extra.get_or_insert(Default::default());
-        Arc::make_mut(extra).zerowidth.push(character);
+        self.update_extra(|extra| extra.zerowidth.push(character));
     }
 
     /// Graphic present in the cell.
     #[inline]
     pub fn graphics(&self) -> Option<&GraphicsCell> {
-        self.extra.as_deref().and_then(|extra| extra.graphics.as_ref())
+        self.style.extra.as_ref()?.graphics.as_ref()
     }
 
     /// Extract the graphics value from the cell.
     #[inline]
     pub fn take_graphics(&mut self) -> Option<GraphicsCell> {
-        if let Some(extra) = &mut self.extra {
-            if extra.graphics.is_some() {
-                return Arc::make_mut(extra).graphics.take();
-            }
-        }
-
-        None
+        let graphics = self.graphics()?.clone();
+        self.update_extra(|extra| extra.graphics = None);
+        Some(graphics)
     }
 
     /// Write the graphic data in the cell.
     #[inline]
     pub fn set_graphics(&mut self, graphics_cell: GraphicsCell) {
-        let extra = self.extra.get_or_insert_with(Default::default);
-        Arc::make_mut(extra).graphics = Some(graphics_cell);
+        self.update_extra(|extra| extra.graphics = Some(graphics_cell));
 
         self.flags_mut().insert(Flags::GRAPHICS);
     }
@@ -204,53 +289,65 @@ impl Cell {
     #[inline(never)]
     pub fn clear_wide(&mut self) {
         self.flags.remove(Flags::WIDE_CHAR);
-        if let Some(extra) = self.extra.as_mut() {
-            Arc::make_mut(extra).zerowidth = Vec::new();
+        if self.zerowidth().is_some() {
+            self.update_extra(|extra| extra.zerowidth = Vec::new());
         }
         self.c = ' ';
     }
 
     /// Set underline color on the cell.
     pub fn set_underline_color(&mut self, color: Option<Color>) {
-        // If we reset color and we don't have zerowidth we should drop extra storage.
-        if color.is_none()
-            && self
-                .extra
-                .as_ref()
-                .map_or(true, |extra| extra.zerowidth.is_empty() && extra.hyperlink.is_none())
-        {
-            self.extra = None;
-        } else {
-            let extra = self.extra.get_or_insert(Default::default());
-            Arc::make_mut(extra).underline_color = color;
+        if self.underline_color() != color {
+            self.update_extra(|extra| extra.underline_color = color);
         }
     }
 
     /// Underline color stored in this cell.
     #[inline]
     pub fn underline_color(&self) -> Option<Color> {
-        self.extra.as_ref()?.underline_color
+        self.style.extra.as_ref()?.underline_color
     }
 
     /// Set hyperlink.
     pub fn set_hyperlink(&mut self, hyperlink: Option<Hyperlink>) {
-        let should_drop = hyperlink.is_none()
-            && self.extra.as_ref().map_or(true, |extra| {
-                extra.zerowidth.is_empty() && extra.underline_color.is_none()
-            });
-
-        if should_drop {
-            self.extra = None;
-        } else {
-            let extra = self.extra.get_or_insert(Default::default());
-            Arc::make_mut(extra).hyperlink = hyperlink;
+        let current = self.style.extra.as_ref().and_then(|extra| extra.hyperlink.as_ref());
+        if current != hyperlink.as_ref() {
+            self.update_extra(|extra| extra.hyperlink = hyperlink);
         }
     }
 
     /// Hyperlink stored in this cell.
     #[inline]
     pub fn hyperlink(&self) -> Option<Hyperlink> {
-        self.extra.as_ref()?.hyperlink.clone()
+        self.style.extra.as_ref()?.hyperlink.clone()
+    }
+}
+
+/// Serialization format of a [`Cell`].
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct SerdeCell {
+    c: char,
+    fg: Color,
+    bg: Color,
+    flags: Flags,
+    extra: Option<CellExtra>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Cell> for SerdeCell {
+    fn from(cell: Cell) -> Self {
+        let StyleData { fg, bg, ref extra } = *cell.style;
+        Self { c: cell.c, fg, bg, flags: cell.flags, extra: extra.as_deref().cloned() }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<SerdeCell> for Cell {
+    fn from(cell: SerdeCell) -> Self {
+        let extra = cell.extra.map(Box::new);
+        let style = Style::new(StyleData { fg: cell.fg, bg: cell.bg, extra });
+        Self { c: cell.c, flags: cell.flags, style }
     }
 }
 
@@ -258,8 +355,8 @@ impl GridCell for Cell {
     #[inline]
     fn is_empty(&self) -> bool {
         (self.c == ' ' || self.c == '\t')
-            && self.bg == Color::Named(NamedColor::Background)
-            && self.fg == Color::Named(NamedColor::Foreground)
+            && self.bg() == Color::Named(NamedColor::Background)
+            && self.fg() == Color::Named(NamedColor::Foreground)
             && !self.flags.intersects(
                 Flags::INVERSE
                     | Flags::ALL_UNDERLINES
@@ -269,7 +366,7 @@ impl GridCell for Cell {
                     | Flags::LEADING_WIDE_CHAR_SPACER
                     | Flags::GRAPHICS,
             )
-            && self.extra.as_ref().map(|extra| extra.zerowidth.is_empty()) != Some(false)
+            && self.zerowidth().is_none()
     }
 
     #[inline]
@@ -284,14 +381,200 @@ impl GridCell for Cell {
 
     #[inline]
     fn reset(&mut self, template: &Self) {
-        *self = Cell { bg: template.bg, ..Cell::default() };
+        *self = template.blank();
+    }
+}
+
+impl SpillCell for Cell {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        write_varint(buf, u64::from(self.c));
+        encode_color(self.fg(), buf);
+        encode_color(self.bg(), buf);
+
+        // Graphics are not persisted, since their textures are owned by the renderer.
+        let flags = self.flags - Flags::GRAPHICS;
+        buf.extend_from_slice(&flags.bits().to_le_bytes());
+
+        let extra = match &self.style.extra {
+            Some(extra) => extra,
+            None => {
+                buf.push(0);
+                return;
+            },
+        };
+
+        let mut present = 0;
+        present |= u8::from(!extra.zerowidth.is_empty());
+        present |= u8::from(extra.underline_color.is_some()) << 1;
+        present |= u8::from(extra.hyperlink.is_some()) << 2;
+        buf.push(present);
+
+        if !extra.zerowidth.is_empty() {
+            write_varint(buf, extra.zerowidth.len() as u64);
+            for c in &extra.zerowidth {
+                write_varint(buf, u64::from(*c));
+            }
+        }
+
+        if let Some(color) = extra.underline_color {
+            encode_color(color, buf);
+        }
+
+        if let Some(hyperlink) = &extra.hyperlink {
+            encode_str(hyperlink.id(), buf);
+            encode_str(hyperlink.uri(), buf);
+        }
+    }
+
+    fn decode(buf: &mut &[u8], previous: Option<&Self>) -> Option<Self> {
+        let c = char::from_u32(read_varint(buf)?.try_into().ok()?)?;
+        let fg = decode_color(buf)?;
+        let bg = decode_color(buf)?;
+
+        let (&[low, high, present], rest) = take_bytes::<3>(buf)?;
+        let flags = Flags::from_bits_truncate(u16::from_le_bytes([low, high]));
+        *buf = rest;
+
+        let mut data = StyleData { fg, bg, extra: None };
+        if present != 0 {
+            data.extra = Some(Box::new(decode_extra(buf, present)?));
+        }
+
+        // Share the style with the previous cell, since most neighboring cells use the same one.
+        let style = match previous {
+            Some(previous) if *previous.style == data => previous.style.clone(),
+            _ => Style::new(data),
+        };
+
+        Some(Cell { c, flags, style })
+    }
+}
+
+/// Decode the extra attributes of a cell.
+///
+/// The `present` bitmask indicates which of the attributes were encoded.
+fn decode_extra(buf: &mut &[u8], present: u8) -> Option<CellExtra> {
+    let mut extra = CellExtra::default();
+
+    if present & 1 != 0 {
+        let len = read_varint(buf)?;
+        for _ in 0..len {
+            extra.zerowidth.push(char::from_u32(read_varint(buf)?.try_into().ok()?)?);
+        }
+    }
+
+    if present & 2 != 0 {
+        extra.underline_color = Some(decode_color(buf)?);
+    }
+
+    if present & 4 != 0 {
+        let id = decode_str(buf)?;
+        let uri = decode_str(buf)?;
+        extra.hyperlink = Some(Hyperlink::new(Some(id), uri));
+    }
+
+    Some(extra)
+}
+
+/// Named colors in the order of their binary representation.
+const NAMED_COLORS: [NamedColor; 29] = [
+    NamedColor::Black,
+    NamedColor::Red,
+    NamedColor::Green,
+    NamedColor::Yellow,
+    NamedColor::Blue,
+    NamedColor::Magenta,
+    NamedColor::Cyan,
+    NamedColor::White,
+    NamedColor::BrightBlack,
+    NamedColor::BrightRed,
+    NamedColor::BrightGreen,
+    NamedColor::BrightYellow,
+    NamedColor::BrightBlue,
+    NamedColor::BrightMagenta,
+    NamedColor::BrightCyan,
+    NamedColor::BrightWhite,
+    NamedColor::Foreground,
+    NamedColor::Background,
+    NamedColor::Cursor,
+    NamedColor::DimBlack,
+    NamedColor::DimRed,
+    NamedColor::DimGreen,
+    NamedColor::DimYellow,
+    NamedColor::DimBlue,
+    NamedColor::DimMagenta,
+    NamedColor::DimCyan,
+    NamedColor::DimWhite,
+    NamedColor::BrightForeground,
+    NamedColor::DimForeground,
+];
+
+fn encode_color(color: Color, buf: &mut Vec<u8>) {
+    match color {
+        Color::Named(named) => {
+            let index = NAMED_COLORS.iter().position(|color| *color == named).unwrap_or(0);
+            buf.extend_from_slice(&[0, index as u8]);
+        },
+        Color::Spec(Rgb { r, g, b }) => buf.extend_from_slice(&[1, r, g, b]),
+        Color::Indexed(index) => buf.extend_from_slice(&[2, index]),
     }
 }
 
+fn decode_color(buf: &mut &[u8]) -> Option<Color> {
+    let (&kind, rest) = buf.split_first()?;
+    let (color, rest) = match kind {
+        0 => {
+            let (&index, rest) = rest.split_first()?;
+            (Color::Named(*NAMED_COLORS.get(index as usize)?), rest)
+        },
+        1 => {
+            let (&[r, g, b], rest) = take_bytes::<3>(rest)?;
+            (Color::Spec(Rgb { r, g, b }), rest)
+        },
+        2 => {
+            let (&index, rest) = rest.split_first()?;
+            (Color::Indexed(index), rest)
+        },
+        _ => return None,
+    };
+
+    *buf = rest;
+
+    Some(color)
+}
+
+/// Split a fixed number of bytes from the start of `buf`.
+fn take_bytes<const N: usize>(buf: &[u8]) -> Option<(&[u8; N], &[u8])> {
+    if buf.len() < N {
+        return None;
+    }
+
+    let (bytes, rest) = buf.split_at(N);
+    Some((bytes.try_into().ok()?, rest))
+}
+
+fn encode_str(text: &str, buf: &mut Vec<u8>) {
+    write_varint(buf, text.len() as u64);
+    buf.extend_from_slice(text.as_bytes());
+}
+
+fn decode_str(buf: &mut &[u8]) -> Option<String> {
+    let len = read_varint(buf)? as usize;
+    if buf.len() < len {
+        return None;
+    }
+
+    let (text, rest) = buf.split_at(len);
+    *buf = rest;
+
+    String::from_utf8(text.to_vec()).ok()
+}
+
 impl From<Color> for Cell {
     #[inline]
     fn from(color: Color) -> Self {
-        Self { bg: color, ..Cell::default() }
+        let style = Style::new(StyleData { bg: color, ..StyleData::default() });
+        Self { style, ..Cell::default() }
     }
 }
 
@@ -310,9 +593,7 @@ impl LineLength for grid::Row<Cell> {
         }
 
         for (index, cell) in self[..].iter().rev().enumerate() {
-            if cell.c != ' '
-                || cell.extra.as_ref().map(|extra| extra.zerowidth.is_empty()) == Some(false)
-            {
+            if cell.c != ' ' || cell.zerowidth().is_some() {
                 length = Column(self.len() - index);
                 break;
             }
@@ -334,12 +615,37 @@ mod tests {
     #[test]
     fn cell_size_is_below_cap() {
         // Expected cell size on 64-bit architectures.
-        const EXPECTED_CELL_SIZE: usize = 24;
+        //
+        // The character and flags take 6 bytes and the style pointer 8, which is padded to 16
+        // bytes. A 32-bit style handle would fit into 12 bytes, but resolving it requires access
+        // to the grid's style table for every color lookup.
+        const EXPECTED_CELL_SIZE: usize = 16;
 
         // Ensure that cell size isn't growing by accident.
         assert!(mem::size_of::<Cell>() <= EXPECTED_CELL_SIZE);
     }
 
+    #[test]
+    fn spill_roundtrip() {
+        let fg = Color::Spec(Rgb { r: 1, g: 2, b: 3 });
+        let mut cell = Cell::new('x', fg, Color::Indexed(42), Flags::BOLD | Flags::UNDERCURL);
+        cell.push_zerowidth('\u{301}');
+        cell.set_underline_color(Some(Color::Named(NamedColor::DimCyan)));
+        cell.set_hyperlink(Some(Hyperlink::new(Some("id"), String::from("https://example.org"))));
+
+        let mut buf = Vec::new();
+        cell.encode(&mut buf);
+        cell.encode(&mut buf);
+        Cell::default().encode(&mut buf);
+
+        let mut bytes = buf.as_slice();
+        let first = Cell::decode(&mut bytes, None).unwrap();
+        assert_eq!(first, cell);
+        assert_eq!(Cell::decode(&mut bytes, Some(&first)), Some(cell));
+        assert_eq!(Cell::decode(&mut bytes, Some(&first)), Some(Cell::default()));
+        assert!(bytes.is_empty());
+    }
+
     #[test]
     fn line_length_works() {
         let mut row = Row::<Cell>::new(10);
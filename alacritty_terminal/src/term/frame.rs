@@ -0,0 +1,426 @@
+//! Serializable frame diffs of the terminal viewport.
+//!
+//! A [`Frame`] contains all cells which changed since the terminal damage was last reset, grouped
+//! into runs of cells sharing the same attributes, together with the cursor, terminal modes and
+//! window title. Frames can be sent to another process, like a browser frontend, and applied to a
+//! [`Mirror`] to reproduce the terminal's viewport:
+//!
+//! ```rust
+//! use alacritty_terminal::headless::Headless;
+//! use alacritty_terminal::index::{Column, Line};
+//! use alacritty_terminal::term::frame::{Frame, Mirror};
+//!
+//! let mut terminal = Headless::new(20, 3);
+//! let mut mirror = Mirror::new(20, 3);
+//!
+//! terminal.feed(b"hello");
+//! let frame = Frame::from_damage(terminal.term_mut());
+//! terminal.term_mut().reset_damage();
+//!
+//! mirror.apply(&frame).unwrap();
+//! assert_eq!(mirror.grid()[Line(0)][Column(0)].c, 'h');
+//! ```
+
+use std::fmt::{self, Display, Formatter};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::graphics::GraphicId;
+use crate::grid::{Dimensions, Grid};
+use crate::index::{Column, Line, Point};
+use crate::term::cell::{Cell, CellExtra, Flags};
+use crate::term::style::{Style, StyleData};
+use crate::term::{
+    point_to_viewport, LineDamageBounds, RenderableCursor, Term, TermDamage, TermMode,
+};
+use crate::vte::ansi::{Color, CursorShape};
+
+/// Version of the frame format.
+///
+/// This is increased whenever a change to the format would cause a [`Mirror`] to misinterpret
+/// frames produced by a different version.
+pub const FRAME_VERSION: u32 = 1;
+
+/// Changes to the terminal viewport.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Frame {
+    /// Version of the frame format, see [`FRAME_VERSION`].
+    pub version: u32,
+
+    /// Number of columns in the viewport.
+    pub columns: usize,
+
+    /// Number of lines in the viewport.
+    pub screen_lines: usize,
+
+    /// Changed cells.
+    pub runs: Vec<CellRun>,
+
+    /// Cursor position and shape.
+    pub cursor: FrameCursor,
+
+    /// Bits of the active [`TermMode`].
+    pub mode: u32,
+
+    /// Current window title.
+    pub title: Option<String>,
+
+    /// Graphics referenced by any of the changed cells.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+    pub graphics: Vec<GraphicId>,
+}
+
+impl Frame {
+    /// Collect the damaged parts of the terminal viewport.
+    ///
+    /// This does not reset the terminal damage, [`Term::reset_damage`] should be called once the
+    /// frame has been produced. Since damage can only be consumed once, a terminal which is
+    /// mirrored cannot rely on damage tracking for anything else.
+    pub fn from_damage<T>(term: &mut Term<T>) -> Self {
+        let columns = term.columns();
+        let screen_lines = term.screen_lines();
+
+        let damage: Vec<_> = match term.damage() {
+            TermDamage::Full => {
+                (0..screen_lines).map(|line| LineDamageBounds::new(line, 0, columns - 1)).collect()
+            },
+            TermDamage::Partial(damage) => damage.collect(),
+        };
+
+        let mut runs = Vec::new();
+        let mut graphics = Vec::new();
+        let display_offset = term.grid().display_offset();
+        for bounds in damage {
+            let line = Line(bounds.line as i32) - display_offset;
+            let row = &term.grid()[line];
+
+            let mut run: Option<(CellRun, &Style)> = None;
+            for column in bounds.left..=bounds.right.min(columns - 1) {
+                let cell = &row[Column(column)];
+
+                if let Some(cell_graphics) = cell.graphics() {
+                    graphics.extend(cell_graphics.iter().map(|graphic| graphic.graphic_id()));
+                }
+
+                match &mut run {
+                    Some((run, style)) if run.flags == cell.flags && **style == cell.style => {
+                        run.text.push(cell.c);
+                    },
+                    _ => {
+                        runs.extend(run.take().map(|(run, _)| run));
+                        run = Some((CellRun::new(bounds.line, column, cell), &cell.style));
+                    },
+                }
+            }
+            runs.extend(run.map(|(run, _)| run));
+        }
+
+        graphics.sort_unstable();
+        graphics.dedup();
+
+        let cursor = RenderableCursor::new(term);
+        let cursor = FrameCursor {
+            point: point_to_viewport(display_offset, cursor.point)
+                .filter(|point| point.line < screen_lines),
+            shape: cursor.shape,
+        };
+
+        Self {
+            version: FRAME_VERSION,
+            columns,
+            screen_lines,
+            runs,
+            cursor,
+            mode: term.mode().bits(),
+            title: term.title().map(String::from),
+            graphics,
+        }
+    }
+}
+
+/// Consecutive cells on a single line sharing the same attributes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CellRun {
+    /// Viewport line of the run.
+    pub line: usize,
+
+    /// Column of the first cell.
+    pub column: usize,
+
+    /// Characters of all cells, one per cell.
+    pub text: String,
+
+    pub flags: Flags,
+    pub fg: Color,
+    pub bg: Color,
+
+    /// Zerowidth characters, underline color, hyperlink and graphics of every cell.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub extra: Option<CellExtra>,
+}
+
+impl CellRun {
+    fn new(line: usize, column: usize, cell: &Cell) -> Self {
+        let StyleData { fg, bg, ref extra } = *cell.style;
+        Self {
+            line,
+            column,
+            text: cell.c.to_string(),
+            flags: cell.flags,
+            fg,
+            bg,
+            extra: extra.as_deref().cloned(),
+        }
+    }
+}
+
+/// Cursor state of a frame.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FrameCursor {
+    /// Viewport position of the cursor, `None` if it is scrolled out of the viewport.
+    pub point: Option<Point<usize>>,
+
+    #[cfg_attr(feature = "serde", serde(with = "CursorShapeDef"))]
+    pub shape: CursorShape,
+}
+
+/// Serialization format of [`CursorShape`].
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "CursorShape")]
+enum CursorShapeDef {
+    Block,
+    Underline,
+    Beam,
+    HollowBlock,
+    Hidden,
+}
+
+/// Errors when applying a frame to a mirror.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameError {
+    /// Frame was produced with an incompatible format version.
+    Version(u32),
+
+    /// Run exceeds the frame's dimensions.
+    OutOfBounds { line: usize, column: usize },
+}
+
+impl Display for FrameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameError::Version(version) => {
+                write!(f, "Unsupported frame version {} (expected {})", version, FRAME_VERSION)
+            },
+            FrameError::OutOfBounds { line, column } => {
+                write!(f, "Cell run at line {} column {} is out of bounds", line, column)
+            },
+        }
+    }
+}
+
+impl std::error::Error for FrameError {}
+
+/// Copy of a terminal viewport, built from frames.
+#[derive(Clone, Debug)]
+pub struct Mirror {
+    grid: Grid<Cell>,
+    cursor: FrameCursor,
+    mode: TermMode,
+    title: Option<String>,
+}
+
+impl Mirror {
+    /// Create an empty mirror.
+    ///
+    /// The mirror is resized automatically to match the dimensions of the frames applied to it.
+    pub fn new(columns: usize, screen_lines: usize) -> Self {
+        Self {
+            grid: Grid::new(screen_lines, columns, 0),
+            cursor: Default::default(),
+            mode: Default::default(),
+            title: None,
+        }
+    }
+
+    /// Update the mirror with a new frame.
+    ///
+    /// If the frame is invalid, the mirror is left unchanged.
+    pub fn apply(&mut self, frame: &Frame) -> Result<(), FrameError> {
+        if frame.version != FRAME_VERSION {
+            return Err(FrameError::Version(frame.version));
+        }
+
+        for run in &frame.runs {
+            if run.line >= frame.screen_lines
+                || run.column + run.text.chars().count() > frame.columns
+            {
+                return Err(FrameError::OutOfBounds { line: run.line, column: run.column });
+            }
+        }
+
+        // Resizing always causes full damage, so the old content can just be dropped.
+        if frame.columns != self.grid.columns() || frame.screen_lines != self.grid.screen_lines() {
+            self.grid = Grid::new(frame.screen_lines, frame.columns, 0);
+        }
+
+        for run in &frame.runs {
+            let extra = run.extra.clone().map(Box::new);
+            let style = Style::new(StyleData { fg: run.fg, bg: run.bg, extra });
+
+            let row = &mut self.grid[Line(run.line as i32)];
+            for (i, c) in run.text.chars().enumerate() {
+                row[Column(run.column + i)] = Cell { c, flags: run.flags, style: style.clone() };
+            }
+        }
+
+        self.cursor = frame.cursor;
+        self.mode = TermMode::from_bits_truncate(frame.mode);
+        self.title = frame.title.clone();
+
+        Ok(())
+    }
+
+    /// Mirrored viewport content.
+    #[inline]
+    pub fn grid(&self) -> &Grid<Cell> {
+        &self.grid
+    }
+
+    /// Cursor of the mirrored terminal.
+    #[inline]
+    pub fn cursor(&self) -> FrameCursor {
+        self.cursor
+    }
+
+    /// Active modes of the mirrored terminal.
+    #[inline]
+    pub fn mode(&self) -> TermMode {
+        self.mode
+    }
+
+    /// Window title of the mirrored terminal.
+    #[inline]
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::grid::Scroll;
+    use crate::headless::Headless;
+
+    /// Send a frame through JSON and apply it, checking that the mirror matches the terminal.
+    fn sync(terminal: &mut Headless, mirror: &mut Mirror) -> Frame {
+        let frame = Frame::from_damage(terminal.term_mut());
+        terminal.term_mut().reset_damage();
+
+        let json = serde_json::to_string(&frame).unwrap();
+        let received: Frame = serde_json::from_str(&json).unwrap();
+        assert_eq!(received, frame);
+        mirror.apply(&received).unwrap();
+
+        let term = terminal.term();
+        let display_offset = term.grid().display_offset();
+        assert_eq!(mirror.grid().columns(), term.columns());
+        assert_eq!(mirror.grid().screen_lines(), term.screen_lines());
+        for line in 0..term.screen_lines() {
+            let source = &term.grid()[Line(line as i32) - display_offset];
+            let mirrored = &mirror.grid()[Line(line as i32)];
+            for column in 0..term.columns() {
+                assert_eq!(mirrored[Column(column)], source[Column(column)], "at {line}:{column}");
+            }
+        }
+        assert_eq!(mirror.mode(), *term.mode());
+        assert_eq!(mirror.title(), term.title());
+
+        frame
+    }
+
+    #[test]
+    fn styled_round_trip() {
+        let mut terminal = Headless::new(20, 4);
+        let mut mirror = Mirror::new(20, 4);
+
+        terminal.feed(b"a\x1b[1;31mbc\x1b[0m \x1b[4:3;58:5:2mx\x1b[0m\r\n");
+        terminal.feed(b"\x1b]8;id=x;https://example.org\x1b\\link\x1b]8;;\x1b\\ ");
+        terminal.feed("漢e\u{301}\r\n\x1b]2;title\x07\x1b[?25l".as_bytes());
+        let frame = sync(&mut terminal, &mut mirror);
+
+        assert_eq!(
+            frame.cursor,
+            FrameCursor { point: Some(Point::new(2, Column(0))), shape: CursorShape::Hidden }
+        );
+        assert_eq!(mirror.cursor(), frame.cursor);
+        assert_eq!(mirror.title(), Some("title"));
+    }
+
+    #[test]
+    fn partial_damage() {
+        let mut terminal = Headless::new(10, 3);
+        let mut mirror = Mirror::new(10, 3);
+
+        terminal.feed(b"one\r\ntwo\r\nthree");
+        sync(&mut terminal, &mut mirror);
+
+        // Only the changed cells and cursor positions are transmitted.
+        terminal.feed(b"\x1b[1;2H\x1b[32mX");
+        let frame = sync(&mut terminal, &mut mirror);
+        let lines: Vec<_> = frame.runs.iter().map(|run| run.line).collect();
+        assert_eq!(lines, [0, 0, 2]);
+        assert_eq!(frame.runs[0].text, "X");
+        assert!(frame.runs.iter().all(|run| run.text.len() < 10));
+
+        // Nothing changed.
+        let frame = sync(&mut terminal, &mut mirror);
+        assert!(frame.runs.iter().all(|run| run.line == 0));
+    }
+
+    #[test]
+    fn scroll_and_resize() {
+        let mut terminal = Headless::new(10, 3);
+        let mut mirror = Mirror::new(10, 3);
+
+        for i in 0..10 {
+            terminal.feed(format!("line {i}\r\n").as_bytes());
+            sync(&mut terminal, &mut mirror);
+        }
+
+        terminal.term_mut().scroll_display(Scroll::Delta(4));
+        let frame = sync(&mut terminal, &mut mirror);
+        assert_eq!(frame.cursor.point, None);
+
+        terminal.term_mut().scroll_display(Scroll::Bottom);
+        sync(&mut terminal, &mut mirror);
+
+        terminal.resize(6, 5);
+        sync(&mut terminal, &mut mirror);
+
+        terminal.feed(b"\x1b[?1049h\x1b[?2004halt");
+        let frame = sync(&mut terminal, &mut mirror);
+        assert!(mirror.mode().contains(TermMode::ALT_SCREEN | TermMode::BRACKETED_PASTE));
+        assert_eq!(frame.mode, mirror.mode().bits());
+    }
+
+    #[test]
+    fn invalid_frames() {
+        let mut terminal = Headless::new(5, 2);
+        let mut mirror = Mirror::new(5, 2);
+        terminal.feed(b"abc");
+
+        let mut frame = Frame::from_damage(terminal.term_mut());
+        frame.runs[0].text.push_str("too long");
+        assert_eq!(mirror.apply(&frame), Err(FrameError::OutOfBounds { line: 0, column: 0 }));
+        assert!(mirror.grid()[Line(0)][Column(0)].c == ' ');
+
+        frame.version = FRAME_VERSION + 1;
+        assert_eq!(mirror.apply(&frame), Err(FrameError::Version(FRAME_VERSION + 1)));
+    }
+}
@@ -14,12 +14,12 @@ use log::{debug, trace};
 use unicode_width::UnicodeWidthChar;
 
 use crate::event::{Event, EventListener};
-use crate::grid::{Dimensions, Grid, GridIterator, Scroll};
+use crate::grid::{Dimensions, Grid, GridIterator, Scroll, SpillConfig};
 use crate::index::{self, Boundary, Column, Direction, Line, Point, Side};
 use crate::selection::{Selection, SelectionRange, SelectionType};
 use crate::term::cell::{Cell, Flags, LineLength};
 use crate::term::color::Colors;
-use crate::vi_mode::{ViModeCursor, ViMotion};
+use crate::vi_mode::{ViMarks, ViModeCursor, ViMotion};
 use crate::vte::ansi::{
     self, Attr, CharsetIndex, Color, CursorShape, CursorStyle, Handler, Hyperlink, KeyboardModes,
     KeyboardModesApplyBehavior, NamedColor, NamedMode, NamedPrivateMode, PrivateMode, Rgb,
@@ -31,7 +31,10 @@ use crate::graphics::{Graphics, UpdateQueues};
 
 pub mod cell;
 pub mod color;
+pub mod frame;
 pub mod search;
+pub mod snapshot;
+pub(crate) mod style;
 
 /// Minimum number of columns.
 ///
@@ -280,6 +283,12 @@ pub struct Term<T> {
     /// Cursor for keyboard selection.
     pub vi_mode_cursor: ViModeCursor,
 
+    /// Vi mode marks and jump list of the active grid.
+    pub vi_marks: ViMarks,
+
+    /// Vi mode marks and jump list of the inactive grid.
+    inactive_vi_marks: ViMarks,
+
     pub selection: Option<Selection>,
 
     /// Currently active grid.
@@ -336,6 +345,9 @@ pub struct Term<T> {
     /// Information about damaged cells.
     damage: TermDamageState,
 
+    /// Whether a character was printed since the flag was last taken.
+    printed: bool,
+
     /// Config directly for the terminal.
     config: Config,
 }
@@ -346,6 +358,11 @@ pub struct Config {
     /// The maximum amount of scrolling history.
     pub scrolling_history: usize,
 
+    /// Limits for moving old scrollback history out of memory.
+    ///
+    /// History is kept entirely in memory when this is `None`.
+    pub scrolling_spill: Option<SpillConfig>,
+
     /// Default cursor style to reset the cursor to.
     pub default_cursor_style: CursorStyle,
 
@@ -368,6 +385,7 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             scrolling_history: 10000,
+            scrolling_spill: None,
             semantic_escape_chars: SEMANTIC_ESCAPE_CHARS.to_owned(),
             default_cursor_style: Default::default(),
             vi_mode_cursor_style: Default::default(),
@@ -423,7 +441,8 @@ impl<T> Term<T> {
         let num_lines = dimensions.screen_lines();
 
         let history_size = options.scrolling_history;
-        let grid = Grid::new(num_lines, num_cols, history_size);
+        let mut grid = Grid::new(num_lines, num_cols, history_size);
+        grid.update_spill(options.scrolling_spill);
         let alt = Grid::new(num_lines, num_cols, 0);
 
         let tabs = TabStops::new(grid.columns());
@@ -438,6 +457,8 @@ impl<T> Term<T> {
             inactive_grid: alt,
             active_charset: Default::default(),
             vi_mode_cursor: Default::default(),
+            vi_marks: Default::default(),
+            inactive_vi_marks: Default::default(),
             tabs,
             mode: Default::default(),
             scroll_region,
@@ -452,6 +473,7 @@ impl<T> Term<T> {
             selection: None,
             graphics: Graphics::new(dimensions),
             damage,
+            printed: false,
             config: options,
         }
     }
@@ -528,8 +550,10 @@ impl<T> Term<T> {
 
         if self.mode.contains(TermMode::ALT_SCREEN) {
             self.inactive_grid.update_history(self.config.scrolling_history);
+            self.inactive_grid.update_spill(self.config.scrolling_spill);
         } else {
             self.grid.update_history(self.config.scrolling_history);
+            self.grid.update_spill(self.config.scrolling_spill);
         }
 
         if self.config.kitty_keyboard != old_config.kitty_keyboard {
@@ -594,7 +618,7 @@ impl<T> Term<T> {
     ) -> String {
         let mut text = String::new();
 
-        let grid_line = &self.grid[line];
+        let grid_line = self.grid.row_ref(line);
         let line_length = cmp::min(grid_line.line_length(), cols.end + 1);
 
         // Include wide char when trailing spacer is selected.
@@ -631,8 +655,7 @@ impl<T> Term<T> {
         }
 
         if cols.end >= self.columns() - 1
-            && (line_length.0 == 0
-                || !self.grid[line][line_length - 1].flags.contains(Flags::WRAPLINE))
+            && (line_length.0 == 0 || !grid_line[line_length - 1].flags.contains(Flags::WRAPLINE))
         {
             text.push('\n');
         }
@@ -643,7 +666,7 @@ impl<T> Term<T> {
             && grid_line[line_length - 1].flags.contains(Flags::LEADING_WIDE_CHAR_SPACER)
             && include_wrapped_wide
         {
-            text.push(self.grid[line - 1i32][Column(0)].c);
+            text.push(self.grid.row_ref(line - 1i32)[Column(0)].c);
         }
 
         text
@@ -712,6 +735,10 @@ impl<T> Term<T> {
             self.selection = selection.rotate(self, &range, -delta);
         }
 
+        // Move marks with the content, their columns are clamped once they're used.
+        let range = Line(0)..Line(cmp::max(num_lines, old_lines) as i32);
+        self.vi_marks.rotate(&self.grid, &range, -delta);
+
         // Clamp vi cursor to viewport.
         let vi_point = self.vi_mode_cursor.point;
         let viewport_top = Line(-(self.grid.display_offset() as i32));
@@ -736,6 +763,12 @@ impl<T> Term<T> {
         &self.mode
     }
 
+    /// Current window title.
+    #[inline]
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
     /// Swap primary and alternate screen buffer.
     pub fn swap_alt(&mut self) {
         if !self.mode.contains(TermMode::ALT_SCREEN) {
@@ -747,6 +780,7 @@ impl<T> Term<T> {
 
             // Reset alternate screen contents.
             self.inactive_grid.reset_region(..);
+            self.inactive_vi_marks = Default::default();
         }
 
         mem::swap(&mut self.keyboard_mode_stack, &mut self.inactive_keyboard_mode_stack);
@@ -755,11 +789,39 @@ impl<T> Term<T> {
         self.set_keyboard_mode(keyboard_mode, KeyboardModesApplyBehavior::Replace);
 
         mem::swap(&mut self.grid, &mut self.inactive_grid);
+        mem::swap(&mut self.vi_marks, &mut self.inactive_vi_marks);
         self.mode ^= TermMode::ALT_SCREEN;
         self.selection = None;
         self.mark_fully_damaged();
     }
 
+    /// Reset all terminal modes without clearing any content.
+    ///
+    /// This leaves the alternate screen, so a new process can start on the primary screen with
+    /// its scrollback intact.
+    pub fn reset_modes(&mut self)
+    where
+        T: EventListener,
+    {
+        if self.mode.contains(TermMode::ALT_SCREEN) {
+            self.swap_alt();
+        }
+
+        self.active_charset = Default::default();
+        self.cursor_style = None;
+        self.grid.cursor.template = Default::default();
+        self.scroll_region = Line(0)..Line(self.screen_lines() as i32);
+        self.keyboard_mode_stack = Default::default();
+        self.inactive_keyboard_mode_stack = Default::default();
+
+        // Preserve vi mode across resets.
+        self.mode &= TermMode::VI;
+        self.mode.insert(TermMode::default());
+
+        self.event_proxy.send_event(Event::CursorBlinkingChange);
+        self.mark_fully_damaged();
+    }
+
     /// Scroll screen down.
     ///
     /// Text moves down; clear at bottom
@@ -783,6 +845,8 @@ impl<T> Term<T> {
             *line = cmp::min(*line + lines, region.end - 1);
         }
 
+        self.vi_marks.rotate(&self.grid, &region, -(lines as i32));
+
         // Scroll between origin and bottom
         self.grid.scroll_down(&region, lines);
         self.mark_fully_damaged();
@@ -805,6 +869,9 @@ impl<T> Term<T> {
 
         self.grid.scroll_up(&region, lines);
 
+        // Scroll marks after the grid, to remove marks which were rotated out of the history.
+        self.vi_marks.rotate(&self.grid, &region, lines as i32);
+
         // Scroll vi mode cursor.
         let viewport_top = Line(-(self.grid.display_offset() as i32));
         let top = if region.start == 0 { viewport_top } else { region.start };
@@ -872,8 +939,48 @@ impl<T> Term<T> {
         }
 
         // Move cursor.
+        let origin = self.vi_mode_cursor.point;
         self.vi_mode_cursor = self.vi_mode_cursor.motion(self, motion);
         self.vi_mode_recompute_selection();
+
+        if motion.is_jump() && self.vi_mode_cursor.point != origin {
+            self.vi_marks.push_jump(origin);
+        }
+    }
+
+    /// Move vi cursor to a point in the grid, remembering its origin in the jump list.
+    #[inline]
+    pub fn vi_jump(&mut self, point: Point)
+    where
+        T: EventListener,
+    {
+        if point != self.vi_mode_cursor.point {
+            self.vi_marks.push_jump(self.vi_mode_cursor.point);
+        }
+
+        self.vi_goto_point(point);
+    }
+
+    /// Move vi cursor to the origin of the previous jump.
+    #[inline]
+    pub fn vi_jump_backward(&mut self)
+    where
+        T: EventListener,
+    {
+        if let Some(point) = self.vi_marks.jump_backward(self.vi_mode_cursor.point) {
+            self.vi_goto_point(point.grid_clamp(self, Boundary::Grid));
+        }
+    }
+
+    /// Move vi cursor to the position before jumping backward.
+    #[inline]
+    pub fn vi_jump_forward(&mut self)
+    where
+        T: EventListener,
+    {
+        if let Some(point) = self.vi_marks.jump_forward(self.vi_mode_cursor.point) {
+            self.vi_goto_point(point.grid_clamp(self, Boundary::Grid));
+        }
     }
 
     /// Move vi cursor to a point in the grid.
@@ -925,7 +1032,7 @@ impl<T> Term<T> {
 
     /// Jump to the end of a wide cell.
     pub fn expand_wide(&self, mut point: Point, direction: Direction) -> Point {
-        let flags = self.grid[point.line][point.column].flags;
+        let flags = self.grid.row_ref(point.line)[point.column].flags;
 
         match direction {
             Direction::Right if flags.contains(Flags::LEADING_WIDE_CHAR_SPACER) => {
@@ -941,7 +1048,10 @@ impl<T> Term<T> {
                 }
 
                 let prev = point.sub(self, Boundary::Grid, 1);
-                if self.grid[prev].flags.contains(Flags::LEADING_WIDE_CHAR_SPACER) {
+                if self.grid.row_ref(prev.line)[prev.column]
+                    .flags
+                    .contains(Flags::LEADING_WIDE_CHAR_SPACER)
+                {
                     point = prev;
                 }
             },
@@ -1000,14 +1110,91 @@ impl<T> Term<T> {
         self.damage_cursor();
     }
 
+    /// Check if a character was printed since the last call, resetting the flag.
+    ///
+    /// When this is taken after every byte passed to the parser, a printed character means that
+    /// the parser returned to its ground state, unless the byte replayed a synchronized update.
+    #[inline]
+    pub(crate) fn take_printed(&mut self) -> bool {
+        mem::take(&mut self.printed)
+    }
+
+    /// Write a run of printable ASCII characters at the cursor position.
+    ///
+    /// This is equivalent to calling [`Handler::input`] for every character, but fills the cursor
+    /// row with as much of the run as fits at once. Insert mode and charsets other than ASCII
+    /// fall back to writing one character at a time.
+    pub fn input_ascii(&mut self, mut run: &[u8])
+    where
+        T: EventListener,
+    {
+        debug_assert!(run.iter().all(|byte| (b' '..=b'~').contains(byte)));
+
+        if self.mode.contains(TermMode::INSERT)
+            || self.grid.cursor.charsets[self.active_charset] != StandardCharset::Ascii
+        {
+            for byte in run {
+                self.input(*byte as char);
+            }
+            return;
+        }
+
+        while let Some(&last) = run.last() {
+            if self.grid.cursor.input_needs_wrap {
+                self.wrapline();
+            }
+
+            // Without line wrapping, every character overwrites the last column.
+            if self.grid.cursor.input_needs_wrap {
+                self.write_at_cursor(last as char);
+                return;
+            }
+
+            let columns = self.columns();
+            let point = self.grid.cursor.point;
+            let len = run.len().min(columns - point.column.0);
+            let flags = self.grid.cursor.template.flags;
+            let style = self.grid.cursor.template.style.clone();
+
+            for (i, byte) in run[..len].iter().enumerate() {
+                let column = point.column + i;
+                let cell = &mut self.grid[point.line][column];
+
+                // Clear overwritten fullwidth characters like a regular write would.
+                if cell.flags.intersects(Flags::WIDE_CHAR | Flags::WIDE_CHAR_SPACER) {
+                    self.grid.cursor.point.column = column;
+                    self.write_at_cursor(*byte as char);
+                } else {
+                    cell.c = *byte as char;
+                    cell.flags = flags;
+                    cell.style = style.clone();
+                }
+            }
+
+            let end = point.column.0 + len;
+            if end < columns {
+                self.grid.cursor.point.column = Column(end);
+            } else {
+                self.grid.cursor.point.column = Column(columns - 1);
+                self.grid.cursor.input_needs_wrap = true;
+            }
+
+            run = &run[len..];
+        }
+    }
+
+    /// Share the cursor's style with all cells of the grid using the same attributes.
+    fn intern_template_style(&mut self) {
+        let style = mem::take(&mut self.grid.cursor.template.style);
+        self.grid.cursor.template.style = self.grid.style_table().intern(style);
+    }
+
     /// Write `c` to the cell at the cursor position.
     #[inline(always)]
     fn write_at_cursor(&mut self, c: char) {
         let c = self.grid.cursor.charsets[self.active_charset].map(c);
-        let fg = self.grid.cursor.template.fg;
-        let bg = self.grid.cursor.template.bg;
         let flags = self.grid.cursor.template.flags;
-        let extra = self.grid.cursor.template.extra.clone();
+        let style = self.grid.cursor.template.style.clone();
 
         let mut cursor_cell = self.grid.cursor_cell();
 
@@ -1032,10 +1219,8 @@ impl<T> Term<T> {
         }
 
         cursor_cell.c = c;
-        cursor_cell.fg = fg;
-        cursor_cell.bg = bg;
         cursor_cell.flags = flags;
-        cursor_cell.extra = extra;
+        cursor_cell.style = style;
     }
 
     #[inline]
@@ -1081,6 +1266,8 @@ impl<T: EventListener> Handler for Term<T> {
     /// A character to be displayed.
     #[inline(never)]
     fn input(&mut self, c: char) {
+        self.printed = true;
+
         // Number of cells the char will occupy.
         let width = match c.width() {
             Some(width) => width,
@@ -1207,7 +1394,7 @@ impl<T: EventListener> Handler for Term<T> {
     #[inline]
     fn insert_blank(&mut self, count: usize) {
         let cursor = &self.grid.cursor;
-        let bg = cursor.template.bg;
+        let blank = cursor.template.blank();
 
         // Ensure inserting within terminal bounds
         let count = cmp::min(count, self.columns() - cursor.point.column.0);
@@ -1228,7 +1415,7 @@ impl<T: EventListener> Handler for Term<T> {
         // Cells were just moved out toward the end of the line;
         // fill in between source and dest with blanks.
         for cell in &mut row[source.0..destination] {
-            *cell = bg.into();
+            *cell = blank.clone();
         }
     }
 
@@ -1546,12 +1733,12 @@ impl<T: EventListener> Handler for Term<T> {
         let end = cmp::min(start + count, Column(self.columns()));
 
         // Cleared cells have current background color set.
-        let bg = self.grid.cursor.template.bg;
+        let blank = self.grid.cursor.template.blank();
         let line = cursor.point.line;
         self.damage.damage_line(line.0 as usize, start.0, end.0);
         let row = &mut self.grid[line];
         for cell in &mut row[start..end] {
-            *cell = bg.into();
+            *cell = blank.clone();
         }
     }
 
@@ -1559,7 +1746,7 @@ impl<T: EventListener> Handler for Term<T> {
     fn delete_chars(&mut self, count: usize) {
         let columns = self.columns();
         let cursor = &self.grid.cursor;
-        let bg = cursor.template.bg;
+        let blank = cursor.template.blank();
 
         // Ensure deleting within terminal bounds.
         let count = cmp::min(count, columns);
@@ -1580,7 +1767,7 @@ impl<T: EventListener> Handler for Term<T> {
         // 1 cell.
         let end = columns - count;
         for cell in &mut row[end..] {
-            *cell = bg.into();
+            *cell = blank.clone();
         }
     }
 
@@ -1631,7 +1818,7 @@ impl<T: EventListener> Handler for Term<T> {
         trace!("Clearing line: {:?}", mode);
 
         let cursor = &self.grid.cursor;
-        let bg = cursor.template.bg;
+        let blank = cursor.template.blank();
         let point = cursor.point;
 
         let (left, right) = match mode {
@@ -1645,7 +1832,7 @@ impl<T: EventListener> Handler for Term<T> {
 
         let row = &mut self.grid[point.line];
         for cell in &mut row[left..right] {
-            *cell = bg.into();
+            *cell = blank.clone();
         }
 
         let range = self.grid.cursor.point.line..=self.grid.cursor.point.line;
@@ -1744,7 +1931,7 @@ impl<T: EventListener> Handler for Term<T> {
     #[inline]
     fn clear_screen(&mut self, mode: ansi::ClearMode) {
         trace!("Clearing screen: {:?}", mode);
-        let bg = self.grid.cursor.template.bg;
+        let blank = self.grid.cursor.template.blank();
 
         let screen_lines = self.screen_lines();
 
@@ -1761,7 +1948,7 @@ impl<T: EventListener> Handler for Term<T> {
                 // Clear up to the current column in the current line.
                 let end = cmp::min(cursor.column + 1, Column(self.columns()));
                 for cell in &mut self.grid[cursor.line][..end] {
-                    *cell = bg.into();
+                    *cell = blank.clone();
                 }
 
                 let range = Line(0)..=cursor.line;
@@ -1770,7 +1957,7 @@ impl<T: EventListener> Handler for Term<T> {
             ansi::ClearMode::Below => {
                 let cursor = self.grid.cursor.point;
                 for cell in &mut self.grid[cursor.line][cursor.column..] {
-                    *cell = bg.into();
+                    *cell = blank.clone();
                 }
 
                 if (cursor.line.0 as usize) < screen_lines - 1 {
@@ -1793,6 +1980,9 @@ impl<T: EventListener> Handler for Term<T> {
 
                     self.vi_mode_cursor.point.line =
                         (self.vi_mode_cursor.point.line - lines).grid_clamp(self, Boundary::Grid);
+
+                    let range = Line(0)..Line(screen_lines as i32);
+                    self.vi_marks.rotate(&self.grid, &range, lines as i32);
                 }
 
                 self.selection = None;
@@ -1804,6 +1994,7 @@ impl<T: EventListener> Handler for Term<T> {
                     self.vi_mode_cursor.point.line.grid_clamp(self, Boundary::Cursor);
 
                 self.selection = self.selection.take().filter(|s| !s.intersects_range(..Line(0)));
+                self.vi_marks.update(|point| (point.line >= 0).then_some(point));
             },
             // We have no history to clear.
             ansi::ClearMode::Saved => (),
@@ -1841,6 +2032,8 @@ impl<T: EventListener> Handler for Term<T> {
         self.title = None;
         self.selection = None;
         self.vi_mode_cursor = Default::default();
+        self.vi_marks = Default::default();
+        self.inactive_vi_marks = Default::default();
         self.keyboard_mode_stack = Default::default();
         self.inactive_keyboard_mode_stack = Default::default();
 
@@ -1869,6 +2062,7 @@ impl<T: EventListener> Handler for Term<T> {
     fn set_hyperlink(&mut self, hyperlink: Option<Hyperlink>) {
         trace!("Setting hyperlink: {:?}", hyperlink);
         self.grid.cursor.template.set_hyperlink(hyperlink.map(|e| e.into()));
+        self.intern_template_style();
     }
 
     /// Set a terminal attribute.
@@ -1877,12 +2071,12 @@ impl<T: EventListener> Handler for Term<T> {
         trace!("Setting attribute: {:?}", attr);
         let cursor = &mut self.grid.cursor;
         match attr {
-            Attr::Foreground(color) => cursor.template.fg = color,
-            Attr::Background(color) => cursor.template.bg = color,
+            Attr::Foreground(color) => cursor.template.set_fg(color),
+            Attr::Background(color) => cursor.template.set_bg(color),
             Attr::UnderlineColor(color) => cursor.template.set_underline_color(color),
             Attr::Reset => {
-                cursor.template.fg = Color::Named(NamedColor::Foreground);
-                cursor.template.bg = Color::Named(NamedColor::Background);
+                cursor.template.set_fg(Color::Named(NamedColor::Foreground));
+                cursor.template.set_bg(Color::Named(NamedColor::Background));
                 cursor.template.flags = Flags::empty();
                 cursor.template.set_underline_color(None);
             },
@@ -1923,6 +2117,8 @@ impl<T: EventListener> Handler for Term<T> {
                 debug!("Term got unhandled attr: {:?}", attr);
             },
         }
+
+        self.intern_template_style();
     }
 
     #[inline]
@@ -2834,6 +3030,49 @@ mod tests {
         assert_eq!(term.grid()[cursor].c, '▒');
     }
 
+    #[test]
+    fn input_ascii_matches_input() {
+        let setups: [fn(&mut Term<VoidListener>); 5] = [
+            |_| (),
+            |term| {
+                for _ in 0..3 {
+                    term.input('汉');
+                }
+                term.goto(0, 1);
+            },
+            |term| term.unset_private_mode(ansi::NamedPrivateMode::LineWrap.into()),
+            |term| {
+                term.input('x');
+                term.carriage_return();
+                term.set_mode(ansi::NamedMode::Insert.into());
+            },
+            |term| {
+                term.configure_charset(
+                    CharsetIndex::G0,
+                    StandardCharset::SpecialCharacterAndLineDrawing,
+                );
+            },
+        ];
+
+        for setup in setups {
+            let size = TermSize::new(5, 3);
+            let mut expected = Term::new(Config::default(), &size, VoidListener);
+            let mut term = Term::new(Config::default(), &size, VoidListener);
+            setup(&mut expected);
+            setup(&mut term);
+
+            let run = b"hello world";
+            for byte in run {
+                expected.input(*byte as char);
+            }
+            term.input_ascii(run);
+
+            assert_eq!(term.grid, expected.grid);
+            assert_eq!(term.grid.cursor.point, expected.grid.cursor.point);
+            assert_eq!(term.grid.cursor.input_needs_wrap, expected.grid.cursor.input_needs_wrap);
+        }
+    }
+
     #[test]
     fn clearing_viewport_keeps_history_position() {
         let size = TermSize::new(10, 20);
@@ -2970,6 +3209,47 @@ mod tests {
         assert_eq!(term.vi_mode_cursor.point.line, Line(-12));
     }
 
+    #[test]
+    fn vi_marks_follow_content() {
+        let size = TermSize::new(5, 10);
+        let config = Config { scrolling_history: 5, ..Config::default() };
+        let mut term = Term::new(config, &size, VoidListener);
+
+        for _ in 0..9 {
+            term.newline();
+        }
+
+        term.vi_marks.set('a', Point::new(Line(2), Column(3)));
+        term.vi_marks.set('b', Point::new(Line(8), Column(0)));
+        term.vi_marks.push_jump(Point::new(Line(8), Column(0)));
+
+        // Marks move into the history with their content.
+        term.linefeed();
+        term.linefeed();
+        assert_eq!(term.vi_marks.get('a'), Some(Point::new(Line(0), Column(3))));
+        assert_eq!(term.vi_marks.get('b'), Some(Point::new(Line(6), Column(0))));
+
+        term.linefeed();
+        term.linefeed();
+        term.linefeed();
+        assert_eq!(term.vi_marks.get('a'), Some(Point::new(Line(-3), Column(3))));
+
+        // Marks are removed once their content is rotated out of the history.
+        for _ in 0..5 {
+            term.linefeed();
+        }
+        assert_eq!(term.vi_marks.get('a'), None);
+        assert_eq!(term.vi_marks.get('b'), Some(Point::new(Line(-2), Column(0))));
+        assert_eq!(
+            term.vi_marks.jump_backward(Point::default()),
+            Some(Point::new(Line(-2), Column(0)))
+        );
+
+        // Clearing the history removes its marks.
+        term.clear_screen(ansi::ClearMode::Saved);
+        assert_eq!(term.vi_marks.get('b'), None);
+    }
+
     #[test]
     fn grow_lines_updates_active_cursor_pos() {
         let mut size = TermSize::new(100, 10);
@@ -3320,6 +3600,25 @@ mod tests {
         assert!(term.damage.full);
     }
 
+    #[test]
+    fn reset_modes_keeps_content() {
+        let size = TermSize::new(5, 3);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+
+        // Write to the primary screen, then switch to the alternate screen.
+        term.input('a');
+        term.set_private_mode(NamedPrivateMode::SwapScreenAndSetRestoreCursor.into());
+        term.set_private_mode(NamedPrivateMode::ReportMouseClicks.into());
+        term.set_scrolling_region(1, Some(2));
+        assert!(term.mode.contains(TermMode::ALT_SCREEN | TermMode::MOUSE_REPORT_CLICK));
+
+        term.reset_modes();
+
+        assert_eq!(term.mode, TermMode::default());
+        assert_eq!(term.scroll_region, Line(0)..Line(3));
+        assert_eq!(term.grid[Line(0)][Column(0)].c, 'a');
+    }
+
     #[test]
     fn window_title() {
         let size = TermSize::new(7, 17);
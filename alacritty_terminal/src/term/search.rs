@@ -1,7 +1,8 @@
-use std::cmp::max;
+use std::cmp::{self, max};
 use std::error::Error;
 use std::mem;
 use std::ops::RangeInclusive;
+use std::time::Instant;
 
 use log::{debug, warn};
 use regex_automata::hybrid::dfa::{Builder, Cache, Config, DFA};
@@ -10,8 +11,8 @@ use regex_automata::nfa::thompson::Config as ThompsonConfig;
 use regex_automata::util::syntax::Config as SyntaxConfig;
 use regex_automata::{Anchored, Input, MatchKind};
 
-use crate::grid::{BidirectionalIterator, Dimensions, GridIterator, Indexed};
-use crate::index::{Boundary, Column, Direction, Point, Side};
+use crate::grid::{BidirectionalIterator, Dimensions, Grid, RowRef};
+use crate::index::{Boundary, Column, Direction, Line, Point, Side};
 use crate::term::cell::{Cell, Flags};
 use crate::term::Term;
 
@@ -20,6 +21,39 @@ const BRACKET_PAIRS: [(char, char); 4] = [('(', ')'), ('[', ']'), ('{', '}'), ('
 
 pub type Match = RangeInclusive<Point>;
 
+/// Characters which need to be escaped to be matched literally.
+const REGEX_META_CHARACTERS: &str = "\\.+*?()|[]{}^$#&-~";
+
+/// ASCII word boundary, which is supported by the lazy DFAs unlike Unicode word boundaries.
+const WORD_BOUNDARY: &str = "(?-u:\\b)";
+
+/// Case sensitivity of a search.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub enum SearchCase {
+    /// Ignore case unless the search contains uppercase characters.
+    #[default]
+    Smart,
+
+    /// Always match case.
+    Sensitive,
+
+    /// Never match case.
+    Insensitive,
+}
+
+/// Modifiers changing how the search text is matched.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// Match the search text literally, instead of interpreting it as a regex.
+    pub literal: bool,
+
+    /// Case sensitivity of the search.
+    pub case: SearchCase,
+
+    /// Only match at ASCII word boundaries.
+    pub whole_word: bool,
+}
+
 /// Terminal regex search state.
 #[derive(Clone, Debug)]
 pub struct RegexSearch {
@@ -32,12 +66,31 @@ pub struct RegexSearch {
 impl RegexSearch {
     /// Build the forward and backward search DFAs.
     pub fn new(search: &str) -> Result<RegexSearch, Box<BuildError>> {
+        Self::with_options(search, SearchOptions::default())
+    }
+
+    /// Build the forward and backward search DFAs with custom search modifiers.
+    pub fn with_options(
+        search: &str,
+        options: SearchOptions,
+    ) -> Result<RegexSearch, Box<BuildError>> {
+        let case_insensitive = match options.case {
+            SearchCase::Smart => !search.chars().any(|c| c.is_uppercase()),
+            SearchCase::Sensitive => false,
+            SearchCase::Insensitive => true,
+        };
+
+        let mut pattern = if options.literal { escape(search) } else { search.to_owned() };
+        if options.whole_word {
+            pattern = format!("{}(?:{}){}", WORD_BOUNDARY, pattern, WORD_BOUNDARY);
+        }
+        let search = pattern.as_str();
+
         // Setup configs for both DFA directions.
         //
         // Bounds are based on Regex's meta engine:
         // https://github.com/rust-lang/regex/blob/061ee815ef2c44101dba7b0b124600fcb03c1912/regex-automata/src/meta/wrappers.rs#L581-L599
-        let has_uppercase = search.chars().any(|c| c.is_uppercase());
-        let syntax_config = SyntaxConfig::new().case_insensitive(!has_uppercase);
+        let syntax_config = SyntaxConfig::new().case_insensitive(case_insensitive);
         let config =
             Config::new().minimum_cache_clear_count(Some(3)).minimum_bytes_per_state(Some(10));
         let max_size = config.get_cache_capacity();
@@ -78,6 +131,36 @@ impl RegexSearch {
     }
 }
 
+/// Regex matching a word literally.
+///
+/// Like with vi's `*`, the word is bounded by ASCII word boundaries where it starts or ends with an
+/// ASCII word character.
+pub fn word_regex(word: &str) -> String {
+    let is_word_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+
+    let mut regex = escape(word);
+    if word.starts_with(is_word_char) {
+        regex.insert_str(0, WORD_BOUNDARY);
+    }
+    if word.ends_with(is_word_char) {
+        regex.push_str(WORD_BOUNDARY);
+    }
+
+    regex
+}
+
+/// Escape all regex meta characters in a string.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if REGEX_META_CHARACTERS.contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
 /// Runtime-evaluated DFA.
 #[derive(Clone, Debug)]
 struct LazyDfa {
@@ -283,8 +366,8 @@ impl<T> Term<T> {
 
         // Advance the iterator.
         let next = match regex.direction {
-            Direction::Right => GridIterator::next,
-            Direction::Left => GridIterator::prev,
+            Direction::Right => SearchIter::next,
+            Direction::Left => SearchIter::prev,
         };
 
         // Get start state for the DFA.
@@ -292,14 +375,12 @@ impl<T> Term<T> {
         let input = Input::new(&[]).anchored(regex_anchored);
         let mut state = regex.dfa.start_state_forward(&mut regex.cache, &input).unwrap();
 
-        let mut iter = self.grid.iter_from(start);
+        let mut iter = SearchIter::new(&self.grid, start);
         let mut last_wrapped = false;
         let mut regex_match = None;
         let mut done = false;
 
-        let mut cell = iter.cell();
-        self.skip_fullwidth(&mut iter, &mut cell, regex.direction);
-        let mut c = cell.c;
+        let (mut c, _) = self.skip_fullwidth(&mut iter, regex.direction);
 
         let mut point = iter.point();
         let mut last_point = point;
@@ -385,24 +466,20 @@ impl<T> Term<T> {
             }
 
             // Advance grid cell iterator.
-            let mut cell = match next(&mut iter) {
-                Some(Indexed { cell, .. }) => cell,
-                None => {
-                    // Wrap around to other end of the scrollback buffer.
-                    let line = topmost_line - point.line + screen_lines - 1;
-                    let start = Point::new(line, last_column - point.column);
-                    iter = self.grid.iter_from(start);
-                    iter.cell()
-                },
-            };
+            if !next(&mut iter) {
+                // Wrap around to other end of the scrollback buffer.
+                let line = topmost_line - point.line + screen_lines - 1;
+                let start = Point::new(line, last_column - point.column);
+                iter = SearchIter::new(&self.grid, start);
+            }
 
             // Check for completion before potentially skipping over fullwidth characters.
             done = iter.point() == end;
 
-            self.skip_fullwidth(&mut iter, &mut cell, regex.direction);
+            let (cell_c, flags) = self.skip_fullwidth(&mut iter, regex.direction);
 
-            let wrapped = cell.flags.contains(Flags::WRAPLINE);
-            c = cell.c;
+            let wrapped = flags.contains(Flags::WRAPLINE);
+            c = cell_c;
 
             last_point = mem::replace(&mut point, iter.point());
 
@@ -432,40 +509,45 @@ impl<T> Term<T> {
         Ok(regex_match)
     }
 
-    /// Advance a grid iterator over fullwidth characters.
-    fn skip_fullwidth<'a>(
-        &self,
-        iter: &'a mut GridIterator<'_, Cell>,
-        cell: &mut &'a Cell,
-        direction: Direction,
-    ) {
+    /// Advance a search iterator over fullwidth characters.
+    ///
+    /// Returns the character and flags of the cell which should be passed to the regex.
+    fn skip_fullwidth(&self, iter: &mut SearchIter<'_>, direction: Direction) -> (char, Flags) {
+        let cell = iter.cell();
+        let (mut c, flags) = (cell.c, cell.flags);
+        let mut cell_flags = flags;
+
         match direction {
             // In the alternate screen buffer there might not be a wide char spacer after a wide
             // char, so we only advance the iterator when the wide char is not in the last column.
             Direction::Right
-                if cell.flags.contains(Flags::WIDE_CHAR)
-                    && iter.point().column < self.last_column() =>
+                if flags.contains(Flags::WIDE_CHAR) && iter.point().column < self.last_column() =>
             {
                 iter.next();
             },
-            Direction::Right if cell.flags.contains(Flags::LEADING_WIDE_CHAR_SPACER) => {
-                if let Some(Indexed { cell: new_cell, .. }) = iter.next() {
-                    *cell = new_cell;
+            Direction::Right if flags.contains(Flags::LEADING_WIDE_CHAR_SPACER) => {
+                if iter.next() {
+                    let cell = iter.cell();
+                    (c, cell_flags) = (cell.c, cell.flags);
                 }
                 iter.next();
             },
-            Direction::Left if cell.flags.contains(Flags::WIDE_CHAR_SPACER) => {
-                if let Some(Indexed { cell: new_cell, .. }) = iter.prev() {
-                    *cell = new_cell;
+            Direction::Left if flags.contains(Flags::WIDE_CHAR_SPACER) => {
+                if iter.prev() {
+                    let cell = iter.cell();
+                    (c, cell_flags) = (cell.c, cell.flags);
                 }
 
                 let prev = iter.point().sub(self, Boundary::Grid, 1);
-                if self.grid[prev].flags.contains(Flags::LEADING_WIDE_CHAR_SPACER) {
+                let prev_flags = self.grid.row_ref(prev.line)[prev.column].flags;
+                if prev_flags.contains(Flags::LEADING_WIDE_CHAR_SPACER) {
                     iter.prev();
                 }
             },
             _ => (),
         }
+
+        (c, cell_flags)
     }
 
     /// Find next matching bracket.
@@ -585,7 +667,9 @@ impl<T> Term<T> {
     /// Find the beginning of the current line across linewraps.
     pub fn line_search_left(&self, mut point: Point) -> Point {
         while point.line > self.topmost_line()
-            && self.grid[point.line - 1i32][self.last_column()].flags.contains(Flags::WRAPLINE)
+            && self.grid.row_ref(point.line - 1i32)[self.last_column()]
+                .flags
+                .contains(Flags::WRAPLINE)
         {
             point.line -= 1;
         }
@@ -598,7 +682,7 @@ impl<T> Term<T> {
     /// Find the end of the current line across linewraps.
     pub fn line_search_right(&self, mut point: Point) -> Point {
         while point.line + 1 < self.screen_lines()
-            && self.grid[point.line][self.last_column()].flags.contains(Flags::WRAPLINE)
+            && self.grid.row_ref(point.line)[self.last_column()].flags.contains(Flags::WRAPLINE)
         {
             point.line += 1;
         }
@@ -609,6 +693,68 @@ impl<T> Term<T> {
     }
 }
 
+/// Bidirectional cell cursor used for regex searches.
+///
+/// Searches can walk through the entire scrollback history, so unlike
+/// [`GridIterator`](crate::grid::GridIterator) this only keeps the current line alive instead of
+/// caching every history block it reads.
+struct SearchIter<'a> {
+    grid: &'a Grid<Cell>,
+    point: Point,
+    row: RowRef<'a, Cell>,
+}
+
+impl<'a> SearchIter<'a> {
+    fn new(grid: &'a Grid<Cell>, point: Point) -> Self {
+        Self { grid, point, row: grid.row_ref(point.line) }
+    }
+
+    /// Current iterator position.
+    fn point(&self) -> Point {
+        self.point
+    }
+
+    /// Cell at the current iterator position.
+    fn cell(&self) -> &Cell {
+        &self.row[self.point.column]
+    }
+
+    /// Move to the next cell, returning `false` at the end of the grid.
+    fn next(&mut self) -> bool {
+        let last_column = self.grid.last_column();
+        if self.point >= Point::new(self.grid.bottommost_line(), last_column) {
+            return false;
+        }
+
+        if self.point.column == last_column {
+            self.point.column = Column(0);
+            self.point.line += 1;
+            self.row = self.grid.row_ref(self.point.line);
+        } else {
+            self.point.column += 1;
+        }
+
+        true
+    }
+
+    /// Move to the previous cell, returning `false` at the start of the grid.
+    fn prev(&mut self) -> bool {
+        if self.point == Point::new(self.grid.topmost_line(), Column(0)) {
+            return false;
+        }
+
+        if self.point.column == Column(0) {
+            self.point.column = self.grid.last_column();
+            self.point.line -= 1;
+            self.row = self.grid.row_ref(self.point.line);
+        } else {
+            self.point.column -= 1;
+        }
+
+        true
+    }
+}
+
 /// Iterator over regex matches.
 pub struct RegexIter<'a, T> {
     point: Point,
@@ -677,13 +823,146 @@ impl<'a, T> Iterator for RegexIter<'a, T> {
     }
 }
 
+/// Number of lines searched by [`MatchCounter`] before checking its deadline.
+const COUNT_CHUNK_LINES: usize = 1000;
+
+/// Incremental counter for all regex matches in the terminal.
+///
+/// Counting matches across the entire scrollback history can take a long time, so the work is
+/// split into chunks of lines which are searched until a deadline is reached.
+#[derive(Clone, Debug)]
+pub struct MatchCounter {
+    /// Start points of all matches found so far, from top to bottom.
+    starts: Vec<Point>,
+
+    /// Start of the next chunk which has not been searched yet.
+    next: Option<Point>,
+
+    /// Total lines of the terminal when matches were last updated.
+    total_lines: usize,
+
+    /// Terminal dimensions when counting started.
+    columns: usize,
+    screen_lines: usize,
+
+    /// Lines scrolled into the history when matches were last updated.
+    history_rotations: usize,
+}
+
+impl MatchCounter {
+    /// Start counting the matches in a terminal.
+    pub fn new<T>(term: &Term<T>) -> Self {
+        let start = Point::new(term.topmost_line(), Column(0));
+        Self {
+            starts: Vec::new(),
+            next: Some(start),
+            total_lines: term.total_lines(),
+            columns: term.columns(),
+            screen_lines: term.screen_lines(),
+            history_rotations: term.grid().history_rotations(),
+        }
+    }
+
+    /// Continue counting matches until all lines are searched or the deadline is reached.
+    ///
+    /// Returns `true` once all matches have been counted.
+    pub fn count<T>(&mut self, term: &Term<T>, regex: &mut RegexSearch, deadline: Instant) -> bool {
+        self.update(term);
+
+        while let Some(start) = self.next {
+            // Never split a chunk inside of a wrapped line, since matches could span across it.
+            let last_line = cmp::min(start.line + (COUNT_CHUNK_LINES - 1), term.bottommost_line());
+            let end = term.line_search_right(Point::new(last_line, Column(0)));
+
+            let matches = RegexIter::new(start, end, Direction::Right, term, regex);
+            self.starts.extend(matches.map(|regex_match| *regex_match.start()));
+
+            self.next =
+                (end.line < term.bottommost_line()).then(|| Point::new(end.line + 1, Column(0)));
+
+            if Instant::now() >= deadline {
+                break;
+            }
+        }
+
+        self.is_done()
+    }
+
+    /// Move the counted matches along with the lines scrolled into the history.
+    ///
+    /// The screen is searched again after scrolling, since new lines were scrolled into it.
+    /// Counting is restarted when the terminal was resized or its history was cleared.
+    pub fn update<T>(&mut self, term: &Term<T>) {
+        let rotated = term.grid().history_rotations().wrapping_sub(self.history_rotations);
+        let total_lines = term.total_lines();
+
+        // The history only grows by the lines scrolled into it, unless it was cleared.
+        let resized = term.columns() != self.columns || term.screen_lines() != self.screen_lines;
+        let cleared = total_lines < self.total_lines
+            || total_lines - self.total_lines > rotated
+            || rotated >= total_lines;
+        if resized || cleared {
+            *self = Self::new(term);
+            return;
+        } else if rotated == 0 {
+            return;
+        }
+
+        self.history_rotations = term.grid().history_rotations();
+        self.total_lines = total_lines;
+
+        // Drop matches which left the history and the ones which need to be searched again.
+        let topmost_line = term.topmost_line();
+        let screen_start = term.line_search_left(Point::new(Line(0), Column(0)));
+        self.starts.retain_mut(|start| {
+            start.line -= rotated;
+            start.line >= topmost_line && *start < screen_start
+        });
+
+        let next =
+            self.next.map(|next| Point::new(max(next.line - rotated, topmost_line), next.column));
+        self.next = Some(next.map_or(screen_start, |next| cmp::min(next, screen_start)));
+    }
+
+    /// Whether all lines have been searched.
+    #[inline]
+    pub fn is_done(&self) -> bool {
+        self.next.is_none()
+    }
+
+    /// Number of matches found so far.
+    #[inline]
+    pub fn total(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// Start points of all matches found so far, from top to bottom.
+    #[inline]
+    pub fn matches(&self) -> &[Point] {
+        &self.starts
+    }
+
+    /// Number of counted matches starting at or before a point.
+    pub fn preceding(&self, point: Point) -> usize {
+        self.starts.partition_point(|start| *start <= point)
+    }
+
+    /// One-based index of the match starting at a point, if it has been counted.
+    pub fn position(&self, start: Point) -> Option<usize> {
+        self.starts.binary_search(&start).ok().map(|index| index + 1)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::event::VoidListener;
+    use crate::grid::SpillConfig;
     use crate::index::{Column, Line};
     use crate::term::test::{mock_term, TermSize};
     use crate::term::Config;
+    use crate::vte::ansi::Handler;
 
     #[test]
     fn regex_right() {
@@ -1135,6 +1414,137 @@ mod tests {
         assert_eq!(term.regex_search_right(&mut regex, start, end), Some(start..=end));
     }
 
+    #[test]
+    fn match_counter() {
+        let term = mock_term(&"foo\r\n".repeat(1500));
+        let mut regex = RegexSearch::new("o+").unwrap();
+        let mut counter = MatchCounter::new(&term);
+
+        // Only a single chunk is searched once the deadline has passed.
+        assert!(!counter.count(&term, &mut regex, Instant::now()));
+        assert_eq!(counter.total(), COUNT_CHUNK_LINES);
+
+        assert!(counter.count(&term, &mut regex, Instant::now()));
+        assert_eq!(counter.total(), 1500);
+
+        assert_eq!(counter.position(Point::new(Line(1200), Column(1))), Some(1201));
+        assert_eq!(counter.position(Point::new(Line(1200), Column(2))), None);
+        assert_eq!(counter.preceding(Point::new(Line(1200), Column(2))), 1201);
+    }
+
+    #[test]
+    fn match_counter_scrolling() {
+        let size = TermSize::new(5, 3);
+        let config = Config { scrolling_history: 2, ..Default::default() };
+        let mut term = Term::new(config, &size, VoidListener);
+        let mut regex = RegexSearch::new("o").unwrap();
+
+        let write_lines = |term: &mut Term<VoidListener>, lines: &[&str]| {
+            for line in lines {
+                line.chars().for_each(|c| term.input(c));
+                term.carriage_return();
+                term.linefeed();
+            }
+        };
+
+        write_lines(&mut term, &["ab", "co"]);
+        let mut counter = MatchCounter::new(&term);
+        assert!(counter.count(&term, &mut regex, Instant::now()));
+        assert_eq!(counter.matches(), [Point::new(Line(1), Column(1))]);
+
+        // Matches move along with the lines scrolled into the history.
+        write_lines(&mut term, &["o", ""]);
+        assert!(counter.count(&term, &mut regex, Instant::now()));
+        let matches = [Point::new(Line(-1), Column(1)), Point::new(Line(0), Column(0))];
+        assert_eq!(counter.matches(), matches);
+
+        // Matches are dropped once they're rotated out of the history.
+        write_lines(&mut term, &["", ""]);
+        assert!(counter.count(&term, &mut regex, Instant::now()));
+        assert_eq!(counter.matches(), [Point::new(Line(-2), Column(0))]);
+
+        // Clearing the history restarts counting.
+        term.grid_mut().clear_history();
+        assert!(counter.count(&term, &mut regex, Instant::now()));
+        assert_eq!(counter.total(), 0);
+    }
+
+    #[test]
+    fn search_spilled_history() {
+        let size = TermSize::new(5, 3);
+        let spill = SpillConfig { memory_budget: 0, disk_budget: usize::MAX };
+        let config =
+            Config { scrolling_history: 5000, scrolling_spill: Some(spill), ..Default::default() };
+        let mut term = Term::new(config, &size, VoidListener);
+        for i in 0..5000 {
+            let c = if i == 0 { 'x' } else { 'o' };
+            term.input(c);
+            term.carriage_return();
+            term.linefeed();
+        }
+
+        // Search through the entire history without finding anything after the origin.
+        let mut regex = RegexSearch::new("x").unwrap();
+        let origin = Point::new(Line(2), Column(4));
+        let regex_match = term.search_next(&mut regex, origin, Direction::Left, Side::Left, None);
+        let point = Point::new(term.topmost_line(), Column(0));
+        assert_eq!(regex_match, Some(point..=point));
+        assert!(term.grid().resident_blocks() <= 1);
+
+        let mut regex = RegexSearch::new("o").unwrap();
+        let mut counter = MatchCounter::new(&term);
+        while !counter.count(&term, &mut regex, Instant::now()) {
+            assert!(term.grid().resident_blocks() <= 1);
+        }
+        assert_eq!(counter.total(), 4999);
+        assert!(term.grid().resident_blocks() <= 1);
+    }
+
+    #[test]
+    fn search_options() {
+        let term = mock_term("foo.bar( Foo.Bar( foobar");
+        let start = Point::new(Line(0), Column(0));
+        let end = Point::new(Line(0), Column(23));
+
+        // Literal search does not interpret regex syntax.
+        let options = SearchOptions { literal: true, ..Default::default() };
+        let mut regex = RegexSearch::with_options("foo.bar(", options).unwrap();
+        let match_end = Point::new(Line(0), Column(7));
+        assert_eq!(term.regex_search_right(&mut regex, start, end), Some(start..=match_end));
+        assert!(RegexSearch::new("foo.bar(").is_err());
+
+        // Case sensitive search skips the uppercase match.
+        let options = SearchOptions { case: SearchCase::Sensitive, ..Default::default() };
+        let mut regex = RegexSearch::with_options("foo", options).unwrap();
+        let origin = Point::new(Line(0), Column(1));
+        let match_start = Point::new(Line(0), Column(18));
+        let match_end = Point::new(Line(0), Column(20));
+        assert_eq!(term.regex_search_right(&mut regex, origin, end), Some(match_start..=match_end));
+        let mut regex = RegexSearch::new("foo").unwrap();
+        let match_start = Point::new(Line(0), Column(9));
+        let match_end = Point::new(Line(0), Column(11));
+        assert_eq!(term.regex_search_right(&mut regex, origin, end), Some(match_start..=match_end));
+
+        // Case insensitive search ignores uppercase characters.
+        let options = SearchOptions { case: SearchCase::Insensitive, ..Default::default() };
+        let mut regex = RegexSearch::with_options("BAR", options).unwrap();
+        let match_start = Point::new(Line(0), Column(4));
+        let match_end = Point::new(Line(0), Column(6));
+        assert_eq!(term.regex_search_right(&mut regex, start, end), Some(match_start..=match_end));
+
+        // Whole word search skips partial matches.
+        let options = SearchOptions { whole_word: true, ..Default::default() };
+        let mut regex = RegexSearch::with_options("bar", options).unwrap();
+        let match_start = Point::new(Line(0), Column(4));
+        let match_end = Point::new(Line(0), Column(6));
+        assert_eq!(term.regex_search_right(&mut regex, start, end), Some(match_start..=match_end));
+        let mut regex = RegexSearch::with_options("foobar", options).unwrap();
+        let match_start = Point::new(Line(0), Column(18));
+        assert_eq!(term.regex_search_right(&mut regex, start, end), Some(match_start..=end));
+        let mut regex = RegexSearch::with_options("oob", options).unwrap();
+        assert_eq!(term.regex_search_right(&mut regex, start, end), None);
+    }
+
     #[test]
     fn newline_breaking_semantic() {
         #[rustfmt::skip]
@@ -1155,4 +1565,27 @@ mod tests {
         assert_eq!(start, Point::new(Line(1), Column(0)));
         assert_eq!(end, Point::new(Line(1), Column(2)));
     }
+
+    #[test]
+    fn word_regex_search() {
+        let term = mock_term("foo.bar foo.barbaz -x a-x");
+        let start = Point::new(Line(0), Column(0));
+        let end = Point::new(Line(0), Column(24));
+
+        assert_eq!(word_regex("foo.bar"), "(?-u:\\b)foo\\.bar(?-u:\\b)");
+
+        // Partial words are not matched.
+        let mut regex = RegexSearch::new(&word_regex("foo.bar")).unwrap();
+        let origin = Point::new(Line(0), Column(1));
+        let match_start = Point::new(Line(0), Column(0));
+        let match_end = Point::new(Line(0), Column(6));
+        assert_eq!(term.regex_search_right(&mut regex, origin, end), None);
+        assert_eq!(term.regex_search_right(&mut regex, start, end), Some(match_start..=match_end));
+
+        // Boundaries are only added next to word characters.
+        let mut regex = RegexSearch::new(&word_regex("-x")).unwrap();
+        let origin = Point::new(Line(0), Column(20));
+        let match_start = Point::new(Line(0), Column(23));
+        assert_eq!(term.regex_search_right(&mut regex, origin, end), Some(match_start..=end));
+    }
 }
@@ -0,0 +1,521 @@
+//! Owned copies of the terminal viewport for rendering without the terminal lock.
+//!
+//! A [`Snapshot`] contains everything [`Term::renderable_content`] provides, together with the
+//! terminal damage and graphics updates since the last snapshot. Snapshots are exchanged through
+//! a [`SnapshotBuffer`]: whoever holds the terminal lock publishes a new snapshot after changing
+//! the terminal, while the renderer takes the latest one without ever touching the lock.
+//!
+//! Search and hint matches are published as [`Highlights`] of the snapshot, since they have to
+//! match its content. While highlights are active, only their owner publishes snapshots.
+//!
+//! Snapshots are recycled after rendering, so publishing only copies the viewport lines which
+//! changed since the recycled snapshot was published.
+//!
+//! ```rust
+//! use alacritty_terminal::headless::Headless;
+//! use alacritty_terminal::term::snapshot::SnapshotBuffer;
+//!
+//! let mut terminal = Headless::new(20, 3);
+//! let buffer = SnapshotBuffer::new();
+//!
+//! terminal.feed(b"hello");
+//! buffer.publish(terminal.term_mut());
+//!
+//! let snapshot = buffer.take().unwrap();
+//! assert_eq!(snapshot.cells[0].c, 'h');
+//! buffer.recycle(snapshot);
+//! ```
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use std::{iter, mem, ptr};
+
+use parking_lot::Mutex;
+
+use crate::event::EventListener;
+use crate::graphics::UpdateQueues;
+use crate::grid::{Dimensions, Indexed};
+use crate::index::{Line, Point};
+use crate::selection::SelectionRange;
+use crate::term::cell::Cell;
+use crate::term::color::Colors;
+use crate::term::search::Match;
+use crate::term::{LineDamageBounds, RenderableCursor, Term, TermDamage, TermMode};
+use crate::vte::ansi::CursorShape;
+
+/// Number of publications whose damage is kept for updating recycled snapshots.
+const MAX_DAMAGE_HISTORY: usize = 4;
+
+/// Terminal damage accumulated by a snapshot.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SnapshotDamage {
+    /// The entire viewport needs to be redrawn.
+    Full,
+
+    /// Only the listed line ranges have changed.
+    Partial(Vec<LineDamageBounds>),
+}
+
+impl Default for SnapshotDamage {
+    fn default() -> Self {
+        Self::Partial(Vec::new())
+    }
+}
+
+impl SnapshotDamage {
+    /// Add damage which has not been rendered yet.
+    fn merge(&mut self, other: Self) {
+        match (self, other) {
+            (Self::Partial(lines), Self::Partial(other)) => lines.extend(other),
+            (damage, _) => *damage = Self::Full,
+        }
+    }
+}
+
+/// Ranges highlighted on top of the terminal content.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Highlights {
+    /// Visible search matches.
+    pub search: Vec<Match>,
+
+    /// Visible hint matches.
+    pub hints: Vec<Match>,
+
+    /// Remaining label of every hint match.
+    pub hint_labels: Vec<Vec<char>>,
+}
+
+impl Highlights {
+    /// Check if nothing is highlighted.
+    pub fn is_empty(&self) -> bool {
+        self.search.is_empty() && self.hints.is_empty()
+    }
+
+    fn clear(&mut self) {
+        self.search.clear();
+        self.hints.clear();
+        self.hint_labels.clear();
+    }
+}
+
+/// Owned copy of the terminal's renderable content.
+pub struct Snapshot {
+    /// All cells in the viewport.
+    pub cells: Vec<Indexed<Cell>>,
+
+    pub selection: Option<SelectionRange>,
+    pub cursor: RenderableCursor,
+    pub display_offset: usize,
+    pub colors: Colors,
+    pub mode: TermMode,
+
+    /// Position of the terminal cursor, even while vi mode is active.
+    pub cursor_point: Point,
+
+    /// Position of the vi mode cursor.
+    pub vi_cursor_point: Point,
+
+    pub columns: usize,
+    pub screen_lines: usize,
+    pub total_lines: usize,
+    pub is_focused: bool,
+
+    /// Damage since the previous snapshot was taken.
+    pub damage: SnapshotDamage,
+
+    /// Graphics updates since the previous snapshot was taken.
+    pub graphics: Option<UpdateQueues>,
+
+    /// Search and hint matches added by the publisher.
+    pub highlights: Highlights,
+
+    /// Publication which last updated the cells, `0` if they were never updated.
+    generation: u64,
+}
+
+impl Default for Snapshot {
+    fn default() -> Self {
+        Self {
+            cells: Vec::new(),
+            selection: None,
+            cursor: RenderableCursor { shape: CursorShape::Hidden, point: Point::default() },
+            display_offset: 0,
+            colors: Colors::default(),
+            mode: TermMode::empty(),
+            cursor_point: Point::default(),
+            vi_cursor_point: Point::default(),
+            columns: 0,
+            screen_lines: 0,
+            total_lines: 0,
+            is_focused: false,
+            damage: SnapshotDamage::Full,
+            graphics: None,
+            highlights: Highlights::default(),
+            generation: 0,
+        }
+    }
+}
+
+impl Snapshot {
+    /// Create a snapshot of the terminal.
+    ///
+    /// This takes the terminal's damage and graphics updates.
+    pub fn new<T: EventListener>(term: &mut Term<T>) -> Self {
+        let mut snapshot = Self::default();
+        snapshot.update(term);
+        snapshot
+    }
+
+    /// Replace the snapshot's content with the current state of the terminal.
+    ///
+    /// The cell buffer is reused, to avoid reallocating it for every snapshot.
+    pub fn update<T: EventListener>(&mut self, term: &mut Term<T>) {
+        let damage = take_damage(term);
+        self.update_cells(term, None);
+        self.update_state(term, damage);
+    }
+
+    /// Copy the cells of the terminal's viewport.
+    ///
+    /// With `stale` lines, only the cells of these viewport lines are copied.
+    fn update_cells<T>(&mut self, term: &Term<T>, stale: Option<&[bool]>) {
+        let stale =
+            match stale {
+                Some(stale) => stale,
+                None => {
+                    let display_iter = term.grid().display_iter();
+                    self.cells.clear();
+                    self.cells.extend(display_iter.map(|indexed| Indexed {
+                        point: indexed.point,
+                        cell: indexed.cell.clone(),
+                    }));
+                    return;
+                },
+            };
+
+        let grid = term.grid();
+        let columns = grid.columns();
+        let display_offset = grid.display_offset() as i32;
+        for (line, _) in stale.iter().enumerate().filter(|(_, stale)| **stale) {
+            let row = grid.row_ref(Line(line as i32 - display_offset));
+            let cells = &mut self.cells[line * columns..(line + 1) * columns];
+            for (cell, source) in cells.iter_mut().zip(&row[..]) {
+                cell.cell.clone_from(source);
+            }
+        }
+    }
+
+    /// Viewport lines which changed since the cells of this snapshot were copied.
+    ///
+    /// Returns `None` if all lines have to be copied.
+    fn stale_lines<T>(
+        &self,
+        term: &Term<T>,
+        history: &DamageHistory,
+        damage: &SnapshotDamage,
+    ) -> Option<Vec<bool>> {
+        if self.columns != term.columns()
+            || self.screen_lines != term.screen_lines()
+            || self.display_offset != term.grid().display_offset()
+        {
+            return None;
+        }
+
+        let mut stale = vec![false; self.screen_lines];
+        for damage in history.since(self.generation)?.chain(iter::once(damage)) {
+            let lines = match damage {
+                SnapshotDamage::Partial(lines) => lines,
+                SnapshotDamage::Full => return None,
+            };
+
+            for bounds in lines.iter().filter(|bounds| bounds.is_damaged()) {
+                stale[bounds.line] = true;
+            }
+        }
+
+        Some(stale)
+    }
+
+    /// Copy everything except the cells from the terminal.
+    fn update_state<T: EventListener>(&mut self, term: &mut Term<T>, damage: SnapshotDamage) {
+        let content = term.renderable_content();
+        self.selection = content.selection;
+        self.cursor = content.cursor;
+        self.display_offset = content.display_offset;
+        self.colors = *content.colors;
+        self.mode = content.mode;
+
+        self.cursor_point = term.grid().cursor.point;
+        self.vi_cursor_point = term.vi_mode_cursor.point;
+        self.columns = term.columns();
+        self.screen_lines = term.screen_lines();
+        self.total_lines = term.total_lines();
+        self.is_focused = term.is_focused;
+
+        self.damage = damage;
+        self.graphics = term.graphics_take_queues();
+    }
+
+    /// Carry over damage and graphics updates of a snapshot which was never rendered.
+    fn merge_unrendered(&mut self, older: &mut Self) {
+        let damage = mem::replace(&mut self.damage, mem::take(&mut older.damage));
+        self.damage.merge(damage);
+
+        self.graphics = match (older.graphics.take(), self.graphics.take()) {
+            (Some(mut older), Some(newer)) => {
+                older.pending.extend(newer.pending);
+                older.remove_queue.extend(newer.remove_queue);
+                older.clear_subregions.extend(newer.clear_subregions);
+                Some(older)
+            },
+            (older, newer) => older.or(newer),
+        };
+    }
+}
+
+/// Take the terminal's damage since the last snapshot.
+fn take_damage<T>(term: &mut Term<T>) -> SnapshotDamage {
+    let damage = match term.damage() {
+        TermDamage::Full => SnapshotDamage::Full,
+        TermDamage::Partial(lines) => SnapshotDamage::Partial(lines.collect()),
+    };
+    term.reset_damage();
+    damage
+}
+
+/// Damage of the most recent publications.
+#[derive(Default)]
+struct DamageHistory {
+    /// Number of snapshots published so far.
+    generation: u64,
+
+    /// Damage of the last publications, from oldest to newest.
+    damage: VecDeque<SnapshotDamage>,
+}
+
+impl DamageHistory {
+    /// Record the damage of a new publication, returning its generation.
+    fn push(&mut self, damage: SnapshotDamage) -> u64 {
+        if self.damage.len() == MAX_DAMAGE_HISTORY {
+            self.damage.pop_front();
+        }
+        self.damage.push_back(damage);
+
+        self.generation += 1;
+        self.generation
+    }
+
+    /// Damage of all publications after `generation`.
+    ///
+    /// Returns `None` if the damage is not known anymore.
+    fn since(&self, generation: u64) -> Option<impl Iterator<Item = &SnapshotDamage>> {
+        let missed = self.generation.checked_sub(generation)? as usize;
+        if generation == 0 || missed > self.damage.len() {
+            return None;
+        }
+
+        Some(self.damage.iter().skip(self.damage.len() - missed))
+    }
+}
+
+/// Double buffer for passing snapshots from the terminal to the renderer.
+///
+/// Publishing and taking snapshots is lock-free. Snapshots which were published but never taken
+/// are dropped when a newer snapshot is published, after merging their damage into it.
+///
+/// All snapshots must be published from the same terminal, so holding its lock guarantees that
+/// only one snapshot is published at a time.
+#[derive(Default)]
+pub struct SnapshotBuffer {
+    /// Latest published snapshot.
+    ready: AtomicPtr<Snapshot>,
+
+    /// Snapshot returned by the renderer, reused for publishing.
+    spare: AtomicPtr<Snapshot>,
+
+    /// Damage of recent publications, only accessed while publishing.
+    history: Mutex<DamageHistory>,
+
+    /// Whether snapshots are only published by the owner of the highlights.
+    highlighting: AtomicBool,
+}
+
+impl SnapshotBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Publish the current state of the terminal.
+    pub fn publish<T: EventListener>(&self, term: &mut Term<T>) {
+        self.publish_with(term, |_, _| ());
+    }
+
+    /// Publish the current state of the terminal, with highlights added by `highlight`.
+    pub fn publish_with<T, F>(&self, term: &mut Term<T>, highlight: F)
+    where
+        T: EventListener,
+        F: FnOnce(&Term<T>, &mut Highlights),
+    {
+        let mut snapshot = take(&self.spare).unwrap_or_default();
+
+        // Only copy the lines which changed since the recycled snapshot was published.
+        let mut history = self.history.lock();
+        let damage = take_damage(term);
+        let stale = snapshot.stale_lines(term, &history, &damage);
+        snapshot.update_cells(term, stale.as_deref());
+        snapshot.update_state(term, damage.clone());
+        snapshot.generation = history.push(damage);
+        drop(history);
+
+        snapshot.highlights.clear();
+        highlight(term, &mut snapshot.highlights);
+
+        // Carry over everything the renderer missed from the previous snapshot.
+        let unrendered = take(&self.ready);
+        if let Some(mut unrendered) = unrendered {
+            snapshot.merge_unrendered(&mut unrendered);
+            self.recycle(unrendered);
+        }
+
+        let previous = self.ready.swap(Box::into_raw(snapshot), Ordering::AcqRel);
+        debug_assert!(previous.is_null(), "concurrent snapshot publishing");
+    }
+
+    /// Reserve publishing for the owner of the highlights.
+    ///
+    /// Snapshots without highlights would show outdated matches, so while this is set the
+    /// [`EventLoop`] only requests a redraw and leaves publishing to its listener.
+    ///
+    /// [`EventLoop`]: crate::event_loop::EventLoop
+    pub fn set_highlighting(&self, highlighting: bool) {
+        self.highlighting.store(highlighting, Ordering::Relaxed);
+    }
+
+    /// Whether snapshots are only published by the owner of the highlights.
+    pub fn highlighting(&self) -> bool {
+        self.highlighting.load(Ordering::Relaxed)
+    }
+
+    /// Take the latest published snapshot.
+    ///
+    /// Returns `None` if nothing was published since the last call.
+    pub fn take(&self) -> Option<Box<Snapshot>> {
+        take(&self.ready)
+    }
+
+    /// Return a rendered snapshot, so its buffers can be reused.
+    pub fn recycle(&self, snapshot: Box<Snapshot>) {
+        let previous = self.spare.swap(Box::into_raw(snapshot), Ordering::AcqRel);
+        drop(from_raw(previous));
+    }
+}
+
+impl Drop for SnapshotBuffer {
+    fn drop(&mut self) {
+        drop(take(&self.ready));
+        drop(take(&self.spare));
+    }
+}
+
+/// Take ownership of the snapshot stored in a slot.
+fn take(slot: &AtomicPtr<Snapshot>) -> Option<Box<Snapshot>> {
+    from_raw(slot.swap(ptr::null_mut(), Ordering::AcqRel))
+}
+
+fn from_raw(snapshot: *mut Snapshot) -> Option<Box<Snapshot>> {
+    // SAFETY: Slots only ever contain null or pointers from `Box::into_raw`, which are removed
+    // from the slot before being converted back.
+    (!snapshot.is_null()).then(|| unsafe { Box::from_raw(snapshot) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::headless::Headless;
+    use crate::index::{Column, Line};
+
+    #[test]
+    fn snapshot_content() {
+        let mut terminal = Headless::new(5, 2);
+        terminal.feed(b"ab\r\ncd");
+
+        let snapshot = Snapshot::new(terminal.term_mut());
+        assert_eq!(snapshot.cells.len(), 10);
+        assert_eq!(snapshot.cells[6].point, Point::new(Line(1), Column(1)));
+        assert_eq!(snapshot.cells[6].c, 'd');
+        assert_eq!(snapshot.cursor_point, Point::new(Line(1), Column(2)));
+        assert_eq!(snapshot.damage, SnapshotDamage::Full);
+
+        // Damage is only reported once.
+        let snapshot = Snapshot::new(terminal.term_mut());
+        assert_eq!(snapshot.damage, SnapshotDamage::Partial(vec![LineDamageBounds::new(1, 2, 2)]));
+    }
+
+    #[test]
+    fn unrendered_damage_is_merged() {
+        let mut terminal = Headless::new(5, 2);
+        let buffer = SnapshotBuffer::new();
+        buffer.publish(terminal.term_mut());
+        buffer.recycle(buffer.take().unwrap());
+
+        terminal.feed(b"a");
+        buffer.publish(terminal.term_mut());
+        terminal.feed(b"\r\nb");
+        buffer.publish(terminal.term_mut());
+
+        let snapshot = buffer.take().unwrap();
+        assert!(buffer.take().is_none());
+        assert_eq!(snapshot.cells[5].c, 'b');
+
+        let SnapshotDamage::Partial(lines) = &snapshot.damage else { panic!("full damage") };
+        assert!(lines.iter().any(|bounds| bounds.line == 0 && bounds.left == 0));
+        assert!(lines.iter().any(|bounds| bounds.line == 1 && bounds.left == 0));
+    }
+
+    #[test]
+    fn recycled_snapshot_copies_damaged_lines() {
+        let mut terminal = Headless::new(5, 3);
+        let buffer = SnapshotBuffer::new();
+        terminal.feed(b"a\r\n");
+        buffer.publish(terminal.term_mut());
+
+        // Modify an undamaged line, to check which cells are copied.
+        let mut snapshot = buffer.take().unwrap();
+        snapshot.cells[0].cell.c = 'x';
+        buffer.recycle(snapshot);
+
+        terminal.feed(b"b");
+        buffer.publish(terminal.term_mut());
+
+        let snapshot = buffer.take().unwrap();
+        assert_eq!(snapshot.cells[0].c, 'x');
+        assert_eq!(snapshot.cells[5].c, 'b');
+        buffer.recycle(snapshot);
+
+        // Scrolling the viewport requires copying every line.
+        terminal.feed(b"\r\n\r\n");
+        buffer.publish(terminal.term_mut());
+
+        let snapshot = buffer.take().unwrap();
+        assert_eq!(snapshot.cells[0].c, 'b');
+        assert_eq!(snapshot.cells[5].c, ' ');
+    }
+
+    #[test]
+    fn highlights_are_replaced() {
+        let mut terminal = Headless::new(5, 2);
+        let buffer = SnapshotBuffer::new();
+        let point = Point::new(Line(0), Column(0));
+
+        buffer.publish_with(terminal.term_mut(), |_, highlights| {
+            highlights.search.push(point..=point);
+        });
+        let snapshot = buffer.take().unwrap();
+        assert_eq!(snapshot.highlights.search, [point..=point]);
+        buffer.recycle(snapshot);
+
+        buffer.publish(terminal.term_mut());
+        assert!(buffer.take().unwrap().highlights.is_empty());
+    }
+}
@@ -0,0 +1,325 @@
+//! Shared cell attributes.
+//!
+//! Terminal content usually only uses a handful of distinct colors and attributes, so instead of
+//! storing them in every single cell, cells share a reference counted copy of their attributes.
+//!
+//! Every grid owns a [`StyleTable`] which deduplicates the styles of its cursor, so all cells
+//! written with the same attributes refer to the same allocation. Styles created in any other way
+//! are only shared between the cells they're cloned into.
+
+use std::borrow::Borrow;
+use std::cmp::max;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{BuildHasherDefault, Hash, Hasher};
+use std::sync::Arc;
+
+use crate::term::cell::CellExtra;
+use crate::vte::ansi::{Color, NamedColor, Rgb};
+
+/// Minimum number of styles in a table before unused styles are removed.
+const MIN_PRUNE_LEN: usize = 64;
+
+/// Attributes of cells without any colors or extra attributes.
+static DEFAULT_STYLE: StyleData = StyleData {
+    fg: Color::Named(NamedColor::Foreground),
+    bg: Color::Named(NamedColor::Background),
+    extra: None,
+};
+
+/// Attributes shared between cells.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StyleData {
+    pub fg: Color,
+    pub bg: Color,
+    pub extra: Option<Box<CellExtra>>,
+}
+
+impl Default for StyleData {
+    fn default() -> Self {
+        DEFAULT_STYLE.clone()
+    }
+}
+
+impl StyleData {
+    /// Hash of all attributes.
+    fn digest(&self) -> u32 {
+        let mut hasher = StyleHasher::default();
+        hash_color(self.fg, &mut hasher);
+        hash_color(self.bg, &mut hasher);
+        self.extra.hash(&mut hasher);
+        hasher.finish() as u32
+    }
+}
+
+impl Hash for StyleData {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u32(self.digest());
+    }
+}
+
+/// Shared attributes of a cell.
+///
+/// The default style is represented without any allocation, so cells using it never need to
+/// touch the reference count at all.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Style(Option<Arc<StyleData>>);
+
+impl Style {
+    /// Create a style for a set of attributes.
+    ///
+    /// The style is not shared with any other style using the same attributes, use a
+    /// [`StyleTable`] for that.
+    pub fn new(data: StyleData) -> Self {
+        if data == DEFAULT_STYLE {
+            return Self::default();
+        }
+
+        Self(Some(Arc::new(data)))
+    }
+
+    /// Style for blank cells cleared using this style.
+    ///
+    /// Blank cells only keep the background color, so most styles can be shared with them
+    /// directly. Otherwise a new style is created, which should be cloned for clearing multiple
+    /// cells.
+    pub fn blank(&self) -> Self {
+        if self.fg == DEFAULT_STYLE.fg && self.extra.is_none() {
+            return self.clone();
+        }
+
+        Self::new(StyleData { bg: self.bg, ..StyleData::default() })
+    }
+
+    /// Modify the attributes of this style.
+    ///
+    /// This will not affect any other cells using the same style.
+    pub fn update<F: FnOnce(&mut StyleData)>(&mut self, f: F) {
+        let mut data = (**self).clone();
+        f(&mut data);
+
+        if data != **self {
+            *self = Self::new(data);
+        }
+    }
+}
+
+impl std::ops::Deref for Style {
+    type Target = StyleData;
+
+    #[inline]
+    fn deref(&self) -> &StyleData {
+        match &self.0 {
+            Some(data) => data,
+            None => &DEFAULT_STYLE,
+        }
+    }
+}
+
+impl fmt::Debug for Style {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+/// Deduplication of the styles used by a grid.
+///
+/// Styles which are not used by any cell anymore are removed whenever the table doubled in size.
+#[derive(Clone, Default)]
+pub struct StyleTable {
+    styles: HashSet<Entry, BuildHasherDefault<StyleHasher>>,
+
+    /// Number of styles at which unused styles are removed.
+    prune_len: usize,
+}
+
+impl StyleTable {
+    /// Get the shared style with the same attributes as `style`.
+    pub fn intern(&mut self, style: Style) -> Style {
+        let data = match style.0 {
+            Some(data) => data,
+            None => return Style::default(),
+        };
+
+        if let Some(entry) = self.styles.get(&*data) {
+            return Style(Some(entry.style.clone()));
+        }
+
+        if self.styles.len() >= self.prune_len {
+            self.prune();
+        }
+
+        self.styles.insert(Entry { digest: data.digest(), style: data.clone() });
+
+        Style(Some(data))
+    }
+
+    /// Remove all styles only referenced by the table.
+    fn prune(&mut self) {
+        self.styles.retain(|entry| Arc::strong_count(&entry.style) > 1);
+        self.prune_len = max(self.styles.len() * 2, MIN_PRUNE_LEN);
+    }
+}
+
+impl fmt::Debug for StyleTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StyleTable").field("len", &self.styles.len()).finish()
+    }
+}
+
+/// Style stored in a [`StyleTable`], compared by its attributes.
+///
+/// The hash of the attributes is stored with the style, to avoid hashing the attributes again
+/// when the table is resized.
+#[derive(Clone)]
+struct Entry {
+    style: Arc<StyleData>,
+    digest: u32,
+}
+
+impl Borrow<StyleData> for Entry {
+    fn borrow(&self) -> &StyleData {
+        &self.style
+    }
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.style == other.style
+    }
+}
+
+impl Eq for Entry {}
+
+impl Hash for Entry {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u32(self.digest);
+    }
+}
+
+/// Fast non-cryptographic hasher for style attributes.
+#[derive(Default)]
+struct StyleHasher(u64);
+
+impl Hasher for StyleHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.write_u64(u64::from(byte));
+        }
+    }
+
+    #[inline]
+    fn write_u8(&mut self, value: u8) {
+        self.write_u64(u64::from(value));
+    }
+
+    #[inline]
+    fn write_u16(&mut self, value: u16) {
+        self.write_u64(u64::from(value));
+    }
+
+    #[inline]
+    fn write_u32(&mut self, value: u32) {
+        self.write_u64(u64::from(value));
+    }
+
+    #[inline]
+    fn write_u64(&mut self, value: u64) {
+        self.0 = (self.0.rotate_left(5) ^ value).wrapping_mul(0x51_7c_c1_b7_27_22_0a_95);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, value: usize) {
+        self.write_u64(value as u64);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        // Move the well mixed upper bits to the bottom, since they're used for the table index.
+        self.0.rotate_left(26)
+    }
+}
+
+/// Hash a color.
+///
+/// This is required since the ANSI color types do not implement [`Hash`].
+pub fn hash_color<H: Hasher>(color: Color, state: &mut H) {
+    match color {
+        Color::Named(named) => (0u8, named as u16).hash(state),
+        Color::Spec(Rgb { r, g, b }) => (1u8, r, g, b).hash(state),
+        Color::Indexed(index) => (2u8, index).hash(state),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn style(index: u8) -> StyleData {
+        StyleData { fg: Color::Indexed(index), ..StyleData::default() }
+    }
+
+    fn shared(a: &Style, b: &Style) -> bool {
+        match (&a.0, &b.0) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn default_style_has_no_allocation() {
+        assert!(Style::new(StyleData::default()).0.is_none());
+        assert!(Style::new(style(1)).blank().0.is_none());
+        assert!(StyleTable::default().intern(Style::default()).0.is_none());
+    }
+
+    #[test]
+    fn interned_styles_are_shared() {
+        let mut table = StyleTable::default();
+        let a = table.intern(Style::new(style(1)));
+        let c = table.intern(Style::new(style(2)));
+        let b = table.intern(Style::new(style(1)));
+
+        assert!(shared(&a, &b));
+        assert_ne!(a, c);
+        assert_eq!(a.fg, Color::Indexed(1));
+        assert_eq!(c.fg, Color::Indexed(2));
+    }
+
+    #[test]
+    fn blank_style() {
+        let style = Style::new(StyleData { bg: Color::Indexed(3), ..style(1) });
+
+        let blank = style.blank();
+        assert_eq!(*blank, StyleData { bg: Color::Indexed(3), ..StyleData::default() });
+        assert!(shared(&blank, &blank.blank()));
+    }
+
+    #[test]
+    fn unused_styles_are_removed() {
+        let mut table = StyleTable::default();
+        let used = table.intern(Style::new(style(0)));
+        for index in 1..MIN_PRUNE_LEN as u8 {
+            table.intern(Style::new(style(index)));
+        }
+        assert_eq!(table.styles.len(), MIN_PRUNE_LEN);
+
+        table.intern(Style::new(style(255)));
+
+        assert_eq!(table.styles.len(), 2);
+        assert!(shared(&used, &table.intern(Style::new(style(0)))));
+    }
+
+    #[test]
+    fn update_style() {
+        let mut a = Style::new(style(4));
+        let b = a.clone();
+
+        a.update(|data| data.bg = Color::Indexed(5));
+
+        assert_eq!(a.fg, Color::Indexed(4));
+        assert_eq!(a.bg, Color::Indexed(5));
+        assert_eq!(b.bg, DEFAULT_STYLE.bg);
+    }
+}
@@ -0,0 +1,434 @@
+//! Terminal backends connected to a device instead of a child process.
+//!
+//! These allow using the terminal as a console for serial ports, or for any other byte stream
+//! like an inherited file descriptor or a Unix socket. Since there is no shell on the other end,
+//! resizing the terminal is not propagated and the end of the stream is reported as child exit.
+
+use std::fmt::{self, Display, Formatter};
+use std::fs::{File, OpenOptions};
+use std::io::{Error, ErrorKind, Read, Result};
+use std::mem::MaybeUninit;
+use std::os::fd::{AsRawFd, BorrowedFd, OwnedFd, RawFd};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use log::error;
+use polling::{Event, PollMode, Poller};
+
+use crate::event::{OnResize, WindowSize};
+use crate::tty::{
+    set_nonblocking, ChildEvent, EventedPty, EventedReadWrite, PTY_CHILD_EVENT_TOKEN,
+    PTY_READ_WRITE_TOKEN,
+};
+
+/// Device connected to the terminal instead of a shell.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Device {
+    /// Serial port.
+    Serial(SerialOptions),
+
+    /// File descriptor inherited from the parent process.
+    ///
+    /// The descriptor is duplicated, so it remains owned by the caller.
+    Fd(RawFd),
+
+    /// Unix stream socket.
+    Socket(PathBuf),
+}
+
+impl Display for Device {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Serial(serial) => write!(f, "{}", serial.path.display()),
+            Self::Fd(fd) => write!(f, "fd {}", fd),
+            Self::Socket(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// Serial port configuration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SerialOptions {
+    /// Path of the serial device.
+    pub path: PathBuf,
+
+    /// Line speed in bits per second.
+    pub baud_rate: u32,
+
+    pub parity: Parity,
+    pub flow_control: FlowControl,
+}
+
+impl SerialOptions {
+    /// Serial port using 115200 baud without parity and flow control.
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, baud_rate: 115200, parity: Parity::None, flow_control: FlowControl::None }
+    }
+}
+
+/// Serial parity bit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Parity {
+    #[default]
+    None,
+    Odd,
+    Even,
+}
+
+/// Serial flow control.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlowControl {
+    #[default]
+    None,
+    /// XON/XOFF.
+    Software,
+    /// RTS/CTS.
+    Hardware,
+}
+
+/// Terminal backend for a [`Device`].
+pub struct DevicePty {
+    reader: DeviceReader,
+    writer: File,
+    hangup: UnixStream,
+}
+
+impl DevicePty {
+    /// File used for reading from the device.
+    pub fn file(&self) -> &File {
+        &self.reader.file
+    }
+}
+
+/// Connect to a device.
+pub fn new(device: &Device) -> Result<DevicePty> {
+    let file = match device {
+        Device::Serial(options) => open_serial(options)?,
+        Device::Fd(fd) => {
+            let fd = unsafe { BorrowedFd::borrow_raw(*fd) }.try_clone_to_owned()?;
+            File::from(fd)
+        },
+        Device::Socket(path) => {
+            let stream = UnixStream::connect(path).map_err(|err| {
+                Error::new(err.kind(), format!("Failed to connect to {:?}: {}", path, err))
+            })?;
+            File::from(OwnedFd::from(stream))
+        },
+    };
+
+    unsafe {
+        set_nonblocking(file.as_raw_fd());
+    }
+
+    // Signal the end of the stream through a separate socket, like a `SIGCHLD` for PTYs.
+    let (sender, hangup) = UnixStream::pair()?;
+    hangup.set_nonblocking(true)?;
+
+    let writer = file.try_clone()?;
+    let reader = DeviceReader { file, hangup: Some(sender) };
+
+    Ok(DevicePty { reader, writer, hangup })
+}
+
+/// Reader reporting errors and the end of the stream as a hangup.
+///
+/// The event loop expects `read` to return [`ErrorKind::WouldBlock`] when no data is available,
+/// so instead of signaling the end of the stream by returning zero bytes, the hangup socket is
+/// closed to emit a child event.
+pub struct DeviceReader {
+    file: File,
+    hangup: Option<UnixStream>,
+}
+
+impl DeviceReader {
+    fn hang_up(&mut self) -> Result<usize> {
+        self.hangup = None;
+        Err(ErrorKind::WouldBlock.into())
+    }
+}
+
+impl Read for DeviceReader {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        match self.file.read(buf) {
+            Ok(0) if !buf.is_empty() => self.hang_up(),
+            Err(err) if !matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::Interrupted) => {
+                if self.hangup.is_some() {
+                    error!("Error reading from device: {}", err);
+                }
+                self.hang_up()
+            },
+            result => result,
+        }
+    }
+}
+
+impl EventedReadWrite for DevicePty {
+    type Reader = DeviceReader;
+    type Writer = File;
+
+    #[inline]
+    unsafe fn register(
+        &mut self,
+        poll: &Arc<Poller>,
+        mut interest: Event,
+        poll_opts: PollMode,
+    ) -> Result<()> {
+        interest.key = PTY_READ_WRITE_TOKEN;
+        unsafe {
+            poll.add_with_mode(&self.reader.file, interest, poll_opts)?;
+        }
+
+        unsafe {
+            poll.add_with_mode(
+                &self.hangup,
+                Event::readable(PTY_CHILD_EVENT_TOKEN),
+                PollMode::Level,
+            )
+        }
+    }
+
+    #[inline]
+    fn reregister(
+        &mut self,
+        poll: &Arc<Poller>,
+        mut interest: Event,
+        poll_opts: PollMode,
+    ) -> Result<()> {
+        interest.key = PTY_READ_WRITE_TOKEN;
+        poll.modify_with_mode(&self.reader.file, interest, poll_opts)?;
+
+        poll.modify_with_mode(&self.hangup, Event::readable(PTY_CHILD_EVENT_TOKEN), PollMode::Level)
+    }
+
+    #[inline]
+    fn deregister(&mut self, poll: &Arc<Poller>) -> Result<()> {
+        poll.delete(&self.reader.file)?;
+        poll.delete(&self.hangup)
+    }
+
+    #[inline]
+    fn reader(&mut self) -> &mut DeviceReader {
+        &mut self.reader
+    }
+
+    #[inline]
+    fn writer(&mut self) -> &mut File {
+        &mut self.writer
+    }
+}
+
+impl EventedPty for DevicePty {
+    #[inline]
+    fn next_child_event(&mut self) -> Option<ChildEvent> {
+        // The hangup socket only becomes readable once the sender is dropped.
+        match self.hangup.read(&mut [0]) {
+            Ok(0) => Some(ChildEvent::Exited(None)),
+            _ => None,
+        }
+    }
+}
+
+impl OnResize for DevicePty {
+    /// Devices have no concept of a window size, so resizing is ignored.
+    fn on_resize(&mut self, _window_size: WindowSize) {}
+}
+
+/// Open a serial port and apply its line settings.
+fn open_serial(options: &SerialOptions) -> Result<File> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_NOCTTY | libc::O_NONBLOCK)
+        .open(&options.path)
+        .map_err(|err| {
+            Error::new(err.kind(), format!("Failed to open {:?}: {}", options.path, err))
+        })?;
+    let fd = file.as_raw_fd();
+
+    let speed = speed(options.baud_rate).ok_or_else(|| {
+        let msg = format!("Unsupported baud rate {}", options.baud_rate);
+        Error::new(ErrorKind::InvalidInput, msg)
+    })?;
+
+    let mut termios = MaybeUninit::uninit();
+    if unsafe { libc::tcgetattr(fd, termios.as_mut_ptr()) } != 0 {
+        return Err(Error::last_os_error());
+    }
+    let mut termios: libc::termios = unsafe { termios.assume_init() };
+
+    // Pass all bytes through unmodified, 8 data bits and 1 stop bit.
+    unsafe { libc::cfmakeraw(&mut termios) };
+    termios.c_cflag |= libc::CLOCAL | libc::CREAD;
+    termios.c_cflag &= !(libc::PARENB | libc::PARODD | libc::CSTOPB | libc::CRTSCTS);
+    termios.c_iflag &= !(libc::INPCK | libc::IXON | libc::IXOFF | libc::IXANY);
+    termios.c_cc[libc::VMIN] = 1;
+    termios.c_cc[libc::VTIME] = 0;
+
+    match options.parity {
+        Parity::None => (),
+        Parity::Odd => {
+            termios.c_cflag |= libc::PARENB | libc::PARODD;
+            termios.c_iflag |= libc::INPCK;
+        },
+        Parity::Even => {
+            termios.c_cflag |= libc::PARENB;
+            termios.c_iflag |= libc::INPCK;
+        },
+    }
+
+    match options.flow_control {
+        FlowControl::None => (),
+        FlowControl::Software => termios.c_iflag |= libc::IXON | libc::IXOFF,
+        FlowControl::Hardware => termios.c_cflag |= libc::CRTSCTS,
+    }
+
+    let result = unsafe {
+        if libc::cfsetispeed(&mut termios, speed) != 0
+            || libc::cfsetospeed(&mut termios, speed) != 0
+        {
+            -1
+        } else {
+            libc::tcsetattr(fd, libc::TCSANOW, &termios)
+        }
+    };
+    if result != 0 {
+        return Err(Error::last_os_error());
+    }
+
+    Ok(file)
+}
+
+/// Convert a baud rate to its termios speed.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn speed(baud_rate: u32) -> Option<libc::speed_t> {
+    let speed = match baud_rate {
+        50 => libc::B50,
+        75 => libc::B75,
+        110 => libc::B110,
+        134 => libc::B134,
+        150 => libc::B150,
+        200 => libc::B200,
+        300 => libc::B300,
+        600 => libc::B600,
+        1200 => libc::B1200,
+        1800 => libc::B1800,
+        2400 => libc::B2400,
+        4800 => libc::B4800,
+        9600 => libc::B9600,
+        19200 => libc::B19200,
+        38400 => libc::B38400,
+        57600 => libc::B57600,
+        115200 => libc::B115200,
+        230400 => libc::B230400,
+        460800 => libc::B460800,
+        500000 => libc::B500000,
+        576000 => libc::B576000,
+        921600 => libc::B921600,
+        1000000 => libc::B1000000,
+        1152000 => libc::B1152000,
+        1500000 => libc::B1500000,
+        2000000 => libc::B2000000,
+        2500000 => libc::B2500000,
+        3000000 => libc::B3000000,
+        3500000 => libc::B3500000,
+        4000000 => libc::B4000000,
+        _ => return None,
+    };
+    Some(speed)
+}
+
+/// Convert a baud rate to its termios speed.
+///
+/// BSD derived systems use the baud rate directly as speed.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn speed(baud_rate: u32) -> Option<libc::speed_t> {
+    Some(baud_rate as libc::speed_t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+    use std::os::unix::net::UnixListener;
+    use std::{fs, process};
+
+    /// Read a fixed number of bytes from the non-blocking device.
+    fn read_bytes(pty: &mut DevicePty, len: usize) -> Vec<u8> {
+        let mut bytes = vec![0; len];
+        let mut read = 0;
+        while read < len {
+            match pty.reader().read(&mut bytes[read..]) {
+                Ok(count) => read += count,
+                Err(err) => assert_eq!(err.kind(), ErrorKind::WouldBlock),
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    fn socket_hangup() {
+        let path = std::env::temp_dir().join(format!("alacritty-device-{}.sock", process::id()));
+        let _ = fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let mut pty = new(&Device::Socket(path.clone())).unwrap();
+        let (mut peer, _) = listener.accept().unwrap();
+        fs::remove_file(&path).unwrap();
+
+        peer.write_all(b"boot").unwrap();
+        pty.writer().write_all(b"x").unwrap();
+
+        assert_eq!(read_bytes(&mut pty, 4), b"boot");
+        assert_eq!(pty.next_child_event(), None);
+
+        let mut received = [0];
+        (&peer).read_exact(&mut received).unwrap();
+        assert_eq!(&received, b"x");
+
+        drop(peer);
+        let err = pty.reader().read(&mut [0; 4]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+        assert_eq!(pty.next_child_event(), Some(ChildEvent::Exited(None)));
+    }
+
+    #[test]
+    fn inherited_fd() {
+        let (local, mut peer) = UnixStream::pair().unwrap();
+
+        let mut pty = new(&Device::Fd(local.as_raw_fd())).unwrap();
+        drop(local);
+
+        peer.write_all(b"ok").unwrap();
+        assert_eq!(read_bytes(&mut pty, 2), b"ok");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn serial_settings() {
+        let pty = rustix_openpty::openpty(None, None).unwrap();
+        let path = fs::read_link(format!("/proc/self/fd/{}", pty.user.as_raw_fd())).unwrap();
+
+        let options = SerialOptions {
+            baud_rate: 9600,
+            parity: Parity::Even,
+            flow_control: FlowControl::Software,
+            ..SerialOptions::new(path.clone())
+        };
+        let device = new(&Device::Serial(options)).unwrap();
+
+        let mut termios = MaybeUninit::uninit();
+        assert_eq!(unsafe { libc::tcgetattr(device.file().as_raw_fd(), termios.as_mut_ptr()) }, 0);
+        let termios = unsafe { termios.assume_init() };
+        assert_eq!(unsafe { libc::cfgetospeed(&termios) }, libc::B9600);
+        assert_ne!(termios.c_iflag & libc::INPCK, 0);
+        assert_ne!(termios.c_iflag & libc::IXON, 0);
+        assert_eq!(termios.c_lflag & libc::ECHO, 0);
+
+        let options = SerialOptions { baud_rate: 12345, ..SerialOptions::new(path) };
+        let err = new(&Device::Serial(options)).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+}
@@ -1,11 +1,14 @@
 //! TTY related functionality.
 
 use std::path::PathBuf;
+use std::process::ExitStatus;
 use std::sync::Arc;
 use std::{env, io};
 
 use polling::{Event, PollMode, Poller};
 
+#[cfg(not(windows))]
+pub mod device;
 #[cfg(not(windows))]
 mod unix;
 #[cfg(not(windows))]
@@ -24,6 +27,12 @@ pub struct Options {
     /// [`None`] will use the default shell.
     pub shell: Option<Shell>,
 
+    /// Device to connect to instead of spawning a shell.
+    ///
+    /// This takes precedence over the [`Options::shell`].
+    #[cfg(not(windows))]
+    pub device: Option<device::Device>,
+
     /// Shell startup directory.
     pub working_directory: Option<PathBuf>,
 
@@ -44,6 +53,16 @@ impl Shell {
     pub fn new(program: String, args: Vec<String>) -> Self {
         Self { program, args }
     }
+
+    /// Path of the shell program.
+    pub fn program(&self) -> &str {
+        &self.program
+    }
+
+    /// Arguments passed to the shell program.
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
 }
 
 /// This trait defines the behaviour needed to read and/or write to a stream.
@@ -67,8 +86,8 @@ pub trait EventedReadWrite {
 /// Events concerning TTY child processes.
 #[derive(Debug, PartialEq, Eq)]
 pub enum ChildEvent {
-    /// Indicates the child has exited, with an error code if available.
-    Exited(Option<i32>),
+    /// Indicates the child has exited, with its exit status if available.
+    Exited(Option<ExitStatus>),
 }
 
 /// A pseudoterminal (or PTY).
@@ -81,6 +100,14 @@ pub trait EventedPty: EventedReadWrite {
     ///
     /// Returns `Some(event)` on success, or `None` if there are no events to retrieve.
     fn next_child_event(&mut self) -> Option<ChildEvent>;
+
+    /// Check if the child is currently reading a password.
+    ///
+    /// This is assumed whenever echo is disabled while in canonical mode, which is what programs
+    /// like `sudo` or `ssh` do while prompting for a password.
+    fn is_reading_password(&self) -> bool {
+        false
+    }
 }
 
 /// Setup environment variables.
@@ -4,7 +4,7 @@ use std::ffi::CStr;
 use std::fs::File;
 use std::io::{Error, ErrorKind, Read, Result};
 use std::mem::MaybeUninit;
-use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
 use std::os::unix::net::UnixStream;
 use std::os::unix::process::CommandExt;
 use std::process::{Child, Command, Stdio};
@@ -370,9 +370,27 @@ impl EventedPty for Pty {
                 None
             },
             Ok(None) => None,
-            Ok(exit_status) => Some(ChildEvent::Exited(exit_status.and_then(|s| s.code()))),
+            Ok(exit_status) => Some(ChildEvent::Exited(exit_status)),
         }
     }
+
+    #[inline]
+    fn is_reading_password(&self) -> bool {
+        password_mode(self.file.as_raw_fd())
+    }
+}
+
+/// Check if echo is disabled in canonical mode.
+///
+/// The master side of a PTY reports the terminal attributes set by the slave.
+fn password_mode(fd: RawFd) -> bool {
+    let mut termios = MaybeUninit::<libc::termios>::uninit();
+    if unsafe { libc::tcgetattr(fd, termios.as_mut_ptr()) } != 0 {
+        return false;
+    }
+    let local_modes = unsafe { termios.assume_init() }.c_lflag;
+
+    local_modes & libc::ICANON != 0 && local_modes & libc::ECHO == 0
 }
 
 impl OnResize for Pty {
@@ -408,7 +426,7 @@ impl ToWinsize for WindowSize {
     }
 }
 
-unsafe fn set_nonblocking(fd: c_int) {
+pub(crate) unsafe fn set_nonblocking(fd: c_int) {
     use libc::{fcntl, F_GETFL, F_SETFL, O_NONBLOCK};
 
     let res = fcntl(fd, F_SETFL, fcntl(fd, F_GETFL, 0) | O_NONBLOCK);
@@ -420,3 +438,26 @@ fn test_get_pw_entry() {
     let mut buf: [i8; 1024] = [0; 1024];
     let _pw = get_pw_entry(&mut buf).unwrap();
 }
+
+#[test]
+fn test_password_mode() {
+    let pty = openpty(None, None).unwrap();
+    let (master, slave) = (pty.controller.as_raw_fd(), pty.user.as_raw_fd());
+
+    let mut termios = MaybeUninit::<libc::termios>::uninit();
+    assert_eq!(unsafe { libc::tcgetattr(slave, termios.as_mut_ptr()) }, 0);
+    let mut termios = unsafe { termios.assume_init() };
+    termios.c_lflag |= libc::ICANON | libc::ECHO;
+    assert_eq!(unsafe { libc::tcsetattr(slave, libc::TCSANOW, &termios) }, 0);
+    assert!(!password_mode(master));
+
+    // Disable echo like `sudo` does while reading a password.
+    termios.c_lflag &= !libc::ECHO;
+    assert_eq!(unsafe { libc::tcsetattr(slave, libc::TCSANOW, &termios) }, 0);
+    assert!(password_mode(master));
+
+    // Raw mode applications like editors also disable echo.
+    termios.c_lflag &= !libc::ICANON;
+    assert_eq!(unsafe { libc::tcsetattr(slave, libc::TCSANOW, &termios) }, 0);
+    assert!(!password_mode(master));
+}
@@ -1,4 +1,6 @@
 use std::cmp::min;
+use std::collections::VecDeque;
+use std::ops::Range;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -54,6 +56,13 @@ pub enum ViMotion {
     Bracket,
 }
 
+impl ViMotion {
+    /// Check if the motion is remembered in the jump list.
+    pub fn is_jump(self) -> bool {
+        matches!(self, Self::High | Self::Middle | Self::Low | Self::Bracket)
+    }
+}
+
 /// Cursor tracking vi mode position.
 #[derive(Default, Copy, Clone, PartialEq, Eq)]
 pub struct ViModeCursor {
@@ -176,6 +185,131 @@ impl ViModeCursor {
     }
 }
 
+/// Maximum number of positions remembered by the jump list.
+const MAX_JUMPS: usize = 100;
+
+/// Vi mode marks and jump list.
+///
+/// All positions move with the terminal content, like the selection.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ViMarks {
+    /// Positions of the marks `a` to `z`.
+    marks: [Option<Point>; 26],
+
+    /// Origins of previous jumps, from oldest to newest.
+    jumps: VecDeque<Point>,
+
+    /// Position in the jump list, equal to its length unless jumping through it.
+    jump_index: usize,
+}
+
+impl ViMarks {
+    /// Set a mark.
+    ///
+    /// Returns `false` if the name is not a lowercase ASCII letter.
+    pub fn set(&mut self, name: char, point: Point) -> bool {
+        match Self::index(name) {
+            Some(index) => {
+                self.marks[index] = Some(point);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Position of a mark.
+    pub fn get(&self, name: char) -> Option<Point> {
+        self.marks[Self::index(name)?]
+    }
+
+    /// Remember the origin of a jump.
+    ///
+    /// This discards all positions jumped back from.
+    pub fn push_jump(&mut self, origin: Point) {
+        self.jumps.truncate(self.jump_index);
+
+        if self.jumps.back() != Some(&origin) {
+            self.jumps.push_back(origin);
+        }
+
+        if self.jumps.len() > MAX_JUMPS {
+            self.jumps.pop_front();
+        }
+
+        self.jump_index = self.jumps.len();
+    }
+
+    /// Go back to the origin of the previous jump.
+    ///
+    /// The `current` position is remembered when starting to jump back, to allow returning to it.
+    pub fn jump_backward(&mut self, current: Point) -> Option<Point> {
+        if self.jump_index == self.jumps.len() {
+            self.push_jump(current);
+        }
+
+        while self.jump_index > 0 {
+            self.jump_index -= 1;
+            if self.jumps[self.jump_index] != current {
+                return Some(self.jumps[self.jump_index]);
+            }
+        }
+
+        None
+    }
+
+    /// Go forward to a position jumped back from.
+    pub fn jump_forward(&mut self, current: Point) -> Option<Point> {
+        while self.jump_index + 1 < self.jumps.len() {
+            self.jump_index += 1;
+            if self.jumps[self.jump_index] != current {
+                return Some(self.jumps[self.jump_index]);
+            }
+        }
+
+        None
+    }
+
+    /// Move all positions within `range` by `delta` lines.
+    ///
+    /// Positions which are moved out of the range or the grid are removed.
+    pub fn rotate<D: Dimensions>(&mut self, dimensions: &D, range: &Range<Line>, delta: i32) {
+        let topmost_line = if range.start == 0 { dimensions.topmost_line() } else { range.start };
+        self.update(|mut point| {
+            if (point.line < range.start && range.start != 0) || point.line >= range.end {
+                return Some(point);
+            }
+
+            point.line -= delta;
+            (point.line >= topmost_line && point.line < range.end).then_some(point)
+        });
+    }
+
+    /// Update all positions, removing them when `f` returns `None`.
+    pub fn update<F: FnMut(Point) -> Option<Point>>(&mut self, mut f: F) {
+        for mark in &mut self.marks {
+            *mark = mark.and_then(&mut f);
+        }
+
+        // Keep the jump list position in front of the same entries.
+        let mut index = 0;
+        let mut jump_index = 0;
+        self.jumps.retain_mut(|point| {
+            let updated = f(*point);
+            if let Some(updated) = updated {
+                *point = updated;
+                jump_index += (index < self.jump_index) as usize;
+            }
+            index += 1;
+            updated.is_some()
+        });
+        self.jump_index = jump_index;
+    }
+
+    fn index(name: char) -> Option<usize> {
+        name.is_ascii_lowercase().then(|| name as usize - 'a' as usize)
+    }
+}
+
 /// Find next end of line to move to.
 fn last<T>(term: &Term<T>, mut point: Point) -> Point {
     // Expand across wide cells.
@@ -392,6 +526,29 @@ mod tests {
         Term::new(Config::default(), &size, VoidListener)
     }
 
+    #[test]
+    fn jump_list() {
+        let mut marks = ViMarks::default();
+        let point = |line| Point::new(Line(line), Column(0));
+
+        marks.push_jump(point(0));
+        marks.push_jump(point(5));
+
+        // Jumping back remembers the current position.
+        assert_eq!(marks.jump_backward(point(9)), Some(point(5)));
+        assert_eq!(marks.jump_backward(point(5)), Some(point(0)));
+        assert_eq!(marks.jump_backward(point(0)), None);
+        assert_eq!(marks.jump_forward(point(0)), Some(point(5)));
+        assert_eq!(marks.jump_forward(point(5)), Some(point(9)));
+        assert_eq!(marks.jump_forward(point(9)), None);
+
+        // New jumps discard the positions jumped back from.
+        marks.jump_backward(point(9));
+        marks.push_jump(point(3));
+        assert_eq!(marks.jump_forward(point(3)), None);
+        assert_eq!(marks.jump_backward(point(7)), Some(point(3)));
+    }
+
     #[test]
     fn motion_simple() {
         let mut term = term();
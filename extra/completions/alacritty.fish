@@ -2,6 +2,15 @@ complete -c alacritty -n "__fish_use_subcommand" -l embed -d 'X11 window ID to e
 complete -c alacritty -n "__fish_use_subcommand" -l config-file -d 'Specify alternative configuration file [default: $XDG_CONFIG_HOME/alacritty/alacritty.toml]' -r -F
 complete -c alacritty -n "__fish_use_subcommand" -l socket -d 'Path for IPC socket creation' -r -F
 complete -c alacritty -n "__fish_use_subcommand" -l working-directory -d 'Start the shell in the specified working directory' -r -F
+complete -c alacritty -n "__fish_use_subcommand" -l serial -d 'Connect to a serial port instead of running a shell' -r -F
+complete -c alacritty -n "__fish_use_subcommand" -l baud-rate -d 'Serial line speed [default: 115200]' -r
+complete -c alacritty -n "__fish_use_subcommand" -l parity -d 'Serial parity [default: none]' -r -f -a "{none	'',odd	'',even	''}"
+complete -c alacritty -n "__fish_use_subcommand" -l flow-control -d 'Serial flow control [default: none]' -r -f -a "{none	'',software	'',hardware	''}"
+complete -c alacritty -n "__fish_use_subcommand" -l device-fd -d 'Connect to an inherited file descriptor instead of running a shell' -r
+complete -c alacritty -n "__fish_use_subcommand" -l device-socket -d 'Connect to a Unix socket instead of running a shell' -r -F
+complete -c alacritty -n "__fish_use_subcommand" -l replay -d 'Replay an asciicast recording instead of running a shell' -r -F
+complete -c alacritty -n "__fish_use_subcommand" -l replay-speed -d 'Replay speed multiplier [default: 1]' -r
+complete -c alacritty -n "__fish_use_subcommand" -l record -d 'Record the terminal output to an asciicast file' -r -F
 complete -c alacritty -n "__fish_use_subcommand" -s e -l command -d 'Command and args to execute (must be last argument)' -r
 complete -c alacritty -n "__fish_use_subcommand" -s T -l title -d 'Defines the window title [default: Alacritty]' -r
 complete -c alacritty -n "__fish_use_subcommand" -l class -d 'Defines window class/app_id on X11/Wayland [default: Alacritty]' -r
@@ -11,6 +20,7 @@ complete -c alacritty -n "__fish_use_subcommand" -l ref-test -d 'Generates ref t
 complete -c alacritty -n "__fish_use_subcommand" -s q -d 'Reduces the level of verbosity (the min level is -qq)'
 complete -c alacritty -n "__fish_use_subcommand" -s v -d 'Increases the level of verbosity (the max level is -vvv)'
 complete -c alacritty -n "__fish_use_subcommand" -l hold -d 'Remain open after child process exit'
+complete -c alacritty -n "__fish_use_subcommand" -l record-input -d 'Include keyboard input in the recording'
 complete -c alacritty -n "__fish_use_subcommand" -s h -l help -d 'Print help'
 complete -c alacritty -n "__fish_use_subcommand" -s V -l version -d 'Print version'
 complete -c alacritty -n "__fish_use_subcommand" -f -a "msg" -d 'Send a message to the Alacritty socket'
@@ -22,11 +32,21 @@ complete -c alacritty -n "__fish_seen_subcommand_from msg; and not __fish_seen_s
 complete -c alacritty -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from help" -f -a "config" -d 'Update the Alacritty configuration'
 complete -c alacritty -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from help" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
 complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -l working-directory -d 'Start the shell in the specified working directory' -r -F
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -l serial -d 'Connect to a serial port instead of running a shell' -r -F
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -l baud-rate -d 'Serial line speed [default: 115200]' -r
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -l parity -d 'Serial parity [default: none]' -r -f -a "{none	'',odd	'',even	''}"
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -l flow-control -d 'Serial flow control [default: none]' -r -f -a "{none	'',software	'',hardware	''}"
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -l device-fd -d 'Connect to an inherited file descriptor instead of running a shell' -r
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -l device-socket -d 'Connect to a Unix socket instead of running a shell' -r -F
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -l replay -d 'Replay an asciicast recording instead of running a shell' -r -F
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -l replay-speed -d 'Replay speed multiplier [default: 1]' -r
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -l record -d 'Record the terminal output to an asciicast file' -r -F
 complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -s e -l command -d 'Command and args to execute (must be last argument)' -r
 complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -s T -l title -d 'Defines the window title [default: Alacritty]' -r
 complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -l class -d 'Defines window class/app_id on X11/Wayland [default: Alacritty]' -r
 complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -s o -l option -d 'Override configuration file options [example: \'cursor.style="Beam"\']' -r
 complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -l hold -d 'Remain open after child process exit'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -l record-input -d 'Include keyboard input in the recording'
 complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -s h -l help -d 'Print help'
 complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from config" -s w -l window-id -d 'Window ID for the new config' -r
 complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from config" -s r -l reset -d 'Clear all runtime configuration changes'